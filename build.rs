@@ -11,6 +11,7 @@ use minify_js::{minify, Session, TopLevelMode};
 use std::{
     fs::{read_dir, read_to_string, File, OpenOptions},
     io::{Read, Write},
+    process::Command,
 };
 
 // ------- Constants -------
@@ -23,6 +24,12 @@ const STYLE_FOLDERS: [&str; 2] = ["themes", "colorschemes"];
 const PACKAGE_ENVIRONMENT_VARIABLE: &str = "PKG_ENV";
 /// A constant for the `prod` value of the `pkg_env` environment variable.
 const PRODUCTION_PKG_ENV_VARIABLE_VALUE: &str = "prod";
+/// A constant for the name of the environment variable which provides the git commit the app is
+/// built from.
+const GIT_COMMIT_ENVIRONMENT_VARIABLE: &str = "WEBSURFX_GIT_COMMIT";
+/// A constant for the paths of the git files which change when a commit is checked out or
+/// created.
+const GIT_PATHS: [&str; 2] = [".git/HEAD", ".git/refs"];
 
 /// A main function which minifies both css and js files using `lightningcss` and `minify_js` when
 /// the `PKG_ENV` environment and it is set to the value of `prod`.
@@ -32,6 +39,12 @@ const PRODUCTION_PKG_ENV_VARIABLE_VALUE: &str = "prod";
 /// This function returns the unit type when the minification process runs successfully otherwise
 /// it returns a standard error.
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // The build script declares the inputs it depends on, so it is only run again when the static
+    // files, the `PKG_ENV` environment variable or the git commit change.
+    println!("cargo:rerun-if-changed={COMMON_STATIC_SOURCE_CODE_FOLDER}");
+    println!("cargo:rerun-if-env-changed={PACKAGE_ENVIRONMENT_VARIABLE}");
+    set_git_commit();
+
     if let Ok(pkg_env_var) = std::env::var(PACKAGE_ENVIRONMENT_VARIABLE) {
         if pkg_env_var.to_lowercase() == PRODUCTION_PKG_ENV_VARIABLE_VALUE {
             // A for loop that loops over each file name containing in the `colorschemes` and `themes` folders
@@ -83,3 +96,37 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
     Ok(())
 }
+
+/// A function which provides the short hash of the git commit the app is built from to the app
+/// through the `WEBSURFX_GIT_COMMIT` environment variable, unless the variable is already set
+/// (for example when the app is built from a source archive without the git history). Nothing is
+/// provided when git is not available. The build script is run again once the environment
+/// variable is changed or a commit is checked out or created.
+fn set_git_commit() {
+    println!("cargo:rerun-if-env-changed={GIT_COMMIT_ENVIRONMENT_VARIABLE}");
+    // The paths which do not exist (like in a source archive) would make the build script run on
+    // every build, so they are only declared when they exist.
+    for path in GIT_PATHS {
+        if std::path::Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={path}");
+        }
+    }
+
+    if std::env::var(GIT_COMMIT_ENVIRONMENT_VARIABLE).is_ok() {
+        return;
+    }
+
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok());
+
+    if let Some(commit) = commit {
+        println!(
+            "cargo:rustc-env={GIT_COMMIT_ENVIRONMENT_VARIABLE}={}",
+            commit.trim()
+        );
+    }
+}
@@ -0,0 +1,12 @@
+/**
+ * Navigates to the search results of the provided category for the current search query,
+ * starting again from the first page.
+ * @param {string} category - The name of the category of the search results.
+ * @returns {void}
+ */
+function navigate_category(category) {
+  const url = new URL(window.location)
+  url.searchParams.set('category', category)
+  url.searchParams.delete('page')
+  window.location.href = url.toString()
+}
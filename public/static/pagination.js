@@ -6,7 +6,6 @@ function navigate_forward() {
     let url = new URL(window.location);
     let searchParams = url.searchParams;
 
-    let q = searchParams.get('q');
     let page = parseInt(searchParams.get('page'));
 
     if (isNaN(page)) {
@@ -15,7 +14,9 @@ function navigate_forward() {
         page++;
     }
 
-    window.location.href = `${url.origin}${url.pathname}?q=${encodeURIComponent(q)}&page=${page}`;
+    // The query is kept as is, whether it is provided as the `q` parameter or as a path segment.
+    searchParams.set('page', page);
+    window.location.href = url.toString();
 }
 
 /**
@@ -26,7 +27,6 @@ function navigate_backward() {
     let url = new URL(window.location);
     let searchParams = url.searchParams;
 
-    let q = searchParams.get('q');
     let page = parseInt(searchParams.get('page'));
 
     if (isNaN(page)) {
@@ -35,5 +35,7 @@ function navigate_backward() {
         page--;
     }
 
-    window.location.href = `${url.origin}${url.pathname}?q=${encodeURIComponent(q)}&page=${page}`;
+    // The query is kept as is, whether it is provided as the `q` parameter or as a path segment.
+    searchParams.set('page', page);
+    window.location.href = url.toString();
 }
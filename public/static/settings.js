@@ -70,14 +70,19 @@ function setClientSettings() {
 
   cookie_dictionary['engines'] = engines
 
-  // Set the expiration date for the cookie to 1 year from the current date
-  let expiration_date = new Date()
-  expiration_date.setFullYear(expiration_date.getFullYear() + 1)
+  // Get the cookie attributes configured by the server and only send the cookie over https
+  // if the website was accessed over https unless configured otherwise
+  let settings = document.querySelector('.settings')
+  let attributes = settings.dataset.cookieAttributes
+  if (
+    settings.dataset.cookieSecureAuto !== undefined &&
+    window.location.protocol === 'https:'
+  ) {
+    attributes += '; Secure'
+  }
 
   // Save the cookie to the user's machine
-  document.cookie = `appCookie=${JSON.stringify(
-    cookie_dictionary,
-  )}; expires=${expiration_date.toUTCString()}`
+  document.cookie = `appCookie=${JSON.stringify(cookie_dictionary)}; ${attributes}`
 
   // Display a success message to the user
   document.querySelector('.message').innerText =
@@ -0,0 +1,217 @@
+//! Benchmark module of the application
+//!
+//! This module contains the main function of the `websurfx-bench` command, which runs a set of
+//! search queries through the same aggregation path as the search page of the server, without
+//! starting the server, and reports the latency percentiles and the cache hit rate. It is meant
+//! for tuning the timeouts and the concurrency options before going live.
+use mimalloc::MiMalloc;
+
+use actix_web::web;
+use std::time::{Duration, Instant};
+use websurfx::{
+    cache::cacher::{create_cache, SharedCache},
+    config::Config,
+    models::{engine_models::Category, server_models::Cookie},
+    server::routes::search::{cache_key, results, selected_engines, CacheUse},
+};
+
+#[global_allocator]
+static GLOBAL: MiMalloc = MiMalloc;
+
+/// The search queries which are benchmarked when no queries are provided.
+const DEFAULT_QUERIES: [&str; 5] = [
+    "rust programming language",
+    "weather",
+    "linux kernel",
+    "open source search engine",
+    "privacy",
+];
+
+/// The usage of the command which is shown with the `--help` argument.
+const USAGE: &str = "Usage: websurfx-bench [--rounds <N>] [--file <PATH>] [QUERY]...
+
+Runs the search queries through the aggregation path of the server and reports the latency
+percentiles and the cache hit rate. The queries are read from the arguments and from the file
+with one query per line, a default set of queries is used when none are provided.
+
+Options:
+  -n, --rounds <N>   The number of times the queries are searched (defaults to 2).
+  -f, --file <PATH>  The file from which the queries are read.
+  -h, --help         Print this help message.";
+
+/// The error which is returned when invalid command line arguments are provided.
+const INVALID_ARGUMENTS: &str = "Invalid arguments, run `websurfx-bench --help` for the usage";
+
+/// A named struct which stores the options of the benchmark provided as command line arguments.
+struct Options {
+    /// The number of times the queries are searched.
+    rounds: usize,
+    /// The search queries to be benchmarked.
+    queries: Vec<String>,
+}
+
+/// A helper function which parses the options of the benchmark from the command line arguments.
+///
+/// # Error
+///
+/// Returns an error if the arguments are invalid or the queries file could not be read.
+fn parse_options() -> Result<Options, Box<dyn std::error::Error>> {
+    let mut options = Options {
+        rounds: 2,
+        queries: Vec::new(),
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-n" | "--rounds" => {
+                options.rounds = args
+                    .next()
+                    .and_then(|rounds| rounds.parse().ok())
+                    .ok_or(INVALID_ARGUMENTS)?
+            }
+            "-f" | "--file" => {
+                let path = args.next().ok_or(INVALID_ARGUMENTS)?;
+                options.queries.extend(
+                    std::fs::read_to_string(path)?
+                        .lines()
+                        .map(str::trim)
+                        .filter(|query| !query.is_empty())
+                        .map(str::to_owned),
+                );
+            }
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            _ if arg.starts_with('-') => return Err(INVALID_ARGUMENTS.into()),
+            _ => options.queries.push(arg),
+        }
+    }
+
+    if options.queries.is_empty() {
+        options.queries = DEFAULT_QUERIES.map(str::to_owned).to_vec();
+    }
+
+    Ok(options)
+}
+
+/// A helper function which provides the latency below which the provided percentage of the
+/// searches completed, using the nearest rank method.
+///
+/// # Arguments
+///
+/// * `latencies` - It takes the sorted latencies of the searches as an argument.
+/// * `percentage` - It takes the percentage of the searches as an argument.
+fn percentile(latencies: &[Duration], percentage: f64) -> Duration {
+    let rank = (percentage / 100.0 * latencies.len() as f64).ceil() as usize;
+    latencies
+        .get(
+            rank.saturating_sub(1)
+                .min(latencies.len().saturating_sub(1)),
+        )
+        .copied()
+        .unwrap_or_default()
+}
+
+/// The function that runs the benchmark with the same config file as the server and prints a
+/// summary of the results.
+///
+/// # Error
+///
+/// Returns an error if the arguments are invalid or the config file could not be parsed.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let options = parse_options()?;
+
+    let config = Config::parse(false)?;
+    let cache = web::Data::new(SharedCache::new(
+        create_cache(&config).await,
+        config.caching.max_cached_results,
+    ));
+
+    // The engines enabled by default in the config file are queried, just like for a user who
+    // has not changed the settings.
+    let search_settings = Cookie::build(
+        &config.style,
+        config
+            .search
+            .upstream_search_engines
+            .iter()
+            .filter_map(|(engine, enabled)| enabled.then_some(engine.as_str().into()))
+            .collect(),
+        config.search.safe_search,
+    );
+    let (engines, _) = selected_engines(&config, &search_settings);
+
+    let mut latencies: Vec<Duration> = Vec::new();
+    let (mut cache_hits, mut failed_searches) = (0, 0);
+    let (mut timed_out_searches, mut engine_errors) = (0, 0);
+
+    for _ in 0..options.rounds {
+        for query in &options.queries {
+            let key = cache_key(
+                &config,
+                query,
+                0,
+                search_settings.safe_search_level,
+                &engines,
+                Category::General,
+            );
+            if cache.cached_results(&key).await.is_ok() {
+                cache_hits += 1;
+            }
+
+            let start = Instant::now();
+            let search_results = results(
+                &config,
+                &cache,
+                query,
+                0,
+                &search_settings,
+                &engines,
+                Category::General,
+                CacheUse::Read,
+            )
+            .await;
+            latencies.push(start.elapsed());
+
+            match search_results {
+                Ok((mut search_results, _)) => {
+                    timed_out_searches += usize::from(search_results.timed_out);
+                    engine_errors += search_results.engine_errors_info().len();
+                }
+                Err(error) => {
+                    log::error!("Benchmark Error: The search for `{query}` failed: {error}");
+                    failed_searches += 1;
+                }
+            }
+        }
+    }
+
+    latencies.sort();
+    let searches = latencies.len();
+    let ms = |latency: Duration| latency.as_millis();
+
+    println!(
+        "searches: {searches} ({} queries x {} rounds), failed: {failed_searches}",
+        options.queries.len(),
+        options.rounds
+    );
+    println!(
+        "latency: p50 {}ms, p90 {}ms, p99 {}ms, max {}ms",
+        ms(percentile(&latencies, 50.0)),
+        ms(percentile(&latencies, 90.0)),
+        ms(percentile(&latencies, 99.0)),
+        ms(latencies.last().copied().unwrap_or_default())
+    );
+    println!(
+        "cache hits: {cache_hits}/{searches} ({:.1}%)",
+        cache_hits as f64 * 100.0 / searches.max(1) as f64
+    );
+    println!(
+        "searches with timed out engines: {timed_out_searches}, engine errors: {engine_errors}"
+    );
+
+    Ok(())
+}
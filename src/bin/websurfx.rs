@@ -40,7 +40,11 @@ async fn main() -> std::io::Result<()> {
         config.server.binding_ip
     );
     log::info!(
-        "Open http://{}:{}/ in your browser",
+        "Open {}://{}:{}/ in your browser",
+        match config.server.tls.enabled {
+            true => "https",
+            false => "http",
+        },
         config.server.binding_ip,
         config.server.port,
     );
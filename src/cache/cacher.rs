@@ -7,13 +7,25 @@ use mini_moka::sync::Cache as MokaCache;
 #[cfg(feature = "memory-cache")]
 use mini_moka::sync::ConcurrentCacheExt;
 
+#[cfg(feature = "memory-cache")]
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
 #[cfg(feature = "memory-cache")]
 use std::time::Duration;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::Mutex;
 
-use crate::{config::Config, models::aggregation_models::SearchResults};
+use crate::{
+    config::{server::Analytics, Config},
+    models::{
+        aggregation_models::SearchResults,
+        analytics_models::{AnalyticsCounts, AnalyticsReport},
+    },
+};
 
 use super::error::CacheError;
+#[cfg(feature = "memory-cache")]
+use super::eviction::LruIndex;
 #[cfg(feature = "redis-cache")]
 use super::redis_cacher::RedisCache;
 
@@ -46,7 +58,22 @@ pub trait Cacher: Send + Sync {
     ///
     /// Returns the `SearchResults` from the cache if the program executes normally otherwise
     /// returns a `CacheError` if the results cannot be retrieved from the cache.
-    async fn cached_results(&mut self, url: &str) -> Result<SearchResults, Report<CacheError>>;
+    async fn cached_results(&mut self, url: &str) -> Result<SearchResults, Report<CacheError>> {
+        Ok(self.cached_results_json(url).await?.try_into()?)
+    }
+
+    /// A function which fetches the cached results as the bytes of their json, which are
+    /// decompressed and decrypted but not deserialized.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - It takes an url as a string.
+    ///
+    /// # Error
+    ///
+    /// Returns the json bytes of the `SearchResults` from the cache if the program executes
+    /// normally otherwise returns a `CacheError` if the results cannot be retrieved from the cache.
+    async fn cached_results_json(&mut self, url: &str) -> Result<Vec<u8>, Report<CacheError>>;
 
     /// A function which caches the results by using the `url` as the key and
     /// `json results` as the value and stores it in the cache
@@ -67,6 +94,91 @@ pub trait Cacher: Send + Sync {
         urls: &[String],
     ) -> Result<(), Report<CacheError>>;
 
+    /// A function which counts a search in the analytics of the provided hour, the counts of an
+    /// hour are no longer kept once the hour is outside of the time window.
+    ///
+    /// # Arguments
+    ///
+    /// * `hour` - It takes the number of hours since the unix epoch at which the search was made
+    ///   as an argument.
+    /// * `window` - It takes the size of the time window (in hours) as an argument.
+    /// * `query` - It takes the normalized or hashed search query as an argument.
+    /// * `engines` - It takes the names of the queried upstream search engines as an argument.
+    ///
+    /// # Error
+    ///
+    /// Returns a unit type if the search was counted without a failure otherwise it returns a
+    /// `CacheError`. The backends which can not store the analytics do nothing.
+    async fn record_search(
+        &mut self,
+        _hour: u64,
+        _window: u64,
+        _query: &str,
+        _engines: &[String],
+    ) -> Result<(), Report<CacheError>> {
+        Ok(())
+    }
+
+    /// A function which fetches the analytics counted within the provided hours.
+    ///
+    /// # Arguments
+    ///
+    /// * `hours` - It takes the range of the hours since the unix epoch to fetch the analytics of
+    ///   as an argument.
+    ///
+    /// # Error
+    ///
+    /// Returns the aggregated counts of the provided hours if the program executes normally
+    /// otherwise returns a `CacheError`. The backends which can not store the analytics return no
+    /// counts.
+    async fn search_analytics(
+        &mut self,
+        _hours: RangeInclusive<u64>,
+    ) -> Result<AnalyticsCounts, Report<CacheError>> {
+        Ok(AnalyticsCounts::default())
+    }
+
+    /// A function which fetches the cached autocomplete suggestions of a partially typed search
+    /// query.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - It takes the normalized partially typed search query as an argument.
+    ///
+    /// # Error
+    ///
+    /// Returns the cached suggestions if the program executes normally otherwise returns a
+    /// `CacheError` if the suggestions cannot be retrieved from the cache. The backends which can
+    /// not store the suggestions never have them cached.
+    async fn cached_suggestions(
+        &mut self,
+        _prefix: &str,
+    ) -> Result<Vec<String>, Report<CacheError>> {
+        Err(Report::new(CacheError::MissingValue))
+    }
+
+    /// A function which caches the autocomplete suggestions of a partially typed search query, the
+    /// suggestions are kept apart from the search results and expire after the provided time.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - It takes the normalized partially typed search query as an argument.
+    /// * `suggestions` - It takes the suggestions of the query as an argument.
+    /// * `ttl` - It takes the time (in seconds) for which the suggestions are kept as an argument.
+    ///
+    /// # Error
+    ///
+    /// Returns a unit type if the suggestions are cached without a failure otherwise it returns a
+    /// `CacheError`. The backends which can not store the suggestions do nothing.
+    async fn cache_suggestions(
+        &mut self,
+        _prefix: &str,
+        _suggestions: &[String],
+        _ttl: u16,
+    ) -> Result<(), Report<CacheError>> {
+        Ok(())
+    }
+
     /// A helper function which computes the hash of the url and formats and returns it as string.
     ///
     /// # Arguments
@@ -252,14 +364,14 @@ pub trait Cacher: Send + Sync {
 
     ///
     /// # Error
-    /// Returns the SearchResults struct on success otherwise it returns a CacheError
-    /// on failure.
+    /// Returns the json bytes of the SearchResults struct on success otherwise it returns a
+    /// CacheError on failure.
 
     #[allow(unused_mut)] // needs to be mutable when any of the features is enabled
     fn post_process_search_results(
         &mut self,
         mut bytes: Vec<u8>,
-    ) -> Result<SearchResults, Report<CacheError>> {
+    ) -> Result<Vec<u8>, Report<CacheError>> {
         #[cfg(feature = "compress-cache-results")]
         {
             let decompressed = self.decompress_results(&bytes)?;
@@ -278,7 +390,7 @@ pub trait Cacher: Send + Sync {
             bytes = decompressed_decrypted;
         }
 
-        Ok(bytes.try_into()?)
+        Ok(bytes)
     }
 }
 
@@ -314,14 +426,19 @@ impl Cacher for RedisCache {
     async fn build(config: &Config) -> Self {
         log::info!(
             "Initialising redis cache. Listening to {}",
-            &config.redis_url
+            &config.caching.redis_url
         );
-        RedisCache::new(&config.redis_url, 5, config.cache_expiry_time)
-            .await
-            .expect("Redis cache configured")
+        RedisCache::new(
+            &config.caching.redis_url,
+            5,
+            config.caching.retention_time(),
+            &config.caching.key_prefix,
+        )
+        .await
+        .expect("Redis cache configured")
     }
 
-    async fn cached_results(&mut self, url: &str) -> Result<SearchResults, Report<CacheError>> {
+    async fn cached_results_json(&mut self, url: &str) -> Result<Vec<u8>, Report<CacheError>> {
         use base64::Engine;
         let hashed_url_string: &str = &self.hash_url(url);
         let base64_string = self.cached_json(hashed_url_string).await?;
@@ -362,6 +479,43 @@ impl Cacher for RedisCache {
         self.cache_json(base64_strings, hashed_url_strings.into_iter())
             .await
     }
+
+    async fn record_search(
+        &mut self,
+        hour: u64,
+        window: u64,
+        query: &str,
+        engines: &[String],
+    ) -> Result<(), Report<CacheError>> {
+        self.increment_analytics(hour, window * 3600, query, engines)
+            .await
+    }
+
+    async fn search_analytics(
+        &mut self,
+        hours: RangeInclusive<u64>,
+    ) -> Result<AnalyticsCounts, Report<CacheError>> {
+        self.analytics(hours).await
+    }
+
+    async fn cached_suggestions(
+        &mut self,
+        prefix: &str,
+    ) -> Result<Vec<String>, Report<CacheError>> {
+        let hashed_prefix = self.hash_url(prefix);
+        self.suggestions(&hashed_prefix).await
+    }
+
+    async fn cache_suggestions(
+        &mut self,
+        prefix: &str,
+        suggestions: &[String],
+        ttl: u16,
+    ) -> Result<(), Report<CacheError>> {
+        let hashed_prefix = self.hash_url(prefix);
+        self.store_suggestions(&hashed_prefix, suggestions, ttl)
+            .await
+    }
 }
 /// TryInto implementation for SearchResults from Vec<u8>
 use std::convert::TryInto;
@@ -382,11 +536,25 @@ impl TryInto<Vec<u8>> for &SearchResults {
     }
 }
 
+/// The maximum number of partially typed search queries whose suggestions are kept in the
+/// in-memory cache.
+#[cfg(feature = "memory-cache")]
+const MAX_CACHED_SUGGESTIONS: u64 = 10_000;
+
 /// Memory based cache backend.
 #[cfg(feature = "memory-cache")]
 pub struct InMemoryCache {
     /// The backend cache which stores data.
     cache: MokaCache<String, Vec<u8>>,
+    /// The size and the last use of the cached entries, which evicts the least recently used
+    /// entries once the configured limits are exceeded.
+    index: LruIndex,
+    /// The analytics counted within each hour of the time window, keyed by the number of hours
+    /// since the unix epoch.
+    analytics: HashMap<u64, AnalyticsCounts>,
+    /// The cached autocomplete suggestions along with the unix timestamp at which they expire,
+    /// keyed by the hash of the partially typed search query.
+    suggestions: MokaCache<String, (u64, Vec<String>)>,
 }
 
 #[cfg(feature = "memory-cache")]
@@ -397,16 +565,29 @@ impl Cacher for InMemoryCache {
 
         InMemoryCache {
             cache: MokaCache::builder()
-                .time_to_live(Duration::from_secs(config.caching.cache_expiry_time.into()))
+                .time_to_live(Duration::from_secs(config.caching.retention_time().into()))
                 .build(),
+            index: LruIndex::new(
+                config.caching.max_cache_entries,
+                config.caching.max_cache_size,
+            ),
+            analytics: HashMap::new(),
+            suggestions: MokaCache::new(MAX_CACHED_SUGGESTIONS),
         }
     }
 
-    async fn cached_results(&mut self, url: &str) -> Result<SearchResults, Report<CacheError>> {
+    async fn cached_results_json(&mut self, url: &str) -> Result<Vec<u8>, Report<CacheError>> {
         let hashed_url_string = self.hash_url(url);
         match self.cache.get(&hashed_url_string) {
-            Some(res) => self.post_process_search_results(res),
-            None => Err(Report::new(CacheError::MissingValue)),
+            Some(res) => {
+                self.index.touch(&hashed_url_string);
+                self.post_process_search_results(res)
+            }
+            None => {
+                // The entry may have expired, so it no longer counts towards the limits.
+                self.index.remove(&hashed_url_string);
+                Err(Report::new(CacheError::MissingValue))
+            }
         }
     }
 
@@ -418,12 +599,79 @@ impl Cacher for InMemoryCache {
         for (url, search_result) in urls.iter().zip(search_results.iter()) {
             let hashed_url_string = self.hash_url(url);
             let bytes = self.pre_process_search_results(search_result)?;
+            let evicted = self.index.insert(&hashed_url_string, bytes.len() as u64);
             self.cache.insert(hashed_url_string, bytes);
+            for key in evicted {
+                self.cache.invalidate(&key);
+            }
         }
 
         self.cache.sync();
         Ok(())
     }
+
+    async fn record_search(
+        &mut self,
+        hour: u64,
+        window: u64,
+        query: &str,
+        engines: &[String],
+    ) -> Result<(), Report<CacheError>> {
+        // drop the counts of the hours which are no longer within the time window.
+        self.analytics
+            .retain(|counted_hour, _| *counted_hour + window > hour);
+
+        let counts = self.analytics.entry(hour).or_default();
+        *counts.queries.entry(query.to_owned()).or_default() += 1;
+        for engine in engines {
+            *counts.engines.entry(engine.to_owned()).or_default() += 1;
+        }
+        Ok(())
+    }
+
+    async fn search_analytics(
+        &mut self,
+        hours: RangeInclusive<u64>,
+    ) -> Result<AnalyticsCounts, Report<CacheError>> {
+        let mut counts = AnalyticsCounts::default();
+        for (_, hour_counts) in self
+            .analytics
+            .iter()
+            .filter(|(hour, _)| hours.contains(hour))
+        {
+            counts.merge(hour_counts);
+        }
+        Ok(counts)
+    }
+
+    async fn cached_suggestions(
+        &mut self,
+        prefix: &str,
+    ) -> Result<Vec<String>, Report<CacheError>> {
+        let hashed_prefix = self.hash_url(prefix);
+        match self.suggestions.get(&hashed_prefix) {
+            Some((expires_at, suggestions)) if expires_at > current_time() => Ok(suggestions),
+            Some(_) => {
+                self.suggestions.invalidate(&hashed_prefix);
+                Err(Report::new(CacheError::MissingValue))
+            }
+            None => Err(Report::new(CacheError::MissingValue)),
+        }
+    }
+
+    async fn cache_suggestions(
+        &mut self,
+        prefix: &str,
+        suggestions: &[String],
+        ttl: u16,
+    ) -> Result<(), Report<CacheError>> {
+        let hashed_prefix = self.hash_url(prefix);
+        self.suggestions.insert(
+            hashed_prefix,
+            (current_time() + u64::from(ttl), suggestions.to_vec()),
+        );
+        Ok(())
+    }
 }
 
 /// Cache backend which utilises both memory and redis based caches.
@@ -450,10 +698,10 @@ impl Cacher for HybridCache {
         }
     }
 
-    async fn cached_results(&mut self, url: &str) -> Result<SearchResults, Report<CacheError>> {
-        match self.redis_cache.cached_results(url).await {
+    async fn cached_results_json(&mut self, url: &str) -> Result<Vec<u8>, Report<CacheError>> {
+        match self.redis_cache.cached_results_json(url).await {
             Ok(res) => Ok(res),
-            Err(_) => self.memory_cache.cached_results(url).await,
+            Err(_) => self.memory_cache.cached_results_json(url).await,
         }
     }
 
@@ -469,6 +717,59 @@ impl Cacher for HybridCache {
 
         Ok(())
     }
+
+    async fn record_search(
+        &mut self,
+        hour: u64,
+        window: u64,
+        query: &str,
+        engines: &[String],
+    ) -> Result<(), Report<CacheError>> {
+        self.redis_cache
+            .record_search(hour, window, query, engines)
+            .await?;
+        self.memory_cache
+            .record_search(hour, window, query, engines)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn search_analytics(
+        &mut self,
+        hours: RangeInclusive<u64>,
+    ) -> Result<AnalyticsCounts, Report<CacheError>> {
+        match self.redis_cache.search_analytics(hours.clone()).await {
+            Ok(counts) => Ok(counts),
+            Err(_) => self.memory_cache.search_analytics(hours).await,
+        }
+    }
+
+    async fn cached_suggestions(
+        &mut self,
+        prefix: &str,
+    ) -> Result<Vec<String>, Report<CacheError>> {
+        match self.redis_cache.cached_suggestions(prefix).await {
+            Ok(suggestions) => Ok(suggestions),
+            Err(_) => self.memory_cache.cached_suggestions(prefix).await,
+        }
+    }
+
+    async fn cache_suggestions(
+        &mut self,
+        prefix: &str,
+        suggestions: &[String],
+        ttl: u16,
+    ) -> Result<(), Report<CacheError>> {
+        self.redis_cache
+            .cache_suggestions(prefix, suggestions, ttl)
+            .await?;
+        self.memory_cache
+            .cache_suggestions(prefix, suggestions, ttl)
+            .await?;
+
+        Ok(())
+    }
 }
 
 /// Dummy cache backend
@@ -481,7 +782,7 @@ impl Cacher for DisabledCache {
         DisabledCache
     }
 
-    async fn cached_results(&mut self, _url: &str) -> Result<SearchResults, Report<CacheError>> {
+    async fn cached_results_json(&mut self, _url: &str) -> Result<Vec<u8>, Report<CacheError>> {
         Err(Report::new(CacheError::MissingValue))
     }
 
@@ -498,6 +799,9 @@ impl Cacher for DisabledCache {
 pub struct SharedCache {
     /// The internal cache protected from concurrent access by a mutex
     cache: Mutex<Box<dyn Cacher>>,
+    /// The maximum number of results of each search which are stored in the cache (`0` means no
+    /// limit).
+    max_results: usize,
 }
 
 impl SharedCache {
@@ -506,11 +810,14 @@ impl SharedCache {
     /// # Arguments
     ///
     /// * `cache` - It takes the `Cache` enum variant as an argument with the prefered cache type.
+    /// * `max_results` - It takes the maximum number of results of each search which are stored in
+    ///   the cache as an argument (`0` means no limit).
     ///
     /// Returns a newly constructed `SharedCache` struct.
-    pub fn new(cache: impl Cacher + 'static) -> Self {
+    pub fn new(cache: impl Cacher + 'static, max_results: usize) -> Self {
         Self {
             cache: Mutex::new(Box::new(cache)),
+            max_results,
         }
     }
 
@@ -530,6 +837,23 @@ impl SharedCache {
         mut_cache.cached_results(url).await
     }
 
+    /// A getter function which retrieves the json of the cached SearchResulsts from the internal
+    /// cache without deserializing it, so that it can be sent as it is.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - It takes the search url as an argument which will be used as the key to fetch the
+    ///   cached results from the cache.
+    ///
+    /// # Error
+    ///
+    /// Returns the json bytes of the search results from the cache if nothing goes wrong
+    /// otherwise returns a `CacheError`.
+    pub async fn cached_results_json(&self, url: &str) -> Result<Vec<u8>, Report<CacheError>> {
+        let mut mut_cache = self.cache.lock().await;
+        mut_cache.cached_results_json(url).await
+    }
+
     /// A setter function which caches the results by using the `url` as the key and
     /// `SearchResults` as the value.
     ///
@@ -540,6 +864,9 @@ impl SharedCache {
     /// * `url` - It takes the search url as an argument which will be used as the key for storing
     /// results in the cache.
     ///
+    /// Only the top ranked results up to the configured maximum are stored in the cache, the
+    /// provided `SearchResults` are left untouched.
+    ///
     /// # Error
     ///
     /// Returns an unit type if the results are cached succesfully otherwise returns a `CacheError`
@@ -549,9 +876,144 @@ impl SharedCache {
         search_results: &[SearchResults],
         urls: &[String],
     ) -> Result<(), Report<CacheError>> {
+        let trimmed_results: Vec<SearchResults>;
+        let search_results = if self.max_results != 0
+            && search_results
+                .iter()
+                .any(|results| results.results.len() > self.max_results)
+        {
+            trimmed_results = search_results
+                .iter()
+                .map(|results| {
+                    let mut results = results.clone();
+                    results.results.truncate(self.max_results);
+                    results
+                })
+                .collect();
+            &trimmed_results
+        } else {
+            search_results
+        };
+
         let mut mut_cache = self.cache.lock().await;
         mut_cache.cache_results(search_results, urls).await
     }
+
+    /// A function which counts a search in the analytics of the current hour. No information
+    /// about the user who made the search is stored.
+    ///
+    /// # Arguments
+    ///
+    /// * `analytics` - It takes the analytics related config options as an argument.
+    /// * `query` - It takes the normalized search query as an argument, which is hashed before it
+    ///   is stored if configured.
+    /// * `engines` - It takes the names of the queried upstream search engines as an argument.
+    ///
+    /// # Error
+    ///
+    /// Returns an unit type if the search was counted succesfully otherwise returns a
+    /// `CacheError` on a failure.
+    pub async fn record_search(
+        &self,
+        analytics: &Analytics,
+        query: &str,
+        engines: &[&str],
+    ) -> Result<(), Report<CacheError>> {
+        let query = match analytics.hash_queries {
+            true => blake3::hash(query.as_bytes()).to_string(),
+            false => query.to_owned(),
+        };
+        let engines: Vec<String> = engines.iter().map(|engine| engine.to_lowercase()).collect();
+
+        let mut mut_cache = self.cache.lock().await;
+        mut_cache
+            .record_search(current_hour(), analytics.window.into(), &query, &engines)
+            .await
+    }
+
+    /// A getter function which retrieves the analytics report of the configured time window.
+    ///
+    /// # Arguments
+    ///
+    /// * `analytics` - It takes the analytics related config options as an argument.
+    ///
+    /// # Error
+    ///
+    /// Returns an `AnalyticsReport` with the most searched queries and the engine usage counts if
+    /// nothing goes wrong otherwise returns a `CacheError`.
+    pub async fn search_analytics(
+        &self,
+        analytics: &Analytics,
+    ) -> Result<AnalyticsReport, Report<CacheError>> {
+        let hour = current_hour();
+        let first_hour = (hour + 1).saturating_sub(analytics.window.into());
+
+        let mut mut_cache = self.cache.lock().await;
+        let counts = mut_cache.search_analytics(first_hour..=hour).await?;
+        Ok(AnalyticsReport::new(
+            counts,
+            analytics.window,
+            analytics.hash_queries,
+            analytics.top_queries,
+        ))
+    }
+
+    /// A getter function which retrieves the cached autocomplete suggestions of a partially typed
+    /// search query from the internal cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - It takes the normalized partially typed search query as an argument which will
+    ///   be used as the key to fetch the cached suggestions from the cache.
+    ///
+    /// # Error
+    ///
+    /// Returns the cached suggestions if nothing goes wrong otherwise returns a `CacheError`.
+    pub async fn cached_suggestions(
+        &self,
+        prefix: &str,
+    ) -> Result<Vec<String>, Report<CacheError>> {
+        let mut mut_cache = self.cache.lock().await;
+        mut_cache.cached_suggestions(prefix).await
+    }
+
+    /// A setter function which caches the autocomplete suggestions of a partially typed search
+    /// query, apart from the cached search results.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - It takes the normalized partially typed search query as an argument which will
+    ///   be used as the key for storing the suggestions in the cache.
+    /// * `suggestions` - It takes the suggestions of the query as an argument.
+    /// * `ttl` - It takes the time (in seconds) for which the suggestions are kept as an argument.
+    ///
+    /// # Error
+    ///
+    /// Returns an unit type if the suggestions are cached succesfully otherwise returns a
+    /// `CacheError` on a failure.
+    pub async fn cache_suggestions(
+        &self,
+        prefix: &str,
+        suggestions: &[String],
+        ttl: u16,
+    ) -> Result<(), Report<CacheError>> {
+        let mut mut_cache = self.cache.lock().await;
+        mut_cache.cache_suggestions(prefix, suggestions, ttl).await
+    }
+}
+
+/// A helper function which returns the number of hours elapsed since the unix epoch, which is used
+/// to split the analytics into hourly counts.
+fn current_hour() -> u64 {
+    current_time() / 3600
+}
+
+/// A helper function which returns the number of seconds elapsed since the unix epoch.
+fn current_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
 }
 
 /// A function to initialise the cache backend.
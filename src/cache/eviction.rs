@@ -0,0 +1,123 @@
+//! This module provides the size accounting and the least recently used eviction of the entries
+//! of the in-memory cache, so that the memory used by the cache stays bounded on the long running
+//! instances.
+
+use std::collections::{BTreeMap, HashMap};
+
+/// A named struct which keeps track of the size of the entries of the in-memory cache and of the
+/// order in which they were last used, so that the least recently used entries are evicted once
+/// the configured limits are exceeded.
+#[derive(Debug, Default)]
+pub struct LruIndex {
+    /// The maximum number of entries (`0` means no limit).
+    max_entries: usize,
+    /// The maximum total size of the entries in bytes (`0` means no limit).
+    max_size: u64,
+    /// The last use and the size of each entry, keyed by the key of the entry.
+    entries: HashMap<String, (u64, u64)>,
+    /// The keys of the entries keyed by their last use, which orders them from the least
+    /// recently used entry on.
+    order: BTreeMap<u64, String>,
+    /// The counter providing the next use, which only ever increases.
+    uses: u64,
+    /// The total size of the entries in bytes.
+    size: u64,
+}
+
+impl LruIndex {
+    /// Constructs a new empty `LruIndex` with the provided limits.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_entries` - It takes the maximum number of entries as an argument (`0` means no
+    ///   limit).
+    /// * `max_size` - It takes the maximum total size of the entries in bytes as an argument (`0`
+    ///   means no limit).
+    pub fn new(max_entries: usize, max_size: u64) -> Self {
+        LruIndex {
+            max_entries,
+            max_size,
+            ..Default::default()
+        }
+    }
+
+    /// Adds an entry or updates the size of an existing entry, marking it as the most recently
+    /// used entry, and evicts the least recently used entries while the limits are exceeded.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - It takes the key of the entry as an argument.
+    /// * `size` - It takes the size of the entry in bytes as an argument.
+    ///
+    /// # Returns
+    ///
+    /// Returns the keys of the evicted entries in the order in which they were evicted, which
+    /// include the provided key when the entry alone exceeds the maximum size.
+    pub fn insert(&mut self, key: &str, size: u64) -> Vec<String> {
+        self.remove(key);
+        self.uses += 1;
+        self.entries.insert(key.to_owned(), (self.uses, size));
+        self.order.insert(self.uses, key.to_owned());
+        self.size += size;
+
+        let mut evicted = Vec::new();
+        while self.is_over_limits() {
+            let Some((_, key)) = self.order.pop_first() else {
+                break;
+            };
+            if let Some((_, size)) = self.entries.remove(&key) {
+                self.size -= size;
+            }
+            evicted.push(key);
+        }
+        evicted
+    }
+
+    /// Marks an entry as the most recently used entry, when it is tracked.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - It takes the key of the used entry as an argument.
+    pub fn touch(&mut self, key: &str) {
+        let Some((last_use, _)) = self.entries.get_mut(key) else {
+            return;
+        };
+        self.order.remove(last_use);
+        self.uses += 1;
+        *last_use = self.uses;
+        self.order.insert(self.uses, key.to_owned());
+    }
+
+    /// Stops tracking an entry, like an entry which expired.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - It takes the key of the removed entry as an argument.
+    pub fn remove(&mut self, key: &str) {
+        if let Some((last_use, size)) = self.entries.remove(key) {
+            self.order.remove(&last_use);
+            self.size -= size;
+        }
+    }
+
+    /// Returns the number of the tracked entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether no entries are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the total size of the tracked entries in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// A helper function which checks whether the tracked entries exceed any of the limits.
+    fn is_over_limits(&self) -> bool {
+        (self.max_entries != 0 && self.entries.len() > self.max_entries)
+            || (self.max_size != 0 && self.size > self.max_size)
+    }
+}
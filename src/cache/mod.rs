@@ -6,6 +6,9 @@ pub mod cacher;
 /// encryption module contains encryption utils such the cipher and key
 pub mod encryption;
 pub mod error;
+#[cfg(feature = "memory-cache")]
+/// eviction module contains the size accounting and the eviction of the in-memory cache entries
+pub mod eviction;
 
 #[cfg(feature = "redis-cache")]
 pub mod redis_cacher;
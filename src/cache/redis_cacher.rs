@@ -3,9 +3,11 @@
 
 use error_stack::Report;
 use futures::future::try_join_all;
-use redis::{aio::ConnectionManager, AsyncCommands, Client, RedisError};
+use redis::{aio::ConnectionManager, AsyncCommands, Client, FromRedisValue, RedisError};
+use std::ops::RangeInclusive;
 
 use super::error::CacheError;
+use crate::models::analytics_models::AnalyticsCounts;
 
 /// A named struct which stores the redis Connection url address to which the client will
 /// connect to.
@@ -20,6 +22,8 @@ pub struct RedisCache {
     current_connection: u8,
     /// It stores the max TTL for keys.
     cache_ttl: u16,
+    /// It stores the prefix prepended to all the keys.
+    key_prefix: String,
 }
 
 impl RedisCache {
@@ -30,6 +34,8 @@ impl RedisCache {
     /// * `redis_connection_url` - It takes the redis Connection url address.
     /// * `pool_size` - It takes the size of the connection pool (in other words the number of
     /// connections that should be stored in the pool).
+    /// * `cache_ttl` - It takes the max TTL for keys (in seconds).
+    /// * `key_prefix` - It takes the prefix to be prepended to all the keys.
     ///
     /// # Error
     ///
@@ -39,6 +45,7 @@ impl RedisCache {
         redis_connection_url: &str,
         pool_size: u8,
         cache_ttl: u16,
+        key_prefix: &str,
     ) -> Result<Self, Box<dyn std::error::Error>> {
         let client = Client::open(redis_connection_url)?;
         let mut tasks: Vec<_> = Vec::new();
@@ -52,6 +59,7 @@ impl RedisCache {
             pool_size,
             current_connection: Default::default(),
             cache_ttl,
+            key_prefix: key_prefix.to_owned(),
         };
         Ok(redis_cache)
     }
@@ -60,7 +68,7 @@ impl RedisCache {
     ///
     /// # Arguments
     ///
-    /// * `key` - It takes a string as key.
+    /// * `key` - It takes a string as key, which is prepended with the key prefix.
     ///
     /// # Error
     ///
@@ -68,6 +76,7 @@ impl RedisCache {
     /// on a failure.
     pub async fn cached_json(&mut self, key: &str) -> Result<String, Report<CacheError>> {
         self.current_connection = Default::default();
+        let key = &format!("{}{key}", self.key_prefix);
 
         let mut result: Result<String, RedisError> = self.connection_pool
             [self.current_connection as usize]
@@ -110,7 +119,7 @@ impl RedisCache {
     /// # Arguments
     ///
     /// * `json_results` - It takes the json results string as an argument.
-    /// * `key` - It takes the key as a String.
+    /// * `key` - It takes the key as a String, which is prepended with the key prefix.
     ///
     /// # Error
     ///
@@ -125,7 +134,11 @@ impl RedisCache {
         let mut pipeline = redis::Pipeline::with_capacity(3);
 
         for (key, json_result) in keys.zip(json_results) {
-            pipeline.set_ex(key, json_result, self.cache_ttl.into());
+            pipeline.set_ex(
+                format!("{}{key}", self.key_prefix),
+                json_result,
+                self.cache_ttl.into(),
+            );
         }
 
         let mut result: Result<(), RedisError> = pipeline
@@ -162,4 +175,194 @@ impl RedisCache {
             }
         }
     }
+
+    /// A function which increments the analytics counters of the provided hour on the redis
+    /// server, the counters are stored as sorted sets which expire once the hour is outside of the
+    /// time window.
+    ///
+    /// # Arguments
+    ///
+    /// * `hour` - It takes the number of hours since the unix epoch at which the search was made
+    ///   as an argument.
+    /// * `ttl` - It takes the time (in seconds) for which the counters are kept as an argument.
+    /// * `query` - It takes the normalized or hashed search query as an argument.
+    /// * `engines` - It takes the names of the queried upstream search engines as an argument.
+    ///
+    /// # Error
+    ///
+    /// Returns an unit type if the counters are incremented succesfully otherwise returns a
+    /// `CacheError` on a failure.
+    pub async fn increment_analytics(
+        &mut self,
+        hour: u64,
+        ttl: u64,
+        query: &str,
+        engines: &[String],
+    ) -> Result<(), Report<CacheError>> {
+        let (queries_key, engines_key) = self.analytics_keys(hour);
+        let ttl = i64::try_from(ttl).unwrap_or(i64::MAX);
+
+        let mut pipeline = redis::Pipeline::with_capacity(4 + engines.len());
+        pipeline
+            .zincr(&queries_key, query, 1)
+            .ignore()
+            .expire(&queries_key, ttl)
+            .ignore();
+        for engine in engines {
+            pipeline.zincr(&engines_key, engine, 1).ignore();
+        }
+        pipeline.expire(&engines_key, ttl).ignore();
+
+        self.query_pipeline(&pipeline).await
+    }
+
+    /// A function which fetches the analytics counters of the provided hours from the redis
+    /// server and aggregates them.
+    ///
+    /// # Arguments
+    ///
+    /// * `hours` - It takes the range of the hours since the unix epoch to fetch the counters of
+    ///   as an argument.
+    ///
+    /// # Error
+    ///
+    /// Returns the aggregated counts on success otherwise returns a `CacheError` on a failure.
+    pub async fn analytics(
+        &mut self,
+        hours: RangeInclusive<u64>,
+    ) -> Result<AnalyticsCounts, Report<CacheError>> {
+        let mut pipeline = redis::Pipeline::new();
+        for hour in hours {
+            let (queries_key, engines_key) = self.analytics_keys(hour);
+            pipeline
+                .zrange_withscores(queries_key, 0, -1)
+                .zrange_withscores(engines_key, 0, -1);
+        }
+
+        let counters: Vec<Vec<(String, f64)>> = self.query_pipeline(&pipeline).await?;
+
+        let mut counts = AnalyticsCounts::default();
+        for (index, counter) in counters.into_iter().enumerate() {
+            let map = match index % 2 {
+                0 => &mut counts.queries,
+                _ => &mut counts.engines,
+            };
+            for (member, score) in counter {
+                *map.entry(member).or_default() += score as u64;
+            }
+        }
+        Ok(counts)
+    }
+
+    /// A function which fetches the cached autocomplete suggestions of a partially typed search
+    /// query from the redis server.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - It takes the hash of the normalized query as an argument, which is prepended
+    ///   with the key prefix of the suggestions.
+    ///
+    /// # Error
+    ///
+    /// Returns the suggestions on success otherwise returns a `CacheError` on a failure or when
+    /// the suggestions are not cached.
+    pub async fn suggestions(&mut self, key: &str) -> Result<Vec<String>, Report<CacheError>> {
+        let mut pipeline = redis::Pipeline::with_capacity(1);
+        pipeline.get(self.suggestions_key(key));
+
+        let (json,): (Option<String>,) = self.query_pipeline(&pipeline).await?;
+        let json = json.ok_or(CacheError::MissingValue)?;
+        Ok(serde_json::from_str(&json).map_err(|_| CacheError::SerializationError)?)
+    }
+
+    /// A function which caches the autocomplete suggestions of a partially typed search query on
+    /// the redis server, which expire after the provided time.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - It takes the hash of the normalized query as an argument, which is prepended
+    ///   with the key prefix of the suggestions.
+    /// * `suggestions` - It takes the suggestions of the query as an argument.
+    /// * `ttl` - It takes the time (in seconds) for which the suggestions are kept as an argument.
+    ///
+    /// # Error
+    ///
+    /// Returns an unit type if the suggestions are cached succesfully otherwise returns a
+    /// `CacheError` on a failure.
+    pub async fn store_suggestions(
+        &mut self,
+        key: &str,
+        suggestions: &[String],
+        ttl: u16,
+    ) -> Result<(), Report<CacheError>> {
+        let json =
+            serde_json::to_string(suggestions).map_err(|_| CacheError::SerializationError)?;
+
+        let mut pipeline = redis::Pipeline::with_capacity(1);
+        pipeline
+            .set_ex(self.suggestions_key(key), json, ttl.into())
+            .ignore();
+        self.query_pipeline(&pipeline).await
+    }
+
+    /// A helper function which provides the key of the cached suggestions of a query, prepended
+    /// with the key prefix so that it is kept apart from the keys of the cached search results.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - It takes the hash of the normalized query as an argument.
+    fn suggestions_key(&self, key: &str) -> String {
+        format!("{}suggestions:{key}", self.key_prefix)
+    }
+
+    /// A helper function which provides the keys of the sorted sets storing the query and the
+    /// engine counters of the provided hour, prepended with the key prefix.
+    ///
+    /// # Arguments
+    ///
+    /// * `hour` - It takes the number of hours since the unix epoch as an argument.
+    fn analytics_keys(&self, hour: u64) -> (String, String) {
+        (
+            format!("{}analytics:queries:{hour}", self.key_prefix),
+            format!("{}analytics:engines:{hour}", self.key_prefix),
+        )
+    }
+
+    /// A helper function which runs the provided pipeline on the redis server. If the current
+    /// connection is dropped with a connection error then the pipeline is run again with the next
+    /// connection from the pool until the connections in the pool are exhausted.
+    ///
+    /// # Arguments
+    ///
+    /// * `pipeline` - It takes the pipeline of redis commands to run as an argument.
+    ///
+    /// # Error
+    ///
+    /// Returns the result of the pipeline on success otherwise returns a `CacheError` on a
+    /// failure.
+    async fn query_pipeline<T: FromRedisValue>(
+        &mut self,
+        pipeline: &redis::Pipeline,
+    ) -> Result<T, Report<CacheError>> {
+        self.current_connection = Default::default();
+        loop {
+            let result: Result<T, RedisError> = pipeline
+                .query_async(&mut self.connection_pool[self.current_connection as usize])
+                .await;
+            match result {
+                Err(error) => match error.is_connection_dropped() {
+                    true => {
+                        self.current_connection += 1;
+                        if self.current_connection == self.pool_size {
+                            return Err(Report::new(
+                                CacheError::PoolExhaustionWithConnectionDropError,
+                            ));
+                        }
+                    }
+                    false => return Err(Report::new(CacheError::RedisError(error))),
+                },
+                Ok(res) => return Ok(res),
+            }
+        }
+    }
 }
@@ -12,6 +12,52 @@ pub struct Caching {
     /// The URI to the redis server to use for caching.
     #[cfg(feature = "redis-cache")]
     pub redis_url: String,
+    /// The prefix prepended to all the keys stored on the redis server, which allows multiple
+    /// instances to share a redis server without their keys colliding.
+    #[cfg(feature = "redis-cache")]
+    pub key_prefix: String,
+    /// The maximum number of results of each search which are stored in the cache, the top ranked
+    /// results are kept and the rest are dropped (`0` means no limit).
+    pub max_cached_results: usize,
+    /// The minimum number of results a cached search has to have to be served from the cache,
+    /// the cached searches with fewer results are fetched again from the upstream search engines
+    /// (`0` means the cached searches are always served).
+    pub min_cached_results: usize,
+    /// Whether the search query is lowercased when building the cache key, so that the searches
+    /// which only differ by case share a cache entry.
+    pub lowercase_cache_key: bool,
+    /// Whether the identical searches which miss the cache at the same time share a single
+    /// aggregation of the results from the upstream search engines.
+    pub coalesce_searches: bool,
+    /// The number of searches handled concurrently by the server above which the server is
+    /// considered to be under high load (`0` disables the high load mode).
+    pub high_load_threshold: usize,
+    /// The maximum age of the cached searches which are served while the server is under high
+    /// load (in seconds).
+    pub high_load_max_age: u16,
+    /// The maximum number of search results which are written to the cache in the background at
+    /// the same time, the excess writes are dropped (`0` means no limit).
+    pub max_background_writes: usize,
+    /// Whether the cached search results which can not be served otherwise (like the expired or
+    /// the thin ones) are served when the live fetch of a page fails, and whether the failures of
+    /// the prefetched adjacent pages are skipped instead of failing the search.
+    pub serve_cached_on_error: bool,
+    /// Whether the response of each upstream search engine is cached on its own as well, so that
+    /// the searches querying a different selection of engines reuse the cached responses of the
+    /// engines which were queried before instead of querying them again.
+    pub cache_engine_responses: bool,
+    /// Whether the `nocache` search parameter, which skips the cached search results of a search,
+    /// is honored in debug mode and for the requests providing the admin token.
+    pub allow_nocache: bool,
+    /// Whether the search results fetched for the searches skipping the cache are cached, so that
+    /// they replace the cached search results.
+    pub nocache_writes: bool,
+    /// The maximum number of searches which are stored in the in-memory cache, the least recently
+    /// used searches are evicted beyond it (`0` means no limit).
+    pub max_cache_entries: usize,
+    /// The maximum total size of the searches which are stored in the in-memory cache (in
+    /// bytes), the least recently used searches are evicted beyond it (`0` means no limit).
+    pub max_cache_size: u64,
 }
 
 impl Default for Caching {
@@ -20,7 +66,35 @@ impl Default for Caching {
             #[cfg(any(feature = "redis-cache", feature = "memory-cache"))]
             cache_expiry_time: 600,
             #[cfg(feature = "redis-cache")]
-            redis_url: "redis://127.0.0.1:8082",
+            redis_url: "redis://127.0.0.1:8082".to_string(),
+            #[cfg(feature = "redis-cache")]
+            key_prefix: "websurfx:".to_string(),
+            max_cached_results: 0,
+            min_cached_results: 0,
+            lowercase_cache_key: false,
+            coalesce_searches: true,
+            high_load_threshold: 0,
+            high_load_max_age: 3600,
+            max_background_writes: 32,
+            serve_cached_on_error: true,
+            cache_engine_responses: false,
+            allow_nocache: true,
+            nocache_writes: true,
+            max_cache_entries: 10000,
+            max_cache_size: 256 * 1024 * 1024,
+        }
+    }
+}
+
+impl Caching {
+    /// Returns the time for which the search results are kept in the cache (in seconds), which
+    /// is extended to the maximum age of the cached searches served under high load when the
+    /// high load mode is enabled.
+    #[cfg(any(feature = "redis-cache", feature = "memory-cache"))]
+    pub fn retention_time(&self) -> u16 {
+        match self.high_load_threshold {
+            0 => self.cache_expiry_time,
+            _ => self.cache_expiry_time.max(self.high_load_max_age),
         }
     }
 }
@@ -1,13 +1,27 @@
 #![allow(missing_docs, clippy::missing_docs_in_private_items)]
 //! This module provides the modules which handles the functionality to parse the lua/json config
 //! and convert the config options into rust readable form.
-use crate::config::{caching::Caching, search::Search, server::Server, style::Style};
+use crate::config::{
+    caching::Caching,
+    search::{LocalSource, SafeSearchTermRule, Search},
+    server::{SameSite, Server},
+    style::{CachedLink, Style},
+};
+use crate::engines::json::source_url;
+use crate::models::engine_models::{EngineHandler, SAFE_SEARCH_LEVELS};
+use crate::templates::views::search::RESULT_TEMPLATES;
 use log::LevelFilter;
+use regex::Regex;
+use reqwest::header::{HeaderName, HeaderValue};
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
 
 pub mod caching;
 pub mod search;
 pub mod server;
+pub mod shared;
 pub mod style;
 
 /// A named struct which stores the parsed config file options.
@@ -18,6 +32,10 @@ pub struct Config {
     pub style: Style,
     pub caching: Caching,
     pub search: Search,
+    /// The number of times the config had been reloaded when it was parsed, which allows the
+    /// values built from the config to detect that they are outdated.
+    #[serde(skip)]
+    pub generation: u64,
 }
 
 impl Config {
@@ -25,9 +43,7 @@ impl Config {
     fn set_logging_level(&self) {
         if let Ok(pkg_env_var) = std::env::var("PKG_ENV") {
             if pkg_env_var.to_lowercase() == "dev" {
-                env_logger::Builder::new()
-                    .filter(None, LevelFilter::Trace)
-                    .init();
+                self.init_logger(LevelFilter::Trace);
                 return;
             }
         }
@@ -40,7 +56,21 @@ impl Config {
             (false, false) => LevelFilter::Error,
         };
 
-        env_logger::Builder::new().filter(None, log_level).init();
+        self.init_logger(log_level);
+    }
+
+    /// a helper function that initializes the logger with the provided logging level. The http
+    /// clients log the urls of the upstream requests, which contain the search query, so their
+    /// debug logs are suppressed when sensitive terms are configured.
+    fn init_logger(&self, log_level: LevelFilter) {
+        let mut builder = env_logger::Builder::new();
+        builder.filter(None, log_level);
+        if !self.search.sensitive_terms.is_empty() {
+            for target in ["reqwest", "hyper"] {
+                builder.filter(Some(target), log_level.min(LevelFilter::Info));
+            }
+        }
+        builder.init();
     }
 }
 
@@ -54,19 +84,544 @@ fn process_settings(mut conf: Config) -> Result<Config, Box<dyn std::error::Erro
         }
     };
 
-    conf.caching.cache_expiry_time = match conf.caching.cache_expiry_time {
-        0..=59 => {
-            log::error!("Config Error: The value of `cache_expiry_time` must be greater than 60");
-            log::error!("Falling back to using the value `60` for the option");
-            60
+    if conf.search.api_safe_search.is_some_and(|level| level > 4) {
+        log::error!(
+            "Config Error: The value of `api_safe_search` option should be an integer from 0 to 4."
+        );
+        log::error!("Falling back to using the value of `safe_search` for the option");
+        conf.search.api_safe_search = None;
+    }
+
+    conf.server.aggregator.engine_failure_tolerance = match conf
+        .server
+        .aggregator
+        .engine_failure_tolerance
+    {
+        tolerance if (0.0..=1.0).contains(&tolerance) => tolerance,
+        _ => {
+            log::error!("Config Error: The value of `engine_failure_tolerance` option should be a fraction from 0.0 to 1.0.");
+            log::error!("Falling back to using the value `0.5` for the option");
+            0.5
         }
-        _ => conf.caching.cache_expiry_time,
     };
 
+    if !RESULT_TEMPLATES.contains(&conf.style.results_template.as_str()) {
+        log::error!(
+            "Config Error: The value of `results_template` option should be one of {:?}.",
+            RESULT_TEMPLATES
+        );
+        log::error!(
+            "Falling back to using the value `{}` for the option",
+            RESULT_TEMPLATES[0]
+        );
+        conf.style.results_template = RESULT_TEMPLATES[0].to_string();
+    }
+
+    if !conf.style.cached_link.url.contains("{url}") {
+        let default_url = CachedLink::default().url;
+        log::error!("Config Error: The value of `url` option of the cached link should contain the `{{url}}` placeholder.");
+        log::error!("Falling back to using the value `{default_url}` for the option");
+        conf.style.cached_link.url = default_url;
+    }
+
+    conf.style.shortcuts.retain(|shortcut| {
+        let valid = !shortcut.label.trim().is_empty()
+            && reqwest::Url::parse(&shortcut.url)
+                .is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+        if !valid {
+            log::warn!("Config Error: Skipping the invalid shortcut `{}`, the shortcuts should have a label and an http or https url", shortcut.label);
+        }
+        valid
+    });
+
+    for (engine, headers) in conf.search.engine_headers.iter_mut() {
+        for (name, value) in headers.0.iter_mut() {
+            *value = resolve_env_vars(value).map_err(|var| {
+                format!("Config Error: The environment variable `{var}` referenced by the custom header `{name}` for the engine `{engine}` is not set")
+            })?;
+        }
+    }
+
+    if let Some(admin_token) = conf.server.admin_token.as_mut() {
+        *admin_token = resolve_env_vars(admin_token).map_err(|var| {
+            format!("Config Error: The environment variable `{var}` referenced by the `admin_token` option is not set")
+        })?;
+    }
+
+    if let Some(secret) = conf.style.click_redirect.secret.as_mut() {
+        *secret = resolve_env_vars(secret).map_err(|var| {
+            format!("Config Error: The environment variable `{var}` referenced by the `secret` option of the click redirect is not set")
+        })?;
+    }
+
+    if let Some(secret) = conf.style.favicons.secret.as_mut() {
+        *secret = resolve_env_vars(secret).map_err(|var| {
+            format!("Config Error: The environment variable `{var}` referenced by the `secret` option of the favicons is not set")
+        })?;
+    }
+
+    if let Some(secret) = conf.server.thumbnails.secret.as_mut() {
+        *secret = resolve_env_vars(secret).map_err(|var| {
+            format!("Config Error: The environment variable `{var}` referenced by the `secret` option of the thumbnails is not set")
+        })?;
+    }
+
+    conf.search = process_search_settings(conf.search);
+
+    conf.server = process_server_settings(conf.server);
+
+    conf.caching = process_caching_settings(conf.caching);
+
     Ok(conf)
 }
 
+/// A helper function which validates the server settings, falling back to the default values
+/// for the invalid options.
+///
+/// # Arguments
+///
+/// * `server` - It takes the parsed server settings as an argument.
+fn process_server_settings(mut server: Server) -> Server {
+    if server.analytics.window == 0 {
+        log::error!("Config Error: The value of `window` option of the analytics should be a non zero positive integer.");
+        log::error!("Falling back to using the value `24` for the option");
+        server.analytics.window = 24;
+    }
+
+    if server.engine_stats.window == 0 {
+        log::error!("Config Error: The value of `window` option of the engine stats should be a non zero positive integer.");
+        log::error!("Falling back to using the value `24` for the option");
+        server.engine_stats.window = 24;
+    }
+
+    if server.aggregator.fastest_engines_first && !server.engine_stats.enabled {
+        log::warn!("Config Error: The `fastest_engines_first` option of the aggregator requires the engine stats to be enabled.");
+        log::warn!(
+            "The requests to the upstream search engines are dispatched in their configured order"
+        );
+    }
+
+    if server.batch_search.concurrency == 0 {
+        log::error!("Config Error: The value of `concurrency` option of the batch search should be a non zero positive integer.");
+        log::error!("Falling back to using the value `4` for the option");
+        server.batch_search.concurrency = 4;
+    }
+
+    if server.featured_snippet.max_length == 0 {
+        log::error!("Config Error: The value of `max_length` option of the featured snippet should be a non zero positive integer.");
+        log::error!("Falling back to using the value `300` for the option");
+        server.featured_snippet.max_length = 300;
+    }
+
+    match HeaderValue::from_str(&server.cache_control) {
+        Ok(cache_control) => server.cache_control_header = cache_control,
+        Err(_) => {
+            log::error!(
+                "Config Error: The value of `cache_control` option should be a valid header value."
+            );
+            log::error!("Falling back to using the value `no-store` for the option");
+            server.cache_control = "no-store".to_string();
+            server.cache_control_header = HeaderValue::from_static("no-store");
+        }
+    }
+
+    if server.cookie.same_site == SameSite::None && server.cookie.secure != Some(true) {
+        log::error!("Config Error: The `none` value of the `same_site` option of the cookie requires the `secure` option to be enabled.");
+        log::error!("Falling back to enabling the `secure` option");
+        server.cookie.secure = Some(true);
+    }
+
+    server
+}
+
+/// A helper function which validates the caching settings, falling back to the default values
+/// for the invalid options.
+///
+/// # Arguments
+///
+/// * `caching` - It takes the parsed caching settings as an argument.
+fn process_caching_settings(mut caching: Caching) -> Caching {
+    #[cfg(any(feature = "redis-cache", feature = "memory-cache"))]
+    {
+        caching.cache_expiry_time = match caching.cache_expiry_time {
+            0..=59 => {
+                log::error!(
+                    "Config Error: The value of `cache_expiry_time` must be greater than 60"
+                );
+                log::error!("Falling back to using the value `60` for the option");
+                60
+            }
+            _ => caching.cache_expiry_time,
+        };
+    }
+
+    if caching.max_cached_results != 0 && caching.min_cached_results > caching.max_cached_results {
+        log::error!("Config Error: The value of `min_cached_results` option should not be greater than the value of `max_cached_results` option, otherwise no cached search would be served.");
+        log::error!("Falling back to using the value of `max_cached_results` for the option");
+        caching.min_cached_results = caching.max_cached_results;
+    }
+
+    #[cfg(any(feature = "redis-cache", feature = "memory-cache"))]
+    if caching.high_load_threshold != 0 && caching.high_load_max_age < caching.cache_expiry_time {
+        log::error!("Config Error: The value of `high_load_max_age` option should not be less than the value of `cache_expiry_time` option, otherwise the cached searches would expire sooner under high load.");
+        log::error!("Falling back to using the value of `cache_expiry_time` for the option");
+        caching.high_load_max_age = caching.cache_expiry_time;
+    }
+
+    caching
+}
+
+/// A helper function which validates the per engine options of the search settings, skipping
+/// the invalid entries and normalizing the engine names to lowercase.
+///
+/// # Arguments
+///
+/// * `search` - It takes the parsed search settings as an argument.
+fn process_search_settings(mut search: Search) -> Search {
+    search.query_rewrite_rules = search
+        .query_rewrite_rules
+        .into_iter()
+        .map(|(engine, rules)| {
+            let rules = rules
+                .into_iter()
+                .filter_map(|mut rule| match Regex::new(&rule.find) {
+                    Ok(pattern) => {
+                        rule.pattern = Some(pattern);
+                        Some(rule)
+                    }
+                    Err(error) => {
+                        log::warn!("Config Error: Skipping the invalid query rewrite rule `{}` for the engine `{engine}`: {error}", rule.find);
+                        None
+                    }
+                })
+                .collect();
+            (engine.to_lowercase(), rules)
+        })
+        .collect();
+
+    search.engine_headers = search
+        .engine_headers
+        .into_iter()
+        .map(|(engine, mut headers)| {
+            headers.0.retain(|name, value| {
+                let is_valid = HeaderName::try_from(name.as_str()).is_ok()
+                    && HeaderValue::try_from(value.as_str()).is_ok();
+                if !is_valid {
+                    log::warn!("Config Error: Skipping the invalid custom header `{name}` for the engine `{engine}`");
+                }
+                is_valid
+            });
+            (engine.to_lowercase(), headers)
+        })
+        .collect();
+
+    search.engine_weights = valid_engine_weights(search.engine_weights);
+    search.category_engine_weights = search
+        .category_engine_weights
+        .into_iter()
+        .map(|(category, weights)| (category, valid_engine_weights(weights)))
+        .collect();
+
+    search.safe_search_mappings = search
+        .safe_search_mappings
+        .into_iter()
+        .filter(|(engine, values)| {
+            let is_valid = values.len() == SAFE_SEARCH_LEVELS;
+            if !is_valid {
+                log::warn!("Config Error: Skipping the safe search mapping for the engine `{engine}`, it should provide a value for each of the {SAFE_SEARCH_LEVELS} safe search levels");
+            }
+            is_valid
+        })
+        .map(|(engine, values)| (engine.to_lowercase(), values))
+        .collect();
+
+    search.engine_result_limits = search
+        .engine_result_limits
+        .into_iter()
+        .map(|(engine, limit)| (engine.to_lowercase(), limit))
+        .collect();
+
+    search.engine_rate_limits = search
+        .engine_rate_limits
+        .into_iter()
+        .map(|(engine, limit)| (engine.to_lowercase(), limit))
+        .collect();
+
+    search.engine_categories = search
+        .engine_categories
+        .into_iter()
+        .map(|(engine, categories)| (engine.to_lowercase(), categories))
+        .collect();
+
+    search.fallback_engine_tiers = search
+        .fallback_engine_tiers
+        .into_iter()
+        .map(|tier| tier.iter().map(|engine| engine.to_lowercase()).collect())
+        .collect();
+
+    let mut denied_engines: Vec<String> = Vec::new();
+    for engine in search.denied_engines {
+        let engine = engine.to_lowercase();
+        if EngineHandler::new(&engine).is_err() {
+            log::warn!("Config Error: Skipping the unknown denied engine `{engine}`");
+        } else if !denied_engines.contains(&engine) {
+            denied_engines.push(engine);
+        }
+    }
+    search.denied_engines = denied_engines;
+    // The denied engines are neither offered on the settings page nor enabled by default.
+    search
+        .upstream_search_engines
+        .retain(|engine, _| !search.denied_engines.contains(&engine.to_lowercase()));
+
+    let mut forced_engines: Vec<String> = Vec::new();
+    for engine in search.forced_engines {
+        let engine = engine.to_lowercase();
+        if EngineHandler::new(&engine).is_err() {
+            log::warn!("Config Error: Skipping the unknown forced engine `{engine}`");
+        } else if search.denied_engines.contains(&engine) {
+            log::warn!("Config Error: Skipping the forced engine `{engine}`, it is denied");
+        } else if !forced_engines.contains(&engine) {
+            forced_engines.push(engine);
+        }
+    }
+    search.forced_engines = forced_engines;
+
+    search.engine_aliases = search
+        .engine_aliases
+        .into_iter()
+        .map(|(alias, engine)| {
+            // The aliases are matched like the fuzzy engine names which they are compared with.
+            let alias: String = alias
+                .chars()
+                .filter(|character| character.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect();
+            (alias, engine.to_lowercase())
+        })
+        .filter(|(alias, engine)| {
+            let is_valid = EngineHandler::new(engine).is_ok();
+            if !is_valid {
+                log::warn!(
+                    "Config Error: Skipping the alias `{alias}` of the unknown engine `{engine}`"
+                );
+            }
+            is_valid
+        })
+        .collect();
+
+    search.domain_reputation = search
+        .domain_reputation
+        .into_iter()
+        .filter(|(domain, adjustment)| {
+            let is_valid = adjustment.is_finite() && *adjustment >= 0.0;
+            if !is_valid {
+                log::warn!("Config Error: Skipping the invalid reputation adjustment `{adjustment}` for the domain `{domain}`, the adjustment should be a non negative number");
+            }
+            is_valid
+        })
+        .map(|(domain, adjustment)| {
+            let domain = domain.trim().trim_start_matches("*.").trim_matches('.');
+            (domain.to_lowercase(), adjustment)
+        })
+        .collect();
+
+    search.query_synonyms = search
+        .query_synonyms
+        .into_iter()
+        .filter(|(word, expansion)| {
+            let is_valid = !word.is_empty()
+                && !word.contains(char::is_whitespace)
+                && !expansion.trim().is_empty();
+            if !is_valid {
+                log::warn!("Config Error: Skipping the invalid query synonym `{word}`, the synonyms should map a single word to a non empty expansion");
+            }
+            is_valid
+        })
+        .map(|(word, expansion)| (word.to_lowercase(), expansion.trim().to_owned()))
+        .collect();
+
+    if usize::from(search.safe_search_terms.min_level) >= SAFE_SEARCH_LEVELS {
+        log::error!("Config Error: The value of `min_level` option of the safe search terms should be a safe search level from 0 to 4.");
+        log::error!("Falling back to using the value `3` for the option");
+        search.safe_search_terms.min_level = 3;
+    }
+
+    if !search.json_source.url.is_empty() {
+        let url = source_url(&search.json_source.url, "websurfx", 0, "0");
+        if !reqwest::Url::parse(&url).is_ok_and(|url| url.scheme() == "https") {
+            log::error!("Config Error: The url of the json source should be a valid https url.");
+            log::error!("Falling back to not querying the json source");
+            search.json_source.url.clear();
+        }
+    }
+
+    search.local_source = process_local_source(search.local_source);
+
+    search.safe_search_terms.rules.retain_mut(|rule| {
+        let is_valid_redirect = |url: &str| {
+            url.starts_with('/') || url.starts_with("https://") || url.starts_with("http://")
+        };
+        rule.pattern = SafeSearchTermRule::term_pattern(&rule.term);
+        let is_valid = rule.pattern.is_some()
+            && match (&rule.rewrite, &rule.redirect) {
+                (Some(_), None) => true,
+                (None, Some(url)) => is_valid_redirect(url),
+                _ => false,
+            };
+        if !is_valid {
+            log::warn!("Config Error: Skipping the invalid safe search term rule `{}`, the rules should provide either a rewrite or an http, https or relative redirect url", rule.term);
+        }
+        is_valid
+    });
+
+    search
+}
+
+/// A helper function which validates the directory of local documents searched by the `Local`
+/// upstream search engine.
+///
+/// # Arguments
+///
+/// * `local_source` - It takes the parsed directory of local documents as an argument.
+fn process_local_source(mut local_source: LocalSource) -> LocalSource {
+    if !local_source.directory.is_empty() && !Path::new(&local_source.directory).is_dir() {
+        log::error!(
+            "Config Error: The directory of the local source should be an existing directory."
+        );
+        log::error!("Falling back to not searching the local source");
+        local_source.directory.clear();
+    }
+    if !local_source.url.is_empty() {
+        let url = local_source.url.replace("{path}", "websurfx.md");
+        if !reqwest::Url::parse(&url).is_ok_and(|url| matches!(url.scheme(), "https" | "http")) {
+            log::error!(
+                "Config Error: The url of the local source should be a valid http or https url."
+            );
+            log::error!("Falling back to linking the local documents by their file url");
+            local_source.url.clear();
+        }
+    }
+    // The browsers do not open the `file` urls from a page served over http or https, so the
+    // documents would only be listed without their links being usable.
+    if !local_source.directory.is_empty() && local_source.url.is_empty() {
+        log::warn!("Config Error: The url of the local source should be provided, the local documents are linked by their file url which the browsers do not open from the search page");
+    }
+
+    local_source
+}
+
+/// A helper function which drops the invalid engine weights (the negative or non finite weights)
+/// with a warning and lowercases the engine names the weights are keyed by.
+///
+/// # Arguments
+///
+/// * `weights` - It takes the configured weights of the engines as an argument.
+fn valid_engine_weights(weights: HashMap<String, f32>) -> HashMap<String, f32> {
+    weights
+        .into_iter()
+        .filter(|(engine, weight)| {
+            let is_valid = weight.is_finite() && *weight >= 0.0;
+            if !is_valid {
+                log::warn!("Config Error: Skipping the invalid weight `{weight}` for the engine `{engine}`, the weight should be a non negative number");
+            }
+            is_valid
+        })
+        .map(|(engine, weight)| (engine.to_lowercase(), weight))
+        .collect()
+}
+
+/// A helper function which replaces the `${NAME}` references to the environment variables in the
+/// provided config value with the values of the environment variables, so that secrets like api
+/// keys do not have to be stored in the config file.
+///
+/// # Arguments
+///
+/// * `value` - It takes the config value which could contain references as an argument.
+///
+/// # Error
+///
+/// Returns the config value with the references replaced on success otherwise returns the name
+/// of the first referenced environment variable which is not set.
+fn resolve_env_vars(value: &str) -> Result<String, String> {
+    static REFERENCE: OnceLock<Regex> = OnceLock::new();
+    let reference =
+        REFERENCE.get_or_init(|| Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").unwrap());
+
+    let mut resolved = String::with_capacity(value.len());
+    let mut last_match = 0;
+    for captures in reference.captures_iter(value) {
+        let (Some(whole), Some(name)) = (captures.get(0), captures.get(1)) else {
+            continue;
+        };
+        let env_value = std::env::var(name.as_str()).map_err(|_| name.as_str().to_owned())?;
+        resolved.push_str(&value[last_match..whole.start()]);
+        resolved.push_str(&env_value);
+        last_match = whole.end();
+    }
+    resolved.push_str(&value[last_match..]);
+    Ok(resolved)
+}
+
 #[cfg(feature = "json-config")]
 pub mod json;
 #[cfg(feature = "lua-config")]
 pub mod lua;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_control() {
+        let server = process_server_settings(Server {
+            cache_control: "private, max-age=60".to_owned(),
+            ..Default::default()
+        });
+        assert_eq!(server.cache_control_header, "private, max-age=60");
+
+        // The invalid header values fall back to the default when the config is loaded.
+        let server = process_server_settings(Server {
+            cache_control: "max-age=60\nX-Injected: 1".to_owned(),
+            ..Default::default()
+        });
+        assert_eq!(server.cache_control, "no-store");
+        assert_eq!(server.cache_control_header, "no-store");
+    }
+
+    #[test]
+    fn test_resolve_env_vars() {
+        std::env::set_var("WEBSURFX_TEST_API_KEY", "key");
+        std::env::set_var("WEBSURFX_TEST_REGION", "eu");
+
+        assert_eq!(
+            resolve_env_vars("Bearer ${WEBSURFX_TEST_API_KEY}").as_deref(),
+            Ok("Bearer key")
+        );
+        assert_eq!(
+            resolve_env_vars(
+                "${WEBSURFX_TEST_REGION}-${WEBSURFX_TEST_API_KEY}${WEBSURFX_TEST_REGION}"
+            )
+            .as_deref(),
+            Ok("eu-keyeu")
+        );
+
+        // The name of the first referenced variable which is not set is returned.
+        assert_eq!(
+            resolve_env_vars("${WEBSURFX_TEST_API_KEY}${WEBSURFX_TEST_UNSET}"),
+            Err("WEBSURFX_TEST_UNSET".to_owned())
+        );
+
+        // The values without references, or with malformed ones, are kept as they are.
+        for value in [
+            "",
+            "plain value",
+            "$WEBSURFX_TEST_API_KEY",
+            "${}",
+            "${1ABC}",
+            "${OPEN",
+        ] {
+            assert_eq!(resolve_env_vars(value).as_deref(), Ok(value));
+        }
+    }
+}
@@ -1,6 +1,9 @@
 #![allow(missing_docs)]
+use crate::models::engine_models::Category;
+use regex::Regex;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt};
 
 /// Stores configurations related to caching.
 #[derive(Clone, Deserialize, Debug)]
@@ -15,6 +18,404 @@ pub struct Search {
     /// * 3 - High
     /// * 4 - Aggressive
     pub safe_search: u8,
+    /// The safe search level used instead of `safe_search` for the api requests (the requests
+    /// preferring a json response), the api requests use `safe_search` as well when not set.
+    pub api_safe_search: Option<u8>,
+    /// Whether the cached search results are filtered again at the safe search level of each
+    /// search when they are read from the cache, instead of trusting the level at which they were
+    /// cached.
+    pub strict_safe_search: bool,
+    /// The rules rewriting or redirecting the searches whose query contains one of the configured
+    /// terms when the safe search level is high enough.
+    pub safe_search_terms: SafeSearchTerms,
+    /// The native values of the safe search parameter of particular upstream search engines for
+    /// each safe search level, keyed by the name of the engine. The engines without a configured
+    /// mapping use their default values.
+    pub safe_search_mappings: HashMap<String, Vec<String>>,
+    /// The paths of the blocklist files which are merged into a single blocklist, the default
+    /// `blocklist.txt` file is used when no files are configured.
+    pub blocklists: Vec<String>,
+    /// The paths of the allowlist files which are merged into a single allowlist, the default
+    /// `allowlist.txt` file is used when no files are configured.
+    pub allowlists: Vec<String>,
+    /// The paths of the sensitive terms files, the searches whose query matches any of their
+    /// regex patterns are redacted from the logs and excluded from the analytics.
+    pub sensitive_terms: Vec<String>,
+    /// The regex based find and replace rules applied to the search query before it is sent to
+    /// a particular upstream search engine, keyed by the name of the engine.
+    pub query_rewrite_rules: HashMap<String, Vec<QueryRewriteRule>>,
+    /// The custom http headers sent along with the requests to a particular upstream search
+    /// engine, keyed by the name of the engine.
+    pub engine_headers: HashMap<String, EngineHeaders>,
+    /// The search engines which are always queried in addition to the engines selected by the
+    /// user and which can not be disabled by the user.
+    pub forced_engines: Vec<String>,
+    /// The search engines which are never queried, however they were selected, and which take
+    /// precedence over all the other engine options.
+    pub denied_engines: Vec<String>,
+    /// Whether the search query is sent to the upstream search engines with the surrounding
+    /// whitespace trimmed and the internal whitespace collapsed.
+    pub normalize_query: bool,
+    /// Whether the engines enabled by default are queried when the user has not selected any
+    /// engines, instead of showing that no engines were selected.
+    pub fallback_to_default_engines: bool,
+    /// Whether the engine names selected in the cookie or provided in the search url are matched
+    /// regardless of the whitespace and the punctuation within them, and are looked up in the
+    /// engine aliases.
+    pub fuzzy_engine_names: bool,
+    /// The alternative names of the upstream search engines which are resolved to the engine
+    /// they refer to when the fuzzy engine names are enabled, keyed by the alternative name.
+    pub engine_aliases: HashMap<String, String>,
+    /// The weights of the upstream search engines keyed by the name of the engine, the engines
+    /// without a configured weight have a weight of `1.0`.
+    pub engine_weights: HashMap<String, f32>,
+    /// The weights of the upstream search engines for particular categories of search results
+    /// keyed by the category and then by the name of the engine, which are multiplied with the
+    /// weights of the engines when the results of the category are ranked.
+    pub category_engine_weights: HashMap<Category, HashMap<String, f32>>,
+    /// The adjustments of the rank scores of the search results keyed by the domain of the
+    /// results, which apply to the subdomains of the domain as well. The rank score of a result
+    /// is multiplied with the adjustment of its most specific configured domain.
+    pub domain_reputation: HashMap<String, f32>,
+    /// The maximum number of upstream search engines queried for a single search (`0` means no
+    /// limit).
+    pub max_engines_per_search: usize,
+    /// The number of results requested from each upstream search engine which supports
+    /// requesting fewer results than its default page (`0` means the default page).
+    pub results_per_page: u32,
+    /// The caps of the number of results requested from particular upstream search engines,
+    /// keyed by the name of the engine.
+    pub engine_result_limits: HashMap<String, u32>,
+    /// The caps of the rate of the requests sent by the instance to particular upstream search
+    /// engines, keyed by the name of the engine.
+    pub engine_rate_limits: HashMap<String, EngineRateLimit>,
+    /// The ordered tiers of upstream search engines which are only queried when the previous
+    /// tiers found fewer results than the minimum. The selected engines which are not part of any
+    /// tier are always queried first.
+    pub fallback_engine_tiers: Vec<Vec<String>>,
+    /// The minimum number of results which have to be found before no more fallback tiers of
+    /// upstream search engines are queried.
+    pub fallback_min_results: usize,
+    /// The categories for which each upstream search engine is enabled keyed by the name of the
+    /// engine, the engines without configured categories are enabled for all the categories.
+    pub engine_categories: HashMap<String, Vec<Category>>,
+    /// The names of the upstream search engines whose errors are displayed first in the listed
+    /// order, the errors of the remaining engines follow ordered by the engine name.
+    pub engine_priority: Vec<String>,
+    /// The maximum number of the errors of the upstream search engines which are kept along with
+    /// the search results, the remaining errors are only counted (`0` means no limit).
+    pub max_engine_errors: usize,
+    /// How the search results with a plain `http` url are handled.
+    pub http_results: HttpResults,
+    /// How the search results without a description are handled.
+    pub missing_snippets: MissingSnippets,
+    /// How the html of the titles and the descriptions of the search results provided by the
+    /// upstream search engines is sanitized.
+    pub snippet_html: SnippetHtml,
+    /// The options for upgrading the urls of the search results to `https` when their host is on
+    /// the HSTS preload list.
+    pub https_upgrade: HttpsUpgrade,
+    /// The expansions of the words of the search query which are sent to the upstream search
+    /// engines instead of the words, keyed by the lowercased word.
+    pub query_synonyms: HashMap<String, String>,
+    /// The json search api queried by the `Json` upstream search engine.
+    pub json_source: JsonSource,
+    /// The directory of local documents searched by the `Local` upstream search engine.
+    pub local_source: LocalSource,
+}
+
+impl Search {
+    /// A function which checks whether the provided upstream search engine is denied by the
+    /// instance, in which case it is never queried.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine` - It takes the name of the engine as an argument.
+    pub fn is_denied_engine(&self, engine: &str) -> bool {
+        self.denied_engines
+            .iter()
+            .any(|denied| denied.eq_ignore_ascii_case(engine))
+    }
+
+    /// A function which returns the configured weight of the provided upstream search engine.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine` - It takes the name of the engine as an argument.
+    pub fn engine_weight(&self, engine: &str) -> f32 {
+        self.engine_weights
+            .get(&engine.to_lowercase())
+            .copied()
+            .unwrap_or(1.0)
+    }
+
+    /// A function which returns the weight of the provided upstream search engine for the
+    /// provided category of search results, which is the configured weight of the engine
+    /// multiplied with its configured weight for the category.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine` - It takes the name of the engine as an argument.
+    /// * `category` - It takes the category of the search results as an argument.
+    pub fn category_engine_weight(&self, engine: &str, category: Category) -> f32 {
+        let category_weight = self
+            .category_engine_weights
+            .get(&category)
+            .and_then(|weights| weights.get(&engine.to_lowercase()))
+            .copied()
+            .unwrap_or(1.0);
+        self.engine_weight(engine) * category_weight
+    }
+
+    /// Returns the number of results requested from the provided upstream search engine, which
+    /// is the smaller of `results_per_page` and the cap of the engine, or `None` when neither is
+    /// configured so that the engine fetches its default page.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine` - It takes the name of the engine as an argument.
+    pub fn engine_result_count(&self, engine: &str) -> Option<u32> {
+        let limit = self
+            .engine_result_limits
+            .get(&engine.to_lowercase())
+            .copied()
+            .unwrap_or(0);
+        match (self.results_per_page, limit) {
+            (0, 0) => None,
+            (0, count) | (count, 0) => Some(count),
+            (results_per_page, limit) => Some(results_per_page.min(limit)),
+        }
+    }
+
+    /// Returns the configured native values of the safe search parameter of the provided upstream
+    /// search engine for each safe search level, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine` - It takes the name of the engine as an argument.
+    pub fn safe_search_mapping(&self, engine: &str) -> Option<Vec<String>> {
+        self.safe_search_mappings
+            .get(&engine.to_lowercase())
+            .cloned()
+    }
+
+    /// Returns whether the provided upstream search engine is enabled for the provided category.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine` - It takes the name of the engine as an argument.
+    /// * `category` - It takes the category of the search results as an argument.
+    pub fn engine_enabled_for(&self, engine: &str, category: Category) -> bool {
+        self.engine_categories
+            .get(&engine.to_lowercase())
+            .map_or(true, |categories| categories.contains(&category))
+    }
+}
+
+/// Configuration options for the local rate limiting of the requests sent to an upstream search
+/// engine. The searches which would exceed any of the caps skip the engine.
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct EngineRateLimit {
+    /// The maximum number of requests sent to the engine within any second (`0` means no limit).
+    pub per_second: u32,
+    /// The maximum number of requests sent to the engine within any minute (`0` means no limit).
+    pub per_minute: u32,
+}
+
+/// The ways in which the search results with a plain `http` url can be handled.
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HttpResults {
+    /// The `http` results are ranked like any other result.
+    #[default]
+    Allow,
+    /// The `http` results are placed after all the `https` results, keeping their order.
+    Downrank,
+    /// The `http` results are removed from the search results.
+    Filter,
+}
+
+/// The ways in which the search results without a description can be handled.
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingSnippets {
+    /// The results without a description are ranked like any other result.
+    #[default]
+    Allow,
+    /// The results without a description are placed after all the described results, keeping
+    /// their order.
+    Downrank,
+    /// The results without a description are removed from the search results.
+    Filter,
+}
+
+/// The ways in which the html of the titles and the descriptions of the search results can be
+/// sanitized.
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SnippetHtml {
+    /// Every tag is removed, leaving the plain text.
+    Strip,
+    /// The formatting tags (like `b` and `em`) are kept without their attributes, and every other
+    /// tag is removed.
+    #[default]
+    Safe,
+}
+
+/// Configuration options for upgrading the urls of the search results to `https`.
+#[derive(Clone, Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpsUpgrade {
+    /// Whether the `http` urls of the search results whose host is on the HSTS preload list are
+    /// upgraded to `https`.
+    pub enabled: bool,
+    /// The paths of the HSTS preload list files which are used along with the bundled list.
+    pub preload_lists: Vec<String>,
+}
+
+/// Configuration options for the json search api queried by the `Json` upstream search engine,
+/// which allows a search api to be integrated without a dedicated engine implementation.
+#[derive(Clone, Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct JsonSource {
+    /// The url template of the search requests, whose `{query}`, `{page}` and `{safe_search}`
+    /// placeholders are replaced by the values of the search.
+    pub url: String,
+    /// The fields of the json response providing the search results.
+    pub mapping: JsonSourceMapping,
+}
+
+/// The paths of the fields of the json response of the json search api which provide the search
+/// results. A path is made of the keys of the nested objects (or the indices of the nested arrays)
+/// separated by dots, like `data.items` or `links.0.href`, the empty path being the whole value.
+#[derive(Clone, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct JsonSourceMapping {
+    /// The path of the array of the search results within the response.
+    pub results: String,
+    /// The path of the title within each search result.
+    pub title: String,
+    /// The path of the url within each search result.
+    pub url: String,
+    /// The path of the description within each search result.
+    pub description: String,
+}
+
+impl Default for JsonSourceMapping {
+    fn default() -> Self {
+        // The default mapping matches the json format of the searx and searxng instances.
+        JsonSourceMapping {
+            results: "results".to_owned(),
+            title: "title".to_owned(),
+            url: "url".to_owned(),
+            description: "content".to_owned(),
+        }
+    }
+}
+
+/// Configuration options for the directory of local documents searched by the `Local` upstream
+/// search engine, which allows the internal documents (like the documentation of a company) to
+/// be searched along with the web.
+#[derive(Clone, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct LocalSource {
+    /// The path of the directory holding the documents, which is searched along with its
+    /// subdirectories (an empty path disables the engine).
+    pub directory: String,
+    /// The url template of the links to the documents, whose `{path}` placeholder is replaced by
+    /// the path of the document relative to the directory. The documents are linked by their
+    /// `file` url when it is empty.
+    pub url: String,
+    /// The extensions of the files which are searched, without the leading dot.
+    pub extensions: Vec<String>,
+    /// The maximum size of a searched file (in bytes), the larger files are skipped.
+    pub max_file_size: u64,
+    /// The maximum number of files which are searched, the remaining files are skipped.
+    pub max_files: usize,
+}
+
+impl Default for LocalSource {
+    fn default() -> Self {
+        LocalSource {
+            directory: String::new(),
+            url: String::new(),
+            extensions: vec!["md".to_owned(), "txt".to_owned()],
+            max_file_size: 1024 * 1024,
+            max_files: 10000,
+        }
+    }
+}
+
+/// Configuration options for the rules which rewrite or redirect the searches containing the
+/// configured terms at the high safe search levels.
+#[derive(Clone, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct SafeSearchTerms {
+    /// The safe search level from which the rules are applied.
+    pub min_level: u8,
+    /// The rules which are applied in the listed order.
+    pub rules: Vec<SafeSearchTermRule>,
+}
+
+impl Default for SafeSearchTerms {
+    fn default() -> Self {
+        SafeSearchTerms {
+            min_level: 3,
+            rules: Vec::new(),
+        }
+    }
+}
+
+/// A rule which either rewrites the configured term in the search query or redirects the search
+/// when the search query contains the term.
+#[derive(Clone, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SafeSearchTermRule {
+    /// The term (one or more whole words, matched regardless of their case) triggering the rule.
+    pub term: String,
+    /// The replacement for the term in the search query.
+    pub rewrite: Option<String>,
+    /// The url to which the search is redirected.
+    pub redirect: Option<String>,
+    /// The compiled regex pattern matching the term as whole words regardless of their case,
+    /// which is compiled when the config is loaded.
+    #[serde(skip)]
+    pub pattern: Option<Regex>,
+}
+
+impl SafeSearchTermRule {
+    /// A function which compiles the regex pattern matching the provided term as whole words
+    /// regardless of their case, the words being separated by any whitespace.
+    ///
+    /// # Arguments
+    ///
+    /// * `term` - It takes the term of the rule as an argument.
+    ///
+    /// # Returns
+    ///
+    /// Returns the compiled pattern, or `None` when the term has no words.
+    pub fn term_pattern(term: &str) -> Option<Regex> {
+        let words: Vec<String> = term.split_whitespace().map(regex::escape).collect();
+        if words.is_empty() {
+            return None;
+        }
+        Regex::new(&format!(r"(?i)\b{}\b", words.join(r"\s+"))).ok()
+    }
+}
+
+/// A regex based find and replace rule which is applied to the search query.
+#[derive(Clone, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct QueryRewriteRule {
+    /// The regex pattern to find in the search query.
+    pub find: String,
+    /// The replacement for the matched parts of the search query.
+    pub replace: String,
+    /// The compiled regex pattern to find in the search query, which is compiled when the config
+    /// is loaded.
+    #[serde(skip)]
+    pub pattern: Option<Regex>,
 }
 
 impl Default for Search {
@@ -32,6 +433,97 @@ impl Default for Search {
                 map
             },
             safe_search: 2,
+            api_safe_search: None,
+            strict_safe_search: false,
+            safe_search_terms: SafeSearchTerms::default(),
+            safe_search_mappings: HashMap::new(),
+            blocklists: Vec::new(),
+            allowlists: Vec::new(),
+            sensitive_terms: Vec::new(),
+            query_rewrite_rules: HashMap::new(),
+            engine_headers: HashMap::new(),
+            forced_engines: Vec::new(),
+            denied_engines: Vec::new(),
+            normalize_query: false,
+            fallback_to_default_engines: false,
+            fuzzy_engine_names: true,
+            engine_aliases: HashMap::from(
+                [
+                    ("ddg", "duckduckgo"),
+                    ("duck", "duckduckgo"),
+                    ("searxng", "searx"),
+                    ("librey", "librex"),
+                ]
+                .map(|(alias, engine)| (alias.to_owned(), engine.to_owned())),
+            ),
+            engine_weights: HashMap::new(),
+            category_engine_weights: HashMap::new(),
+            domain_reputation: HashMap::new(),
+            max_engines_per_search: 0,
+            results_per_page: 0,
+            engine_result_limits: HashMap::new(),
+            engine_rate_limits: HashMap::new(),
+            fallback_engine_tiers: Vec::new(),
+            fallback_min_results: 10,
+            engine_categories: HashMap::new(),
+            engine_priority: Vec::new(),
+            max_engine_errors: 20,
+            http_results: HttpResults::default(),
+            missing_snippets: MissingSnippets::default(),
+            snippet_html: SnippetHtml::default(),
+            https_upgrade: HttpsUpgrade::default(),
+            query_synonyms: HashMap::new(),
+            json_source: JsonSource::default(),
+            local_source: LocalSource::default(),
         }
     }
 }
+
+/// The custom http headers sent along with the requests to an upstream search engine, keyed by
+/// the header name. The values of the sensitive headers are redacted when debug formatted.
+#[derive(Clone, Deserialize, Default)]
+#[serde(transparent)]
+pub struct EngineHeaders(pub HashMap<String, String>);
+
+impl EngineHeaders {
+    /// A function which converts the headers into a `HeaderMap` while skipping any header with an
+    /// invalid name or value.
+    pub fn header_map(&self) -> HeaderMap {
+        self.0
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((
+                    HeaderName::try_from(name).ok()?,
+                    HeaderValue::try_from(value).ok()?,
+                ))
+            })
+            .collect()
+    }
+}
+
+impl fmt::Debug for EngineHeaders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.0.iter().map(|(name, value)| {
+                if is_sensitive_header(name) {
+                    (name.as_str(), "[redacted]")
+                } else {
+                    (name.as_str(), value.as_str())
+                }
+            }))
+            .finish()
+    }
+}
+
+/// A helper function which checks whether the value of the provided header could contain a secret
+/// like an api key, a token or a cookie.
+///
+/// # Arguments
+///
+/// * `name` - It takes the name of the header as an argument.
+pub fn is_sensitive_header(name: &str) -> bool {
+    let name = name.to_lowercase();
+    ["authorization", "cookie", "key", "token", "secret"]
+        .iter()
+        .any(|sensitive| name.contains(sensitive))
+}
@@ -1,5 +1,6 @@
 #![allow(missing_docs)]
 
+use reqwest::header::HeaderValue;
 use serde::Deserialize;
 use std::num::NonZeroU16;
 
@@ -21,6 +22,33 @@ pub struct Server {
     /// Timeout for the search requests sent to the upstream search engines (in seconds).
     pub request_timeout: u8,
     pub rate_limiter: RateLimiter,
+    /// The maximum number of searches which are handled concurrently by the whole server, the
+    /// searches exceeding it are rejected (`0` means no limit).
+    pub max_concurrent_searches: usize,
+    /// The token which has to be provided as a bearer token to access the admin endpoints. The
+    /// admin endpoints are disabled when no token is set.
+    pub admin_token: Option<String>,
+    pub analytics: Analytics,
+    pub engine_stats: EngineStats,
+    pub batch_search: BatchSearch,
+    /// The custom contents of the robots.txt file served by the website, which replace the
+    /// default contents when set.
+    pub robots_txt: Option<String>,
+    /// The value of the `Cache-Control` header of the html pages of the search results.
+    pub cache_control: String,
+    /// The parsed `Cache-Control` header of the html pages of the search results, which is
+    /// parsed when the config is loaded.
+    #[serde(skip)]
+    pub cache_control_header: HeaderValue,
+    /// Whether the api requests with a page which is not a positive number that can be searched
+    /// are rejected with a `400 Bad Request` response, instead of being clamped like the requests
+    /// of the browsers.
+    pub reject_invalid_pages: bool,
+    pub cookie: CookieAttributes,
+    pub thumbnails: Thumbnails,
+    pub featured_snippet: FeaturedSnippet,
+    pub autocomplete: Autocomplete,
+    pub tls: Tls,
 }
 
 impl Default for Server {
@@ -34,12 +62,26 @@ impl Default for Server {
             aggregator: Aggregator::default(),
             request_timeout: 30,
             rate_limiter: RateLimiter::default(),
+            max_concurrent_searches: 0,
+            admin_token: None,
+            analytics: Analytics::default(),
+            engine_stats: EngineStats::default(),
+            batch_search: BatchSearch::default(),
+            robots_txt: None,
+            cache_control: "no-store".to_string(),
+            cache_control_header: HeaderValue::from_static("no-store"),
+            reject_invalid_pages: true,
+            cookie: CookieAttributes::default(),
+            thumbnails: Thumbnails::default(),
+            featured_snippet: FeaturedSnippet::default(),
+            autocomplete: Autocomplete::default(),
+            tls: Tls::default(),
         }
     }
 }
 
 /// Configuration options for the aggregator.
-#[derive(Clone, Deserialize, Default, Debug)]
+#[derive(Clone, Deserialize, Debug)]
 #[serde(default, deny_unknown_fields)]
 pub struct Aggregator {
     /// Whether to use a random_delay for the aggregator.
@@ -47,11 +89,173 @@ pub struct Aggregator {
     /// This setting will add a random delay before sending the request to the search engines,
     /// this is to prevent DDoSing the upstream search engines from a large number of simultaneous requests.
     pub random_delay: bool,
+    /// The fraction (from 0.0 to 1.0) of the selected upstream search engines that are allowed
+    /// to fail before the aggregated search results are marked as degraded.
+    pub engine_failure_tolerance: f32,
+    /// The scoring function used to rank the aggregated search results.
+    pub ranking: Ranking,
+    /// Whether the search results with the same score are ordered by their url and then by the
+    /// first upstream search engine which returned them, so that identical searches provide the
+    /// search results in the same order.
+    pub stable_ordering: bool,
+    /// Whether the requests to the upstream search engines are dispatched in a shuffled order,
+    /// so that the engines are not always contacted in the same order.
+    pub shuffle_engines: bool,
+    /// Whether the requests to the upstream search engines are dispatched in the ascending order
+    /// of their average latency measured by the engine stats, so that the fastest engines are
+    /// contacted first.
+    pub fastest_engines_first: bool,
+    /// Whether the content type of the responses of the upstream search engines is checked
+    /// against the format expected by each engine, so that the unexpected responses are reported
+    /// as errors instead of as empty search results.
+    pub validate_content_type: bool,
+    /// The minimum version of the TLS protocol required for the connections to the upstream
+    /// search engines, the default of the http client being used when not set.
+    pub min_tls_version: Option<TlsVersion>,
+    /// The retries of the requests to the upstream search engines which failed with a transient
+    /// error.
+    pub retries: EngineRetries,
 }
 
-/// Configuration options for the rate limiter middleware.
+impl Default for Aggregator {
+    fn default() -> Self {
+        Aggregator {
+            random_delay: false,
+            engine_failure_tolerance: 0.5,
+            ranking: Ranking::default(),
+            stable_ordering: true,
+            shuffle_engines: false,
+            fastest_engines_first: false,
+            validate_content_type: true,
+            min_tls_version: None,
+            retries: EngineRetries::default(),
+        }
+    }
+}
+
+/// Configuration options for the retries of the requests to the upstream search engines which
+/// timed out or failed to connect, whose timeout can be escalated on each retry.
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct EngineRetries {
+    /// The maximum number of retries of a request to an engine (`0` means no retries).
+    pub attempts: u8,
+    /// The factor by which the timeout of a request is multiplied on each retry, the first request
+    /// using the request timeout of the server.
+    pub timeout_escalation: f32,
+    /// The maximum timeout of a retry in seconds (`0` means no cap).
+    pub max_timeout: u8,
+    /// The maximum time in seconds spent on all the requests to an engine, including its retries
+    /// (`0` means no deadline).
+    pub deadline: u16,
+}
+
+impl Default for EngineRetries {
+    fn default() -> Self {
+        EngineRetries {
+            attempts: 0,
+            timeout_escalation: 1.0,
+            max_timeout: 0,
+            deadline: 60,
+        }
+    }
+}
+
+/// The scoring functions which can be used to rank the aggregated search results.
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Ranking {
+    /// The score of a result is the sum of the reciprocal of its positions in the results of each
+    /// upstream engine that returned it.
+    #[default]
+    ReciprocalRank,
+    /// The reciprocal rank score of a result is multiplied by the number of upstream engines that
+    /// returned it, so that results on which the engines agree are boosted.
+    Consensus,
+}
+
+/// The versions of the TLS protocol which can be required for the connections to the upstream
+/// search engines.
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+pub enum TlsVersion {
+    /// The TLS 1.2 protocol or a newer one.
+    #[serde(rename = "1.2")]
+    Tls1_2,
+    /// The TLS 1.3 protocol.
+    #[serde(rename = "1.3")]
+    Tls1_3,
+}
+
+impl From<TlsVersion> for reqwest::tls::Version {
+    fn from(version: TlsVersion) -> Self {
+        match version {
+            TlsVersion::Tls1_2 => reqwest::tls::Version::TLS_1_2,
+            TlsVersion::Tls1_3 => reqwest::tls::Version::TLS_1_3,
+        }
+    }
+}
+
+/// Configuration options for the attributes of the settings cookie saved by the website.
 #[derive(Clone, Deserialize, Debug)]
 #[serde(default, deny_unknown_fields)]
+pub struct CookieAttributes {
+    /// The `SameSite` attribute of the cookie.
+    pub same_site: SameSite,
+    /// Whether the cookie is only sent over https. When not set, the cookie is only sent over
+    /// https if the website was accessed over https.
+    pub secure: Option<bool>,
+    /// The lifetime of the cookie (in seconds), a value of `0` makes the cookie expire when the
+    /// browser is closed.
+    pub max_age: u32,
+}
+
+impl Default for CookieAttributes {
+    fn default() -> Self {
+        CookieAttributes {
+            same_site: SameSite::default(),
+            secure: None,
+            max_age: 365 * 24 * 60 * 60,
+        }
+    }
+}
+
+impl CookieAttributes {
+    /// Returns the attributes of the cookie, except for the `Secure` attribute when it depends on
+    /// whether the website was accessed over https, in the format used by the `Set-Cookie` header.
+    pub fn attributes(&self) -> String {
+        let same_site = match self.same_site {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        };
+        let mut attributes = format!("path=/; SameSite={same_site}");
+        if self.max_age != 0 {
+            attributes.push_str(&format!("; Max-Age={}", self.max_age));
+        }
+        if self.secure == Some(true) {
+            attributes.push_str("; Secure");
+        }
+        attributes
+    }
+}
+
+/// The values of the `SameSite` attribute of a cookie.
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SameSite {
+    /// The cookie is only sent along with the requests made from the website itself.
+    Strict,
+    /// The cookie is also sent when navigating to the website from other websites.
+    #[default]
+    Lax,
+    /// The cookie is sent along with all the requests, including the requests made from other
+    /// websites which embed the website. It requires the `Secure` attribute.
+    None,
+}
+
+/// Configuration options for the rate limiter middleware.
+#[derive(Clone, Deserialize, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
 pub struct RateLimiter {
     /// The number of request that are allowed within the provided time limit.
     pub number_of_requests: u8,
@@ -67,3 +271,167 @@ impl Default for RateLimiter {
         }
     }
 }
+
+/// Configuration options for the privacy preserving search analytics.
+#[derive(Clone, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Analytics {
+    /// Whether to count the searched queries and the queried upstream search engines.
+    pub enabled: bool,
+    /// Whether to store the hashes of the searched queries instead of the queries themselves.
+    pub hash_queries: bool,
+    /// The size of the rolling time window over which the searches are counted (in hours).
+    pub window: u16,
+    /// The number of the most searched queries returned by the analytics endpoint.
+    pub top_queries: usize,
+}
+
+impl Default for Analytics {
+    fn default() -> Self {
+        Analytics {
+            enabled: false,
+            hash_queries: false,
+            window: 24,
+            top_queries: 10,
+        }
+    }
+}
+
+/// Configuration options for the success rate statistics of the upstream search engines.
+#[derive(Clone, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct EngineStats {
+    /// Whether to count the outcomes of the requests sent to each upstream search engine.
+    pub enabled: bool,
+    /// The size of the rolling time window over which the outcomes are counted (in hours).
+    pub window: u16,
+}
+
+impl Default for EngineStats {
+    fn default() -> Self {
+        EngineStats {
+            enabled: true,
+            window: 24,
+        }
+    }
+}
+
+/// Configuration options for the batch search endpoint.
+#[derive(Clone, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct BatchSearch {
+    /// The maximum number of queries of a single batch, the endpoint is disabled when it is `0`.
+    pub max_queries: usize,
+    /// The maximum number of queries of a batch which are searched concurrently.
+    pub concurrency: usize,
+}
+
+impl Default for BatchSearch {
+    fn default() -> Self {
+        BatchSearch {
+            max_queries: 10,
+            concurrency: 4,
+        }
+    }
+}
+
+/// Configuration options for the preview thumbnails of the search results.
+#[derive(Clone, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Thumbnails {
+    /// Whether the Open Graph images of the result pages are displayed as the thumbnails of the
+    /// search results.
+    pub enabled: bool,
+    /// The maximum number of the top ranked results of a search for which a thumbnail is looked
+    /// for.
+    pub max_per_search: usize,
+    /// Timeout for the requests sent to fetch the result pages and the thumbnails (in seconds).
+    pub timeout: u8,
+    /// The expiry time of the cached thumbnails of the result pages (in seconds).
+    pub cache_expiry_time: u64,
+    /// The secret from which the key signing the urls of the result pages is derived, a random
+    /// key which changes on every restart is used when not set.
+    pub secret: Option<String>,
+}
+
+impl Default for Thumbnails {
+    fn default() -> Self {
+        Thumbnails {
+            enabled: false,
+            max_per_search: 5,
+            timeout: 3,
+            cache_expiry_time: 86400,
+            secret: None,
+        }
+    }
+}
+
+/// Configuration options for the featured snippet extracted from the page of the top ranked
+/// search result.
+#[derive(Clone, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeaturedSnippet {
+    /// Whether the first meaningful paragraph of the page of the top ranked search result is
+    /// displayed as a featured snippet above the search results.
+    pub enabled: bool,
+    /// The maximum number of characters of the featured snippet, the longer paragraphs are cut
+    /// short at a word boundary.
+    pub max_length: usize,
+    /// Timeout for the request sent to fetch the page of the top ranked search result (in
+    /// seconds).
+    pub timeout: u8,
+    /// The expiry time of the cached featured snippets of the result pages (in seconds).
+    pub cache_expiry_time: u64,
+}
+
+impl Default for FeaturedSnippet {
+    fn default() -> Self {
+        FeaturedSnippet {
+            enabled: false,
+            max_length: 300,
+            timeout: 3,
+            cache_expiry_time: 86400,
+        }
+    }
+}
+
+/// Configuration options for the autocomplete endpoint which suggests the completions of a
+/// partially typed search query.
+#[derive(Clone, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Autocomplete {
+    /// Whether the completions of the partially typed search queries are suggested by the
+    /// `/autocomplete` endpoint.
+    pub enabled: bool,
+    /// Timeout for the request sent to fetch the suggestions from the upstream suggest api (in
+    /// seconds).
+    pub timeout: u8,
+    /// The maximum number of suggestions returned for a partially typed search query.
+    pub max_suggestions: usize,
+    /// The expiry time of the suggestions cached in the cache of the search results (in seconds),
+    /// `0` means that the suggestions are not cached.
+    pub cache_expiry_time: u16,
+}
+
+impl Default for Autocomplete {
+    fn default() -> Self {
+        Autocomplete {
+            enabled: false,
+            timeout: 2,
+            max_suggestions: 8,
+            cache_expiry_time: 300,
+        }
+    }
+}
+
+/// Configuration options for serving the website over https directly.
+#[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Tls {
+    /// Whether the website is served over https instead of http.
+    pub enabled: bool,
+    /// The path to the PEM file containing the certificate chain of the website.
+    pub cert_path: String,
+    /// The path to the PEM file containing the private key of the certificate.
+    pub key_path: String,
+}
@@ -0,0 +1,271 @@
+//! This module provides the functionality to share the parsed config between the workers of the
+//! server and to swap it for a freshly parsed config while the server is running.
+
+use super::Config;
+use std::sync::{Arc, RwLock};
+
+/// A named struct which stores the parsed config shared by all the workers of the server, which
+/// can be swapped atomically for a freshly parsed config.
+#[derive(Debug)]
+pub struct SharedConfig {
+    /// The current config.
+    config: RwLock<Arc<Config>>,
+}
+
+impl SharedConfig {
+    /// Constructs a new `SharedConfig` from the parsed config.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - It takes the parsed config as an argument.
+    pub fn new(config: Config) -> Self {
+        Self {
+            config: RwLock::new(Arc::new(config)),
+        }
+    }
+
+    /// Returns the current config, which holds its generation. The requests which already
+    /// obtained the config keep using it until they complete, even if the config is reloaded in
+    /// the meantime.
+    pub fn get(&self) -> Arc<Config> {
+        self.config
+            .read()
+            .unwrap_or_else(|error| error.into_inner())
+            .clone()
+    }
+
+    /// Swaps the current config for the provided config. The options which only take effect when
+    /// the server is started keep their current values.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - It takes the freshly parsed config as an argument.
+    ///
+    /// # Returns
+    ///
+    /// Returns the names of the options which were changed but which require a restart of the
+    /// server to take effect.
+    pub fn reload(&self, mut config: Config) -> Vec<&'static str> {
+        let mut current = self
+            .config
+            .write()
+            .unwrap_or_else(|error| error.into_inner());
+        let restart_required = keep_restart_only_options(&current, &mut config);
+        config.generation = current.generation + 1;
+        *current = Arc::new(config);
+        restart_required
+    }
+}
+
+/// A named struct which stores a value built lazily from the config, which is built again on its
+/// first use after the config was reloaded.
+#[derive(Debug)]
+pub struct Reloadable<T> {
+    /// The built value along with the generation of the config it was built from.
+    value: RwLock<Option<(u64, Arc<T>)>>,
+}
+
+impl<T> Reloadable<T> {
+    /// Constructs a new `Reloadable` which has not been built yet.
+    pub const fn new() -> Self {
+        Self {
+            value: RwLock::new(None),
+        }
+    }
+
+    /// Returns the built value, building it when it has not been built yet or when it was built
+    /// from another generation of the config. The built value is tagged with the generation of
+    /// the config it is built from, so that a value built from an outdated config by a request
+    /// which obtained the config before it was reloaded is built again by the next request.
+    ///
+    /// # Arguments
+    ///
+    /// * `generation` - It takes the generation of the config the value is built from as an
+    ///   argument.
+    /// * `build` - It takes the function which builds the value from the config as an argument.
+    pub fn get_or_build(&self, generation: u64, build: impl FnOnce() -> T) -> Arc<T> {
+        if let Some((built_generation, value)) =
+            &*self.value.read().unwrap_or_else(|error| error.into_inner())
+        {
+            if *built_generation == generation {
+                return value.clone();
+            }
+        }
+
+        let value = Arc::new(build());
+        let mut built = self
+            .value
+            .write()
+            .unwrap_or_else(|error| error.into_inner());
+        // The value built from a newer config is not replaced by the value built from an older
+        // config for the requests which are still using it.
+        if built.as_ref().map_or(true, |(built_generation, _)| {
+            *built_generation <= generation
+        }) {
+            *built = Some((generation, value.clone()));
+        }
+        value
+    }
+}
+
+impl<T> Default for Reloadable<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A helper function which restores the options of the new config which only take effect when
+/// the server is started (like the address it listens on or the cache backend) to their current
+/// values.
+///
+/// # Arguments
+///
+/// * `current` - It takes the current config as an argument.
+/// * `new` - It takes the freshly parsed config as an argument.
+///
+/// # Returns
+///
+/// Returns the names of the options which were restored.
+fn keep_restart_only_options(current: &Config, new: &mut Config) -> Vec<&'static str> {
+    let mut restored: Vec<&'static str> = Vec::new();
+    keep(
+        "server.logging",
+        &current.server.logging,
+        &mut new.server.logging,
+        &mut restored,
+    );
+    keep(
+        "server.debug",
+        &current.server.debug,
+        &mut new.server.debug,
+        &mut restored,
+    );
+    keep(
+        "server.threads",
+        &current.server.threads,
+        &mut new.server.threads,
+        &mut restored,
+    );
+    keep(
+        "server.port",
+        &current.server.port,
+        &mut new.server.port,
+        &mut restored,
+    );
+    keep(
+        "server.binding_ip",
+        &current.server.binding_ip,
+        &mut new.server.binding_ip,
+        &mut restored,
+    );
+    keep(
+        "server.rate_limiter",
+        &current.server.rate_limiter,
+        &mut new.server.rate_limiter,
+        &mut restored,
+    );
+    keep(
+        "server.tls",
+        &current.server.tls,
+        &mut new.server.tls,
+        &mut restored,
+    );
+
+    keep(
+        "caching.max_cached_results",
+        &current.caching.max_cached_results,
+        &mut new.caching.max_cached_results,
+        &mut restored,
+    );
+    keep(
+        "caching.max_cache_entries",
+        &current.caching.max_cache_entries,
+        &mut new.caching.max_cache_entries,
+        &mut restored,
+    );
+    keep(
+        "caching.max_cache_size",
+        &current.caching.max_cache_size,
+        &mut new.caching.max_cache_size,
+        &mut restored,
+    );
+    // The expiry options are read by every search, but they also set the time for which the
+    // cache keeps the search results when it is created, so they are kept for both to agree.
+    #[cfg(any(feature = "redis-cache", feature = "memory-cache"))]
+    keep(
+        "caching.cache_expiry_time",
+        &current.caching.cache_expiry_time,
+        &mut new.caching.cache_expiry_time,
+        &mut restored,
+    );
+    keep(
+        "caching.high_load_threshold",
+        &current.caching.high_load_threshold,
+        &mut new.caching.high_load_threshold,
+        &mut restored,
+    );
+    keep(
+        "caching.high_load_max_age",
+        &current.caching.high_load_max_age,
+        &mut new.caching.high_load_max_age,
+        &mut restored,
+    );
+    #[cfg(feature = "redis-cache")]
+    keep(
+        "caching.redis_url",
+        &current.caching.redis_url,
+        &mut new.caching.redis_url,
+        &mut restored,
+    );
+    #[cfg(feature = "redis-cache")]
+    keep(
+        "caching.key_prefix",
+        &current.caching.key_prefix,
+        &mut new.caching.key_prefix,
+        &mut restored,
+    );
+
+    restored
+}
+
+/// A helper function which restores an option of the new config to its current value if it
+/// was changed, recording the name of the option.
+///
+/// # Arguments
+///
+/// * `name` - It takes the name of the option as an argument.
+/// * `current` - It takes the current value of the option as an argument.
+/// * `new` - It takes the value of the option in the new config as an argument.
+/// * `restored` - It takes the names of the restored options as an argument.
+fn keep<T: PartialEq + Clone>(
+    name: &'static str,
+    current: &T,
+    new: &mut T,
+    restored: &mut Vec<&'static str>,
+) {
+    if current != new {
+        *new = current.clone();
+        restored.push(name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reloadable_generation() {
+        let shared_config = SharedConfig::new(Config::default());
+        let outdated = shared_config.get();
+        shared_config.reload(Config::default());
+        let current = shared_config.get();
+        assert_eq!((outdated.generation, current.generation), (0, 1));
+
+        let reloadable: Reloadable<u64> = Reloadable::new();
+        assert_eq!(*reloadable.get_or_build(current.generation, || 1), 1);
+        // The value built from the outdated config does not replace the value built from the
+        // current config, which keeps being used.
+        assert_eq!(*reloadable.get_or_build(outdated.generation, || 0), 0);
+        assert_eq!(*reloadable.get_or_build(current.generation, || 2), 1);
+    }
+}
@@ -1,6 +1,7 @@
 #![allow(missing_docs)]
 
 use serde::Deserialize;
+use std::collections::HashMap;
 
 /// Stores configurations related to style of the UI.
 #[derive(Clone, Deserialize, Debug)]
@@ -12,6 +13,223 @@ pub struct Style {
     pub colorscheme: String,
     /// The animation to use for the theme.
     pub animation: Option<String>,
+    /// The path of the custom css file which is linked after the theme on every page, so that
+    /// the operators can override the styles of the theme.
+    pub custom_css: Option<String>,
+    /// The name of the template used to render the search results.
+    pub results_template: String,
+    /// Whether the badges of the upstream engines which provided each search result are displayed.
+    pub engine_badges: bool,
+    /// The format in which the urls of the search results are displayed below their titles.
+    pub display_url: DisplayUrl,
+    /// How the internationalized hosts of the search results are displayed.
+    pub idn_hosts: IdnHosts,
+    /// Whether the terms of the search query are highlighted within the titles and the
+    /// descriptions of the search results.
+    pub highlight_query_terms: bool,
+    /// Whether the `Referer` header is left out when a search result is visited, so that the
+    /// search url (including the query) is not leaked to the visited website.
+    pub no_referrer: bool,
+    pub cached_link: CachedLink,
+    pub click_redirect: ClickRedirect,
+    pub favicons: Favicons,
+    /// The view in which the general search results are displayed.
+    pub results_view: ResultsView,
+    /// The order in which the general search results are presented.
+    pub result_order: ResultOrder,
+    /// The custom explanation displayed when all the queried upstream search engines failed,
+    /// which replaces the default explanation when set.
+    pub outage_message: Option<String>,
+    /// The quick links displayed as buttons on the home page, in the configured order.
+    pub shortcuts: Vec<Shortcut>,
+    /// The tips displayed on the search page when a search did not match any results.
+    pub search_tips: SearchTips,
+    /// The language of the user interface, which is used when the browser does not prefer any of
+    /// the available languages.
+    pub locale: String,
+    /// Whether the language of the user interface is selected from the `Accept-Language` header
+    /// of the browser.
+    pub accept_language: bool,
+    /// The paths of the translation files of the user interface keyed by their language, which
+    /// add languages or replace the strings of the bundled translations.
+    pub translations: HashMap<String, String>,
+}
+
+/// Configuration options for the tips displayed on the search page when a search did not match
+/// any results, which can be customized or translated.
+#[derive(Clone, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchTips {
+    /// The heading displayed above the tips.
+    pub heading: String,
+    /// The tips, in the order in which they are displayed. No tips are displayed when it is empty.
+    pub tips: Vec<String>,
+}
+
+impl Default for SearchTips {
+    fn default() -> Self {
+        SearchTips {
+            heading: "Suggestions:".to_string(),
+            tips: [
+                "Make sure that all words are spelled correctly.",
+                "Try different keywords.",
+                "Try more general keywords.",
+                "Try fewer keywords.",
+                "Try enabling more upstream search engines on the settings page.",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+        }
+    }
+}
+
+/// A quick link to a commonly used destination which is displayed as a button on the home page.
+#[derive(Clone, Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Shortcut {
+    /// The label of the button.
+    pub label: String,
+    /// The `http` or `https` url which the button links to.
+    pub url: String,
+    /// The icon (like an emoji) displayed before the label, if any.
+    pub icon: Option<String>,
+}
+
+/// Configuration options for the links to the cached copies of the search results provided by
+/// an archive service.
+#[derive(Clone, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct CachedLink {
+    /// Whether a link to the cached copy of each search result is displayed.
+    pub enabled: bool,
+    /// The url of the cached copy of a search result on the archive service, in which the `{url}`
+    /// placeholder is replaced with the url of the search result.
+    pub url: String,
+}
+
+impl Default for CachedLink {
+    fn default() -> Self {
+        CachedLink {
+            enabled: false,
+            url: "https://web.archive.org/web/{url}".to_string(),
+        }
+    }
+}
+
+/// Configuration options for the click-through redirect which the links of the search results
+/// point to instead of pointing to the results directly.
+#[derive(Clone, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClickRedirect {
+    /// Whether the links of the search results point to the `/r` redirect route.
+    pub enabled: bool,
+    /// Whether the known tracking parameters are removed from the query string of the visited
+    /// urls.
+    pub strip_tracking_params: bool,
+    /// Whether the plain `http` urls are visited over `https`.
+    pub enforce_https: bool,
+    /// The secret from which the key signing the redirected urls is derived, a random key which
+    /// changes on every restart is used when not set.
+    pub secret: Option<String>,
+}
+
+impl Default for ClickRedirect {
+    fn default() -> Self {
+        ClickRedirect {
+            enabled: false,
+            strip_tracking_params: true,
+            enforce_https: false,
+            secret: None,
+        }
+    }
+}
+
+/// Configuration options for the favicons displayed next to the titles of the search results,
+/// which are fetched and cached by the favicon proxy instead of the browsers of the users.
+#[derive(Clone, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Favicons {
+    /// Whether the favicons of the websites of the search results are displayed.
+    pub enabled: bool,
+    /// Timeout for the requests sent to fetch the favicons (in seconds).
+    pub timeout: u8,
+    /// The expiry time of the cached favicons (in seconds), the websites without a favicon being
+    /// cached as well.
+    pub cache_expiry_time: u64,
+    /// The maximum number of websites whose favicons are kept in the favicon cache.
+    pub max_cached: usize,
+    /// Whether a generic icon is displayed for the websites whose favicon is unavailable,
+    /// nothing being displayed for them otherwise.
+    pub fallback_icon: bool,
+    /// The secret from which the key signing the proxied hosts is derived, a random key which
+    /// changes on every restart is used when not set.
+    pub secret: Option<String>,
+}
+
+impl Default for Favicons {
+    fn default() -> Self {
+        Favicons {
+            enabled: false,
+            timeout: 3,
+            cache_expiry_time: 86400,
+            max_cached: 1000,
+            fallback_icon: true,
+            secret: None,
+        }
+    }
+}
+
+/// The formats in which the urls of the search results can be displayed. The links of the
+/// search results always point to the full urls.
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayUrl {
+    /// The full url is displayed as is.
+    #[default]
+    Full,
+    /// The host followed by the segments of the path, like `example.com › docs › page`.
+    Breadcrumb,
+    /// The host followed by the path truncated to a fixed length, like `example.com/docs/pa…`.
+    Short,
+}
+
+/// The ways in which the internationalized (non-ASCII) hosts of the search results can be
+/// displayed, as such hosts can be crafted to look like the hosts of other websites.
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum IdnHosts {
+    /// The hosts are displayed in their unicode form, like `bücher.example`.
+    Unicode,
+    /// The hosts are displayed in their punycode form, like `xn--bcher-kva.example`.
+    Punycode,
+    /// The hosts are displayed in their unicode form, except for the suspicious hosts mixing the
+    /// letters of different scripts which are displayed in their punycode form with a warning.
+    #[default]
+    Annotate,
+}
+
+/// The views in which the general search results can be displayed.
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultsView {
+    /// The results of all the upstream engines are merged into a single ranked list.
+    #[default]
+    Merged,
+    /// The results are grouped by the upstream engine which provided them, with a tab for each
+    /// engine showing its results in the order in which it returned them.
+    Engines,
+}
+
+/// The orders in which the general search results can be presented.
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultOrder {
+    /// The results are presented in the order of their rank scores.
+    #[default]
+    Ranked,
+    /// The results of the upstream engines are interleaved in a round robin, the first result of
+    /// each engine being presented before the second result of any engine and so on.
+    Interleave,
 }
 
 impl Default for Style {
@@ -20,6 +238,24 @@ impl Default for Style {
             theme: "simple".to_string(),
             colorscheme: "catppuccin-mocha".to_string(),
             animation: Some("simple-frosted-glow".to_string()),
+            custom_css: None,
+            results_template: "default".to_string(),
+            engine_badges: true,
+            display_url: DisplayUrl::default(),
+            idn_hosts: IdnHosts::default(),
+            highlight_query_terms: false,
+            no_referrer: true,
+            cached_link: CachedLink::default(),
+            click_redirect: ClickRedirect::default(),
+            favicons: Favicons::default(),
+            results_view: ResultsView::default(),
+            result_order: ResultOrder::default(),
+            outage_message: None,
+            shortcuts: Vec::new(),
+            search_tips: SearchTips::default(),
+            locale: "en".to_string(),
+            accept_language: true,
+            translations: HashMap::new(),
         }
     }
 }
@@ -7,21 +7,53 @@ use std::collections::HashMap;
 use regex::Regex;
 use reqwest::header::HeaderMap;
 use reqwest::Client;
-use scraper::Html;
+use scraper::{ElementRef, Html, Selector};
 
-use crate::models::aggregation_models::SearchResult;
+use crate::models::aggregation_models::{AnswerContent, SearchResult};
 
-use crate::models::engine_models::{EngineError, SearchEngine};
+use crate::models::engine_models::{
+    page_param, provide_answer, result_count, EngineCapabilities, EngineError, Pagination,
+    SearchEngine,
+};
 
 use error_stack::{Report, Result, ResultExt};
 
-use super::search_result_parser::SearchResultParser;
+use super::search_result_parser::{new_selector, parse_relative_time, SearchResultParser};
 
 /// A new Bing engine type defined in-order to implement the `SearchEngine` trait which allows to
 /// reduce code duplication as well as allows to create vector of different search engines easily.
 pub struct Bing {
     /// The parser, used to interpret the search result.
     parser: SearchResultParser,
+    /// The parser, used to interpret the news results of the news vertical.
+    news_parser: SearchResultParser,
+    /// The selector to locate the relative publish time of a news result.
+    news_time: Selector,
+    /// The parser, used to interpret the answer boxes displayed above the search results.
+    answer_parser: AnswerParser,
+}
+
+/// A named struct which stores the selectors locating the parts of the dictionary and the weather
+/// answer boxes of bing.
+struct AnswerParser {
+    /// The selector to locate the dictionary answer box.
+    definition: Selector,
+    /// The selector to locate the defined word relative to the dictionary answer box.
+    definition_word: Selector,
+    /// The selector to locate the part of speech relative to the dictionary answer box.
+    definition_part_of_speech: Selector,
+    /// The selector to locate the first definition relative to the dictionary answer box.
+    definition_text: Selector,
+    /// The selector to locate the weather answer box.
+    weather: Selector,
+    /// The selector to locate the location relative to the weather answer box.
+    weather_location: Selector,
+    /// The selector to locate the current temperature relative to the weather answer box.
+    weather_temperature: Selector,
+    /// The selector to locate the current temperature unit relative to the weather answer box.
+    weather_unit: Selector,
+    /// The selector to locate the current weather condition relative to the weather answer box.
+    weather_condition: Selector,
 }
 
 impl Bing {
@@ -35,35 +67,64 @@ impl Bing {
                 ".tpcn a.tilk",
                 ".b_caption p",
             )?,
+            news_parser: SearchResultParser::new(
+                ".b_no",
+                ".news-card",
+                "a.title",
+                "a.title",
+                ".snippet",
+            )?,
+            news_time: Selector::parse(".source span[aria-label]")
+                .map_err(|_| Report::new(EngineError::UnexpectedError))?,
+            answer_parser: AnswerParser {
+                definition: new_selector(".b_ans .dc_mn")?,
+                definition_word: new_selector(".dc_hd")?,
+                definition_part_of_speech: new_selector(".dc_pos")?,
+                definition_text: new_selector(".dc_dfn")?,
+                weather: new_selector(".b_ans .wtr_core")?,
+                weather_location: new_selector(".wtr_locTitle")?,
+                weather_temperature: new_selector(".wtr_currTemp")?,
+                weather_unit: new_selector(".wtr_currUnit")?,
+                weather_condition: new_selector(".wtr_caption")?,
+            },
         })
     }
-}
 
-#[async_trait::async_trait]
-impl SearchEngine for Bing {
-    async fn results(
-        &self,
-        query: &str,
-        page: u32,
-        user_agent: &str,
-        client: &Client,
-        _safe_search: u8,
-    ) -> Result<HashMap<String, SearchResult>, EngineError> {
-        // Bing uses `start results from this number` convention
-        // So, for 10 results per page, page 0 starts at 1, page 1
-        // starts at 11, and so on.
-        let results_per_page = 10;
-        let start_result = results_per_page * page + 1;
-
-        let url: String = match page {
-            0 => {
-                format!("https://www.bing.com/search?q={query}")
-            }
-            _ => {
-                format!("https://www.bing.com/search?q={query}&first={start_result}")
-            }
-        };
+    /// Parses the dictionary and the weather answer boxes displayed above the search results,
+    /// the answer boxes missing any of their required parts are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - It takes the parsed html page of the search results as an argument.
+    pub fn answers(&self, document: &Html) -> Vec<AnswerContent> {
+        let parser = &self.answer_parser;
+        let definitions = document.select(&parser.definition).filter_map(|answer| {
+            Some(AnswerContent::Definition {
+                word: text_of(answer, &parser.definition_word)?,
+                part_of_speech: text_of(answer, &parser.definition_part_of_speech),
+                definition: text_of(answer, &parser.definition_text)?,
+            })
+        });
+        let weather = document.select(&parser.weather).filter_map(|answer| {
+            Some(AnswerContent::Weather {
+                location: text_of(answer, &parser.weather_location)?,
+                temperature: format!(
+                    "{}{}",
+                    text_of(answer, &parser.weather_temperature)?,
+                    text_of(answer, &parser.weather_unit).unwrap_or_default()
+                ),
+                condition: text_of(answer, &parser.weather_condition)?,
+            })
+        });
+        definitions.chain(weather).collect()
+    }
 
+    /// Builds the headers sent along with the requests to bing.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_agent` - It takes a random user agent string as an argument.
+    fn header_map(user_agent: &str) -> Result<HeaderMap, EngineError> {
         let query_params: Vec<(&str, &str)> = vec![
             ("_EDGE_V", "1"),
             ("SRCHD=AF", "NOFORM"),
@@ -78,7 +139,7 @@ impl SearchEngine for Bing {
             cookie_string.push_str(&format!("{k}={v}; "));
         }
 
-        let header_map = HeaderMap::try_from(&HashMap::from([
+        HeaderMap::try_from(&HashMap::from([
             ("USER_AGENT".to_string(), user_agent.to_string()),
             ("REFERER".to_string(), "https://google.com/".to_string()),
             (
@@ -87,7 +148,42 @@ impl SearchEngine for Bing {
             ),
             ("COOKIE".to_string(), cookie_string),
         ]))
-        .change_context(EngineError::UnexpectedError)?;
+        .change_context(EngineError::UnexpectedError)
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchEngine for Bing {
+    async fn results(
+        &self,
+        query: &str,
+        page: u32,
+        user_agent: &str,
+        client: &Client,
+        _safe_search: u8,
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        // Bing uses `start results from this number` convention
+        // So, for 10 results per page, page 0 starts at 1, page 1
+        // starts at 11, and so on.
+        let results_per_page = result_count(10);
+        let start_result = page_param(self.pagination(), page).unwrap_or_default();
+        // The `count` parameter is only sent when fewer or more results than the default page
+        // are requested.
+        let count = match results_per_page {
+            10 => String::new(),
+            count => format!("&count={count}"),
+        };
+
+        let url: String = match page {
+            0 => {
+                format!("https://www.bing.com/search?q={query}{count}")
+            }
+            _ => {
+                format!("https://www.bing.com/search?q={query}&first={start_result}{count}")
+            }
+        };
+
+        let header_map = Bing::header_map(user_agent)?;
 
         let document: Html = Html::parse_document(
             &Bing::fetch_html_from_upstream(self, &url, header_map, client).await?,
@@ -107,6 +203,11 @@ impl SearchEngine for Bing {
             }
         }
 
+        // The answer boxes are only displayed above the first page of the search results.
+        if page == 0 {
+            self.answers(&document).into_iter().for_each(provide_answer);
+        }
+
         let re_span = Regex::new(r#"<span.*?>.*?(?:</span>&nbsp;·|</span>)"#).unwrap();
         let re_strong = Regex::new(r#"(<strong>|</strong>)"#).unwrap();
 
@@ -121,4 +222,88 @@ impl SearchEngine for Bing {
                 ))
             })
     }
+
+    async fn news_results(
+        &self,
+        query: &str,
+        page: u32,
+        user_agent: &str,
+        client: &Client,
+        _safe_search: u8,
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        // The news vertical follows the same `start results from this number` convention.
+        let start_result = 10 * page + 1;
+        let url = format!("https://www.bing.com/news/search?q={query}&first={start_result}");
+
+        let document: Html = Html::parse_document(
+            &Bing::fetch_html_from_upstream(self, &url, Bing::header_map(user_agent)?, client)
+                .await?,
+        );
+
+        if self
+            .news_parser
+            .parse_for_no_results(&document)
+            .next()
+            .is_some()
+        {
+            return Err(Report::new(EngineError::EmptyResultSet));
+        }
+
+        // scrape all the news results along with their publisher and publish time from the html
+        self.news_parser
+            .parse_for_results_with_item(&document, |item, title, url, desc| {
+                let published = item.select(&self.news_time).next().and_then(|time| {
+                    parse_relative_time(
+                        time.value()
+                            .attr("aria-label")
+                            .unwrap_or(&time.text().collect::<String>()),
+                    )
+                });
+                url.value().attr("href").map(|url| {
+                    SearchResult::new_news(
+                        title.text().collect::<String>().trim(),
+                        url,
+                        desc.text().collect::<String>().trim(),
+                        &["bing"],
+                        item.value().attr("data-author"),
+                        published,
+                    )
+                })
+            })
+    }
+
+    fn pagination(&self) -> Pagination {
+        Pagination::Offset {
+            first: 1,
+            per_page: 10,
+        }
+    }
+
+    fn capabilities(&self) -> EngineCapabilities {
+        EngineCapabilities {
+            pagination: true,
+            categories: true,
+            result_count: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// A helper function which provides the whitespace normalized text of the first element matching
+/// the selector within the provided element, or `None` when there is no such element or when its
+/// text is empty.
+///
+/// # Arguments
+///
+/// * `element` - It takes the element to search within as an argument.
+/// * `selector` - It takes the selector of the element whose text is provided as an argument.
+fn text_of(element: ElementRef<'_>, selector: &Selector) -> Option<String> {
+    let text = element
+        .select(selector)
+        .next()?
+        .text()
+        .collect::<Vec<&str>>()
+        .join(" ");
+    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    (!text.is_empty()).then_some(text)
 }
@@ -5,19 +5,31 @@
 use std::collections::HashMap;
 
 use reqwest::{header::HeaderMap, Client};
-use scraper::Html;
+use scraper::{Html, Selector};
 
 use crate::models::aggregation_models::SearchResult;
 use error_stack::{Report, Result, ResultExt};
 
-use crate::models::engine_models::{EngineError, SearchEngine};
+use crate::models::engine_models::{
+    safe_search_value, EngineCapabilities, EngineError, SearchEngine, SAFE_SEARCH_LEVELS,
+};
 
-use super::search_result_parser::SearchResultParser;
+use super::search_result_parser::{parse_relative_time, SearchResultParser};
+
+/// The default values of the `safe_search` cookie of brave for each safe search level.
+const DEFAULT_SAFE_SEARCH: [&str; SAFE_SEARCH_LEVELS] =
+    ["off", "moderate", "strict", "strict", "strict"];
 
 /// Scrapes the results from the Brave search engine.  
 pub struct Brave {
     /// Utilises generic logic for parsing search results.
     parser: SearchResultParser,
+    /// Utilises generic logic for parsing the news results of the news vertical.
+    news_parser: SearchResultParser,
+    /// The selector to locate the publisher of a news result.
+    news_source: Selector,
+    /// The selector to locate the relative publish time of a news result.
+    news_time: Selector,
 }
 
 impl Brave {
@@ -31,29 +43,30 @@ impl Brave {
                 "a",
                 ".snippet-description",
             )?,
+            news_parser: SearchResultParser::new(
+                "#results h4",
+                "#results .snippet[data-type='news']",
+                ".snippet-title",
+                "a.result-header",
+                ".snippet-description",
+            )?,
+            news_source: Selector::parse(".netloc")
+                .map_err(|_| Report::new(EngineError::UnexpectedError))?,
+            news_time: Selector::parse(".snippet-url > span:last-child")
+                .map_err(|_| Report::new(EngineError::UnexpectedError))?,
         })
     }
-}
-
-#[async_trait::async_trait]
-impl SearchEngine for Brave {
-    async fn results(
-        &self,
-        query: &str,
-        page: u32,
-        user_agent: &str,
-        client: &Client,
-        safe_search: u8,
-    ) -> Result<HashMap<String, SearchResult>, EngineError> {
-        let url = format!("https://search.brave.com/search?q={query}&offset={page}");
 
-        let safe_search_level = match safe_search {
-            0 => "off",
-            1 => "moderate",
-            _ => "strict",
-        };
+    /// Builds the headers sent along with the requests to brave.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_agent` - It takes a random user agent string as an argument.
+    /// * `safe_search` - It takes the safe search level as an argument.
+    fn header_map(user_agent: &str, safe_search: u8) -> Result<HeaderMap, EngineError> {
+        let safe_search_level = safe_search_value(safe_search, DEFAULT_SAFE_SEARCH);
 
-        let header_map = HeaderMap::try_from(&HashMap::from([
+        HeaderMap::try_from(&HashMap::from([
             ("USER_AGENT".to_string(), user_agent.to_string()),
             (
                 "CONTENT_TYPE".to_string(),
@@ -65,19 +78,48 @@ impl SearchEngine for Brave {
                 format!("safe_search={safe_search_level}"),
             ),
         ]))
-        .change_context(EngineError::UnexpectedError)?;
+        .change_context(EngineError::UnexpectedError)
+    }
+
+    /// Checks whether brave reported that no results were found for the search.
+    ///
+    /// # Arguments
+    ///
+    /// * `parser` - It takes the parser of the searched vertical as an argument.
+    /// * `document` - It takes the parsed html document as an argument.
+    fn no_results(parser: &SearchResultParser, document: &Html) -> bool {
+        parser
+            .parse_for_no_results(document)
+            .nth(0)
+            .map(|no_result_msg| {
+                no_result_msg
+                    .inner_html()
+                    .contains("Not many great matches came back for your search")
+            })
+            .unwrap_or(false)
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchEngine for Brave {
+    async fn results(
+        &self,
+        query: &str,
+        page: u32,
+        user_agent: &str,
+        client: &Client,
+        safe_search: u8,
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        let url = format!("https://search.brave.com/search?q={query}&offset={page}");
+
+        let header_map = Brave::header_map(user_agent, safe_search)?;
 
         let document: Html = Html::parse_document(
             &Brave::fetch_html_from_upstream(self, &url, header_map, client).await?,
         );
 
-        if let Some(no_result_msg) = self.parser.parse_for_no_results(&document).nth(0) {
-            if no_result_msg
-                .inner_html()
-                .contains("Not many great matches came back for your search")
-            {
-                return Err(Report::new(EngineError::EmptyResultSet));
-            }
+        if Brave::no_results(&self.parser, &document) {
+            return Err(Report::new(EngineError::EmptyResultSet));
         }
 
         self.parser
@@ -92,4 +134,60 @@ impl SearchEngine for Brave {
                 })
             })
     }
+
+    async fn news_results(
+        &self,
+        query: &str,
+        page: u32,
+        user_agent: &str,
+        client: &Client,
+        safe_search: u8,
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        let url = format!("https://search.brave.com/news?q={query}&offset={page}");
+
+        let header_map = Brave::header_map(user_agent, safe_search)?;
+
+        let document: Html = Html::parse_document(
+            &Brave::fetch_html_from_upstream(self, &url, header_map, client).await?,
+        );
+
+        if Brave::no_results(&self.news_parser, &document) {
+            return Err(Report::new(EngineError::EmptyResultSet));
+        }
+
+        self.news_parser
+            .parse_for_results_with_item(&document, |item, title, url, desc| {
+                let source = item
+                    .select(&self.news_source)
+                    .next()
+                    .map(|source| source.text().collect::<String>());
+                let published = item
+                    .select(&self.news_time)
+                    .next()
+                    .and_then(|time| parse_relative_time(&time.text().collect::<String>()));
+                url.value().attr("href").map(|url| {
+                    SearchResult::new_news(
+                        title.text().collect::<Vec<_>>().join("").trim(),
+                        url.trim(),
+                        desc.inner_html().trim(),
+                        &["brave"],
+                        source.as_deref().map(str::trim),
+                        published,
+                    )
+                })
+            })
+    }
+
+    fn safe_search_values(&self) -> Option<[&'static str; SAFE_SEARCH_LEVELS]> {
+        Some(DEFAULT_SAFE_SEARCH)
+    }
+
+    fn capabilities(&self) -> EngineCapabilities {
+        EngineCapabilities {
+            pagination: true,
+            safe_search: true,
+            categories: true,
+            ..Default::default()
+        }
+    }
 }
@@ -10,7 +10,7 @@ use scraper::Html;
 
 use crate::models::aggregation_models::SearchResult;
 
-use crate::models::engine_models::{EngineError, SearchEngine};
+use crate::models::engine_models::{page_param, EngineError, Pagination, SearchEngine};
 
 use error_stack::{Report, Result, ResultExt};
 
@@ -47,7 +47,7 @@ impl SearchEngine for DuckDuckGo {
         user_agent: &str,
         client: &Client,
         _safe_search: u8,
-    ) -> Result<HashMap<String, SearchResult>, EngineError> {
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
         // Page number can be missing or empty string and so appropriate handling is required
         // so that upstream server recieves valid page number.
         let url: String = match page {
@@ -55,10 +55,11 @@ impl SearchEngine for DuckDuckGo {
                 format!("https://html.duckduckgo.com/html/?q={query}&s=&dc=&v=1&o=json&api=/d.js")
             }
             _ => {
+                let offset = page_param(self.pagination(), page).unwrap_or_default();
+                // The `dc` parameter is the number of the first result of the page.
+                let first_result = offset.parse::<u32>().unwrap_or_default().saturating_add(1);
                 format!(
-                    "https://duckduckgo.com/html/?q={query}&s={}&dc={}&v=1&o=json&api=/d.js",
-                    page * 30,
-                    page * 30 + 1
+                    "https://duckduckgo.com/html/?q={query}&s={offset}&dc={first_result}&v=1&o=json&api=/d.js"
                 )
             }
         };
@@ -94,4 +95,11 @@ impl SearchEngine for DuckDuckGo {
                 ))
             })
     }
+
+    fn pagination(&self) -> Pagination {
+        Pagination::Offset {
+            first: 0,
+            per_page: 30,
+        }
+    }
 }
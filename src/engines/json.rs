@@ -0,0 +1,262 @@
+//! The `json` module handles the fetching of results from a json search api configured in the
+//! config file (like a self-hosted searx instance with the json format enabled) by querying the
+//! configured url with the user provided query and mapping the configured fields of the json
+//! response to the search results.
+
+use std::{collections::HashMap, fmt::Write};
+
+use maud::Escaper;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use reqwest::{header::HeaderMap, Client};
+use serde_json::Value;
+
+use crate::config::search::JsonSourceMapping;
+use crate::models::aggregation_models::SearchResult;
+use crate::models::engine_models::{
+    safe_search_value, translate_page, EngineCapabilities, EngineError, Pagination, ResponseFormat,
+    SearchEngine, JSON_SOURCE, SAFE_SEARCH_LEVELS,
+};
+use error_stack::{Report, Result, ResultExt};
+
+/// The default values of the `{safe_search}` placeholder for each safe search level, which are
+/// the levels themselves.
+const DEFAULT_SAFE_SEARCH: [&str; SAFE_SEARCH_LEVELS] = ["0", "1", "2", "3", "4"];
+
+/// The pagination scheme of the json search apis, whose pages are numbered from `1`.
+const PAGINATION: Pagination = Pagination::Page { first: 1 };
+
+/// A new Json engine type defined in-order to implement the `SearchEngine` trait which allows to
+/// reduce code duplication as well as allows to create vector of different search engines easily.
+pub struct Json;
+
+impl Json {
+    /// Creates the Json engine, which reads the search api to query from the config.
+    pub fn new() -> Result<Json, EngineError> {
+        Ok(Self)
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchEngine for Json {
+    async fn results(
+        &self,
+        query: &str,
+        page: u32,
+        user_agent: &str,
+        client: &Client,
+        safe_search: u8,
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        let Some(source) = JSON_SOURCE
+            .try_with(|source| source.clone())
+            .ok()
+            .flatten()
+            .filter(|source| !source.url.is_empty())
+        else {
+            return Err(Report::new(EngineError::UnexpectedError)
+                .attach_printable("no json search api is configured"));
+        };
+
+        let safe_search = safe_search_value(safe_search, DEFAULT_SAFE_SEARCH);
+        let url = source_url(&source.url, query, page, &safe_search);
+
+        // initializing headers and adding appropriate headers, the authentication headers of the
+        // search api are provided by the custom headers of the engine.
+        let header_map = HeaderMap::try_from(&HashMap::from([(
+            "USER_AGENT".to_string(),
+            user_agent.to_string(),
+        )]))
+        .change_context(EngineError::UnexpectedError)?;
+
+        let response = self
+            .fetch_json_as_bytes_from_upstream(&url, header_map, client)
+            .await?;
+        let results = parse_results(&response, &source.mapping)?;
+        if results.is_empty() {
+            return Err(Report::new(EngineError::EmptyResultSet));
+        }
+        Ok(results)
+    }
+
+    fn response_format(&self) -> ResponseFormat {
+        ResponseFormat::Json
+    }
+
+    fn pagination(&self) -> Pagination {
+        PAGINATION
+    }
+
+    fn safe_search_values(&self) -> Option<[&'static str; SAFE_SEARCH_LEVELS]> {
+        Some(DEFAULT_SAFE_SEARCH)
+    }
+
+    fn capabilities(&self) -> EngineCapabilities {
+        EngineCapabilities {
+            pagination: true,
+            safe_search: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// A function which builds the url of a search request from the url template of the json search
+/// api, replacing the `{query}` placeholder by the url encoded query, the `{page}` placeholder by
+/// the page number starting from `1` and the `{safe_search}` placeholder by the safe search value.
+///
+/// # Arguments
+///
+/// * `template` - It takes the url template of the json search api as an argument.
+/// * `query` - It takes the search query as an argument.
+/// * `page` - It takes the page of the search starting from `0` as an argument.
+/// * `safe_search` - It takes the native safe search value of the search api as an argument.
+pub fn source_url(template: &str, query: &str, page: u32, safe_search: &str) -> String {
+    template
+        .replace(
+            "{query}",
+            &utf8_percent_encode(query, NON_ALPHANUMERIC).to_string(),
+        )
+        .replace(
+            "{page}",
+            &translate_page(PAGINATION, page, None, None).unwrap_or_default(),
+        )
+        .replace(
+            "{safe_search}",
+            &utf8_percent_encode(safe_search, NON_ALPHANUMERIC).to_string(),
+        )
+}
+
+/// A function which parses the json response of the json search api into the search results as
+/// described by the configured mapping. The search results without a title or an `http` or
+/// `https` url are skipped, while a missing description is left empty. The text of the fields is
+/// escaped as the search results are expected to hold html.
+///
+/// # Arguments
+///
+/// * `response` - It takes the body of the json response as an argument.
+/// * `mapping` - It takes the paths of the fields providing the search results as an argument.
+///
+/// # Error
+///
+/// Returns an `EngineError::UnexpectedResponseFormat` error if the response is not valid json or
+/// if the configured path of the search results does not lead to an array.
+fn parse_results(
+    response: &[u8],
+    mapping: &JsonSourceMapping,
+) -> Result<Vec<(String, SearchResult)>, EngineError> {
+    let response: Value = serde_json::from_slice(response)
+        .change_context(EngineError::UnexpectedResponseFormat)
+        .attach_printable("the response of the json search api is not valid json")?;
+    let Some(results) = lookup(&response, &mapping.results).and_then(Value::as_array) else {
+        return Err(
+            Report::new(EngineError::UnexpectedResponseFormat).attach_printable(format!(
+                "the response of the json search api has no array of results at `{}`",
+                mapping.results
+            )),
+        );
+    };
+
+    Ok(results
+        .iter()
+        .filter_map(|result| {
+            let field = |path: &str| lookup(result, path).and_then(Value::as_str);
+            let (title, url) = (field(&mapping.title)?, field(&mapping.url)?);
+            if !(url.starts_with("https://") || url.starts_with("http://")) {
+                return None;
+            }
+            let description = field(&mapping.description).unwrap_or_default();
+            Some(SearchResult::new(
+                &escape(title.trim()),
+                url,
+                &escape(description.trim()),
+                &["json"],
+            ))
+        })
+        .map(|result| (result.url.clone(), result))
+        .collect())
+}
+
+/// A helper function which looks up the value at the provided path within a json value, the
+/// segments of the path being the keys of the nested objects or the indices of the nested arrays.
+///
+/// # Arguments
+///
+/// * `value` - It takes the json value as an argument.
+/// * `path` - It takes the path separated by dots as an argument, the empty path being the value
+///   itself.
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(value, |value, segment| match value {
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => value.get(segment),
+        })
+}
+
+/// A helper function which escapes the html special characters of a text provided by the json
+/// search api.
+///
+/// # Arguments
+///
+/// * `text` - It takes the text to be escaped as an argument.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let _ = Escaper::new(&mut escaped).write_str(text);
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_source() {
+        assert_eq!(
+            source_url(
+                "https://api.example.com/search?q={query}&page={page}&safe={safe_search}",
+                "rust & go",
+                1,
+                "strict"
+            ),
+            "https://api.example.com/search?q=rust%20%26%20go&page=2&safe=strict"
+        );
+
+        let mapping = JsonSourceMapping {
+            results: "data.items".to_owned(),
+            title: "name".to_owned(),
+            url: "links.0.href".to_owned(),
+            description: "snippet".to_owned(),
+        };
+        let response = br#"{"data": {"items": [
+            {"name": "Rust <3", "links": [{"href": "https://www.rust-lang.org/"}], "snippet": "A language."},
+            {"name": "No snippet", "links": [{"href": "https://example.com/"}]},
+            {"name": "No url", "snippet": "Skipped."},
+            {"name": "Script", "links": [{"href": "javascript:alert(1)"}]}
+        ]}}"#;
+        let results = parse_results(response, &mapping).unwrap();
+        let results: Vec<(&str, &str, &str)> = results
+            .iter()
+            .map(|(_, result)| {
+                (
+                    result.title.as_str(),
+                    result.url.as_str(),
+                    result.description.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                ("Rust &lt;3", "https://www.rust-lang.org/", "A language."),
+                ("No snippet", "https://example.com/", ""),
+            ]
+        );
+
+        // The malformed responses and the responses without an array of results are parse errors.
+        assert!(parse_results(b"<html></html>", &mapping).is_err());
+        assert!(parse_results(br#"{"data": {"items": {}}}"#, &mapping).is_err());
+        assert!(
+            parse_results(br#"{"results": []}"#, &JsonSourceMapping::default())
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
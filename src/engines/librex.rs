@@ -8,7 +8,7 @@ use reqwest::Client;
 use scraper::Html;
 
 use crate::models::aggregation_models::SearchResult;
-use crate::models::engine_models::{EngineError, SearchEngine};
+use crate::models::engine_models::{page_param, EngineError, Pagination, SearchEngine};
 
 use error_stack::{Report, Result, ResultExt};
 
@@ -53,7 +53,7 @@ impl SearchEngine for LibreX {
     ///
     /// # Returns
     ///
-    /// Returns a `Result` containing a `HashMap` of search results if successful, otherwise an `EngineError`.
+    /// Returns a `Result` containing a `Vec` of ranked search results if successful, otherwise an `EngineError`.
     /// The `Err` variant is explicit for better documentation.
     async fn results(
         &self,
@@ -62,12 +62,12 @@ impl SearchEngine for LibreX {
         user_agent: &str,
         client: &Client,
         _safe_search: u8,
-    ) -> Result<HashMap<String, SearchResult>, EngineError> {
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
         // Page number can be missing or empty string and so appropriate handling is required
         // so that upstream server recieves valid page number.
         let url: String = format!(
             "https://search.ahwx.org/search.php?q={query}&p={}&t=10",
-            page * 10
+            page_param(self.pagination(), page).unwrap_or_default()
         );
 
         // initializing HeaderMap and adding appropriate headers.
@@ -101,4 +101,11 @@ impl SearchEngine for LibreX {
                 ))
             })
     }
+
+    fn pagination(&self) -> Pagination {
+        Pagination::Offset {
+            first: 0,
+            per_page: 10,
+        }
+    }
 }
@@ -0,0 +1,533 @@
+//! The `local` module handles the searching of a directory of local documents configured in the
+//! config file (like the markdown documentation of a company), so that the internal documents are
+//! searched along with the web and their results are merged like the results of any other
+//! upstream search engine.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    fs::{read_dir, read_to_string, DirEntry},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use maud::Escaper;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use regex::{Regex, RegexBuilder};
+use reqwest::{Client, Url};
+
+use crate::config::{search::LocalSource, shared::Reloadable};
+use crate::models::aggregation_models::SearchResult;
+use crate::models::engine_models::{EngineCapabilities, EngineError, SearchEngine, LOCAL_SOURCE};
+use error_stack::{Report, Result, ResultExt};
+
+/// The characters which are encoded in the components of the paths of the documents within their
+/// url, which are the characters not allowed unencoded in a path segment.
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// The number of documents provided on each page of the results.
+const PER_PAGE: usize = 10;
+
+/// The maximum number of characters of the description of a document.
+const SNIPPET_LENGTH: usize = 200;
+
+/// The number of characters preceding the first match of the query which are kept in the
+/// description of a document.
+const SNIPPET_CONTEXT: usize = 60;
+
+/// The maximum depth of the subdirectories which are searched.
+const MAX_DEPTH: usize = 16;
+
+/// The weight of a match of the query in the title of a document compared to a match in its
+/// content.
+const TITLE_WEIGHT: usize = 10;
+
+/// The contents of the documents read by the previous searches keyed by their path, which are
+/// read again once the documents are modified.
+static DOCUMENTS: Reloadable<Mutex<HashMap<PathBuf, Document>>> = Reloadable::new();
+
+/// The version of a document, which is the modification time and the size of its file.
+type Version = (SystemTime, u64);
+
+/// A document read by a previous search.
+struct Document {
+    /// The version of the document when it was read.
+    version: Version,
+    /// The content of the document.
+    content: Arc<str>,
+}
+
+/// A new Local engine type defined in-order to implement the `SearchEngine` trait which allows to
+/// reduce code duplication as well as allows to create vector of different search engines easily.
+pub struct Local;
+
+impl Local {
+    /// Creates the Local engine, which reads the directory to search from the config.
+    pub fn new() -> Result<Local, EngineError> {
+        Ok(Self)
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchEngine for Local {
+    async fn results(
+        &self,
+        query: &str,
+        page: u32,
+        _user_agent: &str,
+        _client: &Client,
+        _safe_search: u8,
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        let Some((source, generation)) = LOCAL_SOURCE
+            .try_with(|source| source.clone())
+            .ok()
+            .flatten()
+            .filter(|(source, _)| !source.directory.is_empty())
+        else {
+            return Err(Report::new(EngineError::UnexpectedError)
+                .attach_printable("no local directory is configured"));
+        };
+
+        // The documents are read on the blocking threads so that the searches of the other
+        // engines are not held up by the file system.
+        let query = query.to_owned();
+        let results = tokio::task::spawn_blocking(move || {
+            search_directory(&source, &query, page, generation)
+        })
+        .await
+        .change_context(EngineError::UnexpectedError)??;
+        if results.is_empty() {
+            return Err(Report::new(EngineError::EmptyResultSet));
+        }
+        Ok(results)
+    }
+
+    fn capabilities(&self) -> EngineCapabilities {
+        EngineCapabilities {
+            pagination: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// A function which searches the documents of the configured directory for the provided query.
+/// The documents whose title, path or content contains every word of the query (ignoring the
+/// case) are matched, and they are ranked by the number of matches of the words, the matches in
+/// the title weighing more than the matches in the content. The title of a document is its first
+/// markdown heading (or its file name when it has none) and its description is the text around
+/// the first match of the query.
+///
+/// # Arguments
+///
+/// * `source` - It takes the configured directory of local documents as an argument.
+/// * `query` - It takes the search query as an argument.
+/// * `page` - It takes the page of the search starting from `0` as an argument.
+/// * `generation` - It takes the generation of the config as an argument.
+///
+/// # Error
+///
+/// Returns an `EngineError::UnexpectedError` error if the directory can not be read.
+fn search_directory(
+    source: &LocalSource,
+    query: &str,
+    page: u32,
+    generation: u64,
+) -> Result<Vec<(String, SearchResult)>, EngineError> {
+    let terms: Vec<Regex> = query
+        .split_whitespace()
+        .filter_map(|term| {
+            RegexBuilder::new(&regex::escape(term))
+                .case_insensitive(true)
+                .build()
+                .ok()
+        })
+        .collect();
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let root = Path::new(&source.directory);
+    let mut documents: Vec<(usize, String, String, String)> =
+        read_documents(collect_files(root, source)?, generation)
+            .into_iter()
+            .filter_map(|(path, content)| {
+                let relative_path = relative_path(root, &path)?;
+                let title = title(&content, &path);
+                let (score, snippet) = match_document(&content, &title, &relative_path, &terms)?;
+                Some((score, relative_path, title, snippet))
+            })
+            .collect();
+    documents.sort_by(
+        |(first_score, first_path, ..), (second_score, second_path, ..)| {
+            second_score
+                .cmp(first_score)
+                .then_with(|| first_path.cmp(second_path))
+        },
+    );
+
+    Ok(documents
+        .into_iter()
+        .skip(page as usize * PER_PAGE)
+        .take(PER_PAGE)
+        .filter_map(|(_, relative_path, title, snippet)| {
+            let url = document_url(source, root, &relative_path)?;
+            Some((
+                url.clone(),
+                SearchResult::new(&escape(&title), &url, &escape(&snippet), &["local"]),
+            ))
+        })
+        .collect())
+}
+
+/// A function which builds the url of the link to a document, which is the url template of the
+/// directory with its `{path}` placeholder replaced by the url encoded path of the document
+/// relative to the directory, or the `file` url of the document when no url template is
+/// configured.
+///
+/// # Arguments
+///
+/// * `source` - It takes the configured directory of local documents as an argument.
+/// * `root` - It takes the path of the directory as an argument.
+/// * `relative_path` - It takes the path of the document relative to the directory, whose
+///   components are separated by `/`, as an argument.
+fn document_url(source: &LocalSource, root: &Path, relative_path: &str) -> Option<String> {
+    if source.url.is_empty() {
+        let path = root.join(relative_path).canonicalize().ok()?;
+        return Url::from_file_path(path).ok().map(String::from);
+    }
+
+    let encoded_path: Vec<String> = relative_path
+        .split('/')
+        .map(|component| utf8_percent_encode(component, PATH_SEGMENT).to_string())
+        .collect();
+    Some(source.url.replace("{path}", &encoded_path.join("/")))
+}
+
+/// A helper function which collects the files of the directory and of its subdirectories which
+/// have one of the configured extensions and are not larger than the configured maximum size, in
+/// the order of their path, along with their version (their modification time and their size).
+/// The walk stops once the configured maximum number of files is collected. The hidden files and
+/// directories (whose name starts with a dot) and the symbolic links are skipped, so that the
+/// documents outside of the directory are not searched.
+///
+/// # Arguments
+///
+/// * `root` - It takes the path of the directory as an argument.
+/// * `source` - It takes the configured directory of local documents as an argument.
+fn collect_files(
+    root: &Path,
+    source: &LocalSource,
+) -> Result<Vec<(PathBuf, Option<Version>)>, EngineError> {
+    // Only the directory itself failing to be read fails the search, the subdirectories which can
+    // not be read are skipped.
+    let entries = sorted_entries(root).map_err(|error| {
+        Report::new(EngineError::UnexpectedError)
+            .attach_printable(format!("the local directory could not be read: {error}"))
+    })?;
+
+    // The entries of each directory are visited in the order of their name, descending into the
+    // subdirectories as they are found, so that the files are collected in the order of their
+    // path.
+    let mut files = Vec::new();
+    let mut directories = vec![entries.into_iter()];
+    while files.len() < source.max_files {
+        let Some(entries) = directories.last_mut() else {
+            break;
+        };
+        let Some(entry) = entries.next() else {
+            directories.pop();
+            continue;
+        };
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() && directories.len() <= MAX_DEPTH {
+            if let Ok(entries) = sorted_entries(&path) {
+                directories.push(entries.into_iter());
+            }
+        } else if file_type.is_file() {
+            if let Some(version) = searched_version(&path, source) {
+                files.push((path, version));
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// A helper function which returns the entries of a directory in the order of their name.
+///
+/// # Arguments
+///
+/// * `directory` - It takes the path of the directory as an argument.
+fn sorted_entries(directory: &Path) -> std::io::Result<Vec<DirEntry>> {
+    let mut entries: Vec<DirEntry> = read_dir(directory)?
+        .filter_map(|entry| entry.ok())
+        .collect();
+    entries.sort_by_key(|entry| entry.file_name());
+    Ok(entries)
+}
+
+/// A helper function which checks whether a file has one of the configured extensions (ignoring
+/// the case) and is not larger than the configured maximum size, returning the version of the
+/// file (which is `None` when its modification time is not available) if it is searched.
+///
+/// # Arguments
+///
+/// * `path` - It takes the path of the file as an argument.
+/// * `source` - It takes the configured directory of local documents as an argument.
+fn searched_version(path: &Path, source: &LocalSource) -> Option<Option<Version>> {
+    let has_extension = path.extension().is_some_and(|extension| {
+        source
+            .extensions
+            .iter()
+            .any(|searched| extension.eq_ignore_ascii_case(searched.trim_start_matches('.')))
+    });
+    if !has_extension {
+        return None;
+    }
+    let metadata = path.metadata().ok()?;
+    (metadata.len() <= source.max_file_size).then(|| {
+        metadata
+            .modified()
+            .ok()
+            .map(|modified| (modified, metadata.len()))
+    })
+}
+
+/// A helper function which returns the contents of the collected files, reading only the files
+/// which were not read by a previous search or which were modified since. The files which can
+/// not be read are skipped, and the cached documents which were not collected are dropped.
+///
+/// # Arguments
+///
+/// * `files` - It takes the collected files along with their version as an argument.
+/// * `generation` - It takes the generation of the config as an argument.
+fn read_documents(
+    files: Vec<(PathBuf, Option<Version>)>,
+    generation: u64,
+) -> Vec<(PathBuf, Arc<str>)> {
+    let cache = DOCUMENTS.get_or_build(generation, Mutex::default);
+    let lock = || cache.lock().unwrap_or_else(|error| error.into_inner());
+
+    let documents: Vec<(PathBuf, Arc<str>)> = files
+        .into_iter()
+        .filter_map(|(path, version)| {
+            let cached = version.and_then(|version| {
+                lock()
+                    .get(&path)
+                    .filter(|document| document.version == version)
+                    .map(|document| document.content.clone())
+            });
+            if let Some(content) = cached {
+                return Some((path, content));
+            }
+
+            let content: Arc<str> = read_to_string(&path).ok()?.into();
+            if let Some(version) = version {
+                let document = Document {
+                    version,
+                    content: content.clone(),
+                };
+                lock().insert(path.clone(), document);
+            }
+            Some((path, content))
+        })
+        .collect();
+
+    let collected: HashSet<&PathBuf> = documents.iter().map(|(path, _)| path).collect();
+    lock().retain(|path, _| collected.contains(path));
+    documents
+}
+
+/// A helper function which returns the path of a file relative to the directory, whose
+/// components are separated by `/`, or `None` if the path is not valid unicode.
+///
+/// # Arguments
+///
+/// * `root` - It takes the path of the directory as an argument.
+/// * `path` - It takes the path of the file within the directory as an argument.
+fn relative_path(root: &Path, path: &Path) -> Option<String> {
+    let components: Option<Vec<&str>> = path
+        .strip_prefix(root)
+        .ok()?
+        .components()
+        .map(|component| component.as_os_str().to_str())
+        .collect();
+    Some(components?.join("/"))
+}
+
+/// A helper function which returns the title of a document, which is its first markdown heading
+/// or its file name without the extension when it has none.
+///
+/// # Arguments
+///
+/// * `content` - It takes the content of the document as an argument.
+/// * `path` - It takes the path of the document as an argument.
+fn title(content: &str, path: &Path) -> String {
+    content
+        .lines()
+        .find_map(|line| {
+            let heading = line.trim_start().strip_prefix('#')?;
+            Some(heading.trim_start_matches('#').trim())
+        })
+        .filter(|heading| !heading.is_empty())
+        .map(str::to_owned)
+        .unwrap_or_else(|| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        })
+}
+
+/// A helper function which matches a document against the words of the query, returning its
+/// score along with the text around the first match of the query in its content, or `None` if
+/// any of the words is found neither in its title, nor in its path, nor in its content.
+///
+/// # Arguments
+///
+/// * `content` - It takes the content of the document as an argument.
+/// * `title` - It takes the title of the document as an argument.
+/// * `relative_path` - It takes the path of the document relative to the directory as an
+///   argument.
+/// * `terms` - It takes the case insensitive patterns of the words of the query as an argument.
+fn match_document(
+    content: &str,
+    title: &str,
+    relative_path: &str,
+    terms: &[Regex],
+) -> Option<(usize, String)> {
+    let mut score = 0;
+    let mut first_match: Option<usize> = None;
+    for term in terms {
+        let content_matches = term.find_iter(content).count();
+        let title_matches = term.find_iter(title).count();
+        if content_matches + title_matches == 0 && !term.is_match(relative_path) {
+            return None;
+        }
+        score += content_matches + title_matches * TITLE_WEIGHT;
+        if let Some(found) = term.find(content) {
+            first_match = Some(first_match.map_or(found.start(), |start| start.min(found.start())));
+        }
+    }
+
+    let start = first_match.map_or(0, |first_match| {
+        content[..first_match]
+            .char_indices()
+            .rev()
+            .nth(SNIPPET_CONTEXT)
+            .map_or(0, |(index, _)| index)
+    });
+    let window: String = content[start..].chars().take(SNIPPET_LENGTH * 2).collect();
+    let mut snippet: String = window.split_whitespace().collect::<Vec<_>>().join(" ");
+    if let Some((end, _)) = snippet.char_indices().nth(SNIPPET_LENGTH) {
+        snippet.truncate(end);
+        snippet.push('…');
+    }
+    if start > 0 {
+        snippet.insert(0, '…');
+    }
+    Some((score, snippet))
+}
+
+/// A helper function which escapes the html special characters of a text of a document.
+///
+/// # Arguments
+///
+/// * `text` - It takes the text to be escaped as an argument.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let _ = Escaper::new(&mut escaped).write_str(text);
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir, write};
+
+    #[test]
+    fn test_local_source() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path();
+        write(
+            path.join("onboarding.md"),
+            "# Onboarding guide\n\nAsk the IT team for your VPN access.",
+        )
+        .unwrap();
+        create_dir(path.join("it")).unwrap();
+        write(
+            path.join("it/vpn setup.md"),
+            "# VPN <setup>\n\nInstall the VPN client, then sign in. The VPN works everywhere.",
+        )
+        .unwrap();
+        write(path.join("it/notes.txt"), "Printer drivers.").unwrap();
+        write(path.join("it/vpn.pdf"), "VPN").unwrap();
+        write(path.join(".hidden.md"), "VPN").unwrap();
+
+        let mut source = LocalSource {
+            directory: path.to_string_lossy().into_owned(),
+            url: "https://docs.example.com/{path}".to_owned(),
+            ..Default::default()
+        };
+        let search = |source: &LocalSource, query: &str| -> Vec<(String, String, String)> {
+            search_directory(source, query, 0, 0)
+                .unwrap()
+                .into_iter()
+                .map(|(_, result)| (result.title, result.url, result.description))
+                .collect()
+        };
+
+        // The documents matching every word are ranked by their matches, the title matches first.
+        let results = search(&source, "vpn");
+        assert_eq!(
+            results
+                .iter()
+                .map(|(title, url, _)| (title.as_str(), url.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    "VPN &lt;setup&gt;",
+                    "https://docs.example.com/it/vpn%20setup.md"
+                ),
+                ("Onboarding guide", "https://docs.example.com/onboarding.md"),
+            ]
+        );
+        assert!(results[1].2.contains("your VPN access."));
+        assert_eq!(search(&source, "VPN onboarding").len(), 1);
+        assert_eq!(search(&source, "printer")[0].0, "notes");
+        assert!(search(&source, "printer scanner").is_empty());
+
+        // The files following the maximum number of files in the order of their path are skipped.
+        source.max_files = 2;
+        assert_eq!(search(&source, "vpn").len(), 1);
+        source.max_files = 1;
+        assert!(search(&source, "vpn").is_empty());
+        source.max_files = LocalSource::default().max_files;
+
+        // The modified documents are read again.
+        write(
+            path.join("onboarding.md"),
+            "# Onboarding guide\n\nAsk for a laptop.",
+        )
+        .unwrap();
+        assert!(search(&source, "vpn onboarding").is_empty());
+        assert_eq!(search(&source, "laptop").len(), 1);
+
+        // Only the configured extensions are searched.
+        source.extensions = vec!["txt".to_owned()];
+        assert!(search(&source, "vpn").is_empty());
+
+        // The directory which can not be read fails the search.
+        source.directory = path.join("missing").to_string_lossy().into_owned();
+        assert!(search_directory(&source, "vpn", 0, 0).is_err());
+    }
+}
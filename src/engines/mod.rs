@@ -6,7 +6,9 @@
 pub mod bing;
 pub mod brave;
 pub mod duckduckgo;
+pub mod json;
 pub mod librex;
+pub mod local;
 pub mod mojeek;
 pub mod search_result_parser;
 pub mod searx;
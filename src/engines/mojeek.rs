@@ -10,12 +10,18 @@ use scraper::Html;
 
 use crate::models::aggregation_models::SearchResult;
 
-use crate::models::engine_models::{EngineError, SearchEngine};
+use crate::models::engine_models::{
+    page_param, result_count, safe_search_value, EngineCapabilities, EngineError, Pagination,
+    SearchEngine, SAFE_SEARCH_LEVELS,
+};
 
 use error_stack::{Report, Result, ResultExt};
 
 use super::search_result_parser::SearchResultParser;
 
+/// The default values of the `safe` parameter of mojeek for each safe search level.
+const DEFAULT_SAFE_SEARCH: [&str; SAFE_SEARCH_LEVELS] = ["0", "1", "1", "1", "1"];
+
 /// A new Mojeek engine type defined in-order to implement the `SearchEngine` trait which allows to
 /// reduce code duplication as well as allows to create vector of different search engines easily.
 pub struct Mojeek {
@@ -30,7 +36,7 @@ impl Mojeek {
             parser: SearchResultParser::new(
                 ".result-col",
                 ".results-standard li",
-                "a span.url",
+                "h2 a.title",
                 "h2 a.title",
                 "p.s",
             )?,
@@ -47,15 +53,12 @@ impl SearchEngine for Mojeek {
         user_agent: &str,
         client: &Client,
         safe_search: u8,
-    ) -> Result<HashMap<String, SearchResult>, EngineError> {
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
         // Mojeek uses `start results from this number` convention
         // So, for 10 results per page, page 0 starts at 1, page 1
         // starts at 11, and so on.
-        let results_per_page = 10;
-        let start_result = results_per_page * page + 1;
-
-        let results_per_page = results_per_page.to_string();
-        let start_result = start_result.to_string();
+        let results_per_page = result_count(10).to_string();
+        let start_result = page_param(self.pagination(), page).unwrap_or_default();
 
         let search_engines = vec![
             "Bing",
@@ -73,7 +76,7 @@ impl SearchEngine for Mojeek {
             "You",
         ];
         let qss = search_engines.join("%2C");
-        let safe = if safe_search == 0 { "0" } else { "1" };
+        let safe = safe_search_value(safe_search, DEFAULT_SAFE_SEARCH);
 
         // Mojeek detects automated requests, these are preferences that are
         // able to circumvent the countermeasure. Some of these are
@@ -89,7 +92,7 @@ impl SearchEngine for Mojeek {
             ("hp", "minimal"),
             ("lb", "en"),
             ("qss", &qss),
-            ("safe", safe),
+            ("safe", &safe),
         ];
 
         let mut query_params_string = String::new();
@@ -140,12 +143,34 @@ impl SearchEngine for Mojeek {
         // scrape all the results from the html
         self.parser
             .parse_for_results(&document, |title, url, desc| {
-                Some(SearchResult::new(
-                    title.inner_html().trim(),
-                    url.inner_html().trim(),
-                    desc.inner_html().trim(),
-                    &["mojeek"],
-                ))
+                url.value().attr("href").map(|url| {
+                    SearchResult::new(
+                        title.inner_html().trim(),
+                        url.trim(),
+                        desc.inner_html().trim(),
+                        &["mojeek"],
+                    )
+                })
             })
     }
+
+    fn pagination(&self) -> Pagination {
+        Pagination::Offset {
+            first: 1,
+            per_page: 10,
+        }
+    }
+
+    fn safe_search_values(&self) -> Option<[&'static str; SAFE_SEARCH_LEVELS]> {
+        Some(DEFAULT_SAFE_SEARCH)
+    }
+
+    fn capabilities(&self) -> EngineCapabilities {
+        EngineCapabilities {
+            pagination: true,
+            safe_search: true,
+            result_count: true,
+            ..Default::default()
+        }
+    }
 }
@@ -1,9 +1,15 @@
 //! This modules provides helper functionalities for parsing a html document into internal SearchResult.
-use std::collections::HashMap;
-
 use crate::models::{aggregation_models::SearchResult, engine_models::EngineError};
 use error_stack::{Report, Result};
+use regex::Regex;
 use scraper::{html::Select, ElementRef, Html, Selector};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The CSS selectors matching the common markers of the CAPTCHA and consent walls which are shown
+/// by the upstream search engines instead of the results when they block the requests.
+const CAPTCHA_SELECTOR: &str = ".g-recaptcha, .h-captcha, iframe[src*='recaptcha'], \
+    iframe[src*='hcaptcha'], iframe[src*='consent'], #captcha, #b_captcha, .anomaly-modal, \
+    #challenge-form, #cf-challenge-running, form[action*='captcha'], form[action*='consent']";
 
 /// A html search result parser, based on a predefined CSS selectors.
 pub struct SearchResultParser {
@@ -17,6 +23,9 @@ pub struct SearchResultParser {
     result_url: Selector,
     /// selector to locate the description relative to the search result item.
     result_desc: Selector,
+    /// selector to locate the elements which are displayed when the request was blocked by a
+    /// CAPTCHA or a consent wall.
+    captcha: Selector,
 }
 
 impl SearchResultParser {
@@ -34,6 +43,7 @@ impl SearchResultParser {
             result_title: new_selector(result_title_selector)?,
             result_url: new_selector(result_url_selector)?,
             result_desc: new_selector(result_desc_selector)?,
+            captcha: new_selector(CAPTCHA_SELECTOR)?,
         })
     }
 
@@ -42,12 +52,36 @@ impl SearchResultParser {
         document.select(&self.no_result)
     }
 
-    /// Parse the html, and convert the results to SearchResult with the help of the builder function
+    /// Parse the html and check whether it is a CAPTCHA or consent wall page instead of a page of
+    /// search results.
+    pub fn parse_for_captcha(&self, document: &Html) -> bool {
+        document.select(&self.captcha).next().is_some()
+    }
+
+    /// Parse the html, and convert the results to SearchResult with the help of the builder function.
+    /// If no results were found and the page is a CAPTCHA or a consent wall, it returns an
+    /// EngineError instead of an empty list of results.
     pub fn parse_for_results(
         &self,
         document: &Html,
         builder: impl Fn(&ElementRef<'_>, &ElementRef<'_>, &ElementRef<'_>) -> Option<SearchResult>,
-    ) -> Result<HashMap<String, SearchResult>, EngineError> {
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        self.parse_for_results_with_item(document, |_, title, url, desc| builder(title, url, desc))
+    }
+
+    /// Parse the html just like `parse_for_results`, but also provides the element containing the
+    /// whole search result item to the builder function, so that the additional details of the
+    /// result (like the publisher of a news article) can be scraped.
+    pub fn parse_for_results_with_item(
+        &self,
+        document: &Html,
+        builder: impl Fn(
+            &ElementRef<'_>,
+            &ElementRef<'_>,
+            &ElementRef<'_>,
+            &ElementRef<'_>,
+        ) -> Option<SearchResult>,
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
         let res = document
             .select(&self.results)
             .filter_map(|result| {
@@ -55,18 +89,21 @@ impl SearchResultParser {
                 let url = result.select(&self.result_url).next();
                 let desc = result.select(&self.result_desc).next();
                 match (title, url, desc) {
-                    (Some(ref t), Some(ref u), Some(ref d)) => builder(t, u, d),
+                    (Some(ref t), Some(ref u), Some(ref d)) => builder(&result, t, u, d),
                     _ => None,
                 }
             })
             .map(|search_result| (search_result.url.clone(), search_result))
-            .collect();
+            .collect::<Vec<_>>();
+        if res.is_empty() && self.parse_for_captcha(document) {
+            return Err(Report::new(EngineError::Captcha));
+        }
         Ok(res)
     }
 }
 
 /// Create a Selector struct, if the given parameter is a valid css expression, otherwise convert it into an EngineError.
-fn new_selector(selector: &str) -> Result<Selector, EngineError> {
+pub fn new_selector(selector: &str) -> Result<Selector, EngineError> {
     Selector::parse(selector).map_err(|err| {
         Report::new(EngineError::UnexpectedError).attach_printable(format!(
             "invalid CSS selector: {}, err: {:?}",
@@ -74,3 +111,25 @@ fn new_selector(selector: &str) -> Result<Selector, EngineError> {
         ))
     })
 }
+
+/// Converts the relative publish time of a news article shown by the upstream search engines
+/// (like `5m`, `3h`, `2 days ago` or `1 week ago`) into a unix timestamp in seconds, returning
+/// `None` if the time could not be parsed.
+pub fn parse_relative_time(time: &str) -> Option<u64> {
+    let re = Regex::new(r"(\d+)\s*([a-z]+)").unwrap();
+    let time = time.to_lowercase();
+    let captures = re.captures(&time)?;
+    let amount: u64 = captures[1].parse().ok()?;
+    let unit: u64 = match &captures[2] {
+        "s" | "sec" | "secs" | "second" | "seconds" => 1,
+        "m" | "min" | "mins" | "minute" | "minutes" => 60,
+        "h" | "hr" | "hrs" | "hour" | "hours" => 60 * 60,
+        "d" | "day" | "days" => 24 * 60 * 60,
+        "w" | "wk" | "wks" | "week" | "weeks" => 7 * 24 * 60 * 60,
+        "mo" | "mon" | "month" | "months" => 30 * 24 * 60 * 60,
+        "y" | "yr" | "yrs" | "year" | "years" => 365 * 24 * 60 * 60,
+        _ => return None,
+    };
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some(now.saturating_sub(amount.checked_mul(unit)?))
+}
@@ -9,9 +9,15 @@ use std::collections::HashMap;
 
 use super::search_result_parser::SearchResultParser;
 use crate::models::aggregation_models::SearchResult;
-use crate::models::engine_models::{EngineError, SearchEngine};
+use crate::models::engine_models::{
+    page_param, safe_search_value, EngineCapabilities, EngineError, Pagination, SearchEngine,
+    SAFE_SEARCH_LEVELS,
+};
 use error_stack::{Report, Result, ResultExt};
 
+/// The default values of the `safesearch` parameter of searx for each safe search level.
+const DEFAULT_SAFE_SEARCH: [&str; SAFE_SEARCH_LEVELS] = ["0", "1", "2", "2", "2"];
+
 /// A new Searx engine type defined in-order to implement the `SearchEngine` trait which allows to
 /// reduce code duplication as well as allows to create vector of different search engines easily.
 pub struct Searx {
@@ -42,17 +48,13 @@ impl SearchEngine for Searx {
         page: u32,
         user_agent: &str,
         client: &Client,
-        mut safe_search: u8,
-    ) -> Result<HashMap<String, SearchResult>, EngineError> {
-        // Page number can be missing or empty string and so appropriate handling is required
-        // so that upstream server recieves valid page number.
-        if safe_search == 3 {
-            safe_search = 2;
-        };
+        safe_search: u8,
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        let safe_search = safe_search_value(safe_search, DEFAULT_SAFE_SEARCH);
 
         let url: String = format!(
             "https://searx.be/search?q={query}&pageno={}&safesearch={safe_search}",
-            page + 1
+            page_param(self.pagination(), page).unwrap_or_default()
         );
 
         // initializing headers and adding appropriate headers.
@@ -89,4 +91,20 @@ impl SearchEngine for Searx {
                 })
             })
     }
+
+    fn pagination(&self) -> Pagination {
+        Pagination::Page { first: 1 }
+    }
+
+    fn safe_search_values(&self) -> Option<[&'static str; SAFE_SEARCH_LEVELS]> {
+        Some(DEFAULT_SAFE_SEARCH)
+    }
+
+    fn capabilities(&self) -> EngineCapabilities {
+        EngineCapabilities {
+            pagination: true,
+            safe_search: true,
+            ..Default::default()
+        }
+    }
 }
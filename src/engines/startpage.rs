@@ -10,7 +10,9 @@ use scraper::Html;
 
 use crate::models::aggregation_models::SearchResult;
 
-use crate::models::engine_models::{EngineError, SearchEngine};
+use crate::models::engine_models::{
+    page_param, EngineError, Pagination, RequestTemplate, SearchEngine,
+};
 
 use error_stack::{Report, Result, ResultExt};
 
@@ -47,12 +49,12 @@ impl SearchEngine for Startpage {
         user_agent: &str,
         client: &Client,
         _safe_search: u8,
-    ) -> Result<HashMap<String, SearchResult>, EngineError> {
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
         // Page number can be missing or empty string and so appropriate handling is required
         // so that upstream server recieves valid page number.
         let url: String = format!(
             "https://startpage.com/do/dsearch?q={query}&num=10&start={}",
-            page * 10,
+            page_param(self.pagination(), page).unwrap_or_default(),
         );
 
         // initializing HeaderMap and adding appropriate headers.
@@ -86,4 +88,17 @@ impl SearchEngine for Startpage {
                 ))
             })
     }
+
+    fn request_template(&self) -> RequestTemplate {
+        // The search is posted like the search form of the startpage website, which is what the
+        // `enable_post_method` preference of the cookie asks for.
+        RequestTemplate::PostForm("q={q}&num={num}&start={start}")
+    }
+
+    fn pagination(&self) -> Pagination {
+        Pagination::Offset {
+            first: 0,
+            per_page: 10,
+        }
+    }
 }
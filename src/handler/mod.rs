@@ -20,6 +20,8 @@ const CONFIG_FILE_NAME: &str = "config.json";
 const ALLOWLIST_FILE_NAME: &str = "allowlist.txt";
 /// The constant holding the name of the BlockList text file.
 const BLOCKLIST_FILE_NAME: &str = "blocklist.txt";
+/// The constant holding the name of the PinnedResults text file.
+const PINNED_RESULTS_FILE_NAME: &str = "pinned_results.txt";
 
 /// An enum type which provides different variants to handle paths for various files/folders.
 #[derive(Hash, PartialEq, Eq, Debug)]
@@ -30,6 +32,8 @@ pub enum FileType {
     AllowList,
     /// This variant handles all the paths associated with the BlockList text file.
     BlockList,
+    /// This variant handles all the paths associated with the PinnedResults text file.
+    PinnedResults,
     /// This variant handles all the paths associated with the public folder (Theme folder).
     Theme,
 }
@@ -99,6 +103,20 @@ pub fn file_path(file_type: FileType) -> Result<&'static str, Error> {
                         format!("./{}/{}", COMMON_DIRECTORY_NAME, BLOCKLIST_FILE_NAME),
                     ],
                 ),
+                (
+                    FileType::PinnedResults,
+                    vec![
+                        format!(
+                            "{}/.config/{}/{}",
+                            home, COMMON_DIRECTORY_NAME, PINNED_RESULTS_FILE_NAME
+                        ),
+                        format!(
+                            "/etc/xdg/{}/{}",
+                            COMMON_DIRECTORY_NAME, PINNED_RESULTS_FILE_NAME
+                        ),
+                        format!("./{}/{}", COMMON_DIRECTORY_NAME, PINNED_RESULTS_FILE_NAME),
+                    ],
+                ),
             ])
         })
         .get(&file_type)
@@ -116,3 +134,42 @@ pub fn file_path(file_type: FileType) -> Result<&'static str, Error> {
         format!("{:?} file/folder not found!!", file_type),
     ))
 }
+
+/// A function which returns the path of a file provided in the config (like the custom css file).
+/// The absolute paths are used as is, while the relative paths are looked up in the same folders
+/// as the config file.
+///
+/// # Arguments
+///
+/// * `file` - It takes the path of the file provided in the config as an argument.
+///
+/// # Error
+///
+/// Returns a `<File Name> file not found!!` error if the file is not present on any of the paths
+/// on which it is looked up.
+pub fn config_file_path(file: &str) -> Result<String, Error> {
+    if Path::new(file).is_absolute() {
+        return match Path::new(file).exists() {
+            true => Ok(file.to_owned()),
+            false => Err(Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("{file} file not found!!"),
+            )),
+        };
+    }
+
+    let home = env!("HOME");
+    [
+        format!("{home}/.config/{COMMON_DIRECTORY_NAME}/{file}"),
+        format!("/etc/xdg/{COMMON_DIRECTORY_NAME}/{file}"),
+        format!("./{COMMON_DIRECTORY_NAME}/{file}"),
+    ]
+    .into_iter()
+    .find(|path| Path::new(path).exists())
+    .ok_or_else(|| {
+        Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("{file} file not found!!"),
+        )
+    })
+}
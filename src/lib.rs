@@ -27,14 +27,17 @@ use actix_cors::Cors;
 use actix_files as fs;
 use actix_governor::{Governor, GovernorConfigBuilder};
 use actix_web::{
-    dev::Server,
+    body::MessageBody,
+    dev::{Server, ServiceFactory, ServiceRequest, ServiceResponse},
     http::header,
     middleware::{Compress, Logger},
     web, App, HttpServer,
 };
 use cache::cacher::{Cacher, SharedCache};
-use config::Config;
+use config::{shared::SharedConfig, Config};
 use handler::{file_path, FileType};
+use results::filter_lists::sensitive_terms;
+use server::{redaction::redact_url, tls::server_config};
 
 /// Runs the web server on the provided TCP listener and returns a `Server` instance.
 ///
@@ -69,52 +72,139 @@ pub fn run(
 
     let cloned_config_threads_opt = config.server.threads;
 
-    let cache = web::Data::new(SharedCache::new(cache));
-
-    let server = HttpServer::new(move || {
-        let cors: Cors = Cors::default()
-            .allow_any_origin()
-            .allowed_methods(vec!["GET"])
-            .allowed_headers(vec![
-                header::ORIGIN,
-                header::CONTENT_TYPE,
-                header::REFERER,
-                header::COOKIE,
-            ]);
-
-        App::new()
-            // Compress the responses provided by the server for the client requests.
-            .wrap(Compress::default())
-            .wrap(Logger::default()) // added logging middleware for logging.
-            .app_data(web::Data::new(config.clone()))
-            .app_data(cache.clone())
-            .wrap(cors)
-            .wrap(Governor::new(
-                &GovernorConfigBuilder::default()
-                    .per_second(config.server.rate_limiter.time_limit as u64)
-                    .burst_size(config.server.rate_limiter.number_of_requests as u32)
-                    .finish()
-                    .unwrap(),
-            ))
-            // Serve images and static files (css and js files).
-            .service(
-                fs::Files::new("/static", format!("{}/static", public_folder_path))
-                    .show_files_listing(),
-            )
-            .service(
-                fs::Files::new("/images", format!("{}/images", public_folder_path))
-                    .show_files_listing(),
-            )
-            .service(router::robots_data) // robots.txt
-            .service(router::index) // index page
-            .service(server::routes::search::search) // search page
-            .service(router::about) // about page
-            .service(router::settings) // settings page
-            .default_service(web::route().to(router::not_found)) // error page
-    })
-    .workers(cloned_config_threads_opt.get().into())
+    let cache = web::Data::new(SharedCache::new(cache, config.caching.max_cached_results));
+
+    // The config is shared by all the workers, so that reloading it takes effect for all of them.
+    let shared_config = web::Data::new(SharedConfig::new(config.clone()));
+
+    let tls_config = match config.server.tls.enabled {
+        true => Some(server_config(&config.server.tls)?),
+        false => None,
+    };
+
+    let app_factory = move || app(&config, &shared_config, &cache, public_folder_path);
+
+    let server = HttpServer::new(app_factory).workers(cloned_config_threads_opt.get().into());
     // Start server on 127.0.0.1 with the user provided port number. for example 127.0.0.1:8080.
-    .listen(listener)?
+    // The TLS connections are terminated by the server itself when it is enabled.
+    let server = match tls_config {
+        Some(tls_config) => server.listen_rustls_0_21(listener, tls_config)?,
+        None => server.listen(listener)?,
+    }
     .run();
     Ok(server)
 }
+
+/// A helper function which builds the logging middleware, which logs the requests in the default
+/// format with the urls of the searches matching any of the sensitive terms redacted.
+///
+/// # Arguments
+///
+/// * `shared_config` - It takes the config shared by all the workers as an argument.
+fn logger(shared_config: &web::Data<SharedConfig>) -> Logger {
+    let request_config = shared_config.clone();
+    let referer_config = shared_config.clone();
+
+    Logger::new(r#"%a "%{request}xi" %s %b "%{referer}xi" "%{User-Agent}i" %T"#)
+        .custom_request_replace("request", move |req| {
+            let config = request_config.get();
+            format!(
+                "{} {} {:?}",
+                req.method(),
+                redact_url(
+                    &req.uri().to_string(),
+                    &sensitive_terms(&config.search, config.generation)
+                ),
+                req.version()
+            )
+        })
+        .custom_request_replace("referer", move |req| {
+            req.headers()
+                .get(header::REFERER)
+                .and_then(|referer| referer.to_str().ok())
+                .map_or_else(
+                    || "-".to_owned(),
+                    |referer| {
+                        let config = referer_config.get();
+                        redact_url(referer, &sensitive_terms(&config.search, config.generation))
+                            .into_owned()
+                    },
+                )
+        })
+}
+
+/// A helper function which builds the app handling the requests of a worker of the server, with
+/// all the routes of the website registered.
+///
+/// # Arguments
+///
+/// * `config` - It takes the parsed config which provides the rate limiter options as an argument.
+/// * `shared_config` - It takes the config shared by all the workers as an argument.
+/// * `cache` - It takes the shared cache as an argument.
+/// * `public_folder_path` - It takes the path of the public (theme) folder as an argument.
+fn app(
+    config: &Config,
+    shared_config: &web::Data<SharedConfig>,
+    cache: &web::Data<SharedCache>,
+    public_folder_path: &str,
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    let cors: Cors = Cors::default()
+        .allow_any_origin()
+        .allowed_methods(vec!["GET"])
+        .allowed_headers(vec![
+            header::ORIGIN,
+            header::CONTENT_TYPE,
+            header::REFERER,
+            header::COOKIE,
+        ]);
+
+    App::new()
+        // Compress the responses provided by the server for the client requests.
+        .wrap(Compress::default())
+        .wrap(logger(shared_config)) // added logging middleware for logging.
+        .app_data(shared_config.clone())
+        .app_data(cache.clone())
+        .wrap(cors)
+        .wrap(Governor::new(
+            &GovernorConfigBuilder::default()
+                .per_second(config.server.rate_limiter.time_limit as u64)
+                .burst_size(config.server.rate_limiter.number_of_requests as u32)
+                .finish()
+                .unwrap(),
+        ))
+        // Serve images and static files (css and js files).
+        .service(
+            fs::Files::new("/static", format!("{}/static", public_folder_path))
+                .show_files_listing(),
+        )
+        .service(
+            fs::Files::new("/images", format!("{}/images", public_folder_path))
+                .show_files_listing(),
+        )
+        .service(router::robots_data) // robots.txt
+        .service(router::index) // index page
+        .service(server::routes::search::search) // search page
+        .service(server::routes::search::search_batch) // batch search endpoint
+        .service(server::routes::search::search_path) // search page with the query as a path segment
+        .service(router::about) // about page
+        .service(router::custom_css) // custom css file
+        .service(router::settings) // settings page
+        .service(router::engines) // engines list
+        .service(router::engine_stats) // engine success rate statistics
+        .service(router::version) // build information
+        .service(router::analytics) // analytics admin endpoint
+        .service(router::reload_config) // config reload admin endpoint
+        .service(router::thumbnail) // thumbnail proxy
+        .service(router::favicon) // favicon proxy
+        .service(router::autocomplete) // autocomplete suggestions
+        .service(router::redirect) // click-through redirect
+        .default_service(web::route().to(router::not_found)) // error page
+}
@@ -4,6 +4,7 @@
 use super::engine_models::EngineError;
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// A named struct to store the raw scraped search results scraped search results from the
 /// upstream search engines before aggregating it.It derives the Clone trait which is needed
@@ -20,6 +21,43 @@ pub struct SearchResult {
     pub description: String,
     /// The names of the upstream engines from which this results were provided.
     pub engine: SmallVec<[String; 0]>,
+    /// Whether the result was pinned by the server administrator via the pinned results file.
+    #[serde(default)]
+    pub pinned: bool,
+    /// The details of how the result was ranked, these are only provided in debug mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ranking: Option<RankingDetails>,
+    /// The name of the publisher of the news article, only provided for news results.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// The time at which the news article was published (as a unix timestamp in seconds), only
+    /// provided for news results.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published: Option<u64>,
+    /// The url of the thumbnail proxy which fetches the preview thumbnail of the result, only
+    /// provided for the top ranked results when the thumbnails are enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<String>,
+}
+
+/// A named struct which stores the details of how a search result was ranked by the aggregator.
+#[derive(Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RankingDetails {
+    /// The computed rank score of the search result, results with a higher score are ranked
+    /// higher.
+    pub score: f32,
+    /// The positions at which each of the upstream engines returned the search result.
+    pub engine_positions: Vec<EnginePosition>,
+}
+
+/// A named struct which stores the position at which an upstream engine returned a search result.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EnginePosition {
+    /// The name of the upstream engine.
+    pub engine: String,
+    /// The zero based position of the search result in the results of the upstream engine.
+    pub position: u32,
 }
 
 impl SearchResult {
@@ -38,6 +76,54 @@ impl SearchResult {
             url: url.to_owned(),
             description: description.to_owned(),
             engine: engine.iter().map(|name| name.to_string()).collect(),
+            pinned: false,
+            ranking: None,
+            source: None,
+            published: None,
+            thumbnail: None,
+        }
+    }
+
+    /// Constructs a new news `SearchResult` which also provides the publisher and the publish
+    /// time of the news article.
+    ///
+    /// # Arguments
+    ///
+    /// * `title` - The title of the news article.
+    /// * `url` - The url which is accessed when clicked on it
+    ///   (href url in html in simple words).
+    /// * `description` - The description of the news article.
+    /// * `engine` - The names of the upstream engines from which this results were provided.
+    /// * `source` - The name of the publisher of the news article, if provided.
+    /// * `published` - The publish time of the news article as a unix timestamp, if provided.
+    pub fn new_news(
+        title: &str,
+        url: &str,
+        description: &str,
+        engine: &[&str],
+        source: Option<&str>,
+        published: Option<u64>,
+    ) -> Self {
+        SearchResult {
+            source: source.map(str::to_owned),
+            published,
+            ..SearchResult::new(title, url, description, engine)
+        }
+    }
+
+    /// Constructs a new pinned `SearchResult` which has been curated by the server administrator
+    /// rather than provided by an upstream search engine.
+    ///
+    /// # Arguments
+    ///
+    /// * `title` - The title of the search result.
+    /// * `url` - The url which is accessed when clicked on it
+    ///   (href url in html in simple words).
+    /// * `description` - The description of the search result.
+    pub fn new_pinned(title: &str, url: &str, description: &str) -> Self {
+        SearchResult {
+            pinned: true,
+            ..SearchResult::new(title, url, description, &[])
         }
     }
 
@@ -87,6 +173,10 @@ impl EngineErrorInfo {
                 EngineError::NoSuchEngineFound(_) => "EngineNotFound".to_owned(),
                 EngineError::RequestError => "RequestError".to_owned(),
                 EngineError::EmptyResultSet => "EmptyResultSet".to_owned(),
+                EngineError::Timeout => "Timeout".to_owned(),
+                EngineError::UnexpectedResponseFormat => "UnexpectedResponseFormat".to_owned(),
+                EngineError::Captcha => "Captcha".to_owned(),
+                EngineError::RateLimited => "RateLimited".to_owned(),
                 EngineError::UnexpectedError => "UnexpectedError".to_owned(),
             },
             engine: engine.to_owned(),
@@ -94,16 +184,37 @@ impl EngineErrorInfo {
                 EngineError::NoSuchEngineFound(_) => "red".to_owned(),
                 EngineError::RequestError => "green".to_owned(),
                 EngineError::EmptyResultSet => "blue".to_owned(),
+                EngineError::Timeout => "orange".to_owned(),
+                EngineError::UnexpectedResponseFormat => "red".to_owned(),
+                EngineError::Captcha => "purple".to_owned(),
+                EngineError::RateLimited => "yellow".to_owned(),
                 EngineError::UnexpectedError => "red".to_owned(),
             },
         }
     }
 }
 
+/// The reason why no search results are provided for a search, which allows the clients to tell
+/// the different kinds of empty search results apart.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EmptyResultsReason {
+    /// The search query was disallowed by the blocklist.
+    Disallowed,
+    /// All the search results were filtered out.
+    Filtered,
+    /// No upstream search engines were selected.
+    NoEnginesSelected,
+    /// All the queried upstream search engines failed.
+    UpstreamFailure,
+    /// The upstream search engines did not provide any search results.
+    NoResults,
+}
+
 /// A named struct to store, serialize, deserialize the all the search results scraped and
 /// aggregated from the upstream search engines.
 /// `SearchResult` structs.
-#[derive(Serialize, Deserialize, Default, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct SearchResults {
     /// Stores the individual serializable `SearchResult` struct into a vector of
@@ -111,6 +222,10 @@ pub struct SearchResults {
     /// Stores the information on which engines failed with their engine name
     /// and the type of error that caused it.
     pub engine_errors_info: Vec<EngineErrorInfo>,
+    /// Stores the number of the errors of the upstream search engines which were left out of
+    /// `engine_errors_info` as more engines failed than the configured maximum.
+    #[serde(default)]
+    pub omitted_engine_errors: usize,
     /// Stores the flag option which holds the check value that the following
     /// search query was disallowed when the safe search level set to 4 and it
     /// was present in the `Blocklist` file.
@@ -124,6 +239,131 @@ pub struct SearchResults {
     /// Stores the flag option which holds the check value that whether any search engines were
     /// selected or not.
     pub no_engines_selected: bool,
+    /// Stores the flag option which holds the check value that whether all the queried upstream
+    /// search engines failed, which tells a total outage of the upstream search engines apart
+    /// from a search which genuinely has no search results.
+    #[serde(default)]
+    pub upstream_failure: bool,
+    /// Stores the flag option which holds the check value that whether more of the selected
+    /// upstream search engines failed than the configured tolerance allows, in which case the
+    /// search results may be incomplete.
+    pub degraded: bool,
+    /// Stores the flag option which holds the check value that whether any of the selected
+    /// upstream search engines did not respond before the request timeout, in which case the
+    /// search results were cut short.
+    pub timed_out: bool,
+    /// Stores the names of the selected upstream search engines which were not queried as more
+    /// engines than the configured maximum were selected.
+    pub dropped_engines: Vec<String>,
+    /// Stores the unknown upstream search engine names selected in the cookie or provided in the
+    /// `engines` search url parameter, which were ignored.
+    #[serde(default)]
+    pub ignored_engines: Vec<String>,
+    /// Stores the names of the selected upstream search engines which were not queried as they
+    /// are denied by the instance.
+    #[serde(default)]
+    pub denied_engines: Vec<String>,
+    /// Stores the flag option which holds the check value that whether the cached search results
+    /// are served as the live fetch of the search results failed, in which case they may be
+    /// outdated.
+    #[serde(default)]
+    pub stale: bool,
+    /// Stores the flag option which holds the check value that whether the general search
+    /// results are provided instead of the requested category, as none of the selected upstream
+    /// search engines support searching the requested category.
+    pub category_fallback: bool,
+    /// Stores the reason why no search results are provided, which is derived from the flags
+    /// above. It is `None` when there are search results.
+    #[serde(default)]
+    pub reason: Option<EmptyResultsReason>,
+    /// Stores the featured snippet extracted from the page of the top ranked search result, only
+    /// provided when the featured snippets are enabled and a meaningful paragraph was found.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub featured_snippet: Option<FeaturedSnippet>,
+    /// Stores the structured answers (like dictionary definitions or the weather) provided by
+    /// the upstream search engines along with the first page of their search results, at most
+    /// one of each kind.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub answers: Vec<Answer>,
+    /// Stores the unix timestamp (in seconds) at which the search results were fetched, which
+    /// is kept when the search results are cached.
+    #[serde(default)]
+    pub fetched_at: u64,
+    /// Stores the search results grouped by the upstream search engines which provided them,
+    /// which are displayed in the engines view.
+    #[serde(default)]
+    pub engine_groups: Vec<EngineGroup>,
+    /// Stores the refinement terms by which the search results were narrowed down with the
+    /// `refine` search url parameter, the refined search results are not cached.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refinement: Option<String>,
+}
+
+/// A named struct which stores the search results provided by a single upstream search engine.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EngineGroup {
+    /// The name of the upstream search engine.
+    pub engine: String,
+    /// The indices of the search results provided by the engine within the aggregated search
+    /// results, in the order in which the engine returned them.
+    pub results: Vec<usize>,
+}
+
+/// A named struct which stores the featured snippet extracted from the page of the top ranked
+/// search result.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FeaturedSnippet {
+    /// The title of the search result the snippet was extracted from.
+    pub title: String,
+    /// The url of the search result the snippet was extracted from.
+    pub url: String,
+    /// The text of the first meaningful paragraph of the result page.
+    pub text: String,
+}
+
+/// A named struct which stores a structured answer provided by an upstream search engine along
+/// with its search results, which is displayed above the search results.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Answer {
+    /// The name of the upstream search engine which provided the answer.
+    pub engine: String,
+    /// The content of the answer.
+    pub content: AnswerContent,
+}
+
+/// The kinds of the structured answers which are parsed from the answer boxes of the upstream
+/// search engines.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "snake_case",
+    rename_all_fields = "camelCase"
+)]
+pub enum AnswerContent {
+    /// The dictionary definition of a word.
+    Definition {
+        /// The defined word.
+        word: String,
+        /// The part of speech of the word (like `noun`), if provided.
+        part_of_speech: Option<String>,
+        /// The definition of the word.
+        definition: String,
+    },
+    /// The current weather at a location.
+    Weather {
+        /// The name of the location.
+        location: String,
+        /// The current temperature along with its unit (like `21°C`).
+        temperature: String,
+        /// The description of the current weather condition (like `Partly cloudy`).
+        condition: String,
+    },
+}
+
+impl Default for SearchResults {
+    fn default() -> Self {
+        Self::new(Vec::new(), &[])
+    }
 }
 
 impl SearchResults {
@@ -138,30 +378,82 @@ impl SearchResults {
     /// * `engine_errors_info` - Takes an array of structs which contains information regarding
     /// which engines failed with their names, reason and their severity color name.
     pub fn new(results: Vec<SearchResult>, engine_errors_info: &[EngineErrorInfo]) -> Self {
-        Self {
+        let mut search_results = Self {
             results,
             engine_errors_info: engine_errors_info.to_owned(),
+            omitted_engine_errors: 0,
             disallowed: Default::default(),
             filtered: Default::default(),
             safe_search_level: Default::default(),
             no_engines_selected: Default::default(),
-        }
+            upstream_failure: false,
+            degraded: Default::default(),
+            timed_out: Default::default(),
+            dropped_engines: Default::default(),
+            ignored_engines: Default::default(),
+            denied_engines: Default::default(),
+            stale: false,
+            category_fallback: Default::default(),
+            reason: None,
+            featured_snippet: None,
+            answers: Vec::new(),
+            engine_groups: Vec::new(),
+            refinement: None,
+            fetched_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+        };
+        search_results.update_reason();
+        search_results
+    }
+
+    /// A helper function which derives the reason why no search results are provided from the
+    /// flags, in the same order of precedence in which the flags are displayed on the search
+    /// page.
+    fn update_reason(&mut self) {
+        self.reason = if !self.results.is_empty() {
+            None
+        } else if self.disallowed {
+            Some(EmptyResultsReason::Disallowed)
+        } else if self.filtered {
+            Some(EmptyResultsReason::Filtered)
+        } else if self.no_engines_selected {
+            Some(EmptyResultsReason::NoEnginesSelected)
+        } else if self.upstream_failure {
+            Some(EmptyResultsReason::UpstreamFailure)
+        } else {
+            Some(EmptyResultsReason::NoResults)
+        };
     }
 
     /// A setter function that sets disallowed to true.
     pub fn set_disallowed(&mut self) {
         self.disallowed = true;
+        self.update_reason();
     }
 
     /// A setter function that sets the filtered to true.
     pub fn set_filtered(&mut self) {
         self.filtered = true;
+        self.update_reason();
     }
 
     /// A getter function that gets the value of `engine_errors_info`.
     pub fn engine_errors_info(&mut self) -> Vec<EngineErrorInfo> {
         std::mem::take(&mut self.engine_errors_info)
     }
+
+    /// A setter function that sets the number of the errors of the upstream search engines which
+    /// were left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `omitted_engine_errors` - It takes the number of the left out errors as an argument.
+    pub fn set_omitted_engine_errors(&mut self, omitted_engine_errors: usize) {
+        self.omitted_engine_errors = omitted_engine_errors;
+    }
+
     /// A getter function that gets the value of `results`.
     pub fn results(&mut self) -> Vec<SearchResult> {
         self.results.clone()
@@ -180,5 +472,97 @@ impl SearchResults {
     /// A setter function to set the `no_engines_selected` to true.
     pub fn set_no_engines_selected(&mut self) {
         self.no_engines_selected = true;
+        self.update_reason();
+    }
+
+    /// A setter function that sets the `upstream_failure` to true.
+    pub fn set_upstream_failure(&mut self) {
+        self.upstream_failure = true;
+        self.update_reason();
+    }
+
+    /// A setter function that sets the `degraded` to true.
+    pub fn set_degraded(&mut self) {
+        self.degraded = true;
+    }
+
+    /// A setter function that sets the `stale` to true.
+    pub fn set_stale(&mut self) {
+        self.stale = true;
+    }
+
+    /// A setter function that sets the `timed_out` to true.
+    pub fn set_timed_out(&mut self) {
+        self.timed_out = true;
+    }
+
+    /// A setter function that sets the names of the engines which were not queried.
+    ///
+    /// # Arguments
+    ///
+    /// * `engines` - It takes the names of the engines which were not queried as an argument.
+    pub fn set_dropped_engines(&mut self, engines: &[&str]) {
+        self.dropped_engines = engines.iter().map(|engine| engine.to_string()).collect();
+    }
+
+    /// A setter function that sets the unknown engine names which were ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `engines` - It takes the unknown engine names which were ignored as an argument.
+    pub fn set_ignored_engines(&mut self, engines: &[String]) {
+        self.ignored_engines = engines.to_vec();
+    }
+
+    /// A setter function that sets the names of the selected engines which were denied.
+    ///
+    /// # Arguments
+    ///
+    /// * `engines` - It takes the names of the selected engines which were denied as an argument.
+    pub fn set_denied_engines(&mut self, engines: &[String]) {
+        self.denied_engines = engines.to_vec();
+    }
+
+    /// A setter function that sets the `category_fallback` to true.
+    pub fn set_category_fallback(&mut self) {
+        self.category_fallback = true;
+    }
+
+    /// A setter function that sets the search results grouped by the upstream search engines.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine_groups` - It takes the search results grouped by the engines as an argument.
+    pub fn set_engine_groups(&mut self, engine_groups: Vec<EngineGroup>) {
+        self.engine_groups = engine_groups;
+    }
+
+    /// A setter function that sets the structured answers provided by the upstream search engines.
+    ///
+    /// # Arguments
+    ///
+    /// * `answers` - It takes the structured answers as an argument.
+    pub fn set_answers(&mut self, answers: Vec<Answer>) {
+        self.answers = answers;
+    }
+
+    /// A setter function that sets the featured snippet of the search results.
+    ///
+    /// # Arguments
+    ///
+    /// * `featured_snippet` - It takes the extracted featured snippet as an argument.
+    pub fn set_featured_snippet(&mut self, featured_snippet: FeaturedSnippet) {
+        self.featured_snippet = Some(featured_snippet);
+    }
+
+    /// A setter function that sets the refinement terms by which the search results were
+    /// narrowed down.
+    ///
+    /// # Arguments
+    ///
+    /// * `refinement` - It takes the refinement terms as an argument.
+    pub fn set_refinement(&mut self, refinement: &str) {
+        self.refinement = Some(refinement.to_owned());
+        self.update_reason();
     }
 }
@@ -0,0 +1,229 @@
+//! This module provides public models for handling, storing and serializing the privacy
+//! preserving search analytics, which only consist of the aggregated counts of the searched
+//! queries and of the queried upstream search engines, along with the success rate statistics of
+//! the upstream search engines.
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// A named struct which stores the aggregated search analytics counted within a time window.
+#[derive(Default, Debug)]
+pub struct AnalyticsCounts {
+    /// The number of times each (normalized or hashed) search query was searched.
+    pub queries: HashMap<String, u64>,
+    /// The number of searches for which each upstream search engine was queried.
+    pub engines: HashMap<String, u64>,
+}
+
+impl AnalyticsCounts {
+    /// A function which adds the counts of another time window into the counts.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - It takes the counts to be added as an argument.
+    pub fn merge(&mut self, other: &AnalyticsCounts) {
+        for (query, count) in &other.queries {
+            *self.queries.entry(query.to_owned()).or_default() += count;
+        }
+        for (engine, count) in &other.engines {
+            *self.engines.entry(engine.to_owned()).or_default() += count;
+        }
+    }
+}
+
+/// A named struct which stores the number of times a search query was searched.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct QueryCount {
+    /// The normalized search query, or its hash if the queries are hashed.
+    pub query: String,
+    /// The number of times the query was searched.
+    pub count: u64,
+}
+
+/// A named struct which stores the number of searches for which an engine was queried.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct EngineCount {
+    /// The name of the upstream search engine.
+    pub engine: String,
+    /// The number of searches for which the engine was queried.
+    pub count: u64,
+}
+
+/// A named struct which stores the search analytics report returned by the admin endpoint.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyticsReport {
+    /// The size of the rolling time window over which the analytics were counted (in hours).
+    pub window_hours: u16,
+    /// Whether the search queries are hashed instead of being stored as is.
+    pub hashed_queries: bool,
+    /// The most searched queries ordered by their count.
+    pub top_queries: Vec<QueryCount>,
+    /// The number of searches for which each upstream search engine was queried ordered by their
+    /// count.
+    pub engines: Vec<EngineCount>,
+    /// The number of clicks on the search results followed through the click-through redirect.
+    pub clicks: u64,
+    /// The number of background cache writes which were dropped since the server was started, as
+    /// too many cache writes were already in progress.
+    pub dropped_cache_writes: u64,
+}
+
+impl AnalyticsReport {
+    /// Constructs a new `AnalyticsReport` from the aggregated counts, keeping only the most
+    /// searched queries.
+    ///
+    /// # Arguments
+    ///
+    /// * `counts` - It takes the aggregated counts of the time window as an argument.
+    /// * `window_hours` - It takes the size of the time window (in hours) as an argument.
+    /// * `hashed_queries` - It takes whether the queries are hashed as an argument.
+    /// * `top_queries` - It takes the number of the most searched queries to keep as an argument.
+    pub fn new(
+        counts: AnalyticsCounts,
+        window_hours: u16,
+        hashed_queries: bool,
+        top_queries: usize,
+    ) -> Self {
+        let mut queries: Vec<QueryCount> = counts
+            .queries
+            .into_iter()
+            .map(|(query, count)| QueryCount { query, count })
+            .collect();
+        queries.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.query.cmp(&b.query)));
+        queries.truncate(top_queries);
+
+        let mut engines: Vec<EngineCount> = counts
+            .engines
+            .into_iter()
+            .map(|(engine, count)| EngineCount { engine, count })
+            .collect();
+        engines.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.engine.cmp(&b.engine)));
+
+        Self {
+            window_hours,
+            hashed_queries,
+            top_queries: queries,
+            engines,
+            clicks: 0,
+            dropped_cache_writes: 0,
+        }
+    }
+}
+
+/// A named struct which stores the number of requests sent to an upstream search engine by their
+/// outcome.
+#[derive(Serialize, Default, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineOutcomeCounts {
+    /// The number of requests for which the engine returned search results.
+    pub successes: u64,
+    /// The number of requests for which the engine returned no search results, which are not
+    /// counted as failed.
+    pub empty_results: u64,
+    /// The number of requests which timed out.
+    pub timeouts: u64,
+    /// The number of requests which failed for any other reason.
+    pub errors: u64,
+    /// The total time taken by the requests (in milliseconds).
+    #[serde(skip)]
+    pub latency_ms: u64,
+}
+
+impl EngineOutcomeCounts {
+    /// A function which adds the counts of another time window into the counts.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - It takes the counts to be added as an argument.
+    pub fn merge(&mut self, other: &EngineOutcomeCounts) {
+        self.successes += other.successes;
+        self.empty_results += other.empty_results;
+        self.timeouts += other.timeouts;
+        self.errors += other.errors;
+        self.latency_ms += other.latency_ms;
+    }
+
+    /// Returns the total number of requests sent to the engine.
+    pub fn requests(&self) -> u64 {
+        self.successes + self.empty_results + self.timeouts + self.errors
+    }
+
+    /// Returns the fraction (a value from 0.0 to 1.0) of the requests which did not fail, or
+    /// `None` if no request was sent to the engine.
+    pub fn success_ratio(&self) -> Option<f64> {
+        match self.requests() {
+            0 => None,
+            requests => Some((self.successes + self.empty_results) as f64 / requests as f64),
+        }
+    }
+
+    /// Returns the average time taken by the requests (in milliseconds), or `None` if no
+    /// request was sent to the engine.
+    pub fn average_latency_ms(&self) -> Option<u64> {
+        self.latency_ms.checked_div(self.requests())
+    }
+}
+
+/// A named struct which stores the success rate statistics of an upstream search engine.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineStats {
+    /// The name of the upstream search engine.
+    pub engine: String,
+    /// The total number of requests sent to the engine.
+    pub requests: u64,
+    /// The number of requests sent to the engine by their outcome.
+    #[serde(flatten)]
+    pub counts: EngineOutcomeCounts,
+    /// The fraction of the requests which did not fail.
+    pub success_ratio: f64,
+    /// The average time taken by the requests (in milliseconds).
+    pub average_latency_ms: u64,
+}
+
+/// A named struct which stores the engine statistics report returned by the engine stats
+/// endpoint.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineStatsReport {
+    /// The size of the rolling time window over which the outcomes were counted (in hours).
+    pub window_hours: u16,
+    /// The statistics of each upstream search engine ordered by their success ratio, starting
+    /// with the least reliable engine.
+    pub engines: Vec<EngineStats>,
+}
+
+impl EngineStatsReport {
+    /// Constructs a new `EngineStatsReport` from the counts of each engine aggregated over the
+    /// time window, leaving out the engines to which no request was sent.
+    ///
+    /// # Arguments
+    ///
+    /// * `counts` - It takes the aggregated counts of each engine as an argument.
+    /// * `window_hours` - It takes the size of the time window (in hours) as an argument.
+    pub fn new(counts: HashMap<String, EngineOutcomeCounts>, window_hours: u16) -> Self {
+        let mut engines: Vec<EngineStats> = counts
+            .into_iter()
+            .filter_map(|(engine, counts)| {
+                Some(EngineStats {
+                    engine,
+                    requests: counts.requests(),
+                    success_ratio: counts.success_ratio()?,
+                    average_latency_ms: counts.average_latency_ms()?,
+                    counts,
+                })
+            })
+            .collect();
+        engines.sort_by(|a, b| {
+            a.success_ratio
+                .total_cmp(&b.success_ratio)
+                .then_with(|| a.engine.cmp(&b.engine))
+        });
+
+        Self {
+            window_hours,
+            engines,
+        }
+    }
+}
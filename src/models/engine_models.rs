@@ -1,10 +1,67 @@
 //! This module provides the error enum to handle different errors associated while requesting data from
 //! the upstream search engines with the search query provided by the user.
 
-use super::aggregation_models::SearchResult;
+use super::aggregation_models::{AnswerContent, SearchResult};
+use crate::config::search::{JsonSource, LocalSource};
 use error_stack::{Report, Result, ResultExt};
-use reqwest::Client;
-use std::{collections::HashMap, fmt};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use reqwest::{
+    header::{HeaderMap, HeaderValue, CONTENT_TYPE},
+    Client, Url,
+};
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, fmt, time::Duration};
+
+/// The maximum length (in bytes) of the raw response body of an upstream search engine which is
+/// captured while debugging the engine.
+const MAX_RAW_RESPONSE_LENGTH: usize = 64 * 1024;
+
+tokio::task_local! {
+    /// The raw response body of the upstream search engine, which is only captured when the
+    /// engine is queried within the scope of this task local while debugging the engine.
+    pub static RAW_RESPONSE: RefCell<Option<String>>;
+
+    /// Whether the content type of the response of the upstream search engine is checked against
+    /// the format expected by the engine. It is checked when the engine is not queried within the
+    /// scope of this task local.
+    pub static VALIDATE_CONTENT_TYPE: bool;
+
+    /// The number of results requested from the upstream search engine, the engines which
+    /// support requesting fewer results than their default page request this number of results
+    /// when they are queried within the scope of this task local.
+    pub static RESULT_COUNT: Option<u32>;
+
+    /// The configured native values of the safe search parameter of the upstream search engine
+    /// for each safe search level, which are used instead of the default values of the engine
+    /// when it is queried within the scope of this task local.
+    pub static SAFE_SEARCH_VALUES: Option<Vec<String>>;
+
+    /// The structured answers parsed from the answer boxes of the upstream search engine, which
+    /// are only collected when the engine is queried within the scope of this task local.
+    pub static ANSWERS: RefCell<Vec<AnswerContent>>;
+
+    /// The configured json search api, which is queried by the `json` upstream search engine when
+    /// it is queried within the scope of this task local.
+    pub static JSON_SOURCE: Option<JsonSource>;
+
+    /// The configured directory of local documents along with the generation of the config
+    /// providing it, which is searched by the `local` upstream search engine when it is queried
+    /// within the scope of this task local.
+    pub static LOCAL_SOURCE: Option<(LocalSource, u64)>;
+
+    /// The cursor of the requested page of an upstream search engine paginating with cursors,
+    /// which is replaced by the cursor of the next page provided by the engine when it is queried
+    /// within the scope of this task local.
+    pub static CURSOR: RefCell<Option<String>>;
+
+    /// The timeout of the request to the upstream search engine, which replaces the request
+    /// timeout of the http client when the engine is queried within the scope of this task local,
+    /// like when the request is retried with an escalated timeout.
+    pub static REQUEST_TIMEOUT: Duration;
+}
+
+/// The number of the safe search levels, which range from `0` (none) to `4` (aggressive).
+pub const SAFE_SEARCH_LEVELS: usize = 5;
 
 /// A custom error type used for handle engine associated errors.
 #[derive(Debug)]
@@ -17,6 +74,18 @@ pub enum EngineError {
     /// This variant handles the not results found error provide by the upstream
     /// search engines.
     RequestError,
+    /// This variant handles the errors which occur when the upstream search engine does not
+    /// respond before the request timeout.
+    Timeout,
+    /// This variant handles the responses whose content type does not match the format expected
+    /// by the upstream search engine, like an html error page returned instead of json results.
+    UnexpectedResponseFormat,
+    /// This variant handles the CAPTCHA or consent wall pages which are returned by the upstream
+    /// search engines instead of the results when the requests are blocked.
+    Captcha,
+    /// This variant handles the requests which were not sent to the upstream search engine
+    /// because they would have exceeded the rate limit of the engine configured for the instance.
+    RateLimited,
     ///  This variant handles all the errors which are unexpected or occur rarely
     /// and are errors mostly related to failure in initialization of HeaderMap,
     /// Selector errors and all other errors occurring within the code handling
@@ -39,6 +108,30 @@ impl fmt::Display for EngineError {
                     "Error occurred while requesting data from upstream search engine"
                 )
             }
+            EngineError::Timeout => {
+                write!(
+                    f,
+                    "The upstream search engine did not respond before the request timeout"
+                )
+            }
+            EngineError::UnexpectedResponseFormat => {
+                write!(
+                    f,
+                    "The upstream search engine returned a response in an unexpected format"
+                )
+            }
+            EngineError::Captcha => {
+                write!(
+                    f,
+                    "The upstream search engine returned a CAPTCHA or a consent wall instead of results"
+                )
+            }
+            EngineError::RateLimited => {
+                write!(
+                    f,
+                    "The upstream search engine was skipped to stay within its local rate limit"
+                )
+            }
             EngineError::UnexpectedError => {
                 write!(f, "An unexpected error occurred while processing the data")
             }
@@ -48,6 +141,481 @@ impl fmt::Display for EngineError {
 
 impl error_stack::Context for EngineError {}
 
+/// A helper function which converts the error which occurred while requesting data from the
+/// upstream search engine into an `EngineError`, reporting timeouts separately from other request
+/// errors.
+///
+/// # Arguments
+///
+/// * `error` - It takes the error returned by the http client as an argument.
+fn request_error(error: reqwest::Error) -> Report<EngineError> {
+    let context = if error.is_timeout() {
+        EngineError::Timeout
+    } else {
+        EngineError::RequestError
+    };
+    Report::new(error).change_context(context)
+}
+
+/// A helper function which checks that the content type of the response of the upstream search
+/// engine matches the format expected by the engine, unless the check is disabled. The responses
+/// without a content type are accepted.
+///
+/// # Arguments
+///
+/// * `response` - It takes the response of the upstream search engine as an argument.
+/// * `expected` - It takes the format of the responses expected by the engine as an argument.
+///
+/// # Error
+///
+/// Returns an `EngineError::UnexpectedResponseFormat` error if the content type does not match the
+/// expected format.
+fn check_content_type(
+    response: &reqwest::Response,
+    expected: ResponseFormat,
+) -> Result<(), EngineError> {
+    if !VALIDATE_CONTENT_TYPE
+        .try_with(|validate| *validate)
+        .unwrap_or(true)
+    {
+        return Ok(());
+    }
+
+    let Some(content_type) = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+    else {
+        return Ok(());
+    };
+
+    let media_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    match expected.matches(&media_type) {
+        true => Ok(()),
+        false => Err(
+            Report::new(EngineError::UnexpectedResponseFormat).attach_printable(format!(
+                "expected a {expected} response but the content type is `{media_type}`"
+            )),
+        ),
+    }
+}
+
+/// A function which builds the request sent to the upstream search engine for the provided url
+/// as described by the request template of the engine. The `GET` requests are sent to the url as
+/// it is, while the body of the `POST` requests is rendered from the body template of the engine,
+/// whose `{name}` placeholders are replaced by the values of the url parameters of the same name.
+/// The url parameters used by the body are removed from the url of the `POST` requests.
+///
+/// # Arguments
+///
+/// * `client` - It takes the http client sending the request as an argument.
+/// * `url` - It takes the url of the upstream search engine with the user requested search query
+///   appended in the search parameters.
+/// * `header_map` - It takes the http request headers to be sent to the upstream engine as an
+///   argument.
+/// * `template` - It takes the request template of the engine as an argument.
+///
+/// # Error
+///
+/// Returns an `EngineError::UnexpectedError` error if the url is invalid or if the request can
+/// not be built otherwise.
+pub fn build_request(
+    client: &Client,
+    url: &str,
+    mut header_map: HeaderMap,
+    template: RequestTemplate,
+) -> Result<reqwest::Request, EngineError> {
+    let (body_template, content_type, escape): (&str, &str, fn(&str) -> String) = match template {
+        RequestTemplate::Get => {
+            return client
+                .get(url)
+                .headers(header_map)
+                .build()
+                .change_context(EngineError::UnexpectedError)
+        }
+        RequestTemplate::PostForm(body_template) => (
+            body_template,
+            "application/x-www-form-urlencoded",
+            |value| utf8_percent_encode(value, NON_ALPHANUMERIC).to_string(),
+        ),
+        RequestTemplate::PostJson(body_template) => (body_template, "application/json", |value| {
+            serde_json::Value::from(value).to_string()
+        }),
+    };
+
+    let mut url = Url::parse(url).change_context(EngineError::UnexpectedError)?;
+    let params: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    let (body, used_params) = render_body(body_template, &params, escape);
+
+    let remaining_params: Vec<&(String, String)> = params
+        .iter()
+        .filter(|(name, _)| !used_params.contains(&name.as_str()))
+        .collect();
+    match remaining_params.is_empty() {
+        true => url.set_query(None),
+        false => {
+            url.query_pairs_mut().clear().extend_pairs(remaining_params);
+        }
+    }
+
+    header_map.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    client
+        .post(url)
+        .headers(header_map)
+        .body(body)
+        .build()
+        .change_context(EngineError::UnexpectedError)
+}
+
+/// A helper function which sets the timeout of the request to the upstream search engine when the
+/// engine is queried within the scope of the `REQUEST_TIMEOUT` task local.
+///
+/// # Arguments
+///
+/// * `request` - It takes the request to the upstream search engine as an argument.
+fn with_request_timeout(mut request: reqwest::Request) -> reqwest::Request {
+    if let Ok(timeout) = REQUEST_TIMEOUT.try_with(|timeout| *timeout) {
+        *request.timeout_mut() = Some(timeout);
+    }
+    request
+}
+
+/// A helper function which renders the body of a request from a body template, replacing each
+/// `{name}` placeholder by the escaped value of the url parameter of the same name, or by an
+/// escaped empty value when the url has no such parameter.
+///
+/// # Arguments
+///
+/// * `template` - It takes the body template as an argument.
+/// * `params` - It takes the decoded url parameters as an argument.
+/// * `escape` - It takes the function escaping the values for the format of the body as an
+///   argument.
+///
+/// # Returns
+///
+/// Returns the rendered body along with the names of the placeholders used by the template.
+fn render_body<'a>(
+    template: &'a str,
+    params: &[(String, String)],
+    escape: fn(&str) -> String,
+) -> (String, Vec<&'a str>) {
+    let mut body = String::with_capacity(template.len());
+    let mut used_params = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        body.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let name_length = after
+            .find(|character: char| !(character.is_ascii_alphanumeric() || character == '_'))
+            .unwrap_or(after.len());
+
+        // The braces which do not enclose a placeholder name (like the braces of a json object)
+        // are kept as they are.
+        if name_length == 0 || !after[name_length..].starts_with('}') {
+            body.push('{');
+            rest = after;
+            continue;
+        }
+
+        let name = &after[..name_length];
+        let value = params
+            .iter()
+            .find(|(param, _)| param == name)
+            .map_or("", |(_, value)| value.as_str());
+        body.push_str(&escape(value));
+        used_params.push(name);
+        rest = &after[name_length + 1..];
+    }
+    body.push_str(rest);
+
+    (body, used_params)
+}
+
+/// A helper function which stores the truncated raw response body of the upstream search engine
+/// if the engine is being debugged, otherwise it does nothing.
+///
+/// # Arguments
+///
+/// * `body` - It takes the raw response body of the upstream search engine as an argument.
+fn capture_raw_response(body: &[u8]) {
+    let _ = RAW_RESPONSE.try_with(|raw_response| {
+        let body = String::from_utf8_lossy(body);
+        let mut length = body.len().min(MAX_RAW_RESPONSE_LENGTH);
+        while !body.is_char_boundary(length) {
+            length -= 1;
+        }
+        *raw_response.borrow_mut() = Some(body[..length].to_owned());
+    });
+}
+
+/// A function which provides a structured answer parsed from the answer box of the upstream
+/// search engine, which is collected if the answers of the engine are being collected, otherwise
+/// it does nothing.
+///
+/// # Arguments
+///
+/// * `answer` - It takes the parsed answer as an argument.
+pub fn provide_answer(answer: AnswerContent) {
+    let _ = ANSWERS.try_with(|answers| answers.borrow_mut().push(answer));
+}
+
+/// A function which provides the number of results an upstream search engine should request,
+/// which is the number requested within the scope of the `RESULT_COUNT` task local or the
+/// provided number of results of the default page of the engine.
+///
+/// # Arguments
+///
+/// * `default` - It takes the number of results of the default page of the engine as an
+///   argument.
+pub fn result_count(default: u32) -> u32 {
+    RESULT_COUNT
+        .try_with(|count| *count)
+        .ok()
+        .flatten()
+        .filter(|count| *count != 0)
+        .unwrap_or(default)
+}
+
+/// A function which provides the value of the pagination parameter of an upstream search engine
+/// for the requested page, translated as described by the pagination scheme of the engine with
+/// the number of results requested within the scope of the `RESULT_COUNT` task local and the
+/// cursor provided within the scope of the `CURSOR` task local (see `translate_page`).
+///
+/// # Arguments
+///
+/// * `pagination` - It takes the pagination scheme of the engine as an argument.
+/// * `page` - It takes the index of the requested page starting from `0` as an argument.
+pub fn page_param(pagination: Pagination, page: u32) -> Option<String> {
+    let result_count = RESULT_COUNT.try_with(|count| *count).ok().flatten();
+    let cursor = CURSOR
+        .try_with(|cursor| cursor.borrow().clone())
+        .ok()
+        .flatten();
+    translate_page(pagination, page, result_count, cursor.as_deref())
+}
+
+/// A function which translates the index of the requested page (starting from `0`) into the value
+/// of the pagination parameter of an upstream search engine as described by its pagination
+/// scheme.
+///
+/// # Arguments
+///
+/// * `pagination` - It takes the pagination scheme of the engine as an argument.
+/// * `page` - It takes the index of the requested page starting from `0` as an argument.
+/// * `result_count` - It takes the number of results requested from the engine as an argument,
+///   the default page of the engine is assumed when it is `None` or `0`.
+/// * `cursor` - It takes the cursor of the requested page as an argument, if it is known.
+///
+/// # Returns
+///
+/// Returns the value of the pagination parameter, which is empty for the first page of the
+/// engines paginating with cursors. Returns `None` when the page can not be requested, which is
+/// the case of the following pages of these engines when their cursor is unknown.
+pub fn translate_page(
+    pagination: Pagination,
+    page: u32,
+    result_count: Option<u32>,
+    cursor: Option<&str>,
+) -> Option<String> {
+    match pagination {
+        Pagination::Page { first } => Some(first.saturating_add(page).to_string()),
+        Pagination::Offset { first, per_page } => {
+            let per_page = result_count.filter(|count| *count != 0).unwrap_or(per_page);
+            Some(
+                per_page
+                    .saturating_mul(page)
+                    .saturating_add(first)
+                    .to_string(),
+            )
+        }
+        Pagination::Cursor => match page {
+            0 => Some(String::new()),
+            _ => cursor.map(str::to_owned),
+        },
+    }
+}
+
+/// A function which provides the cursor of the next page of an upstream search engine paginating
+/// with cursors, which is stored if the engine is queried within the scope of the `CURSOR` task
+/// local, otherwise it does nothing.
+///
+/// # Arguments
+///
+/// * `cursor` - It takes the cursor of the next page as an argument.
+pub fn provide_next_cursor(cursor: &str) {
+    let _ = CURSOR.try_with(|next_cursor| *next_cursor.borrow_mut() = Some(cursor.to_owned()));
+}
+
+/// A function which provides the native value of the safe search parameter of an upstream search
+/// engine for the provided safe search level, which is the configured value within the scope of
+/// the `SAFE_SEARCH_VALUES` task local or the provided default value of the engine.
+///
+/// # Arguments
+///
+/// * `safe_search` - It takes the safe search level as an argument.
+/// * `defaults` - It takes the default native values of the engine for each safe search level as
+///   an argument.
+pub fn safe_search_value(safe_search: u8, defaults: [&str; SAFE_SEARCH_LEVELS]) -> String {
+    let level = usize::from(safe_search).min(SAFE_SEARCH_LEVELS - 1);
+    SAFE_SEARCH_VALUES
+        .try_with(|values| {
+            values
+                .as_ref()
+                .and_then(|values| values.get(level).cloned())
+        })
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| defaults[level].to_owned())
+}
+
+/// A named struct which describes the search features supported by an upstream search engine.
+#[derive(Serialize, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineCapabilities {
+    /// Whether the engine supports fetching results beyond the first page.
+    pub pagination: bool,
+    /// Whether the engine supports restricting the results based on the safe search level.
+    pub safe_search: bool,
+    /// Whether the engine supports searching different categories of results (images, news, etc).
+    pub categories: bool,
+    /// Whether the engine supports restricting the results to a time range.
+    pub time_range: bool,
+    /// Whether the engine supports restricting the results to a region.
+    pub region: bool,
+    /// Whether the engine supports requesting fewer results than its default page.
+    pub result_count: bool,
+}
+
+/// The formats of the responses which can be expected from an upstream search engine.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ResponseFormat {
+    /// The results are provided as an html page.
+    #[default]
+    Html,
+    /// The results are provided as json.
+    Json,
+}
+
+impl ResponseFormat {
+    /// Checks whether a media type (like `text/html`) matches the format.
+    ///
+    /// # Arguments
+    ///
+    /// * `media_type` - It takes the lowercase media type of the response as an argument.
+    pub fn matches(&self, media_type: &str) -> bool {
+        match self {
+            ResponseFormat::Html => {
+                matches!(media_type, "text/html" | "application/xhtml+xml")
+            }
+            ResponseFormat::Json => {
+                media_type == "application/json" || media_type.ends_with("+json")
+            }
+        }
+    }
+}
+
+impl fmt::Display for ResponseFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResponseFormat::Html => write!(f, "html"),
+            ResponseFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// The templates of the requests sent to an upstream search engine, which describe the http
+/// method of the requests along with the template of their body. The `{name}` placeholders of the
+/// body templates are replaced by the values of the url parameters of the same name (see
+/// `build_request`).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum RequestTemplate {
+    /// The search is requested with a `GET` request to the url of the search.
+    #[default]
+    Get,
+    /// The search is requested with a `POST` request with a form body, whose placeholders are
+    /// replaced by the url encoded values.
+    PostForm(&'static str),
+    /// The search is requested with a `POST` request with a json body, whose placeholders are
+    /// replaced by the values as json strings (quotes included).
+    PostJson(&'static str),
+}
+
+/// The schemes in which the upstream search engines paginate their results, which describe how
+/// the index of the requested page (starting from `0`) is translated into the value of the
+/// pagination parameter of the engine (see `page_param`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pagination {
+    /// The pages are numbered, the first page being numbered `first`.
+    Page {
+        /// The number of the first page.
+        first: u32,
+    },
+    /// The pages start at the offset of their first result, the first result being numbered
+    /// `first`. The pages hold the number of results requested from the engine, which defaults
+    /// to `per_page`.
+    Offset {
+        /// The number of the first result.
+        first: u32,
+        /// The number of results of the default page of the engine.
+        per_page: u32,
+    },
+    /// The pages start at an opaque cursor, which the engine provides along with the results of
+    /// the previous page (see `provide_next_cursor`).
+    Cursor,
+}
+
+/// The categories of search results which can be searched through the `category` search
+/// parameter.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    /// The general web search results.
+    #[default]
+    General,
+    /// The news articles provided by the news verticals of the upstream search engines, which
+    /// are sorted by their publish time.
+    News,
+}
+
+/// A named struct which describes an upstream search engine, whether it is enabled and the
+/// features that it supports.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineInfo {
+    /// The name of the engine as provided in the config file.
+    pub name: String,
+    /// Whether the engine is enabled by default.
+    pub enabled: bool,
+    /// Whether the engine is always queried, regardless of the engines selected by the user.
+    pub forced: bool,
+    /// The features supported by the engine.
+    pub features: EngineCapabilities,
+    /// The native values of the safe search parameter of the engine for each safe search level,
+    /// if the engine supports safe search.
+    pub safe_search_values: Option<Vec<String>>,
+}
+
+/// A named struct which describes the outcome of querying a single upstream search engine along
+/// with its raw response, which is returned when debugging the engine.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineDebugInfo {
+    /// The name of the debugged engine.
+    pub engine: String,
+    /// The results parsed from the response of the engine.
+    pub results: Vec<SearchResult>,
+    /// The error which occurred while querying the engine or parsing its response, if any.
+    pub error: Option<String>,
+    /// The raw response body of the engine truncated to 64 KiB, if a response was received.
+    pub raw_response: Option<String>,
+}
+
 /// A trait to define common behavior for all search engines.
 #[async_trait::async_trait]
 pub trait SearchEngine: Sync + Send {
@@ -75,15 +643,16 @@ pub trait SearchEngine: Sync + Send {
         client: &Client,
     ) -> Result<String, EngineError> {
         // fetch the html from upstream search engine
-        Ok(client
-            .get(url)
-            .headers(header_map) // add spoofed headers to emulate human behavior
-            .send()
+        let request = build_request(client, url, header_map, self.request_template())?;
+        let response = client
+            .execute(with_request_timeout(request))
             .await
-            .change_context(EngineError::RequestError)?
-            .text()
-            .await
-            .change_context(EngineError::RequestError)?)
+            .map_err(request_error)?;
+        let content_type = check_content_type(&response, self.response_format());
+        let html = response.text().await.map_err(request_error)?;
+        capture_raw_response(html.as_bytes());
+        content_type?;
+        Ok(html)
     }
 
     /// This helper function fetches/requests the json search results from the upstream search engine as a vector of bytes.
@@ -109,23 +678,22 @@ pub trait SearchEngine: Sync + Send {
         client: &Client,
     ) -> Result<Vec<u8>, EngineError> {
         // fetch the json response from upstream search engine
-
-        Ok(client
-            .get(url)
-            .headers(header_map) // add spoofed headers to emulate human behavior
-            .send()
+        let request = build_request(client, url, header_map, self.request_template())?;
+        let response = client
+            .execute(with_request_timeout(request))
             .await
-            .change_context(EngineError::RequestError)?
-            .bytes()
-            .await
-            .change_context(EngineError::RequestError)?
-            .to_vec())
+            .map_err(request_error)?;
+        let content_type = check_content_type(&response, self.response_format());
+        let bytes = response.bytes().await.map_err(request_error)?;
+        capture_raw_response(&bytes);
+        content_type?;
+        Ok(bytes.to_vec())
     }
 
     /// This function scrapes results from the upstream engine and puts all the scraped results like
     /// title, visiting_url (href in html),engine (from which engine it was fetched from) and description
-    /// in a RawSearchResult and then adds that to HashMap whose keys are url and values are RawSearchResult
-    /// struct and then returns it within a Result enum.
+    /// in a RawSearchResult and then adds that to a vector of url and RawSearchResult pairs, in the order
+    /// in which they were ranked by the upstream engine, and then returns it within a Result enum.
     ///
     /// # Arguments
     ///
@@ -147,7 +715,65 @@ pub trait SearchEngine: Sync + Send {
         user_agent: &str,
         client: &Client,
         safe_search: u8,
-    ) -> Result<HashMap<String, SearchResult>, EngineError>;
+    ) -> Result<Vec<(String, SearchResult)>, EngineError>;
+
+    /// This function scrapes the news results from the news vertical of the upstream engine, just
+    /// like the `results` function scrapes the general results. The engines which provide a news
+    /// vertical also report the `categories` feature in their capabilities.
+    ///
+    /// # Errors
+    ///
+    /// By default the upstream engine is assumed to have no news vertical, so an
+    /// `EngineError::UnexpectedError` is returned.
+    async fn news_results(
+        &self,
+        _query: &str,
+        _page: u32,
+        _user_agent: &str,
+        _client: &Client,
+        _safe_search: u8,
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        Err(Report::new(EngineError::UnexpectedError)
+            .attach_printable("the upstream search engine does not provide news results"))
+    }
+
+    /// This function returns the format of the responses expected from the upstream search
+    /// engine, which the content type of the responses is checked against. By default the
+    /// results are assumed to be provided as an html page.
+    fn response_format(&self) -> ResponseFormat {
+        ResponseFormat::Html
+    }
+
+    /// This function returns the template of the requests sent to the upstream search engine,
+    /// which describes whether the search is requested with a `GET` or a `POST` request along with
+    /// the body of the latter. By default the search is requested with a `GET` request.
+    fn request_template(&self) -> RequestTemplate {
+        RequestTemplate::Get
+    }
+
+    /// This function returns the scheme in which the upstream search engine paginates its
+    /// results, which the requested page is translated with into the value of the pagination
+    /// parameter of the engine (see `page_param`). By default the pages are assumed to be
+    /// numbered from `0`.
+    fn pagination(&self) -> Pagination {
+        Pagination::Page { first: 0 }
+    }
+
+    /// This function returns the default native values of the safe search parameter of the
+    /// upstream search engine for each safe search level, which can be overridden in the config.
+    /// By default the engine is assumed to not support safe search.
+    fn safe_search_values(&self) -> Option<[&'static str; SAFE_SEARCH_LEVELS]> {
+        None
+    }
+
+    /// This function returns the search features supported by the upstream search engine. By
+    /// default only pagination is assumed to be supported.
+    fn capabilities(&self) -> EngineCapabilities {
+        EngineCapabilities {
+            pagination: true,
+            ..Default::default()
+        }
+    }
 }
 
 /// A named struct which stores the engine struct with the name of the associated engine.
@@ -206,6 +832,14 @@ impl EngineHandler {
                     let engine = crate::engines::bing::Bing::new()?;
                     ("bing", Box::new(engine))
                 }
+                "json" => {
+                    let engine = crate::engines::json::Json::new()?;
+                    ("json", Box::new(engine))
+                }
+                "local" => {
+                    let engine = crate::engines::local::Local::new()?;
+                    ("local", Box::new(engine))
+                }
                 _ => {
                     return Err(Report::from(EngineError::NoSuchEngineFound(
                         engine_name.to_string(),
@@ -219,6 +853,22 @@ impl EngineHandler {
         })
     }
 
+    /// This function returns the name of the associated engine.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// This function returns the search features supported by the associated engine.
+    pub fn capabilities(&self) -> EngineCapabilities {
+        self.engine.capabilities()
+    }
+
+    /// This function returns the default native values of the safe search parameter of the engine
+    /// for each safe search level, if the engine supports safe search.
+    pub fn safe_search_values(&self) -> Option<[&'static str; SAFE_SEARCH_LEVELS]> {
+        self.engine.safe_search_values()
+    }
+
     /// This function converts the EngineHandler type into a tuple containing the engine name and
     /// the associated engine struct.
     pub fn into_name_engine(self) -> (&'static str, Box<dyn SearchEngine>) {
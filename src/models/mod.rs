@@ -3,5 +3,6 @@
 //! custom engine error for the search engine, etc.
 
 pub mod aggregation_models;
+pub mod analytics_models;
 pub mod engine_models;
 pub mod server_models;
@@ -2,9 +2,15 @@
 //! engine website.
 use std::borrow::Cow;
 
-use serde::Deserialize;
+use serde::{
+    de::{DeserializeOwned, IntoDeserializer},
+    Deserialize, Deserializer, Serialize,
+};
 
-use crate::config::style::Style;
+use crate::{
+    config::style::{ResultOrder, ResultsView, Style},
+    models::{aggregation_models::SearchResults, engine_models::Category},
+};
 
 /// A named struct which deserializes all the user provided search parameters and stores them.
 #[derive(Deserialize)]
@@ -13,11 +19,151 @@ pub struct SearchParams {
     /// of the search url.
     pub q: Option<String>,
     /// It stores the search parameter `page` (or pageno in simple words)
-    /// of the search url.
-    pub page: Option<u32>,
+    /// of the search url. The out of range pages (like `0`, the negative pages or the pages too
+    /// large to be numbered) are kept as they are, so that they can be rejected or clamped.
+    #[serde(default, deserialize_with = "deserialize_page")]
+    pub page: Option<i64>,
     /// It stores the search parameter `safesearch` (or safe search level in simple words) of the
     /// search url.
     pub safesearch: Option<u8>,
+    /// It stores the search parameter `template` (or the name of the template used to render the
+    /// search results) of the search url.
+    pub template: Option<String>,
+    /// It stores the search parameter `debug_engine` (or the name of the upstream search engine
+    /// to be debugged) of the search url, which is only used when the debug mode is enabled.
+    pub debug_engine: Option<String>,
+    /// It stores the search parameter `category` (or the category of the search results in
+    /// simple words) of the search url. An unknown category is treated as if no category was
+    /// provided, so that the general search results are shown.
+    #[serde(default, deserialize_with = "deserialize_known")]
+    pub category: Option<Category>,
+    /// It stores the search parameter `engines` (or the comma separated names of the upstream
+    /// search engines to be queried in simple words) of the search url, which overrides the
+    /// engines selected in the cookie for this search only.
+    pub engines: Option<String>,
+    /// It stores the search parameter `view` (or the view in which the general search results
+    /// are displayed in simple words) of the search url, which overrides the configured view. An
+    /// unknown view is treated as if no view was provided.
+    #[serde(default, deserialize_with = "deserialize_known")]
+    pub view: Option<ResultsView>,
+    /// It stores the search parameter `order` (or the order in which the general search results
+    /// are presented in simple words) of the search url, which overrides the configured order. An
+    /// unknown order is treated as if no order was provided.
+    #[serde(default, deserialize_with = "deserialize_known")]
+    pub order: Option<ResultOrder>,
+    /// It stores the search parameter `refine` (or the terms by which the search results are
+    /// narrowed down in simple words) of the search url, which filters the cached search results
+    /// of the query instead of querying the upstream search engines again.
+    pub refine: Option<String>,
+    /// It stores the search parameter `nocache` of the search url, which skips the cached search
+    /// results of the requested page when it is not `0`. It is only honored in debug mode or for
+    /// the requests providing the admin token.
+    pub nocache: Option<u8>,
+}
+
+/// A helper function which deserializes a search parameter which takes one of the known values
+/// (like the `category` search parameter), providing `None` for an unknown value instead of
+/// failing.
+///
+/// # Arguments
+///
+/// * `deserializer` - It takes the deserializer of the search parameter as an argument.
+fn deserialize_known<'de, D: Deserializer<'de>, T: DeserializeOwned>(
+    deserializer: D,
+) -> Result<Option<T>, D::Error> {
+    Ok(
+        Option::<String>::deserialize(deserializer)?.and_then(|value| {
+            T::deserialize(
+                IntoDeserializer::<serde::de::value::Error>::into_deserializer(value.as_str()),
+            )
+            .ok()
+        }),
+    )
+}
+
+/// A helper function which deserializes the `page` search parameter, which takes any integer.
+/// The integers too large to be represented are saturated, so that only the values which are not
+/// integers at all fail to be deserialized.
+///
+/// # Arguments
+///
+/// * `deserializer` - It takes the deserializer of the search parameter as an argument.
+fn deserialize_page<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i64>, D::Error> {
+    let Some(value) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    if let Ok(page) = value.parse() {
+        return Ok(Some(page));
+    }
+
+    let (digits, saturated) = match value.strip_prefix('-') {
+        Some(digits) => (digits, i64::MIN),
+        None => (value.strip_prefix('+').unwrap_or(&value), i64::MAX),
+    };
+    match !digits.is_empty() && digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        true => Ok(Some(saturated)),
+        false => Err(serde::de::Error::custom(format!(
+            "invalid page number `{value}`"
+        ))),
+    }
+}
+
+/// A named struct which deserializes the parameters of the thumbnail proxy url.
+#[derive(Deserialize)]
+pub struct ThumbnailParams {
+    /// It stores the parameter `url` (or the url of the result page whose thumbnail is fetched
+    /// in simple words) of the thumbnail proxy url.
+    pub url: String,
+    /// It stores the parameter `token` of the thumbnail proxy url, which signs the url so that
+    /// only the thumbnails of the search results can be fetched.
+    #[serde(default)]
+    pub token: String,
+}
+
+/// A named struct which deserializes the parameters of the autocomplete url.
+#[derive(Deserialize)]
+pub struct AutocompleteParams {
+    /// It stores the parameter `q` (or the partially typed search query in simple words) of the
+    /// autocomplete url.
+    #[serde(default)]
+    pub q: String,
+}
+
+/// A named struct which deserializes the parameters of the favicon proxy url.
+#[derive(Deserialize)]
+pub struct FaviconParams {
+    /// It stores the parameter `host` (or the host of the website whose favicon is fetched in
+    /// simple words) of the favicon proxy url.
+    pub host: String,
+    /// It stores the parameter `token` of the favicon proxy url, which signs the host so that
+    /// only the favicons of the websites of the search results can be fetched.
+    #[serde(default)]
+    pub token: String,
+}
+
+/// A named struct which deserializes the parameters of the click-through redirect url.
+#[derive(Deserialize)]
+pub struct RedirectParams {
+    /// It stores the parameter `url` (or the url of the visited search result in simple words)
+    /// of the redirect url.
+    pub url: String,
+    /// It stores the parameter `token` of the redirect url, which signs the url so that only
+    /// the urls of the search results can be redirected to.
+    pub token: String,
+}
+
+/// The outcome of searching one of the queries of a batch search, which is serialized as the
+/// search results of the query or as an object holding the reason why the search failed.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum BatchSearchResult {
+    /// The search results of the query.
+    Results(Box<SearchResults>),
+    /// The search of the query failed.
+    Error {
+        /// The reason why the search failed, which does not leak any details about the server.
+        error: &'static str,
+    },
 }
 
 /// A named struct which is used to deserialize the cookies fetched from the client side.
@@ -46,3 +192,27 @@ impl<'a> Cookie<'a> {
         }
     }
 }
+
+/// A named struct which stores the build information of the app, which is returned by the
+/// version endpoint.
+#[derive(Serialize)]
+pub struct VersionInfo {
+    /// The version of the app.
+    pub version: &'static str,
+    /// The short hash of the git commit the app was built from, if it is known.
+    pub commit: Option<&'static str>,
+    /// The names of the cargo features the app was compiled with.
+    pub features: Vec<&'static str>,
+    /// The names of the upstream search engines which are enabled by default or forced.
+    pub engines: Vec<String>,
+}
+
+/// A named struct which stores the outcome of reloading the config, which is returned by the
+/// config reload admin endpoint.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigReloadReport {
+    /// The names of the options which were changed but which only take effect once the server is
+    /// restarted, so they keep their current values until then.
+    pub restart_required: Vec<&'static str>,
+}
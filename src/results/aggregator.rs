@@ -1,29 +1,93 @@
 //! This module provides the functionality to scrape and gathers all the results from the upstream
 //! search engines and then removes duplicate results.
 
+use super::cursors::{cursor_key, with_cursor};
+use super::engine_stats;
+use super::featured_snippet::featured_snippet;
+use super::filter_lists::{allowlist, blocklist, is_sensitive, read_patterns, FilterList};
+use super::hsts::preload_list;
+use super::rate_limits;
+use super::sanitizer::sanitize_results;
+use super::thumbnails::add_thumbnails;
 use super::user_agent::random_user_agent;
+use crate::config::{
+    search::{HttpResults, MissingSnippets, QueryRewriteRule, SafeSearchTerms},
+    server::{EngineRetries, Ranking},
+    shared::Reloadable,
+    Config,
+};
 use crate::handler::{file_path, FileType};
 use crate::models::{
-    aggregation_models::{EngineErrorInfo, SearchResult, SearchResults},
-    engine_models::{EngineError, EngineHandler},
+    aggregation_models::{
+        Answer, AnswerContent, EngineErrorInfo, EngineGroup, EnginePosition, FeaturedSnippet,
+        RankingDetails, SearchResult, SearchResults,
+    },
+    engine_models::{
+        Category, EngineDebugInfo, EngineError, EngineHandler, Pagination, ANSWERS, JSON_SOURCE,
+        LOCAL_SOURCE, RAW_RESPONSE, REQUEST_TIMEOUT, RESULT_COUNT, SAFE_SEARCH_VALUES,
+        VALIDATE_CONTENT_TYPE,
+    },
 };
+use crate::server::{error::SearchError, redaction::REDACTED};
 use error_stack::Report;
-use regex::Regex;
-use reqwest::{Client, ClientBuilder};
-use std::time::{SystemTime, UNIX_EPOCH};
+use regex::{NoExpand, Regex, RegexBuilder};
+use reqwest::{header::HeaderMap, Client, ClientBuilder, Url};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use std::{
-    collections::HashMap,
-    io::{BufReader, Read},
+    borrow::Cow,
+    cell::RefCell,
+    collections::{hash_map::Entry, BTreeMap, HashMap},
+    future::Future,
+    io::BufReader,
+    sync::Arc,
     time::Duration,
 };
 use std::{fs::File, io::BufRead};
 use tokio::task::JoinHandle;
 
-/// A constant for holding the prebuilt Client globally in the app.
-static CLIENT: std::sync::OnceLock<Client> = std::sync::OnceLock::new();
+/// A constant for holding the prebuilt Clients globally in the app, which are built again once the
+/// config is reloaded.
+static CLIENTS: Reloadable<Clients> = Reloadable::new();
+
+/// A static variable which stores the parsed pinned results file, which is parsed again once the
+/// config is reloaded.
+static PINNED_RESULTS: Reloadable<Vec<(Regex, SearchResult)>> = Reloadable::new();
+
+/// A named struct which stores the prebuilt clients used to send the requests to the upstream
+/// search engines.
+struct Clients {
+    /// The client shared by the upstream engines without any custom headers.
+    shared: Client,
+    /// The clients of the upstream engines which have custom headers configured, keyed by the
+    /// name of the engine.
+    engines: HashMap<String, Client>,
+}
+
+impl Clients {
+    /// Returns the client used to send the requests to the provided upstream engine.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine` - It takes the name of the upstream engine as an argument.
+    fn get(&self, engine: &str) -> &Client {
+        self.engines.get(engine).unwrap_or(&self.shared)
+    }
+}
+
+/// The results returned by each upstream search engine in the order in which the engine ranked
+/// them, keyed by the name of the engine.
+pub type EngineResponses = HashMap<String, Vec<(String, SearchResult)>>;
 
 /// Aliases for long type annotations
-type FutureVec = Vec<JoinHandle<Result<HashMap<String, SearchResult>, Report<EngineError>>>>;
+type FutureVec = Vec<
+    JoinHandle<(
+        (
+            Result<Vec<(String, SearchResult)>, Report<EngineError>>,
+            Vec<AnswerContent>,
+        ),
+        Duration,
+    )>,
+>;
 
 /// The function aggregates the scraped results from the user-selected upstream search engines.
 /// These engines can be chosen either from the user interface (UI) or from the configuration file.
@@ -52,135 +116,1066 @@ type FutureVec = Vec<JoinHandle<Result<HashMap<String, SearchResult>, Report<Eng
 ///
 /// * `query` - Accepts a string to query with the above upstream search engines.
 /// * `page` - Accepts an u32 page number.
-/// * `random_delay` - Accepts a boolean value to add a random delay before making the request.
-/// * `debug` - Accepts a boolean value to enable or disable debug mode option.
+/// * `config` - Accepts the parsed config struct which provides the aggregator, debug and
+///   request timeout options.
 /// * `upstream_search_engines` - Accepts a vector of search engine names which was selected by the
-/// * `request_timeout` - Accepts a time (secs) as a value which controls the server request timeout.
-/// user through the UI or the config file.
+///   user through the UI or the config file. When fallback tiers are configured, the engines of
+///   each fallback tier are only queried while too few results were found.
+/// * `safe_search` - Accepts the safe search level to be used for the search request.
+/// * `category` - Accepts the category of the search results to be fetched from the upstream
+///   search engines. The news results are sorted by their publish time.
+/// * `engine_responses` - Accepts the cached responses of the upstream search engines, which are
+///   used instead of querying these engines. The successful responses of the queried engines are
+///   added to them when the engine responses are cached.
 ///
 /// # Error
 ///
-/// Returns an error a reqwest and scraping selector errors if any error occurs in the results
-/// function in either `searx` or `duckduckgo` or both otherwise returns a `SearchResults struct`
-/// containing appropriate values.
+/// Returns a `SearchResults struct` containing appropriate values. The errors of the upstream
+/// search engines are provided within the search results, and the malformed lines of the pinned
+/// results file are logged and skipped.
 pub async fn aggregate(
     query: &str,
     page: u32,
-    random_delay: bool,
-    debug: bool,
+    config: &Config,
     upstream_search_engines: &[EngineHandler],
-    request_timeout: u8,
     safe_search: u8,
-) -> Result<SearchResults, Box<dyn std::error::Error>> {
-    let client = CLIENT.get_or_init(|| {
-        ClientBuilder::new()
-            .timeout(Duration::from_secs(request_timeout as u64)) // Add timeout to request to avoid DDOSing the server
-            .https_only(true)
-            .gzip(true)
-            .brotli(true)
-            .build()
-            .unwrap()
-    });
+    category: Category,
+    engine_responses: &mut EngineResponses,
+) -> Result<SearchResults, SearchError> {
+    let clients = clients(config);
+    let engine_query = expand_synonyms(query, &config.search.query_synonyms);
 
     let user_agent: &str = random_user_agent();
 
     // Add a random delay before making the request.
-    if random_delay || !debug {
-        let nanos = SystemTime::now().duration_since(UNIX_EPOCH)?.subsec_nanos() as f32;
+    if config.server.aggregator.random_delay || !config.server.debug {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .subsec_nanos() as f32;
         let delay = ((nanos / 1_0000_0000 as f32).floor() as u64) + 1;
         tokio::time::sleep(Duration::from_secs(delay)).await;
     }
 
-    let mut names: Vec<&str> = Vec::with_capacity(0);
-
-    // create tasks for upstream result fetching
-    let mut tasks: FutureVec = FutureVec::new();
-
-    for engine_handler in upstream_search_engines {
-        let (name, search_engine) = engine_handler.to_owned().into_name_engine();
-        names.push(name);
-        let query: String = query.to_owned();
-        tasks.push(tokio::spawn(async move {
-            search_engine
-                .results(&query, page, user_agent, client, safe_search)
-                .await
-        }));
-    }
-
-    // get upstream responses
-    let mut responses = Vec::with_capacity(tasks.len());
-
-    for task in tasks {
-        if let Ok(result) = task.await {
-            responses.push(result)
-        }
-    }
-
     // aggregate search results, removing duplicates and handling errors the upstream engines returned
     let mut result_map: HashMap<String, SearchResult> = HashMap::new();
     let mut engine_errors_info: Vec<EngineErrorInfo> = Vec::new();
+    let mut answers: Vec<Answer> = Vec::new();
+    // An engine which returned an empty result set has not failed, so it is not counted, nor is
+    // an engine which was skipped to stay within its rate limit as it was not queried.
+    let mut failed_engines: usize = 0;
+    let mut queried_engines: usize = 0;
+    let mut timed_out = false;
+    let sensitive = is_sensitive(query, &config.search, config.generation);
+    let retries = config.server.aggregator.retries;
+    let request_timeout = Duration::from_secs(config.server.request_timeout as u64);
 
     let mut handle_error = |error: &Report<EngineError>, engine_name: &'static str| {
-        log::error!("Engine Error: {:?}", error);
+        log_engine_error(error, sensitive);
+        match error.current_context() {
+            EngineError::EmptyResultSet | EngineError::RateLimited => (),
+            EngineError::Timeout => {
+                failed_engines += 1;
+                timed_out = true;
+            }
+            _ => failed_engines += 1,
+        }
         engine_errors_info.push(EngineErrorInfo::new(
             error.downcast_ref::<EngineError>().unwrap(),
             engine_name,
         ));
     };
 
-    for _ in 0..responses.len() {
-        let response = responses.pop().unwrap();
-        let engine = names.pop().unwrap();
+    for tier in engine_tiers(
+        upstream_search_engines,
+        &config.search.fallback_engine_tiers,
+    ) {
+        // The next tier of engines is only queried while too few results were found.
+        if queried_engines != 0 && result_map.len() >= config.search.fallback_min_results {
+            break;
+        }
+
+        // create tasks for upstream result fetching
+        let mut dispatched = Vec::with_capacity(tier.len());
+
+        for index in tier_dispatch_order(&tier, config) {
+            let (name, search_engine) = tier[index].to_owned().into_name_engine();
+            // The cached responses of the engines are used as they are, without any request.
+            if let Some(results) = engine_responses.get(name).cloned() {
+                let task = tokio::spawn(async { ((Ok(results), Vec::new()), Duration::ZERO) });
+                dispatched.push((index, name, task));
+                continue;
+            }
+            // The engines which would exceed their rate limit are skipped, so that the search
+            // falls back to the other engines and to the next tiers of engines.
+            if !rate_limits::acquire(name, &config.search.engine_rate_limits) {
+                handle_error(&Report::new(EngineError::RateLimited), name);
+                continue;
+            }
+            let query: String = match config.search.query_rewrite_rules.get(name) {
+                Some(rules) => rewrite_query(&engine_query, rules),
+                None => engine_query.to_owned(),
+            };
+            let client = clients.get(name).clone();
+            // The cursors are only stored for the engines paginating with cursors.
+            let cursor_key = (search_engine.pagination() == Pagination::Cursor)
+                .then(|| cursor_key(name, &query, safe_search, category));
+            let task = tokio::spawn(with_engine_options(config, name, async move {
+                timed(collect_answers(with_cursor(cursor_key, page, async move {
+                    let (search_engine, query, client) = (&search_engine, &query, &client);
+                    with_retries(retries, request_timeout, move || async move {
+                        match category {
+                            Category::General => {
+                                search_engine
+                                    .results(query, page, user_agent, client, safe_search)
+                                    .await
+                            }
+                            Category::News => {
+                                search_engine
+                                    .news_results(query, page, user_agent, client, safe_search)
+                                    .await
+                            }
+                        }
+                    })
+                    .await
+                })))
+                .await
+            }));
+            dispatched.push((index, name, task));
+        }
+        queried_engines += dispatched.len();
+
+        // The tasks are put back in the order of the engines, so that the dispatch order does not
+        // affect the order in which the results are aggregated.
+        dispatched.sort_by_key(|(index, _, _)| *index);
+        let (mut names, tasks): (Vec<&str>, FutureVec) = dispatched
+            .into_iter()
+            .map(|(_, name, task)| (name, task))
+            .unzip();
+
+        // get upstream responses
+        let mut responses = Vec::with_capacity(tasks.len());
+
+        for task in tasks {
+            if let Ok(result) = task.await {
+                responses.push(result)
+            }
+        }
+
+        for _ in 0..responses.len() {
+            let ((response, engine_answers), latency) = responses.pop().unwrap();
+            let engine = names.pop().unwrap();
+            if !engine_responses.contains_key(engine) {
+                engine_stats::record(engine, &response, latency, &config.server.engine_stats);
+                add_engine_response(engine_responses, engine, &response, config);
+            }
+            // The responses are handled from the last engine on, so the answers are prepended
+            // to keep them in the order of the engines.
+            answers.splice(
+                0..0,
+                engine_answers.into_iter().map(|content| Answer {
+                    engine: engine.to_owned(),
+                    content,
+                }),
+            );
 
-        if result_map.is_empty() {
             match response {
                 Ok(results) => {
-                    result_map = results.clone();
+                    let results = sanitize_results(results, config.search.snippet_html);
+                    merge_results(&mut result_map, engine, upgrade_to_https(results, config))
                 }
                 Err(error) => {
                     handle_error(&error, engine);
                 }
             }
-            continue;
-        }
-
-        match response {
-            Ok(result) => {
-                result.into_iter().for_each(|(key, value)| {
-                    result_map
-                        .entry(key)
-                        .and_modify(|result| {
-                            result.add_engines(engine);
-                        })
-                        .or_insert_with(|| -> SearchResult { value });
-                });
-            }
-            Err(error) => {
-                handle_error(&error, engine);
-            }
         }
     }
 
     if safe_search >= 3 {
         let mut blacklist_map: HashMap<String, SearchResult> = HashMap::new();
-        filter_with_lists(
+        filter_with_list(
             &mut result_map,
             &mut blacklist_map,
-            file_path(FileType::BlockList)?,
-        )?;
+            &blocklist(&config.search, config.generation),
+        );
 
-        filter_with_lists(
+        filter_with_list(
             &mut blacklist_map,
             &mut result_map,
-            file_path(FileType::AllowList)?,
-        )?;
+            &allowlist(&config.search, config.generation),
+        );
 
         drop(blacklist_map);
     }
 
-    let results: Vec<SearchResult> = result_map.into_values().collect();
+    let mut results: Vec<SearchResult> = Vec::with_capacity(result_map.len());
+
+    // Pinned results are only shown at the top of the first page.
+    if page == 0 {
+        if let Ok(pinned_results_file_path) = file_path(FileType::PinnedResults) {
+            let pinned = PINNED_RESULTS.get_or_build(config.generation, || {
+                load_pinned_results(pinned_results_file_path).unwrap_or_else(|error| {
+                    log::error!("Failed to load the pinned results file: {error}");
+                    Vec::new()
+                })
+            });
+            for pinned_result in pinned_results(&pinned, query) {
+                result_map.remove(&pinned_result.url);
+                results.push(pinned_result);
+            }
+        }
+    }
+
+    let mut ranked_results: Vec<SearchResult> = result_map.into_values().collect();
+    if config.server.aggregator.stable_ordering {
+        order_ties(&mut ranked_results);
+    }
+    rank_results(
+        &mut ranked_results,
+        config.server.aggregator.ranking,
+        |engine| config.search.category_engine_weight(engine, category),
+    );
+    apply_domain_reputation(&mut ranked_results, &config.search.domain_reputation);
+    if category == Category::News {
+        sort_by_recency(&mut ranked_results);
+    }
+    handle_http_results(&mut ranked_results, config.search.http_results);
+    handle_missing_snippets(&mut ranked_results, config.search.missing_snippets);
+
+    // The engine groups are built before the ranking details are dropped, after the pinned
+    // results which are not provided by any engine.
+    let engine_groups = group_by_engine(&ranked_results, results.len());
+
+    // The ranking details are only provided in debug mode to keep the results lean otherwise.
+    if !config.server.debug {
+        ranked_results
+            .iter_mut()
+            .for_each(|result| result.ranking = None);
+    }
+
+    results.extend(ranked_results);
+
+    let snippet = fetch_result_pages(&mut results, config, page, category).await;
+
+    sort_engine_errors(&mut engine_errors_info, &config.search.engine_priority);
+    let omitted_engine_errors =
+        cap_engine_errors(&mut engine_errors_info, config.search.max_engine_errors);
+
+    let mut search_results = SearchResults::new(results, &engine_errors_info);
+    search_results.set_omitted_engine_errors(omitted_engine_errors);
+    search_results.set_engine_groups(engine_groups);
+
+    if let Some(snippet) = snippet {
+        search_results.set_featured_snippet(snippet);
+    }
+
+    if page == 0 {
+        search_results.set_answers(unique_answers(answers));
+    }
+
+    set_failure_flags(
+        &mut search_results,
+        failed_engines,
+        queried_engines,
+        timed_out,
+        config.server.aggregator.engine_failure_tolerance,
+    );
+
+    Ok(search_results)
+}
+
+/// A helper function which flags the aggregated search results as degraded, timed out or as an
+/// outage of the upstream search engines depending on the failures of the queried engines.
+///
+/// # Arguments
+///
+/// * `search_results` - It takes the aggregated search results to be flagged as an argument.
+/// * `failed_engines` - It takes the number of upstream search engines that failed.
+/// * `queried_engines` - It takes the number of upstream search engines that were queried.
+/// * `timed_out` - It takes whether the aggregation timed out before all the engines answered.
+/// * `tolerance` - It takes the fraction of the engines that are allowed to fail.
+fn set_failure_flags(
+    search_results: &mut SearchResults,
+    failed_engines: usize,
+    queried_engines: usize,
+    timed_out: bool,
+    tolerance: f32,
+) {
+    if is_degraded(failed_engines, queried_engines, tolerance) {
+        search_results.set_degraded();
+    }
+
+    if timed_out {
+        search_results.set_timed_out();
+    }
+
+    // A total outage of the upstream search engines is told apart from a search which genuinely
+    // has no search results, the engines which found no search results not being failures.
+    if queried_engines != 0 && failed_engines == queried_engines {
+        search_results.set_upstream_failure();
+    }
+}
+
+/// A helper function which adds the thumbnails of the top ranked search results and fetches the
+/// page of the top ranked search result to extract the featured snippet, when these are enabled.
+/// The thumbnails are only looked for once they are loaded, and the featured snippet is only
+/// extracted for the general web results of the first page.
+///
+/// # Arguments
+///
+/// * `results` - It takes the ranked search results as an argument.
+/// * `config` - It takes the parsed config struct which provides the thumbnail and the featured
+///   snippet options.
+/// * `page` - It takes the page number of the search as an argument.
+/// * `category` - It takes the category of the search results as an argument.
+///
+/// # Returns
+///
+/// Returns the featured snippet, or `None` if it is disabled or if none could be extracted.
+async fn fetch_result_pages(
+    results: &mut [SearchResult],
+    config: &Config,
+    page: u32,
+    category: Category,
+) -> Option<FeaturedSnippet> {
+    if config.server.thumbnails.enabled {
+        add_thumbnails(results, &config.server.thumbnails);
+    }
+
+    if !config.server.featured_snippet.enabled || page != 0 || category != Category::General {
+        return None;
+    }
+    featured_snippet(results, &config.server.featured_snippet, config.generation).await
+}
+
+/// A helper function which builds the http client used to send the requests to the upstream
+/// search engines.
+///
+/// # Arguments
+///
+/// * `config` - It takes the parsed config struct which provides the request timeout and the
+///   minimum TLS version.
+/// * `default_headers` - It takes the headers to be sent along with every request.
+fn build_client(config: &Config, default_headers: HeaderMap) -> Client {
+    let mut client = ClientBuilder::new()
+        .timeout(Duration::from_secs(config.server.request_timeout as u64)) // Add timeout to request to avoid DDOSing the server
+        .https_only(true)
+        .gzip(true)
+        .brotli(true)
+        .default_headers(default_headers);
+    // The engines which do not support the minimum TLS version fail to connect, which is
+    // reported as a request error of the engine.
+    if let Some(min_tls_version) = config.server.aggregator.min_tls_version {
+        client = client.min_tls_version(min_tls_version.into());
+    }
+    client.build().unwrap()
+}
+
+/// A helper function which returns the prebuilt shared client along with the prebuilt clients of
+/// the upstream engines which have custom headers configured, building them on the first call
+/// and again after the config was reloaded.
+///
+/// # Arguments
+///
+/// * `config` - It takes the parsed config struct which provides the client options.
+fn clients(config: &Config) -> Arc<Clients> {
+    CLIENTS.get_or_build(config.generation, || Clients {
+        shared: build_client(config, HeaderMap::new()),
+        // The custom headers are sent as the default headers of a dedicated client for each
+        // engine, so the headers set by the engine itself take precedence.
+        engines: config
+            .search
+            .engine_headers
+            .iter()
+            .map(|(engine, headers)| {
+                (
+                    engine.to_owned(),
+                    build_client(config, headers.header_map()),
+                )
+            })
+            .collect(),
+    })
+}
+
+/// Queries a single upstream search engine without any delay, capturing its raw response along
+/// with the parsed results or the error which occurred. It is used to debug the engine scrapers
+/// when the upstream search engines change their response format.
+///
+/// # Arguments
+///
+/// * `query` - Accepts a string to query the upstream search engine with.
+/// * `page` - Accepts an u32 page number.
+/// * `config` - Accepts the parsed config struct which provides the client and query rewrite
+///   options.
+/// * `engine` - Accepts the name of the upstream search engine to be debugged.
+/// * `safe_search` - Accepts the safe search level to be used for the search request.
+pub async fn debug_engine(
+    query: &str,
+    page: u32,
+    config: &Config,
+    engine: &str,
+    safe_search: u8,
+) -> EngineDebugInfo {
+    // The denied engines are not queried even in the debug mode.
+    if config.search.is_denied_engine(engine) {
+        return EngineDebugInfo {
+            engine: engine.to_owned(),
+            results: Vec::new(),
+            error: Some("The engine is denied by the instance".to_owned()),
+            raw_response: None,
+        };
+    }
+
+    let (name, search_engine) = match EngineHandler::new(engine) {
+        Ok(engine_handler) => engine_handler.into_name_engine(),
+        Err(error) => {
+            return EngineDebugInfo {
+                engine: engine.to_owned(),
+                results: Vec::new(),
+                error: Some(format!("{error:?}")),
+                raw_response: None,
+            }
+        }
+    };
+
+    let clients = clients(config);
+    let client = clients.get(name);
+    let query = expand_synonyms(query, &config.search.query_synonyms);
+    let query: String = match config.search.query_rewrite_rules.get(name) {
+        Some(rules) => rewrite_query(&query, rules),
+        None => query,
+    };
+
+    RAW_RESPONSE
+        .scope(RefCell::new(None), async {
+            let response = with_engine_options(
+                config,
+                name,
+                search_engine.results(&query, page, random_user_agent(), client, safe_search),
+            )
+            .await;
+            let (results, error) = match response {
+                Ok(results) => (
+                    results.into_iter().map(|(_, result)| result).collect(),
+                    None,
+                ),
+                Err(error) => (Vec::new(), Some(format!("{error:?}"))),
+            };
+            EngineDebugInfo {
+                engine: name.to_owned(),
+                results,
+                error,
+                raw_response: RAW_RESPONSE.with(|raw_response| raw_response.take()),
+            }
+        })
+        .await
+}
+
+/// Splits the provided upstream search engines into the tiers in which they are queried. The
+/// engines which are not part of any of the fallback tiers form the first tier, followed by the
+/// engines of each of the fallback tiers in order. The tiers without any of the provided engines
+/// are left out, so all the engines form a single tier when no fallback tiers are configured.
+///
+/// # Arguments
+///
+/// * `engines` - The upstream search engines to be queried.
+/// * `fallback_tiers` - The names of the engines in each of the fallback tiers.
+pub fn engine_tiers<'a>(
+    engines: &'a [EngineHandler],
+    fallback_tiers: &[Vec<String>],
+) -> Vec<Vec<&'a EngineHandler>> {
+    let mut tiers: Vec<Vec<&EngineHandler>> = vec![Vec::new(); fallback_tiers.len() + 1];
+    for engine in engines {
+        let tier = fallback_tiers
+            .iter()
+            .position(|tier| tier.iter().any(|name| name == engine.name()))
+            .map_or(0, |tier| tier + 1);
+        tiers[tier].push(engine);
+    }
+    tiers.retain(|tier| !tier.is_empty());
+    tiers
+}
+
+/// Provides the order in which the requests to the upstream search engines of a tier are
+/// dispatched, as the indices of the engines. The engines are dispatched in their given order
+/// unless a seed is provided, in which case the order is shuffled with a pseudo random generator
+/// seeded with it, so the same seed always provides the same order.
+///
+/// # Arguments
+///
+/// * `engines` - The number of the upstream search engines to be dispatched.
+/// * `seed` - The optional seed used to shuffle the dispatch order.
+pub fn dispatch_order(engines: usize, seed: Option<u64>) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..engines).collect();
+
+    if let Some(mut state) = seed {
+        // A Fisher-Yates shuffle driven by the splitmix64 generator.
+        for index in (1..order.len()).rev() {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut random = state;
+            random = (random ^ (random >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            random = (random ^ (random >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            random ^= random >> 31;
+            order.swap(index, (random % (index as u64 + 1)) as usize);
+        }
+    }
+
+    order
+}
+
+/// Sorts the dispatch order of the upstream search engines of a tier by their average latency,
+/// so that the fastest engines are dispatched first. The sort is stable, so the engines with the
+/// same latency keep their relative order and the engines whose latency has not been measured
+/// yet are dispatched last.
+///
+/// # Arguments
+///
+/// * `order` - The dispatch order to be sorted, as the indices of the engines.
+/// * `latencies` - The average latency (in milliseconds) of each engine, if it was measured.
+pub fn order_by_latency(order: &mut [usize], latencies: &[Option<u64>]) {
+    order.sort_by_key(|&index| latencies[index].unwrap_or(u64::MAX));
+}
+
+/// A helper function which provides the order in which the requests to the upstream search
+/// engines of a tier are dispatched. The requests are dispatched in a shuffled order if
+/// configured, so that the upstream engines are not always contacted in the same order, and
+/// sorted by the measured latency of the engines if configured.
+///
+/// # Arguments
+///
+/// * `tier` - It takes the upstream search engines of the tier as an argument.
+/// * `config` - It takes the parsed config as an argument.
+fn tier_dispatch_order(tier: &[&EngineHandler], config: &Config) -> Vec<usize> {
+    let seed = config.server.aggregator.shuffle_engines.then(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64
+    });
+    let mut order = dispatch_order(tier.len(), seed);
+
+    if config.server.aggregator.fastest_engines_first {
+        let latencies = engine_stats::average_latencies(&config.server.engine_stats);
+        let latencies: Vec<Option<u64>> = tier
+            .iter()
+            .map(|engine| latencies.get(&engine.name().to_lowercase()).copied())
+            .collect();
+        order_by_latency(&mut order, &latencies);
+    }
+
+    order
+}
+
+/// A helper function which wraps a request to an upstream search engine in the scope of the task
+/// locals providing the engine specific options of the request, like the number of requested
+/// results and the native safe search values of the engine.
+///
+/// # Arguments
+///
+/// * `config` - It takes the parsed config which provides the engine specific options as an
+///   argument.
+/// * `engine` - It takes the name of the upstream search engine as an argument.
+/// * `request` - It takes the future querying the engine as an argument.
+fn with_engine_options<F: Future>(
+    config: &Config,
+    engine: &str,
+    request: F,
+) -> impl Future<Output = F::Output> {
+    RESULT_COUNT.scope(
+        config.search.engine_result_count(engine),
+        SAFE_SEARCH_VALUES.scope(
+            config.search.safe_search_mapping(engine),
+            VALIDATE_CONTENT_TYPE.scope(
+                config.server.aggregator.validate_content_type,
+                JSON_SOURCE.scope(
+                    (engine == "json").then(|| config.search.json_source.clone()),
+                    LOCAL_SOURCE.scope(
+                        (engine == "local")
+                            .then(|| (config.search.local_source.clone(), config.generation)),
+                        request,
+                    ),
+                ),
+            ),
+        ),
+    )
+}
+
+/// A helper function which retries the provided request to an upstream search engine while it
+/// fails with a transient error, like a timeout or a connection failure, the timeout of each retry
+/// being escalated as configured. The retries stop once the deadline of the engine is reached, so
+/// that a struggling engine can not hold up the whole search.
+///
+/// # Arguments
+///
+/// * `retries` - It takes the retry options of the aggregator as an argument.
+/// * `request_timeout` - It takes the request timeout of the server as an argument.
+/// * `request` - It takes the function creating the future querying the engine as an argument.
+///
+/// # Returns
+///
+/// Returns the output of the last request to the engine.
+async fn with_retries<T, F, Fut>(
+    retries: EngineRetries,
+    request_timeout: Duration,
+    request: F,
+) -> Result<T, Report<EngineError>>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, Report<EngineError>>>,
+{
+    if retries.attempts == 0 {
+        return request().await;
+    }
+
+    let started = Instant::now();
+    let mut timeout = retry_timeout(request_timeout, &retries, 0, Duration::ZERO);
+    let mut attempt: u8 = 0;
+    loop {
+        let result = match timeout {
+            Some(timeout) => {
+                tokio::time::timeout(timeout, REQUEST_TIMEOUT.scope(timeout, request()))
+                    .await
+                    .unwrap_or_else(|_| Err(Report::new(EngineError::Timeout)))
+            }
+            None => return Err(Report::new(EngineError::Timeout)),
+        };
+        attempt += 1;
+        let transient = result.as_ref().is_err_and(|error| {
+            matches!(
+                error.current_context(),
+                EngineError::Timeout | EngineError::RequestError
+            )
+        });
+        if !transient || attempt > retries.attempts {
+            return result;
+        }
+        match retry_timeout(request_timeout, &retries, attempt, started.elapsed()) {
+            Some(next_timeout) => timeout = Some(next_timeout),
+            None => return result,
+        }
+    }
+}
+
+/// Computes the timeout of a request to an upstream search engine, which is the request timeout
+/// of the server multiplied by the escalation factor once for each previous attempt, capped by the
+/// maximum timeout and by the time left before the deadline.
+///
+/// # Arguments
+///
+/// * `request_timeout` - It takes the request timeout of the server as an argument.
+/// * `retries` - It takes the retry options of the aggregator as an argument.
+/// * `attempt` - It takes the number of the previous attempts as an argument.
+/// * `elapsed` - It takes the time spent on the previous attempts as an argument.
+///
+/// # Returns
+///
+/// Returns the timeout of the request, or `None` when the deadline has been reached.
+fn retry_timeout(
+    request_timeout: Duration,
+    retries: &EngineRetries,
+    attempt: u8,
+    elapsed: Duration,
+) -> Option<Duration> {
+    let mut timeout =
+        request_timeout.mul_f32(retries.timeout_escalation.max(1.0).powi(attempt as i32));
+    if retries.max_timeout != 0 {
+        timeout = timeout.min(Duration::from_secs(retries.max_timeout as u64));
+    }
+    if retries.deadline != 0 {
+        let left = Duration::from_secs(retries.deadline as u64).checked_sub(elapsed)?;
+        if left.is_zero() {
+            return None;
+        }
+        timeout = timeout.min(left);
+    }
+    Some(timeout)
+}
+
+/// A helper function which collects the structured answers provided by the upstream search
+/// engine while it is queried by the provided future.
+///
+/// # Arguments
+///
+/// * `request` - It takes the future querying the engine as an argument.
+///
+/// # Returns
+///
+/// Returns the output of the future along with the answers provided by the engine.
+async fn collect_answers<F: Future>(request: F) -> (F::Output, Vec<AnswerContent>) {
+    ANSWERS
+        .scope(RefCell::new(Vec::new()), async move {
+            let output = request.await;
+            (output, ANSWERS.with(|answers| answers.take()))
+        })
+        .await
+}
+
+/// A helper function which measures the time taken by the provided future querying an upstream
+/// search engine.
+///
+/// # Arguments
+///
+/// * `request` - It takes the future querying the engine as an argument.
+///
+/// # Returns
+///
+/// Returns the output of the future along with the time it took to complete.
+async fn timed<F: Future>(request: F) -> (F::Output, Duration) {
+    let started = Instant::now();
+    let output = request.await;
+    (output, started.elapsed())
+}
+
+/// Keeps only the first of the structured answers of each kind, so that a single definition and
+/// a single weather forecast are displayed when several upstream search engines provide them.
+///
+/// # Arguments
+///
+/// * `answers` - It takes the answers in the order of the engines as an argument.
+pub fn unique_answers(mut answers: Vec<Answer>) -> Vec<Answer> {
+    let mut kinds = Vec::new();
+    answers.retain(|answer| {
+        let kind = std::mem::discriminant(&answer.content);
+        let first = !kinds.contains(&kind);
+        if first {
+            kinds.push(kind);
+        }
+        first
+    });
+    answers
+}
+
+/// A helper function which logs the error of an upstream search engine. The details of the error
+/// hold the urls of the upstream requests which contain the search query, so only the kind of the
+/// error is logged when the search query matches any of the sensitive terms.
+///
+/// # Arguments
+///
+/// * `error` - It takes the error of the upstream search engine as an argument.
+/// * `sensitive` - It takes whether the search query is sensitive as an argument.
+fn log_engine_error(error: &Report<EngineError>, sensitive: bool) {
+    match sensitive {
+        true => log::error!("Engine Error: {} {REDACTED}", error.current_context()),
+        false => log::error!("Engine Error: {:?}", error),
+    }
+}
+
+/// A helper function which adds the successful response of a queried upstream search engine to
+/// the responses of the engines, when the engine responses are cached.
+///
+/// # Arguments
+///
+/// * `engine_responses` - It takes the responses of the upstream search engines as an argument.
+/// * `engine` - It takes the name of the upstream search engine as an argument.
+/// * `response` - It takes the results or the error returned by the engine as an argument.
+/// * `config` - It takes a parsed config struct.
+fn add_engine_response(
+    engine_responses: &mut EngineResponses,
+    engine: &str,
+    response: &Result<Vec<(String, SearchResult)>, Report<EngineError>>,
+    config: &Config,
+) {
+    if let (true, Ok(results)) = (config.caching.cache_engine_responses, response) {
+        engine_responses.insert(engine.to_owned(), results.to_owned());
+    }
+}
+
+/// A helper function which merges the search results of an upstream search engine into the
+/// aggregated search results, recording the position at which the engine returned each result
+/// and merging the results which were already returned by another engine.
+///
+/// # Arguments
+///
+/// * `result_map` - It takes the aggregated search results keyed by their url as an argument.
+/// * `engine` - It takes the name of the upstream search engine as an argument.
+/// * `results` - It takes the search results of the engine keyed by their url as an argument.
+fn merge_results(
+    result_map: &mut HashMap<String, SearchResult>,
+    engine: &str,
+    results: Vec<(String, SearchResult)>,
+) {
+    for (position, (key, value)) in results.into_iter().enumerate() {
+        let engine_position = EnginePosition {
+            engine: engine.to_owned(),
+            position: position as u32,
+        };
+        match result_map.entry(key) {
+            Entry::Occupied(mut entry) => merge_duplicate(entry.get_mut(), value, engine_position),
+            Entry::Vacant(entry) => {
+                entry.insert(SearchResult {
+                    ranking: Some(RankingDetails {
+                        score: Default::default(),
+                        engine_positions: vec![engine_position],
+                    }),
+                    ..value
+                });
+            }
+        }
+    }
+}
+
+/// A helper function which upgrades the `http` urls of the search results of an upstream search
+/// engine to `https` when their host is on the HSTS preload list, if configured. The results are
+/// upgraded before they are merged, so that the `http` and `https` copies of a result are treated
+/// as duplicates.
+///
+/// # Arguments
+///
+/// * `results` - It takes the search results of the engine keyed by their url as an argument.
+/// * `config` - It takes a parsed config struct.
+fn upgrade_to_https(
+    mut results: Vec<(String, SearchResult)>,
+    config: &Config,
+) -> Vec<(String, SearchResult)> {
+    if !config.search.https_upgrade.enabled {
+        return results;
+    }
+
+    let preload_list = preload_list(&config.search.https_upgrade, config.generation);
+    for (key, result) in results.iter_mut() {
+        if let Some(url) = preload_list.upgrade(&result.url) {
+            *key = url.clone();
+            result.url = url;
+        }
+    }
+    results
+}
+
+/// A helper function which merges a duplicate of a search result provided by another upstream
+/// search engine into the search result. The description of the duplicate is kept when the search
+/// result has none, so that the result is described whenever any of the engines described it.
+///
+/// # Arguments
+///
+/// * `result` - A mutable reference to the aggregated search result.
+/// * `duplicate` - It takes the duplicate search result as an argument.
+/// * `engine_position` - It takes the engine which provided the duplicate and the position of the
+///   duplicate within the results of the engine as an argument.
+fn merge_duplicate(
+    result: &mut SearchResult,
+    duplicate: SearchResult,
+    engine_position: EnginePosition,
+) {
+    // Skip the duplicate results provided by the same engine.
+    if result.engine.contains(&engine_position.engine) {
+        return;
+    }
+
+    if result.description.trim().is_empty() {
+        result.description = duplicate.description;
+    }
+    result.add_engines(&engine_position.engine);
+    result
+        .ranking
+        .get_or_insert_with(Default::default)
+        .engine_positions
+        .push(engine_position);
+}
+
+/// A helper function which checks whether the fraction of the failed upstream search engines
+/// exceeds the provided tolerance.
+///
+/// # Arguments
+///
+/// * `failed_engines` - It takes the number of upstream search engines that failed.
+/// * `selected_engines` - It takes the number of upstream search engines that were queried.
+/// * `tolerance` - It takes the fraction of the engines that are allowed to fail.
+fn is_degraded(failed_engines: usize, selected_engines: usize, tolerance: f32) -> bool {
+    selected_engines != 0 && failed_engines as f32 / selected_engines as f32 > tolerance
+}
+
+/// Computes the rank score of each of the provided search results and sorts them in descending
+/// order of their scores.
+///
+/// The reciprocal rank score of a result is the sum of the reciprocal of its (one based) position
+/// in the results of each upstream engine that returned it, so results ranked highly by multiple
+/// engines are ranked higher. With the consensus ranking this score is further multiplied by the
+/// number of engines that returned the result, which boosts the results the engines agree on
+/// while results returned by a single engine keep their reciprocal rank score. The reciprocal of
+/// the position of a result in the results of an engine is multiplied with the weight of the
+/// engine, so that the results of the engines with a higher weight are ranked higher.
+///
+/// # Arguments
+///
+/// * `results` - A mutable reference to the search results to be ranked.
+/// * `ranking` - The scoring function used to compute the rank scores.
+/// * `engine_weight` - It takes the function which provides the weight of an engine by its name
+///   as an argument.
+pub fn rank_results(
+    results: &mut [SearchResult],
+    ranking: Ranking,
+    engine_weight: impl Fn(&str) -> f32,
+) {
+    for result in results.iter_mut() {
+        if let Some(details) = result.ranking.as_mut() {
+            let reciprocal_rank: f32 = details
+                .engine_positions
+                .iter()
+                .map(|engine_position| {
+                    engine_weight(&engine_position.engine) / (engine_position.position as f32 + 1.0)
+                })
+                .sum();
+            details.score = match ranking {
+                Ranking::ReciprocalRank => reciprocal_rank,
+                Ranking::Consensus => reciprocal_rank * details.engine_positions.len() as f32,
+            };
+        }
+    }
+
+    let score = |result: &SearchResult| {
+        result
+            .ranking
+            .as_ref()
+            .map(|ranking| ranking.score)
+            .unwrap_or_default()
+    };
+    results.sort_by(|a, b| score(b).total_cmp(&score(a)));
+}
+
+/// Orders the search results by their url and then by the name of the first upstream search
+/// engine which returned them. The search results are collected from a hash map whose iteration
+/// order differs between the searches, and as the later sorts by score and by recency are stable,
+/// ordering the search results beforehand breaks the ties between the results with the same
+/// score, so that identical searches provide the search results in the same order.
+///
+/// # Arguments
+///
+/// * `results` - A mutable reference to the search results, before they are ranked.
+pub fn order_ties(results: &mut [SearchResult]) {
+    fn first_engine(result: &SearchResult) -> &str {
+        result
+            .ranking
+            .as_ref()
+            .and_then(|ranking| ranking.engine_positions.first())
+            .map(|engine_position| engine_position.engine.as_str())
+            .or_else(|| result.engine.first().map(String::as_str))
+            .unwrap_or_default()
+    }
+    results.sort_by(|a, b| {
+        a.url
+            .cmp(&b.url)
+            .then_with(|| first_engine(a).cmp(first_engine(b)))
+    });
+}
+
+/// Adjusts the rank scores of the ranked search results by the reputation of their domains and
+/// sorts them again in descending order of their scores, so that the results of the trusted
+/// domains rise and the results of the known spam domains sink. The rank score of a result is
+/// multiplied with the adjustment of the most specific configured domain matching the domain of
+/// its url, a configured domain matching its subdomains as well. The results are left untouched
+/// when no domain reputations are configured.
+///
+/// # Arguments
+///
+/// * `results` - A mutable reference to the ranked search results.
+/// * `domain_reputation` - It takes the score adjustments keyed by the lowercased domain as an
+///   argument.
+pub fn apply_domain_reputation(
+    results: &mut [SearchResult],
+    domain_reputation: &HashMap<String, f32>,
+) {
+    if domain_reputation.is_empty() {
+        return;
+    }
+
+    for result in results.iter_mut() {
+        let Some(host) = Url::parse(&result.url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_lowercase))
+        else {
+            continue;
+        };
+        // The domain is shortened one label at a time, so that the most specific configured
+        // domain is found first.
+        let adjustment = std::iter::successors(Some(host.as_str()), |domain| {
+            domain.split_once('.').map(|(_, parent)| parent)
+        })
+        .find_map(|domain| domain_reputation.get(domain));
+        if let (Some(adjustment), Some(details)) = (adjustment, result.ranking.as_mut()) {
+            details.score *= adjustment;
+        }
+    }
+
+    let score = |result: &SearchResult| {
+        result
+            .ranking
+            .as_ref()
+            .map(|ranking| ranking.score)
+            .unwrap_or_default()
+    };
+    results.sort_by(|a, b| score(b).total_cmp(&score(a)));
+}
+
+/// Applies the provided regex based find and replace rules to the search query in order.
+///
+/// # Arguments
+///
+/// * `query` - The search query to be rewritten.
+/// * `rules` - The rewrite rules for a particular upstream search engine. Their regex patterns
+///   are compiled when the config is loaded, which skips the rules failing to compile.
+pub fn rewrite_query(query: &str, rules: &[QueryRewriteRule]) -> String {
+    rules
+        .iter()
+        .filter_map(|rule| Some((rule.pattern.as_ref()?, &rule.replace)))
+        .fold(query.to_owned(), |query, (pattern, replace)| {
+            pattern.replace_all(&query, replace.as_str()).into_owned()
+        })
+}
 
-    Ok(SearchResults::new(results, &engine_errors_info))
+/// The outcome of applying the safe search term rules to a search query.
+#[derive(Debug, PartialEq)]
+pub enum SafeSearchTermOutcome<'a> {
+    /// The search query to be searched, with the matched terms rewritten.
+    Query(Cow<'a, str>),
+    /// The url to which the search is redirected.
+    Redirect(&'a str),
+}
+
+/// Applies the safe search term rules to the search query when the safe search level is at least
+/// the configured level. The rules are applied in their configured order, the terms matched by
+/// the rewrite rules are replaced in the search query and the first matching redirect rule
+/// redirects the search. The terms are matched as whole words regardless of their case.
+///
+/// # Arguments
+///
+/// * `query` - The search query to which the rules are applied.
+/// * `safe_search` - The safe search level of the search.
+/// * `safe_search_terms` - The safe search term rules config.
+pub fn apply_safe_search_terms<'a>(
+    query: &'a str,
+    safe_search: u8,
+    safe_search_terms: &'a SafeSearchTerms,
+) -> SafeSearchTermOutcome<'a> {
+    let mut query = Cow::Borrowed(query);
+    if safe_search < safe_search_terms.min_level {
+        return SafeSearchTermOutcome::Query(query);
+    }
+
+    // The patterns of the terms are compiled when the config is loaded, which skips the rules
+    // without a pattern.
+    for rule in &safe_search_terms.rules {
+        let Some(term) = &rule.pattern else {
+            continue;
+        };
+        if !term.is_match(&query) {
+            continue;
+        }
+        match (&rule.rewrite, &rule.redirect) {
+            (_, Some(url)) => return SafeSearchTermOutcome::Redirect(url),
+            (Some(rewrite), None) => {
+                let rewritten = term.replace_all(&query, NoExpand(rewrite));
+                query = Cow::Owned(
+                    rewritten
+                        .split_whitespace()
+                        .collect::<Vec<&str>>()
+                        .join(" "),
+                );
+            }
+            (None, None) => (),
+        }
+    }
+
+    SafeSearchTermOutcome::Query(query)
 }
 
 /// Filters a map of search results using a list of regex patterns.
@@ -193,40 +1188,457 @@ pub async fn aggregate(
 ///
 /// # Errors
 ///
-/// Returns an error if the file at `file_path` cannot be opened or read, or if a regex pattern is invalid.
+/// Returns a `SearchError::Filter` error if the file at `file_path` cannot be opened or read, or if a regex pattern is invalid.
 pub fn filter_with_lists(
     map_to_be_filtered: &mut HashMap<String, SearchResult>,
     resultant_map: &mut HashMap<String, SearchResult>,
     file_path: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut reader = BufReader::new(File::open(file_path)?);
-
-    for line in reader.by_ref().lines() {
-        let re = Regex::new(line?.trim())?;
-
-        // Iterate over each search result in the map and check if it matches the regex pattern
-        for (url, search_result) in map_to_be_filtered.clone().into_iter() {
-            if re.is_match(&url.to_lowercase())
-                || re.is_match(&search_result.title.to_lowercase())
-                || re.is_match(&search_result.description.to_lowercase())
-            {
-                // If the search result matches the regex pattern, move it from the original map to the resultant map
-                resultant_map.insert(
-                    url.to_owned(),
-                    map_to_be_filtered.remove(&url.to_owned()).unwrap(),
-                );
+) -> Result<(), SearchError> {
+    let filter_list = read_patterns(file_path)
+        .and_then(|patterns| Ok(FilterList::new(&patterns)?))
+        .map_err(|error| SearchError::Filter(error.to_string()))?;
+    filter_with_list(map_to_be_filtered, resultant_map, &filter_list);
+
+    Ok(())
+}
+
+/// Moves the search results matching the provided filter list from one map to another.
+///
+/// # Arguments
+///
+/// * `map_to_be_filtered` - A mutable reference to a `HashMap` of search results to filter, where the filtered results will be removed from.
+/// * `resultant_map` - A mutable reference to a `HashMap` to hold the filtered results.
+/// * `filter_list` - It takes the compiled regex patterns to use for filtering as an argument.
+pub fn filter_with_list(
+    map_to_be_filtered: &mut HashMap<String, SearchResult>,
+    resultant_map: &mut HashMap<String, SearchResult>,
+    filter_list: &FilterList,
+) {
+    let matching_urls: Vec<String> = map_to_be_filtered
+        .iter()
+        .filter(|(url, search_result)| {
+            filter_list.is_match(&url.to_lowercase())
+                || filter_list.is_match(&search_result.title.to_lowercase())
+                || filter_list.is_match(&search_result.description.to_lowercase())
+        })
+        .map(|(url, _)| url.to_owned())
+        .collect();
+
+    for url in matching_urls {
+        if let Some(search_result) = map_to_be_filtered.remove(&url) {
+            resultant_map.insert(url, search_result);
+        }
+    }
+}
+
+/// Narrows the search results down to the results whose title or description contains every
+/// term of the refinement, ignoring the case. The indices of the search results grouped by the
+/// upstream search engines are updated accordingly, and the featured snippet is only kept when
+/// its search result is kept.
+///
+/// # Arguments
+///
+/// * `search_results` - It takes the search results to be narrowed down as an argument.
+/// * `refinement` - It takes the whitespace separated terms of the refinement as an argument.
+pub fn refine_results(search_results: &mut SearchResults, refinement: &str) {
+    let terms: Vec<String> = refinement
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect();
+    let matches = |result: &SearchResult| {
+        let (title, description) = (
+            result.title.to_lowercase(),
+            result.description.to_lowercase(),
+        );
+        terms
+            .iter()
+            .all(|term| title.contains(term) || description.contains(term))
+    };
+
+    retain_results(search_results, matches);
+    search_results.set_refinement(refinement);
+}
+
+/// Filters the search results read from the cache again at the provided safe search level like
+/// the aggregator filters them, so that the filtering matches the level of the search regardless
+/// of the level at which the search results were cached. The search results
+/// matching the blocklist are removed unless they match the allowlist as well.
+///
+/// # Arguments
+///
+/// * `search_results` - It takes the cached search results to be filtered as an argument.
+/// * `safe_search` - It takes the safe search level of the search as an argument.
+/// * `blocklist` - It takes the compiled blocklist as an argument.
+/// * `allowlist` - It takes the compiled allowlist as an argument.
+pub fn refilter_results(
+    search_results: &mut SearchResults,
+    safe_search: u8,
+    blocklist: &FilterList,
+    allowlist: &FilterList,
+) {
+    search_results.set_safe_search_level(safe_search);
+    if safe_search < 3 {
+        return;
+    }
+
+    let matches = |filter_list: &FilterList, result: &SearchResult| {
+        filter_list.is_match(&result.url.to_lowercase())
+            || filter_list.is_match(&result.title.to_lowercase())
+            || filter_list.is_match(&result.description.to_lowercase())
+    };
+    retain_results(search_results, |result| {
+        !matches(blocklist, result) || matches(allowlist, result)
+    });
+}
+
+/// Reorders the search results by interleaving the results of the upstream search engines in a
+/// round robin, so that the first screen shows the results of as many engines as possible. The
+/// first round holds the first result of each engine, the second round the second result of each
+/// engine and so on, the results within a round keeping their ranked order. A result provided by
+/// several engines is only presented once, in the first round in which one of the engines
+/// provided it. The search results which are not provided by any engine (like the pinned results)
+/// stay at the top, and the search results without engine groups are left in their ranked order.
+///
+/// # Arguments
+///
+/// * `search_results` - It takes the ranked search results to be reordered as an argument.
+pub fn interleave_results(search_results: &mut SearchResults) {
+    let mut grouped = vec![false; search_results.results.len()];
+    for index in search_results
+        .engine_groups
+        .iter()
+        .flat_map(|group| &group.results)
+    {
+        if let Some(grouped) = grouped.get_mut(*index) {
+            *grouped = true;
+        }
+    }
+    let mut order: Vec<usize> = (0..grouped.len())
+        .filter(|index| !grouped[*index])
+        .collect();
+    let mut placed = vec![false; grouped.len()];
+
+    let rounds = search_results
+        .engine_groups
+        .iter()
+        .map(|group| group.results.len())
+        .max()
+        .unwrap_or_default();
+    for round in 0..rounds {
+        let mut round_results: Vec<usize> = search_results
+            .engine_groups
+            .iter()
+            .filter_map(|group| group.results.get(round).copied())
+            .filter(|index| placed.get(*index).is_some_and(|placed| !placed))
+            .collect();
+        round_results.sort_unstable();
+        round_results.dedup();
+        for index in round_results {
+            placed[index] = true;
+            order.push(index);
+        }
+    }
+
+    // The new index of each search result, keyed by its previous index.
+    let new_indices: HashMap<usize, usize> = order
+        .iter()
+        .enumerate()
+        .map(|(new_index, index)| (*index, new_index))
+        .collect();
+    let mut results: Vec<Option<SearchResult>> = std::mem::take(&mut search_results.results)
+        .into_iter()
+        .map(Some)
+        .collect();
+    search_results.results = order
+        .iter()
+        .filter_map(|index| results[*index].take())
+        .collect();
+    for group in search_results.engine_groups.iter_mut() {
+        group.results = group
+            .results
+            .iter()
+            .filter_map(|index| new_indices.get(index).copied())
+            .collect();
+    }
+}
+
+/// A helper function which keeps only the search results matching the provided predicate. The
+/// indices of the search results grouped by the upstream search engines are updated accordingly,
+/// and the featured snippet is only kept when its search result is kept.
+///
+/// # Arguments
+///
+/// * `search_results` - It takes the search results to be filtered as an argument.
+/// * `keep` - It takes the predicate deciding whether a search result is kept as an argument.
+fn retain_results(search_results: &mut SearchResults, keep: impl Fn(&SearchResult) -> bool) {
+    // The new index of each kept search result, keyed by its previous index.
+    let mut kept_indices: HashMap<usize, usize> = HashMap::new();
+    let mut results: Vec<SearchResult> = Vec::new();
+    for (index, result) in std::mem::take(&mut search_results.results)
+        .into_iter()
+        .enumerate()
+    {
+        if keep(&result) {
+            kept_indices.insert(index, results.len());
+            results.push(result);
+        }
+    }
+
+    search_results.engine_groups.retain_mut(|group| {
+        group.results = group
+            .results
+            .iter()
+            .filter_map(|index| kept_indices.get(index).copied())
+            .collect();
+        !group.results.is_empty()
+    });
+    if let Some(snippet) = &search_results.featured_snippet {
+        if !results.iter().any(|result| result.url == snippet.url) {
+            search_results.featured_snippet = None;
+        }
+    }
+
+    search_results.results = results;
+}
+
+/// Groups the ranked search results by the upstream search engines which provided them, using the
+/// positions at which each engine returned them. The groups are ordered by the name of the engine.
+///
+/// # Arguments
+///
+/// * `results` - It takes the ranked search results whose ranking details are provided as an
+///   argument.
+/// * `offset` - It takes the index of the first ranked search result within the aggregated search
+///   results as an argument.
+pub fn group_by_engine(results: &[SearchResult], offset: usize) -> Vec<EngineGroup> {
+    let mut positions: BTreeMap<&str, Vec<(u32, usize)>> = BTreeMap::new();
+    for (index, result) in results.iter().enumerate() {
+        for engine_position in result
+            .ranking
+            .iter()
+            .flat_map(|ranking| &ranking.engine_positions)
+        {
+            positions
+                .entry(&engine_position.engine)
+                .or_default()
+                .push((engine_position.position, offset + index));
+        }
+    }
+
+    positions
+        .into_iter()
+        .map(|(engine, mut engine_positions)| {
+            engine_positions.sort_unstable();
+            EngineGroup {
+                engine: engine.to_owned(),
+                results: engine_positions
+                    .into_iter()
+                    .map(|(_, index)| index)
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Sorts the errors of the upstream search engines, so that they are always displayed in the same
+/// order regardless of the order in which the engines responded. The engines listed in the
+/// priority come first in the listed order, followed by the remaining engines ordered by name.
+///
+/// # Arguments
+///
+/// * `engine_errors_info` - A mutable reference to the errors of the upstream search engines.
+/// * `priority` - The names of the engines whose errors are displayed first in order.
+pub fn sort_engine_errors(engine_errors_info: &mut [EngineErrorInfo], priority: &[String]) {
+    engine_errors_info.sort_by(|a, b| {
+        let rank = |error: &EngineErrorInfo| {
+            priority
+                .iter()
+                .position(|engine| engine.eq_ignore_ascii_case(&error.engine))
+                .unwrap_or(priority.len())
+        };
+        rank(a)
+            .cmp(&rank(b))
+            .then_with(|| a.engine.cmp(&b.engine))
+            .then_with(|| a.error.cmp(&b.error))
+    });
+}
+
+/// Keeps only the first errors of the upstream search engines up to the provided maximum, so that
+/// the search results stay bounded when many engines fail at once.
+///
+/// # Arguments
+///
+/// * `engine_errors_info` - A mutable reference to the sorted errors of the upstream search
+///   engines.
+/// * `max_engine_errors` - The maximum number of errors to be kept (`0` means no limit).
+///
+/// # Returns
+///
+/// Returns the number of the errors which were left out.
+pub fn cap_engine_errors(
+    engine_errors_info: &mut Vec<EngineErrorInfo>,
+    max_engine_errors: usize,
+) -> usize {
+    let omitted = match max_engine_errors {
+        0 => 0,
+        max => engine_errors_info.len().saturating_sub(max),
+    };
+    engine_errors_info.truncate(engine_errors_info.len() - omitted);
+    omitted
+}
+
+/// Sorts the news results by their publish time, so that the most recent news articles come
+/// first. The order of the results with the same publish time is kept and the results without a
+/// publish time are placed last.
+///
+/// # Arguments
+///
+/// * `results` - A mutable reference to the ranked news results to be sorted.
+pub fn sort_by_recency(results: &mut [SearchResult]) {
+    results.sort_by_key(|result| std::cmp::Reverse(result.published));
+}
+
+/// Replaces the words of the search query which have a configured synonym with their expansion.
+/// Only the whole words (separated by whitespace) which match a synonym, ignoring their case,
+/// are replaced so that the query does not drift, and the query is left untouched when none of
+/// its words match.
+///
+/// # Arguments
+///
+/// * `query` - The search query to be expanded.
+/// * `synonyms` - The expansions of the words keyed by the lowercased word.
+pub fn expand_synonyms(query: &str, synonyms: &HashMap<String, String>) -> String {
+    let expansion = |word: &str| synonyms.get(&word.to_lowercase());
+    if !query
+        .split_whitespace()
+        .any(|word| expansion(word).is_some())
+    {
+        return query.to_owned();
+    }
+
+    query
+        .split_whitespace()
+        .map(|word| expansion(word).map_or(word, String::as_str))
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Downranks or filters the search results with a plain `http` url as configured, so that the
+/// `https` results are surfaced first or exclusively. The order of the results is otherwise kept.
+///
+/// # Arguments
+///
+/// * `results` - A mutable reference to the ranked search results.
+/// * `http_results` - How the `http` results are handled.
+pub fn handle_http_results(results: &mut Vec<SearchResult>, http_results: HttpResults) {
+    let is_http = |result: &SearchResult| {
+        result
+            .url
+            .trim_start()
+            .get(..7)
+            .is_some_and(|scheme| scheme.eq_ignore_ascii_case("http://"))
+    };
+
+    match http_results {
+        HttpResults::Allow => (),
+        HttpResults::Downrank => results.sort_by_key(is_http),
+        HttpResults::Filter => results.retain(|result| !is_http(result)),
+    }
+}
+
+/// Downranks or filters the search results without a description as configured, so that the
+/// bare links without any snippet do not make the results look broken. The order of the results
+/// is otherwise kept.
+///
+/// # Arguments
+///
+/// * `results` - A mutable reference to the ranked search results.
+/// * `missing_snippets` - How the results without a description are handled.
+pub fn handle_missing_snippets(results: &mut Vec<SearchResult>, missing_snippets: MissingSnippets) {
+    let lacks_snippet = |result: &SearchResult| result.description.trim().is_empty();
+
+    match missing_snippets {
+        MissingSnippets::Allow => (),
+        MissingSnippets::Downrank => results.sort_by_key(lacks_snippet),
+        MissingSnippets::Filter => results.retain(|result| !lacks_snippet(result)),
+    }
+}
+
+/// Parses the pinned results file into the pinned results along with the compiled regex pattern
+/// of the queries they are shown for.
+///
+/// Each non empty line of the file which does not start with a `#` has the form
+/// `<query regex> -> <url> -> <title>` with an optional `-> <description>` at the end. The regex
+/// patterns are matched regardless of the case, and the malformed lines and the lines with an
+/// invalid regex pattern are logged and skipped.
+///
+/// # Arguments
+///
+/// * `file_path` - A `&str` representing the path to the pinned results file.
+///
+/// # Errors
+///
+/// Returns an error if the file at `file_path` cannot be opened or read.
+pub fn load_pinned_results(file_path: &str) -> std::io::Result<Vec<(Regex, SearchResult)>> {
+    let mut pinned_results: Vec<(Regex, SearchResult)> = Vec::new();
+
+    for line in BufReader::new(File::open(file_path)?).lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut fields = line.split(" -> ").map(str::trim);
+        let (pattern, url, title) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(pattern), Some(url), Some(title)) => (pattern, url, title),
+            _ => {
+                log::warn!("Config Error: Skipping the malformed pinned result `{line}`, the pinned results should have the form `<query regex> -> <url> -> <title>`");
+                continue;
             }
+        };
+        let description = fields.next().unwrap_or_default();
+
+        match RegexBuilder::new(pattern).case_insensitive(true).build() {
+            Ok(pattern) => {
+                pinned_results.push((pattern, SearchResult::new_pinned(title, url, description)))
+            }
+            Err(error) => log::warn!(
+                "Config Error: Skipping the pinned result `{line}` with an invalid regex pattern: {error}"
+            ),
         }
     }
 
-    Ok(())
+    Ok(pinned_results)
+}
+
+/// Returns the pinned results which should be shown for the provided search query, in the order
+/// of the pinned results file and without the duplicate urls.
+///
+/// # Arguments
+///
+/// * `pinned` - It takes the parsed pinned results file as an argument.
+/// * `query` - The search query to be matched against the regex patterns of the pinned results.
+pub fn pinned_results(pinned: &[(Regex, SearchResult)], query: &str) -> Vec<SearchResult> {
+    let mut pinned_results: Vec<SearchResult> = Vec::new();
+    for (pattern, result) in pinned {
+        if pattern.is_match(query) && !pinned_results.iter().any(|pinned| pinned.url == result.url)
+        {
+            pinned_results.push(result.clone());
+        }
+    }
+    pinned_results
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::search::EngineRateLimit;
+    use crate::models::aggregation_models::EmptyResultsReason;
+    use crate::models::engine_models::{page_param, provide_next_cursor, translate_page};
     use smallvec::smallvec;
     use std::collections::HashMap;
+    use std::collections::VecDeque;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -242,6 +1654,11 @@ mod tests {
                 description: "This domain is for use in illustrative examples in documents."
                     .to_owned(),
                 engine: smallvec!["Google".to_owned(), "Bing".to_owned()],
+                pinned: false,
+                ranking: None,
+                source: None,
+                published: None,
+                thumbnail: None,
             },
         );
         map_to_be_filtered.insert(
@@ -251,6 +1668,11 @@ mod tests {
                 url: "https://www.rust-lang.org/".to_owned(),
                 description: "A systems programming language that runs blazingly fast, prevents segfaults, and guarantees thread safety.".to_owned(),
                 engine: smallvec!["Google".to_owned(), "DuckDuckGo".to_owned()],
+                pinned: false,
+                ranking: None,
+                source: None,
+                published: None,
+                thumbnail: None,
             },
         );
 
@@ -286,6 +1708,11 @@ mod tests {
                 description: "This domain is for use in illustrative examples in documents."
                     .to_owned(),
                 engine: smallvec!["Google".to_owned(), "Bing".to_owned()],
+                pinned: false,
+                ranking: None,
+                source: None,
+                published: None,
+                thumbnail: None,
             },
         );
         map_to_be_filtered.insert(
@@ -295,6 +1722,11 @@ mod tests {
                 url: "https://www.rust-lang.org/".to_owned(),
                 description: "A systems programming language that runs blazingly fast, prevents segfaults, and guarantees thread safety.".to_owned(),
                 engine: smallvec!["Google".to_owned(), "DuckDuckGo".to_owned()],
+                pinned: false,
+                ranking: None,
+                source: None,
+                published: None,
+                thumbnail: None,
             },
         );
 
@@ -319,6 +1751,602 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_pinned_results() -> Result<(), Box<dyn std::error::Error>> {
+        let mut file = NamedTempFile::new()?;
+        writeln!(file, "# comments and blank lines are ignored")?;
+        writeln!(file)?;
+        writeln!(
+            file,
+            "^rust std$ -> https://doc.rust-lang.org/std/ -> The Rust Standard Library -> Docs"
+        )?;
+        writeln!(file, "rust -> https://www.rust-lang.org/ -> Rust")?;
+        writeln!(file, "python -> https://www.python.org/ -> Python")?;
+        writeln!(file, "^Go$ -> https://go.dev/ -> Go")?;
+        file.flush()?;
+
+        let pinned = load_pinned_results(file.path().to_str().unwrap())?;
+        let results = pinned_results(&pinned, "Rust std");
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].url, "https://doc.rust-lang.org/std/");
+        assert_eq!(results[0].description, "Docs");
+        assert_eq!(results[1].url, "https://www.rust-lang.org/");
+        assert!(results.iter().all(|result| result.pinned));
+
+        // The regex patterns are matched regardless of the case of the pattern and the query.
+        assert_eq!(pinned_results(&pinned, "go")[0].url, "https://go.dev/");
+        assert!(pinned_results(&pinned, "golang").is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pinned_results_malformed_line() -> Result<(), Box<dyn std::error::Error>> {
+        let mut file = NamedTempFile::new()?;
+        writeln!(file, "rust -> https://www.rust-lang.org/")?;
+        writeln!(file, "(rust -> https://www.rust-lang.org/ -> Rust")?;
+        writeln!(file, "rust -> https://doc.rust-lang.org/ -> Docs")?;
+        file.flush()?;
+
+        // The malformed lines are skipped without failing the other pinned results.
+        let pinned = load_pinned_results(file.path().to_str().unwrap())?;
+        let results = pinned_results(&pinned, "rust");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].url, "https://doc.rust-lang.org/");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rank_results() {
+        let ranked = |url: &str, positions: &[(&str, u32)]| SearchResult {
+            ranking: Some(RankingDetails {
+                score: 0.0,
+                engine_positions: positions
+                    .iter()
+                    .map(|(engine, position)| EnginePosition {
+                        engine: engine.to_string(),
+                        position: *position,
+                    })
+                    .collect(),
+            }),
+            ..SearchResult::new(url, url, "", &[])
+        };
+        let mut results = vec![
+            ranked("https://www.example.com", &[("bing", 3)]),
+            ranked("https://www.rust-lang.org/", &[("bing", 1), ("brave", 2)]),
+            ranked("https://doc.rust-lang.org/", &[("brave", 0)]),
+        ];
+
+        rank_results(&mut results, Ranking::ReciprocalRank, |_| 1.0);
+
+        let urls: Vec<&str> = results.iter().map(|result| result.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://doc.rust-lang.org/",
+                "https://www.rust-lang.org/",
+                "https://www.example.com"
+            ]
+        );
+        let score = results[1].ranking.as_ref().unwrap().score;
+        assert!((score - (1.0 / 2.0 + 1.0 / 3.0)).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_order_ties() {
+        let engine_results = |engine: &str, urls: &[&str]| -> Vec<(String, SearchResult)> {
+            urls.iter()
+                .map(|url| (url.to_string(), SearchResult::new(url, url, "", &[engine])))
+                .collect()
+        };
+        // Each result is returned at the same position by one of the engines, so they all have
+        // the same score.
+        let aggregate_once = || {
+            let mut result_map = HashMap::new();
+            merge_results(
+                &mut result_map,
+                "bing",
+                engine_results("bing", &["https://c.example/", "https://a.example/"]),
+            );
+            merge_results(
+                &mut result_map,
+                "brave",
+                engine_results("brave", &["https://b.example/", "https://d.example/"]),
+            );
+            let mut results: Vec<SearchResult> = result_map.into_values().collect();
+            order_ties(&mut results);
+            rank_results(&mut results, Ranking::ReciprocalRank, |_| 1.0);
+            results
+                .into_iter()
+                .map(|result| result.url)
+                .collect::<Vec<String>>()
+        };
+
+        // The hash maps are seeded randomly, so the results are collected in a different order
+        // by each aggregation while the ties are always broken in the same way.
+        for _ in 0..32 {
+            assert_eq!(
+                aggregate_once(),
+                [
+                    "https://b.example/",
+                    "https://c.example/",
+                    "https://a.example/",
+                    "https://d.example/"
+                ]
+            );
+        }
+
+        // The results with the same url are ordered by the first engine which returned them.
+        let mut results = vec![
+            SearchResult::new("", "https://a.example/", "", &["brave"]),
+            SearchResult::new("", "https://a.example/", "", &["bing"]),
+        ];
+        order_ties(&mut results);
+        assert_eq!(results[0].engine[0], "bing");
+    }
+
+    #[test]
+    fn test_engine_tiers() {
+        let engines: Vec<EngineHandler> = ["bing", "brave", "mojeek", "duckduckgo"]
+            .into_iter()
+            .map(|engine| EngineHandler::new(engine).unwrap())
+            .collect();
+        let fallback_tiers = vec![
+            vec!["mojeek".to_owned()],
+            vec!["searx".to_owned()],
+            vec!["brave".to_owned(), "bing".to_owned()],
+        ];
+
+        let names = |tiers: Vec<Vec<&EngineHandler>>| -> Vec<Vec<&str>> {
+            tiers
+                .into_iter()
+                .map(|tier| tier.into_iter().map(|engine| engine.name()).collect())
+                .collect()
+        };
+
+        assert_eq!(
+            names(engine_tiers(&engines, &fallback_tiers)),
+            vec![vec!["duckduckgo"], vec!["mojeek"], vec!["bing", "brave"]]
+        );
+        assert_eq!(
+            names(engine_tiers(&engines, &[])),
+            vec![vec!["bing", "brave", "mojeek", "duckduckgo"]]
+        );
+    }
+
+    #[test]
+    fn test_sort_engine_errors() {
+        let errors = |engines: &[&str]| -> Vec<EngineErrorInfo> {
+            engines
+                .iter()
+                .map(|engine| EngineErrorInfo::new(&EngineError::RequestError, engine))
+                .collect()
+        };
+        let engines = |errors: &[EngineErrorInfo]| -> Vec<String> {
+            errors.iter().map(|error| error.engine.clone()).collect()
+        };
+
+        // The errors are ordered by the engine name regardless of the order the engines failed.
+        let mut first_response = errors(&["mojeek", "bing", "duckduckgo", "brave"]);
+        let mut second_response = errors(&["brave", "duckduckgo", "mojeek", "bing"]);
+        sort_engine_errors(&mut first_response, &[]);
+        sort_engine_errors(&mut second_response, &[]);
+        assert_eq!(
+            engines(&first_response),
+            vec!["bing", "brave", "duckduckgo", "mojeek"]
+        );
+        assert_eq!(engines(&first_response), engines(&second_response));
+
+        // The engines in the priority come first in the configured order.
+        let mut prioritized = errors(&["mojeek", "bing", "duckduckgo", "brave"]);
+        sort_engine_errors(
+            &mut prioritized,
+            &["duckduckgo".to_owned(), "Mojeek".to_owned()],
+        );
+        assert_eq!(
+            engines(&prioritized),
+            vec!["duckduckgo", "mojeek", "bing", "brave"]
+        );
+
+        // The errors beyond the maximum are only counted, so the prioritized errors are kept.
+        assert_eq!(cap_engine_errors(&mut prioritized, 0), 0);
+        assert_eq!(cap_engine_errors(&mut prioritized, 5), 0);
+        assert_eq!(cap_engine_errors(&mut prioritized, 2), 2);
+        assert_eq!(engines(&prioritized), vec!["duckduckgo", "mojeek"]);
+    }
+
+    #[test]
+    fn test_sort_by_recency() {
+        let news = |url: &str, published: Option<u64>| {
+            SearchResult::new_news(url, url, "", &["bing"], Some("Example News"), published)
+        };
+        let mut results = vec![
+            news("https://www.example.com/old", Some(100)),
+            news("https://www.example.com/undated", None),
+            news("https://www.example.com/new", Some(300)),
+            news("https://www.example.com/same", Some(100)),
+        ];
+
+        sort_by_recency(&mut results);
+
+        let urls: Vec<&str> = results.iter().map(|result| result.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://www.example.com/new",
+                "https://www.example.com/old",
+                "https://www.example.com/same",
+                "https://www.example.com/undated",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_handle_http_results() {
+        let results = vec![
+            SearchResult::new("", "http://www.example.com/one", "", &[]),
+            SearchResult::new("", "https://www.example.com/two", "", &[]),
+            SearchResult::new("", "HTTP://www.example.com/three", "", &[]),
+            SearchResult::new("", "https://www.example.com/four", "", &[]),
+        ];
+        let urls = |results: &[SearchResult]| {
+            results
+                .iter()
+                .map(|result| result.url.to_owned())
+                .collect::<Vec<String>>()
+        };
+
+        let mut allowed = results.clone();
+        handle_http_results(&mut allowed, HttpResults::Allow);
+        assert_eq!(urls(&allowed), urls(&results));
+
+        let mut downranked = results.clone();
+        handle_http_results(&mut downranked, HttpResults::Downrank);
+        assert_eq!(
+            urls(&downranked),
+            vec![
+                "https://www.example.com/two",
+                "https://www.example.com/four",
+                "http://www.example.com/one",
+                "HTTP://www.example.com/three",
+            ]
+        );
+
+        let mut filtered = results;
+        handle_http_results(&mut filtered, HttpResults::Filter);
+        assert_eq!(
+            urls(&filtered),
+            vec![
+                "https://www.example.com/two",
+                "https://www.example.com/four"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_handle_missing_snippets() {
+        let results = vec![
+            SearchResult::new("", "https://www.example.com/one", "", &[]),
+            SearchResult::new("", "https://www.example.com/two", "Two", &[]),
+            SearchResult::new("", "https://www.example.com/three", " ", &[]),
+        ];
+        let urls = |results: &[SearchResult]| {
+            results
+                .iter()
+                .map(|result| result.url.to_owned())
+                .collect::<Vec<String>>()
+        };
+
+        let mut downranked = results.clone();
+        handle_missing_snippets(&mut downranked, MissingSnippets::Downrank);
+        assert_eq!(
+            urls(&downranked),
+            vec![
+                "https://www.example.com/two",
+                "https://www.example.com/one",
+                "https://www.example.com/three",
+            ]
+        );
+
+        let mut filtered = results;
+        handle_missing_snippets(&mut filtered, MissingSnippets::Filter);
+        assert_eq!(urls(&filtered), vec!["https://www.example.com/two"]);
+    }
+
+    #[test]
+    fn test_merge_duplicate() {
+        let url = "https://www.example.com/";
+        let position = |engine: &str| EnginePosition {
+            engine: engine.to_owned(),
+            position: 0,
+        };
+        let mut result = SearchResult::new("Example", url, "", &["bing"]);
+
+        // The duplicates provided by the same engine are skipped.
+        merge_duplicate(
+            &mut result,
+            SearchResult::new("Example", url, "Described", &["bing"]),
+            position("bing"),
+        );
+        assert_eq!(result.description, "");
+
+        merge_duplicate(
+            &mut result,
+            SearchResult::new("Example", url, "Described", &["brave"]),
+            position("brave"),
+        );
+        assert_eq!(result.description, "Described");
+
+        // The description of a result is kept once it has one.
+        merge_duplicate(
+            &mut result,
+            SearchResult::new("Example", url, "Other", &["mojeek"]),
+            position("mojeek"),
+        );
+        assert_eq!(result.description, "Described");
+        assert_eq!(result.engine.as_slice(), ["bing", "brave", "mojeek"]);
+    }
+
+    #[test]
+    fn test_expand_synonyms() {
+        let synonyms = HashMap::from([
+            ("k8s".to_owned(), "kubernetes".to_owned()),
+            ("tf".to_owned(), "terraform".to_owned()),
+        ]);
+
+        assert_eq!(
+            expand_synonyms("K8s  ingress tf", &synonyms),
+            "kubernetes ingress terraform"
+        );
+        // Only the whole words are expanded.
+        assert_eq!(
+            expand_synonyms("k8s-operator tfsec", &synonyms),
+            "k8s-operator tfsec"
+        );
+        assert_eq!(expand_synonyms(" rust  lang ", &synonyms), " rust  lang ");
+        assert_eq!(expand_synonyms("k8s", &HashMap::new()), "k8s");
+    }
+
+    #[test]
+    fn test_rank_results_consensus() {
+        let ranked = |url: &str, positions: &[(&str, u32)]| SearchResult {
+            ranking: Some(RankingDetails {
+                score: 0.0,
+                engine_positions: positions
+                    .iter()
+                    .map(|(engine, position)| EnginePosition {
+                        engine: engine.to_string(),
+                        position: *position,
+                    })
+                    .collect(),
+            }),
+            ..SearchResult::new(url, url, "", &[])
+        };
+        let results = vec![
+            ranked("https://www.example.com", &[("bing", 3)]),
+            ranked(
+                "https://www.rust-lang.org/",
+                &[("bing", 3), ("brave", 3), ("mojeek", 3)],
+            ),
+            ranked("https://doc.rust-lang.org/", &[("brave", 0)]),
+        ];
+
+        let mut consensus_results = results.clone();
+        rank_results(&mut consensus_results, Ranking::Consensus, |_| 1.0);
+
+        let urls: Vec<&str> = consensus_results
+            .iter()
+            .map(|result| result.url.as_str())
+            .collect();
+        assert_eq!(
+            urls,
+            [
+                "https://www.rust-lang.org/",
+                "https://doc.rust-lang.org/",
+                "https://www.example.com"
+            ]
+        );
+        // a result returned by a single engine keeps its reciprocal rank score.
+        let score = consensus_results[2].ranking.as_ref().unwrap().score;
+        assert!((score - 1.0 / 4.0).abs() < f32::EPSILON);
+
+        // without the consensus ranking the agreement of the engines does not outweigh the first
+        // position of a single engine.
+        let mut reciprocal_rank_results = results;
+        rank_results(
+            &mut reciprocal_rank_results,
+            Ranking::ReciprocalRank,
+            |_| 1.0,
+        );
+        assert_eq!(reciprocal_rank_results[0].url, "https://doc.rust-lang.org/");
+    }
+
+    #[test]
+    fn test_rank_results_weighted() {
+        let search = crate::config::search::Search {
+            engine_weights: HashMap::from([("bing".to_owned(), 2.0)]),
+            category_engine_weights: HashMap::from([(
+                Category::News,
+                HashMap::from([("bing".to_owned(), 0.25)]),
+            )]),
+            ..Default::default()
+        };
+        // The category weights are multiplied with the weights of the engines.
+        assert_eq!(
+            search.category_engine_weight("Bing", Category::General),
+            2.0
+        );
+        assert_eq!(search.category_engine_weight("Bing", Category::News), 0.5);
+        assert_eq!(search.category_engine_weight("brave", Category::News), 1.0);
+
+        let ranked = |url: &str, engine: &str| SearchResult {
+            ranking: Some(RankingDetails {
+                score: 0.0,
+                engine_positions: vec![EnginePosition {
+                    engine: engine.to_owned(),
+                    position: 0,
+                }],
+            }),
+            ..SearchResult::new(url, url, "", &[engine])
+        };
+        let results = vec![
+            ranked("https://www.example.com/brave", "brave"),
+            ranked("https://www.example.com/bing", "bing"),
+        ];
+        let first_url = |category: Category| {
+            let mut results = results.clone();
+            rank_results(&mut results, Ranking::ReciprocalRank, |engine| {
+                search.category_engine_weight(engine, category)
+            });
+            results[0].url.clone()
+        };
+
+        assert_eq!(first_url(Category::General), "https://www.example.com/bing");
+        assert_eq!(first_url(Category::News), "https://www.example.com/brave");
+    }
+
+    #[test]
+    fn test_apply_domain_reputation() {
+        let ranked = |url: &str, score: f32| SearchResult {
+            ranking: Some(RankingDetails {
+                score,
+                engine_positions: Vec::new(),
+            }),
+            ..SearchResult::new(url, url, "", &[])
+        };
+        let results = vec![
+            ranked("https://spam.example.com/first", 1.0),
+            ranked("https://www.example.org/second", 0.5),
+            ranked("https://docs.rust-lang.org/third", 0.25),
+        ];
+        let urls = |results: &[SearchResult]| {
+            results
+                .iter()
+                .map(|result| result.url.to_owned())
+                .collect::<Vec<String>>()
+        };
+
+        let mut unchanged = results.clone();
+        apply_domain_reputation(&mut unchanged, &HashMap::new());
+        assert_eq!(urls(&unchanged), urls(&results));
+
+        // The most specific domain wins, so the trusted subdomain of a domain rises while the
+        // spam subdomain of an otherwise neutral domain sinks.
+        let domain_reputation = HashMap::from([
+            ("example.com".to_owned(), 1.0),
+            ("spam.example.com".to_owned(), 0.1),
+            ("rust-lang.org".to_owned(), 4.0),
+        ]);
+        let mut adjusted = results;
+        apply_domain_reputation(&mut adjusted, &domain_reputation);
+        assert_eq!(
+            urls(&adjusted),
+            vec![
+                "https://docs.rust-lang.org/third",
+                "https://www.example.org/second",
+                "https://spam.example.com/first",
+            ]
+        );
+        let score = adjusted[0].ranking.as_ref().unwrap().score;
+        assert!((score - 1.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_rewrite_query() {
+        let rule = |find: &str, replace: &str| QueryRewriteRule {
+            find: find.to_owned(),
+            replace: replace.to_owned(),
+            pattern: Regex::new(find).ok(),
+        };
+        let rules = [rule(r"\+", " plus "), rule(r"^", "lang:en ")];
+
+        assert_eq!(rewrite_query("c++", &rules), "lang:en c plus  plus ");
+        assert_eq!(rewrite_query("rust", &[]), "rust");
+    }
+
+    #[test]
+    fn test_apply_safe_search_terms() {
+        use crate::config::search::SafeSearchTermRule;
+
+        let rule = |term: &str, rewrite: Option<&str>, redirect: Option<&str>| SafeSearchTermRule {
+            term: term.to_owned(),
+            rewrite: rewrite.map(str::to_owned),
+            redirect: redirect.map(str::to_owned),
+            pattern: SafeSearchTermRule::term_pattern(term),
+        };
+        let safe_search_terms = SafeSearchTerms {
+            min_level: 3,
+            rules: vec![
+                rule("adult  movies", Some("family movies"), None),
+                rule("gore", Some(""), None),
+                rule("casino", None, Some("/about")),
+            ],
+        };
+        let query = |query, safe_search| match apply_safe_search_terms(
+            query,
+            safe_search,
+            &safe_search_terms,
+        ) {
+            SafeSearchTermOutcome::Query(query) => Ok(query.into_owned()),
+            SafeSearchTermOutcome::Redirect(url) => Err(url),
+        };
+
+        assert_eq!(
+            query("Adult Movies of 2020", 3),
+            Ok("family movies of 2020".to_owned())
+        );
+        assert_eq!(query("gore  films", 4), Ok("films".to_owned()));
+        // Only the whole words are matched.
+        assert_eq!(query("gorensen", 4), Ok("gorensen".to_owned()));
+        assert_eq!(query("online CASINO games", 3), Err("/about"));
+        // The rules are not applied below the configured safe search level.
+        assert_eq!(
+            query("online casino games", 2),
+            Ok("online casino games".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_is_degraded() {
+        assert!(!is_degraded(1, 2, 0.5));
+        assert!(is_degraded(2, 3, 0.5));
+        assert!(is_degraded(1, 4, 0.0));
+        assert!(!is_degraded(4, 4, 1.0));
+        assert!(!is_degraded(0, 0, 0.0));
+    }
+
+    #[test]
+    fn test_filter_list_load() {
+        let mut ads = NamedTempFile::new().unwrap();
+        writeln!(ads, "# ads").unwrap();
+        writeln!(ads).unwrap();
+        writeln!(ads, "ads\\.example").unwrap();
+        ads.flush().unwrap();
+
+        let mut invalid = NamedTempFile::new().unwrap();
+        writeln!(invalid, "malware").unwrap();
+        writeln!(invalid, "example(").unwrap();
+        invalid.flush().unwrap();
+
+        let mut adult = NamedTempFile::new().unwrap();
+        writeln!(adult, "^adult").unwrap();
+        adult.flush().unwrap();
+
+        let filter_list = FilterList::load(&[
+            ads.path().to_str().unwrap(),
+            invalid.path().to_str().unwrap(),
+            "non-existent-file.txt",
+            adult.path().to_str().unwrap(),
+        ]);
+
+        assert!(filter_list.is_match("https://ads.example/banner"));
+        assert!(filter_list.is_match("adult content"));
+        assert!(!filter_list.is_match("malware"));
+        assert!(!filter_list.is_match("an innocent comment # ads"));
+    }
+
     #[test]
     fn test_filter_with_lists_file_not_found() {
         let mut map_to_be_filtered = HashMap::new();
@@ -346,6 +2374,11 @@ mod tests {
                 description: "This domain is for use in illustrative examples in documents."
                     .to_owned(),
                 engine: smallvec!["Google".to_owned(), "Bing".to_owned()],
+                pinned: false,
+                ranking: None,
+                source: None,
+                published: None,
+                thumbnail: None,
             },
         );
 
@@ -364,4 +2397,524 @@ mod tests {
 
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_dispatch_order() {
+        assert_eq!(dispatch_order(4, None), vec![0, 1, 2, 3]);
+
+        let mut shuffled = dispatch_order(8, Some(42));
+        assert_eq!(shuffled, dispatch_order(8, Some(42)));
+        assert_ne!(shuffled, dispatch_order(8, None));
+        shuffled.sort();
+        assert_eq!(shuffled, dispatch_order(8, None));
+
+        assert!(dispatch_order(0, Some(42)).is_empty());
+    }
+
+    #[test]
+    fn test_order_by_latency() {
+        let mut order = dispatch_order(5, None);
+        order_by_latency(
+            &mut order,
+            &[Some(800), None, Some(150), Some(800), Some(20)],
+        );
+        // The engines with the same latency keep their order and the unmeasured engines are last.
+        assert_eq!(order, vec![4, 2, 0, 3, 1]);
+    }
+
+    #[test]
+    fn test_featured_snippet_extraction() {
+        use crate::results::featured_snippet::{first_paragraph, truncate};
+
+        let paragraph = "Rust is a general purpose programming language emphasizing performance, type safety and concurrency.";
+        let html = format!(
+            "<html><body><nav><p>{paragraph} In the navigation.</p></nav><p>Short caption.</p>
+            <article><p>A byline</p><p>  {paragraph}\n   </p></article>
+            <footer><p>{paragraph} In the footer.</p></footer></body></html>"
+        );
+        assert_eq!(first_paragraph(&html).as_deref(), Some(paragraph));
+        assert_eq!(first_paragraph("<p>Too short to be meaningful.</p>"), None);
+
+        assert_eq!(truncate(paragraph, 200), paragraph);
+        assert_eq!(truncate(paragraph, 30), "Rust is a general purpose…");
+    }
+
+    #[test]
+    fn test_engine_stats_window() {
+        use crate::results::engine_stats::{count_outcome, engine_stats_report, OutcomeCounts};
+
+        let mut outcomes = OutcomeCounts::new();
+        count_outcome(
+            &mut outcomes,
+            "Bing",
+            Err(&EngineError::Timeout),
+            3000,
+            10,
+            2,
+        );
+        count_outcome(&mut outcomes, "Bing", Ok(()), 400, 11, 2);
+        count_outcome(
+            &mut outcomes,
+            "Bing",
+            Err(&EngineError::EmptyResultSet),
+            300,
+            11,
+            2,
+        );
+        count_outcome(
+            &mut outcomes,
+            "Bing",
+            Err(&EngineError::RequestError),
+            200,
+            11,
+            2,
+        );
+        count_outcome(&mut outcomes, "Brave", Ok(()), 100, 11, 2);
+
+        let report = engine_stats_report(&outcomes, 11, 2);
+        assert_eq!(report.engines.len(), 2);
+        assert_eq!(report.engines[0].engine, "bing");
+        assert_eq!(report.engines[0].requests, 4);
+        assert_eq!(report.engines[0].counts.timeouts, 1);
+        assert_eq!(report.engines[0].success_ratio, 0.5);
+        assert_eq!(report.engines[0].average_latency_ms, 975);
+        assert_eq!(report.engines[1].engine, "brave");
+        assert_eq!(report.engines[1].success_ratio, 1.0);
+
+        // The counts of the hours outside of the time window are left out of the report and are
+        // dropped once the engine is counted again.
+        let report = engine_stats_report(&outcomes, 12, 2);
+        assert_eq!(report.engines[0].requests, 3);
+        assert_eq!(report.engines[0].average_latency_ms, 300);
+        count_outcome(&mut outcomes, "Bing", Ok(()), 100, 13, 2);
+        assert_eq!(outcomes["bing"].len(), 1);
+        assert_eq!(engine_stats_report(&outcomes, 14, 2).engines.len(), 1);
+    }
+
+    #[test]
+    fn test_group_by_engine() {
+        let ranked = |title: &str, positions: &[(&str, u32)]| SearchResult {
+            ranking: Some(RankingDetails {
+                score: 0.0,
+                engine_positions: positions
+                    .iter()
+                    .map(|&(engine, position)| EnginePosition {
+                        engine: engine.to_owned(),
+                        position,
+                    })
+                    .collect(),
+            }),
+            ..SearchResult::new(title, title, "", &[])
+        };
+        let results = vec![
+            ranked("a", &[("duckduckgo", 1), ("bing", 0)]),
+            ranked("b", &[("duckduckgo", 0)]),
+            ranked("c", &[("bing", 1)]),
+        ];
+
+        let groups = group_by_engine(&results, 1);
+        let groups: Vec<(&str, Vec<usize>)> = groups
+            .iter()
+            .map(|group| (group.engine.as_str(), group.results.clone()))
+            .collect();
+        assert_eq!(
+            groups,
+            vec![("bing", vec![1, 3]), ("duckduckgo", vec![2, 1])]
+        );
+    }
+
+    #[test]
+    fn test_response_format_matches() {
+        use crate::models::engine_models::ResponseFormat;
+
+        assert!(ResponseFormat::Html.matches("text/html"));
+        assert!(ResponseFormat::Html.matches("application/xhtml+xml"));
+        assert!(!ResponseFormat::Html.matches("application/json"));
+        assert!(ResponseFormat::Json.matches("application/json"));
+        assert!(ResponseFormat::Json.matches("application/vnd.api+json"));
+        assert!(!ResponseFormat::Json.matches("text/html"));
+    }
+
+    #[test]
+    fn test_engine_result_count() {
+        use crate::config::search::Search;
+        use crate::models::engine_models::{result_count, RESULT_COUNT};
+
+        let mut search = Search::default();
+        assert_eq!(search.engine_result_count("Bing"), None);
+
+        search.engine_result_limits.insert("mojeek".to_owned(), 5);
+        assert_eq!(search.engine_result_count("Mojeek"), Some(5));
+        search.results_per_page = 8;
+        assert_eq!(search.engine_result_count("Mojeek"), Some(5));
+        assert_eq!(search.engine_result_count("Bing"), Some(8));
+
+        assert_eq!(result_count(10), 10);
+        assert_eq!(RESULT_COUNT.sync_scope(Some(5), || result_count(10)), 5);
+        assert_eq!(RESULT_COUNT.sync_scope(None, || result_count(10)), 10);
+    }
+
+    #[test]
+    fn test_safe_search_value() {
+        use crate::models::engine_models::{safe_search_value, SAFE_SEARCH_VALUES};
+
+        let defaults = ["off", "moderate", "strict", "strict", "strict"];
+        assert_eq!(safe_search_value(1, defaults), "moderate");
+        assert_eq!(safe_search_value(9, defaults), "strict");
+
+        let mapping = ["0", "0", "1", "2", "2"].map(str::to_owned).to_vec();
+        assert_eq!(
+            SAFE_SEARCH_VALUES.sync_scope(Some(mapping), || safe_search_value(2, defaults)),
+            "1"
+        );
+        assert_eq!(
+            SAFE_SEARCH_VALUES.sync_scope(None, || safe_search_value(2, defaults)),
+            "strict"
+        );
+    }
+
+    #[test]
+    fn test_refine_results() {
+        let mut search_results = SearchResults::new(
+            vec![
+                SearchResult::new(
+                    "Tokio",
+                    "https://tokio.rs",
+                    "An ASYNC runtime for Rust",
+                    &[],
+                ),
+                SearchResult::new("Rust", "https://rust-lang.org", "A language", &[]),
+                SearchResult::new("async-std", "https://async.rs", "Async Rust runtime", &[]),
+            ],
+            &[],
+        );
+        search_results.set_engine_groups(vec![
+            EngineGroup {
+                engine: "bing".to_owned(),
+                results: vec![2, 1, 0],
+            },
+            EngineGroup {
+                engine: "brave".to_owned(),
+                results: vec![1],
+            },
+        ]);
+
+        refine_results(&mut search_results, " async  Runtime ");
+        let urls: Vec<&str> = search_results
+            .results
+            .iter()
+            .map(|result| result.url.as_str())
+            .collect();
+        assert_eq!(urls, vec!["https://tokio.rs", "https://async.rs"]);
+        assert_eq!(search_results.engine_groups.len(), 1);
+        assert_eq!(search_results.engine_groups[0].results, vec![1, 0]);
+        assert!(search_results.refinement.is_some());
+
+        refine_results(&mut search_results, "python");
+        assert!(search_results.results.is_empty());
+        assert!(search_results.engine_groups.is_empty());
+    }
+
+    #[test]
+    fn test_interleave_results() {
+        let mut search_results = SearchResults::new(
+            ["pinned", "a", "b", "c", "d", "e"]
+                .iter()
+                .map(|name| SearchResult::new(name, &format!("https://{name}.com"), "", &[]))
+                .collect(),
+            &[],
+        );
+        search_results.set_engine_groups(vec![
+            EngineGroup {
+                engine: "bing".to_owned(),
+                results: vec![1, 2, 3],
+            },
+            EngineGroup {
+                engine: "brave".to_owned(),
+                results: vec![4, 2, 5],
+            },
+            EngineGroup {
+                engine: "mojeek".to_owned(),
+                results: vec![1],
+            },
+        ]);
+
+        interleave_results(&mut search_results);
+        let titles: Vec<&str> = search_results
+            .results
+            .iter()
+            .map(|result| result.title.as_str())
+            .collect();
+        assert_eq!(titles, vec!["pinned", "a", "d", "b", "c", "e"]);
+        assert_eq!(search_results.engine_groups[0].results, vec![1, 3, 4]);
+        assert_eq!(search_results.engine_groups[1].results, vec![2, 3, 5]);
+        assert_eq!(search_results.engine_groups[2].results, vec![1]);
+
+        // The search results without engine groups are left in their order.
+        let mut search_results = SearchResults::new(
+            vec![
+                SearchResult::new("Rust", "https://rust-lang.org", "", &[]),
+                SearchResult::new("Tokio", "https://tokio.rs", "", &[]),
+            ],
+            &[],
+        );
+        interleave_results(&mut search_results);
+        assert_eq!(search_results.results[0].title, "Rust");
+    }
+
+    #[test]
+    fn test_refilter_results() {
+        let cached_results = SearchResults::new(
+            vec![
+                SearchResult::new("Casino", "https://casino.example.com", "Bet now", &[]),
+                SearchResult::new("Rust", "https://rust-lang.org", "A language", &[]),
+                SearchResult::new("Casino history", "https://museum.org", "The casino", &[]),
+            ],
+            &[],
+        );
+        let blocklist = FilterList::new(&["casino".to_owned()]).unwrap();
+        let allowlist = FilterList::new(&["museum".to_owned()]).unwrap();
+
+        // The search results cached at a lower level are only filtered from the high level on.
+        let mut search_results = cached_results.clone();
+        refilter_results(&mut search_results, 2, &blocklist, &allowlist);
+        assert_eq!(search_results.results.len(), 3);
+        assert_eq!(search_results.safe_search_level, 2);
+
+        let mut search_results = cached_results;
+        refilter_results(&mut search_results, 3, &blocklist, &allowlist);
+        let urls: Vec<&str> = search_results
+            .results
+            .iter()
+            .map(|result| result.url.as_str())
+            .collect();
+        assert_eq!(urls, vec!["https://rust-lang.org", "https://museum.org"]);
+        assert_eq!(search_results.safe_search_level, 3);
+    }
+
+    #[test]
+    fn test_unique_answers() {
+        let definition = |word: &str| AnswerContent::Definition {
+            word: word.to_owned(),
+            part_of_speech: None,
+            definition: "A definition".to_owned(),
+        };
+        let weather = AnswerContent::Weather {
+            location: "Stockholm".to_owned(),
+            temperature: "12°C".to_owned(),
+            condition: "Cloudy".to_owned(),
+        };
+        let answer = |engine: &str, content: &AnswerContent| Answer {
+            engine: engine.to_owned(),
+            content: content.clone(),
+        };
+
+        let answers = unique_answers(vec![
+            answer("bing", &definition("rust")),
+            answer("brave", &definition("oxide")),
+            answer("brave", &weather),
+            answer("bing", &weather),
+        ]);
+        assert_eq!(
+            answers,
+            vec![
+                answer("bing", &definition("rust")),
+                answer("brave", &weather)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_upstream_failure_reason() {
+        let mut search_results = SearchResults::new(Vec::new(), &[]);
+        assert_eq!(search_results.reason, Some(EmptyResultsReason::NoResults));
+
+        search_results.set_upstream_failure();
+        assert!(search_results.upstream_failure);
+        assert_eq!(
+            search_results.reason,
+            Some(EmptyResultsReason::UpstreamFailure)
+        );
+
+        // A search without any selected engines is not an outage of the upstream search engines.
+        search_results.set_no_engines_selected();
+        assert_eq!(
+            search_results.reason,
+            Some(EmptyResultsReason::NoEnginesSelected)
+        );
+    }
+
+    #[test]
+    fn test_engine_rate_limit() {
+        let rate_limit = EngineRateLimit {
+            per_second: 2,
+            per_minute: 3,
+        };
+        let mut sent = VecDeque::new();
+
+        // The requests are capped within any second.
+        assert!(rate_limits::try_acquire(&mut sent, 0, &rate_limit));
+        assert!(rate_limits::try_acquire(&mut sent, 500, &rate_limit));
+        assert!(!rate_limits::try_acquire(&mut sent, 900, &rate_limit));
+
+        // A skipped request is not counted, so the cap within the minute is reached afterwards.
+        assert!(rate_limits::try_acquire(&mut sent, 1_000, &rate_limit));
+        assert!(!rate_limits::try_acquire(&mut sent, 2_000, &rate_limit));
+        assert_eq!(sent, VecDeque::from([0, 500, 1_000]));
+
+        // The requests which are older than a minute are forgotten.
+        assert!(rate_limits::try_acquire(&mut sent, 60_000, &rate_limit));
+        assert_eq!(sent, VecDeque::from([500, 1_000, 60_000]));
+
+        // No cap applies when the limits are `0`.
+        let mut sent = VecDeque::new();
+        assert!((0..100).all(|_| rate_limits::try_acquire(&mut sent, 0, &Default::default())));
+    }
+
+    #[test]
+    fn test_page_pagination() {
+        let pagination = Pagination::Page { first: 1 };
+        assert_eq!(
+            translate_page(pagination, 0, None, None).as_deref(),
+            Some("1")
+        );
+        assert_eq!(
+            translate_page(pagination, 4, None, None).as_deref(),
+            Some("5")
+        );
+        // The number of results requested from the engine does not change the page numbers.
+        assert_eq!(
+            translate_page(pagination, 4, Some(20), None).as_deref(),
+            Some("5")
+        );
+        assert_eq!(
+            translate_page(pagination, u32::MAX, None, None),
+            Some(u32::MAX.to_string())
+        );
+    }
+
+    #[test]
+    fn test_offset_pagination() {
+        let pagination = Pagination::Offset {
+            first: 1,
+            per_page: 10,
+        };
+        assert_eq!(
+            translate_page(pagination, 0, None, None).as_deref(),
+            Some("1")
+        );
+        assert_eq!(
+            translate_page(pagination, 2, None, None).as_deref(),
+            Some("21")
+        );
+        // The offsets follow the number of results requested from the engine.
+        assert_eq!(
+            translate_page(pagination, 2, Some(25), None).as_deref(),
+            Some("51")
+        );
+        assert_eq!(
+            translate_page(pagination, 2, Some(0), None).as_deref(),
+            Some("21")
+        );
+        assert_eq!(
+            translate_page(pagination, u32::MAX, None, None),
+            Some(u32::MAX.to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cursor_pagination() {
+        let pagination = Pagination::Cursor;
+        assert_eq!(
+            translate_page(pagination, 0, None, None).as_deref(),
+            Some("")
+        );
+        assert_eq!(
+            translate_page(pagination, 1, None, Some("abc")).as_deref(),
+            Some("abc")
+        );
+        // The following pages can not be requested without their cursor.
+        assert_eq!(translate_page(pagination, 1, None, None), None);
+
+        // The cursor provided along with the results of a page is used for the next page.
+        let key = cursor_key("cursor_test", "rust", 0, Category::General);
+        let first_page = with_cursor(Some(key.clone()), 0, async {
+            let param = page_param(pagination, 0);
+            provide_next_cursor("second");
+            param
+        })
+        .await;
+        assert_eq!(first_page.as_deref(), Some(""));
+
+        let second_page = with_cursor(Some(key.clone()), 1, async { page_param(pagination, 1) });
+        assert_eq!(second_page.await.as_deref(), Some("second"));
+
+        // The cursors are not shared with the other searches.
+        let other_key = cursor_key("cursor_test", "rust", 0, Category::News);
+        let other_page = with_cursor(Some(other_key), 1, async { page_param(pagination, 1) });
+        assert_eq!(other_page.await, None);
+
+        // Nothing is stored for the engines which do not paginate with cursors.
+        with_cursor(None, 1, async { provide_next_cursor("third") }).await;
+        let third_page = with_cursor(Some(key), 2, async { page_param(pagination, 2) });
+        assert_eq!(third_page.await, None);
+    }
+
+    #[test]
+    fn test_retry_timeout() {
+        let retries = EngineRetries {
+            attempts: 3,
+            timeout_escalation: 1.5,
+            max_timeout: 5,
+            deadline: 10,
+        };
+        let timeout = |attempt, elapsed| {
+            retry_timeout(
+                Duration::from_secs(2),
+                &retries,
+                attempt,
+                Duration::from_secs(elapsed),
+            )
+        };
+
+        // The timeout is escalated on each retry up to the maximum timeout.
+        assert_eq!(timeout(0, 0), Some(Duration::from_secs(2)));
+        assert_eq!(timeout(1, 2), Some(Duration::from_secs(3)));
+        assert_eq!(timeout(2, 5), Some(Duration::from_millis(4_500)));
+        assert_eq!(timeout(3, 5), Some(Duration::from_secs(5)));
+
+        // The timeout never exceeds the time left before the deadline.
+        assert_eq!(timeout(3, 9), Some(Duration::from_secs(1)));
+        assert_eq!(timeout(3, 10), None);
+        assert_eq!(timeout(3, 12), None);
+    }
+
+    #[tokio::test]
+    async fn test_with_retries() {
+        let retries = EngineRetries {
+            attempts: 2,
+            ..Default::default()
+        };
+        let attempts = std::cell::Cell::new(0);
+        let request = |transient| {
+            let attempts = &attempts;
+            move || async move {
+                attempts.set(attempts.get() + 1);
+                Err::<(), _>(Report::new(match transient {
+                    true => EngineError::RequestError,
+                    false => EngineError::Captcha,
+                }))
+            }
+        };
+
+        // The transient errors are retried up to the configured number of retries.
+        let result = with_retries(retries, Duration::from_secs(1), request(true));
+        assert!(result.await.is_err());
+        assert_eq!(attempts.replace(0), 3);
+
+        // The other errors are returned at once.
+        let result = with_retries(retries, Duration::from_secs(1), request(false));
+        assert!(result.await.is_err());
+        assert_eq!(attempts.replace(0), 1);
+    }
 }
@@ -0,0 +1,93 @@
+//! This module provides the storage of the cursors of the upstream search engines paginating with
+//! cursors, so that the following pages of a search can be requested from these engines with the
+//! cursor provided along with the results of the previous page.
+
+use crate::models::engine_models::{Category, CURSOR};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    future::Future,
+    sync::{Mutex, OnceLock},
+};
+
+/// The maximum number of stored cursors, beyond which all the cursors are dropped to keep the
+/// memory used by the cursors bounded.
+const MAX_CURSORS: usize = 10_000;
+
+/// The cursors of the pages of the searches keyed by the key of the search (see `cursor_key`)
+/// and the index of the page.
+pub type Cursors = HashMap<(String, u32), String>;
+
+/// A static variable which stores the cursors of the upstream search engines, which are shared by
+/// all the workers of the server.
+static CURSORS: OnceLock<Mutex<Cursors>> = OnceLock::new();
+
+/// A function which provides the key under which the cursors of the pages of a search are stored
+/// for an upstream search engine.
+///
+/// # Arguments
+///
+/// * `engine` - It takes the name of the upstream search engine as an argument.
+/// * `query` - It takes the search query sent to the engine as an argument.
+/// * `safe_search` - It takes the safe search level of the search as an argument.
+/// * `category` - It takes the category of the search results as an argument.
+pub fn cursor_key(engine: &str, query: &str, safe_search: u8, category: Category) -> String {
+    format!("{engine}\n{safe_search}\n{category:?}\n{query}")
+}
+
+/// A function which queries an upstream search engine within the scope of the `CURSOR` task
+/// local, providing the stored cursor of the requested page to the engine and storing the cursor
+/// of the next page provided by the engine. The engines which do not paginate with cursors are
+/// queried without a cursor key and nothing is stored for them.
+///
+/// # Arguments
+///
+/// * `key` - It takes the key of the search (see `cursor_key`) as an argument, if the engine
+///   paginates with cursors.
+/// * `page` - It takes the index of the requested page as an argument.
+/// * `request` - It takes the future querying the engine as an argument.
+pub async fn with_cursor<F: Future>(key: Option<String>, page: u32, request: F) -> F::Output {
+    let cursor = key.as_ref().and_then(|key| {
+        lock()
+            .get(&(key.to_owned(), page))
+            .map(|cursor| cursor.to_owned())
+    });
+
+    CURSOR
+        .scope(RefCell::new(cursor.clone()), async move {
+            let output = request.await;
+            let next_cursor = CURSOR.with(|next_cursor| next_cursor.take());
+            if let (Some(key), Some(next_cursor)) = (key, next_cursor) {
+                if Some(&next_cursor) != cursor.as_ref() {
+                    store(&mut lock(), key, page.saturating_add(1), next_cursor);
+                }
+            }
+            output
+        })
+        .await
+}
+
+/// A function which stores the cursor of a page of a search, dropping all the stored cursors
+/// first when the maximum number of stored cursors is reached.
+///
+/// # Arguments
+///
+/// * `cursors` - It takes the stored cursors as an argument.
+/// * `key` - It takes the key of the search as an argument.
+/// * `page` - It takes the index of the page as an argument.
+/// * `cursor` - It takes the cursor of the page as an argument.
+pub fn store(cursors: &mut Cursors, key: String, page: u32, cursor: String) {
+    if cursors.len() >= MAX_CURSORS && !cursors.contains_key(&(key.clone(), page)) {
+        cursors.clear();
+    }
+    cursors.insert((key, page), cursor);
+}
+
+/// A helper function which locks the stored cursors, recovering them if a thread panicked while
+/// holding the lock.
+fn lock() -> std::sync::MutexGuard<'static, Cursors> {
+    CURSORS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|error| error.into_inner())
+}
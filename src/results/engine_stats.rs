@@ -0,0 +1,154 @@
+//! This module provides the functionality to count the outcomes of the requests sent to each
+//! upstream search engine and the time they took over a rolling time window, so that the
+//! operators can find out which engines are reliable and the engines can be dispatched by their
+//! latency.
+
+use super::thumbnails::now;
+use crate::config::server;
+use crate::models::{
+    analytics_models::{EngineOutcomeCounts, EngineStatsReport},
+    engine_models::EngineError,
+};
+use error_stack::Report;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+/// The outcome counts of the requests sent to each upstream search engine within each hour of
+/// the time window, keyed by the name of the engine and the number of hours since the unix epoch.
+pub type OutcomeCounts = HashMap<String, BTreeMap<u64, EngineOutcomeCounts>>;
+
+/// A static variable which stores the outcome counts of the upstream search engines, which are
+/// shared by all the workers of the server.
+static OUTCOMES: OnceLock<Mutex<OutcomeCounts>> = OnceLock::new();
+
+/// A function which counts the outcome of a request sent to an upstream search engine, when the
+/// engine stats are enabled.
+///
+/// # Arguments
+///
+/// * `engine` - It takes the name of the upstream search engine as an argument.
+/// * `response` - It takes the results or the error returned by the engine as an argument.
+/// * `latency` - It takes the time taken by the request as an argument.
+/// * `engine_stats` - It takes the engine stats config as an argument.
+pub fn record<T>(
+    engine: &str,
+    response: &Result<T, Report<EngineError>>,
+    latency: Duration,
+    engine_stats: &server::EngineStats,
+) {
+    if !engine_stats.enabled {
+        return;
+    }
+
+    let mut outcomes = outcomes().lock().unwrap_or_else(|error| error.into_inner());
+    count_outcome(
+        &mut outcomes,
+        engine,
+        response
+            .as_ref()
+            .map(|_| ())
+            .map_err(|error| error.current_context()),
+        latency.as_millis() as u64,
+        now() / 3600,
+        engine_stats.window.into(),
+    );
+}
+
+/// A function which builds the engine stats report of the configured time window.
+///
+/// # Arguments
+///
+/// * `engine_stats` - It takes the engine stats config as an argument.
+pub fn report(engine_stats: &server::EngineStats) -> EngineStatsReport {
+    let outcomes = outcomes().lock().unwrap_or_else(|error| error.into_inner());
+    engine_stats_report(&outcomes, now() / 3600, engine_stats.window)
+}
+
+/// A function which provides the average latency (in milliseconds) of the requests sent to each
+/// upstream search engine within the configured time window, keyed by the lowercased name of the
+/// engine. No latencies are provided when the engine stats are disabled.
+///
+/// # Arguments
+///
+/// * `engine_stats` - It takes the engine stats config as an argument.
+pub fn average_latencies(engine_stats: &server::EngineStats) -> HashMap<String, u64> {
+    if !engine_stats.enabled {
+        return HashMap::new();
+    }
+
+    report(engine_stats)
+        .engines
+        .into_iter()
+        .map(|stats| (stats.engine, stats.average_latency_ms))
+        .collect()
+}
+
+/// A function which counts the outcome of a request sent to an upstream search engine within
+/// the provided hour, dropping the counts of the engine which are no longer within the time
+/// window.
+///
+/// # Arguments
+///
+/// * `outcomes` - It takes the outcome counts of the engines as an argument.
+/// * `engine` - It takes the name of the upstream search engine as an argument.
+/// * `outcome` - It takes the outcome of the request as an argument, which is the error returned
+///   by the engine if the request failed.
+/// * `latency_ms` - It takes the time taken by the request (in milliseconds) as an argument.
+/// * `hour` - It takes the number of hours since the unix epoch at which the request was sent as
+///   an argument.
+/// * `window` - It takes the size of the time window (in hours) as an argument.
+pub fn count_outcome(
+    outcomes: &mut OutcomeCounts,
+    engine: &str,
+    outcome: Result<(), &EngineError>,
+    latency_ms: u64,
+    hour: u64,
+    window: u64,
+) {
+    let hours = outcomes.entry(engine.to_lowercase()).or_default();
+    hours.retain(|counted_hour, _| *counted_hour + window > hour);
+
+    let counts = hours.entry(hour).or_default();
+    counts.latency_ms += latency_ms;
+    match outcome {
+        Ok(()) => counts.successes += 1,
+        Err(EngineError::EmptyResultSet) => counts.empty_results += 1,
+        Err(EngineError::Timeout) => counts.timeouts += 1,
+        Err(_) => counts.errors += 1,
+    }
+}
+
+/// A function which builds the engine stats report from the outcome counts of the hours within
+/// the time window ending at the provided hour.
+///
+/// # Arguments
+///
+/// * `outcomes` - It takes the outcome counts of the engines as an argument.
+/// * `hour` - It takes the number of hours since the unix epoch at which the time window ends as
+///   an argument.
+/// * `window` - It takes the size of the time window (in hours) as an argument.
+pub fn engine_stats_report(outcomes: &OutcomeCounts, hour: u64, window: u16) -> EngineStatsReport {
+    let first_hour = (hour + 1).saturating_sub(window.into());
+
+    let counts = outcomes
+        .iter()
+        .map(|(engine, hours)| {
+            let mut counts = EngineOutcomeCounts::default();
+            hours
+                .range(first_hour..=hour)
+                .for_each(|(_, hour_counts)| counts.merge(hour_counts));
+            (engine.to_owned(), counts)
+        })
+        .collect();
+
+    EngineStatsReport::new(counts, window)
+}
+
+/// A helper function which returns the outcome counts of the engines, initializing them on the
+/// first call.
+fn outcomes() -> &'static Mutex<OutcomeCounts> {
+    OUTCOMES.get_or_init(|| Mutex::new(HashMap::new()))
+}
@@ -0,0 +1,368 @@
+//! This module provides the functionality to fetch the favicons of the websites of the search
+//! results on behalf of the users, so that the websites are not contacted by the browsers of the
+//! users for every displayed search result.
+
+use super::thumbnails::{content_type, now, read_body, ThumbnailImage};
+use super::user_agent::random_user_agent;
+use crate::{
+    config::{shared::Reloadable, style::Favicons},
+    server::click_redirect::random_key,
+};
+use hyper::client::connect::dns::Name;
+use reqwest::{
+    dns::{Addrs, Resolve, Resolving},
+    header::{HeaderValue, ACCEPT, USER_AGENT},
+    redirect::Policy,
+    Client, ClientBuilder, Url,
+};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
+};
+
+/// The maximum size of a favicon in bytes.
+const MAX_ICON_SIZE: usize = 100 * 1024;
+/// The number of hex characters of the signature of a proxied host which are kept in the token.
+const TOKEN_LENGTH: usize = 32;
+
+/// The generic icon displayed for the websites whose favicon is unavailable.
+pub const FALLBACK_ICON: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 16 16" fill="none" stroke="gray" stroke-width="1.2"><circle cx="8" cy="8" r="6.5"/><path d="M1.5 8h13M8 1.5c-2 2-2.8 4.2-2.8 6.5S6 12.5 8 14.5M8 1.5c2 2 2.8 4.2 2.8 6.5S10 12.5 8 14.5"/></svg>"#;
+
+/// A static variable which stores the prebuilt client used to fetch the favicons, which is built
+/// again once the config is reloaded.
+static CLIENT: Reloadable<Client> = Reloadable::new();
+
+/// The favicons of the websites along with the unix timestamp at which they were fetched, keyed
+/// by the host of the website.
+type FaviconCache = HashMap<String, (Option<Arc<ThumbnailImage>>, u64)>;
+
+/// A static variable which stores the cached favicons. The websites without a favicon are cached
+/// as well so that they are not fetched again.
+static FAVICONS: OnceLock<Mutex<FaviconCache>> = OnceLock::new();
+
+/// A function which signs the host of the website of a search result, so that the favicon proxy
+/// only fetches the favicons of the websites of the search results and can not be used as an open
+/// proxy.
+///
+/// # Arguments
+///
+/// * `host` - It takes the host of the website as an argument.
+/// * `favicons` - It takes the favicon config as an argument.
+pub fn favicon_token(host: &str, favicons: &Favicons) -> String {
+    let key = match &favicons.secret {
+        Some(secret) => blake3::derive_key("websurfx favicon proxy", secret.as_bytes()),
+        None => blake3::derive_key("websurfx favicon proxy", &random_key()),
+    };
+    let mut token = blake3::keyed_hash(&key, host.as_bytes())
+        .to_hex()
+        .to_string();
+    token.truncate(TOKEN_LENGTH);
+    token
+}
+
+/// A function which checks whether the favicon of the provided host can be fetched by the proxy,
+/// which is the case when the host is a plain domain name signed by the provided token. The
+/// tokens are compared by their hashes so that the comparison takes the same time regardless of
+/// where the tokens differ.
+///
+/// # Arguments
+///
+/// * `host` - It takes the host of the website as an argument.
+/// * `token` - It takes the token provided along with the host as an argument.
+/// * `favicons` - It takes the favicon config as an argument.
+pub fn is_proxied_host(host: &str, token: &str, favicons: &Favicons) -> bool {
+    blake3::hash(favicon_token(host, favicons).as_bytes()) == blake3::hash(token.as_bytes())
+        && favicon_url(host).is_some()
+}
+
+/// A function which fetches the favicon of a website on behalf of the user, the favicon being
+/// served from the favicon cache while its cache entry has not expired.
+///
+/// # Arguments
+///
+/// * `host` - It takes the host of the website as an argument.
+/// * `favicons` - It takes the favicon config as an argument.
+/// * `generation` - It takes the generation of the config as an argument.
+///
+/// # Returns
+///
+/// Returns the favicon, or `None` if the host is invalid or if the website has no favicon which
+/// could be fetched.
+pub async fn fetch_favicon(
+    host: &str,
+    favicons: &Favicons,
+    generation: u64,
+) -> Option<Arc<ThumbnailImage>> {
+    let url = favicon_url(host)?;
+    let now = now();
+    if let Some(favicon) = cached_favicon(host, now, favicons.cache_expiry_time) {
+        return favicon;
+    }
+
+    let favicon = fetch_icon(&client(favicons, generation), url)
+        .await
+        .map(Arc::new);
+    if favicons.max_cached == 0 {
+        return favicon;
+    }
+
+    let mut cache = favicon_cache()
+        .lock()
+        .unwrap_or_else(|error| error.into_inner());
+    if cache.len() >= favicons.max_cached {
+        cache.retain(|_, (_, fetched_at)| {
+            now.saturating_sub(*fetched_at) < favicons.cache_expiry_time
+        });
+        // The oldest favicons are evicted when the cache is full of fresh favicons.
+        while cache.len() >= favicons.max_cached {
+            let Some(oldest) = cache
+                .iter()
+                .min_by_key(|(_, (_, fetched_at))| *fetched_at)
+                .map(|(host, _)| host.clone())
+            else {
+                break;
+            };
+            cache.remove(&oldest);
+        }
+    }
+    cache.insert(host.to_owned(), (favicon.clone(), now));
+    favicon
+}
+
+/// A function which builds the url of the favicon of a website, which is the `/favicon.ico` of
+/// the website over `https`. Only the plain domain names are accepted as the host, so that the
+/// proxy can not be pointed at the ip addresses or the ports of internal services.
+///
+/// # Arguments
+///
+/// * `host` - It takes the host of the website as an argument.
+///
+/// # Returns
+///
+/// Returns the url of the favicon, or `None` if the host is not a plain domain name.
+fn favicon_url(host: &str) -> Option<Url> {
+    let valid = host.contains('.')
+        && host
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || matches!(character, '.' | '-'));
+    if !valid {
+        return None;
+    }
+
+    let url = Url::parse(&format!("https://{host}/favicon.ico")).ok()?;
+    (url.domain() == Some(host)).then_some(url)
+}
+
+/// A helper function which fetches a favicon, or returns `None` if it could not be fetched, if it
+/// is not a raster image or if it is too large.
+///
+/// # Arguments
+///
+/// * `client` - It takes the client used to fetch the favicon as an argument.
+/// * `url` - It takes the url of the favicon as an argument.
+async fn fetch_icon(client: &Client, url: Url) -> Option<ThumbnailImage> {
+    let response = client
+        .get(url)
+        .header(ACCEPT, "image/*")
+        .send()
+        .await
+        .ok()?
+        .error_for_status()
+        .ok()?;
+
+    // The svg images are not served from the origin of the instance, as they can hold scripts.
+    let content_type = content_type(&response)?;
+    if !content_type.starts_with("image/") || content_type.starts_with("image/svg") {
+        return None;
+    }
+
+    let bytes = read_body(response, MAX_ICON_SIZE).await?;
+    (!bytes.is_empty() && bytes.len() < MAX_ICON_SIZE).then_some(ThumbnailImage {
+        content_type,
+        bytes,
+    })
+}
+
+/// A helper function which returns the cached favicon of a website, or `None` if the website is
+/// not cached or if its cache entry has expired.
+///
+/// # Arguments
+///
+/// * `host` - It takes the host of the website as an argument.
+/// * `now` - It takes the current unix timestamp (in seconds) as an argument.
+/// * `expiry_time` - It takes the expiry time of the cached favicons (in seconds) as an argument.
+fn cached_favicon(host: &str, now: u64, expiry_time: u64) -> Option<Option<Arc<ThumbnailImage>>> {
+    let cache = favicon_cache()
+        .lock()
+        .unwrap_or_else(|error| error.into_inner());
+    cache
+        .get(host)
+        .filter(|(_, fetched_at)| now.saturating_sub(*fetched_at) < expiry_time)
+        .map(|(favicon, _)| favicon.clone())
+}
+
+/// A helper function which returns the favicon cache, initializing it on the first call.
+fn favicon_cache() -> &'static Mutex<FaviconCache> {
+    FAVICONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// A helper function which returns the prebuilt client, building it on the first call and again
+/// after the config was reloaded.
+///
+/// # Arguments
+///
+/// * `favicons` - It takes the favicon config which provides the client options as an argument.
+/// * `generation` - It takes the generation of the config as an argument.
+fn client(favicons: &Favicons, generation: u64) -> Arc<Client> {
+    CLIENT.get_or_build(generation, || {
+        let mut default_headers = reqwest::header::HeaderMap::new();
+        if let Ok(user_agent) = HeaderValue::from_str(random_user_agent()) {
+            default_headers.insert(USER_AGENT, user_agent);
+        }
+
+        // The redirects are not followed, as they could point the proxy at any other host.
+        ClientBuilder::new()
+            .timeout(Duration::from_secs(favicons.timeout as u64))
+            .https_only(true)
+            .redirect(Policy::none())
+            .dns_resolver(Arc::new(PublicResolver))
+            .default_headers(default_headers)
+            .build()
+            .unwrap()
+    })
+}
+
+/// A resolver which only resolves the hosts whose addresses are all public, so that the proxy can
+/// not be pointed at the internal services of the network through their domain names.
+pub struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_owned();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            if addrs.is_empty() || !addrs.iter().all(|addr| is_public_ip(addr.ip())) {
+                return Err(
+                    format!("The host `{host}` does not resolve to public addresses").into(),
+                );
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// A helper function which checks whether the provided ip address is a public address, which is
+/// neither a loopback, a private, a link-local nor another special purpose address.
+///
+/// # Arguments
+///
+/// * `ip` - It takes the ip address as an argument.
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                // The shared address space used by the carrier-grade NATs.
+                || (first == 100 && second & 0b1100_0000 == 64)
+                || first == 0)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ip(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    // The unique local and the link-local addresses.
+                    || first & 0xfe00 == 0xfc00
+                    || first & 0xffc0 == 0xfe80)
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_favicon_url() {
+        // Only the plain domain names are proxied.
+        assert_eq!(
+            favicon_url("www.example.com").map(String::from).as_deref(),
+            Some("https://www.example.com/favicon.ico")
+        );
+        for host in [
+            "localhost",
+            "127.0.0.1",
+            "example.com:8080",
+            "example.com/x",
+            "[::1]",
+            "",
+        ] {
+            assert!(favicon_url(host).is_none(), "{host}");
+        }
+    }
+
+    #[test]
+    fn test_is_proxied_host() {
+        let favicons = Favicons {
+            secret: Some("secret".to_owned()),
+            ..Default::default()
+        };
+
+        // Only the signed plain domain names are proxied.
+        let token = favicon_token("www.example.com", &favicons);
+        assert!(is_proxied_host("www.example.com", &token, &favicons));
+        assert!(!is_proxied_host("example.com", &token, &favicons));
+        assert!(!is_proxied_host("www.example.com", "", &favicons));
+        for host in ["localhost", "127.0.0.1"] {
+            assert!(!is_proxied_host(
+                host,
+                &favicon_token(host, &favicons),
+                &favicons
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_public_resolver() {
+        use std::str::FromStr;
+
+        for ip in [
+            "127.0.0.1",
+            "10.0.0.1",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+        for ip in [
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "100.64.0.1",
+            "0.0.0.0",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+        for ip in ["93.184.216.34", "2606:2800:220:1:248:1893:25c8:1946"] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+
+        // The internal hosts are not resolved.
+        let name = Name::from_str("localhost").unwrap();
+        assert!(PublicResolver.resolve(name).await.is_err());
+    }
+}
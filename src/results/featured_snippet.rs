@@ -0,0 +1,266 @@
+//! This module provides the functionality to extract a featured snippet from the page of the top
+//! ranked search result by fetching the page and taking its first meaningful paragraph.
+
+use super::{
+    thumbnails::{content_type, now, read_body},
+    user_agent::random_user_agent,
+};
+use crate::{
+    config::{server, shared::Reloadable},
+    models::aggregation_models::{FeaturedSnippet, SearchResult},
+};
+use reqwest::{
+    header::{HeaderValue, ACCEPT, USER_AGENT},
+    Client, ClientBuilder, Url,
+};
+use scraper::{ElementRef, Html, Selector};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
+};
+
+/// The maximum number of result pages whose featured snippets are kept in the snippet cache.
+const MAX_CACHED_PAGES: usize = 10_000;
+/// The maximum number of bytes of a result page which are searched for a meaningful paragraph.
+const MAX_PAGE_SIZE: usize = 256 * 1024;
+/// The minimum number of characters of a paragraph for it to be considered meaningful.
+const MIN_PARAGRAPH_LENGTH: usize = 80;
+/// The minimum number of words of a paragraph for it to be considered meaningful.
+const MIN_PARAGRAPH_WORDS: usize = 10;
+/// The elements whose paragraphs are not part of the main content of a page.
+const SKIPPED_ELEMENTS: [&str; 6] = ["nav", "header", "footer", "aside", "form", "noscript"];
+
+/// A static variable which stores the prebuilt client used to fetch the result pages, which is
+/// built again once the config is reloaded.
+static CLIENT: Reloadable<Client> = Reloadable::new();
+
+/// The featured snippets of the result pages along with the unix timestamp at which they were
+/// fetched, keyed by the url of the result page.
+type SnippetCache = HashMap<String, (Option<String>, u64)>;
+
+/// A static variable which stores the cached featured snippets of the result pages. The pages
+/// without a meaningful paragraph are cached as well so that they are not fetched again.
+static SNIPPETS: OnceLock<Mutex<SnippetCache>> = OnceLock::new();
+
+/// A function which extracts the featured snippet from the page of the top ranked search result.
+/// The pinned results are skipped as they are not ranked.
+///
+/// # Arguments
+///
+/// * `results` - It takes the ranked search results as an argument.
+/// * `featured_snippet` - It takes the featured snippet config as an argument.
+/// * `generation` - It takes the generation of the config as an argument.
+///
+/// # Returns
+///
+/// Returns the featured snippet, or `None` if the page could not be fetched or if it has no
+/// meaningful paragraph.
+pub async fn featured_snippet(
+    results: &[SearchResult],
+    featured_snippet: &server::FeaturedSnippet,
+    generation: u64,
+) -> Option<FeaturedSnippet> {
+    let result = results.iter().find(|result| !result.pinned)?;
+    let now = now();
+
+    let text = match cached_snippet(&result.url, now, featured_snippet.cache_expiry_time) {
+        Some(text) => text,
+        None => {
+            let text = fetch_snippet(&client(featured_snippet, generation), &result.url)
+                .await
+                .map(|text| truncate(&text, featured_snippet.max_length));
+            cache_snippet(&result.url, text.clone(), now, featured_snippet);
+            text
+        }
+    }?;
+
+    Some(FeaturedSnippet {
+        title: result.title.clone(),
+        url: result.url.clone(),
+        text,
+    })
+}
+
+/// A helper function which fetches a result page and returns its first meaningful paragraph, or
+/// `None` if the page could not be fetched or if it has no meaningful paragraph.
+///
+/// # Arguments
+///
+/// * `client` - It takes the client used to fetch the result page as an argument.
+/// * `page_url` - It takes the url of the result page as an argument.
+async fn fetch_snippet(client: &Client, page_url: &str) -> Option<String> {
+    let response = client
+        .get(Url::parse(page_url).ok()?)
+        .header(ACCEPT, "text/html")
+        .send()
+        .await
+        .ok()?
+        .error_for_status()
+        .ok()?;
+
+    if !content_type(&response)?.starts_with("text/html") {
+        return None;
+    }
+
+    let body = read_body(response, MAX_PAGE_SIZE).await?;
+    first_paragraph(&String::from_utf8_lossy(&body))
+}
+
+/// A function which parses the first meaningful paragraph from the html of a page. The
+/// paragraphs of the `article` or `main` element are preferred when the page has one, and the
+/// paragraphs of the navigation, header, footer and sidebar elements are skipped. The whitespace
+/// of the paragraph is collapsed.
+///
+/// # Arguments
+///
+/// * `html` - It takes the html of the page as an argument.
+pub fn first_paragraph(html: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+
+    ["article p", "main p", "p"].iter().find_map(|selector| {
+        let selector = Selector::parse(selector).ok()?;
+        document
+            .select(&selector)
+            .filter(|paragraph| !is_skipped(paragraph))
+            .map(|paragraph| collapse_whitespace(&paragraph.text().collect::<String>()))
+            .find(|text| is_meaningful(text))
+    })
+}
+
+/// A function which cuts a snippet short at a word boundary when it is longer than the provided
+/// number of characters, marking the cut with an ellipsis.
+///
+/// # Arguments
+///
+/// * `text` - It takes the text of the snippet as an argument.
+/// * `max_length` - It takes the maximum number of characters of the snippet as an argument.
+pub fn truncate(text: &str, max_length: usize) -> String {
+    if text.chars().count() <= max_length {
+        return text.to_owned();
+    }
+
+    let cut: String = text.chars().take(max_length).collect();
+    let cut = match cut.rfind(char::is_whitespace) {
+        Some(index) if index > 0 => &cut[..index],
+        _ => &cut,
+    };
+    format!(
+        "{}…",
+        cut.trim_end_matches(|c: char| c.is_whitespace() || c.is_ascii_punctuation())
+    )
+}
+
+/// A helper function which checks whether a paragraph is placed within an element which is not
+/// part of the main content of the page.
+///
+/// # Arguments
+///
+/// * `paragraph` - It takes the paragraph element as an argument.
+fn is_skipped(paragraph: &ElementRef<'_>) -> bool {
+    paragraph.ancestors().any(|ancestor| {
+        ancestor
+            .value()
+            .as_element()
+            .is_some_and(|element| SKIPPED_ELEMENTS.contains(&element.name()))
+    })
+}
+
+/// A helper function which checks whether a paragraph is long enough to be a meaningful snippet
+/// rather than a caption, a byline or a cookie notice.
+///
+/// # Arguments
+///
+/// * `text` - It takes the text of the paragraph as an argument.
+fn is_meaningful(text: &str) -> bool {
+    text.chars().count() >= MIN_PARAGRAPH_LENGTH
+        && text.split_whitespace().count() >= MIN_PARAGRAPH_WORDS
+}
+
+/// A helper function which collapses the runs of whitespace of a text into single spaces.
+///
+/// # Arguments
+///
+/// * `text` - It takes the text as an argument.
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// A helper function which returns the cached featured snippet of a result page, or `None` if
+/// the page is not cached or if its cache entry has expired.
+///
+/// # Arguments
+///
+/// * `page_url` - It takes the url of the result page as an argument.
+/// * `now` - It takes the current unix timestamp (in seconds) as an argument.
+/// * `expiry_time` - It takes the expiry time of the cached featured snippets (in seconds) as an
+///   argument.
+fn cached_snippet(page_url: &str, now: u64, expiry_time: u64) -> Option<Option<String>> {
+    let cache = snippet_cache()
+        .lock()
+        .unwrap_or_else(|error| error.into_inner());
+    cache
+        .get(page_url)
+        .filter(|(_, fetched_at)| now.saturating_sub(*fetched_at) < expiry_time)
+        .map(|(snippet, _)| snippet.clone())
+}
+
+/// A helper function which caches the featured snippet of a result page, evicting the expired
+/// entries first when the cache is full.
+///
+/// # Arguments
+///
+/// * `page_url` - It takes the url of the result page as an argument.
+/// * `snippet` - It takes the extracted featured snippet of the page as an argument.
+/// * `now` - It takes the current unix timestamp (in seconds) as an argument.
+/// * `featured_snippet` - It takes the featured snippet config as an argument.
+fn cache_snippet(
+    page_url: &str,
+    snippet: Option<String>,
+    now: u64,
+    featured_snippet: &server::FeaturedSnippet,
+) {
+    let mut cache = snippet_cache()
+        .lock()
+        .unwrap_or_else(|error| error.into_inner());
+    if cache.len() >= MAX_CACHED_PAGES {
+        cache.retain(|_, (_, fetched_at)| {
+            now.saturating_sub(*fetched_at) < featured_snippet.cache_expiry_time
+        });
+        if cache.len() >= MAX_CACHED_PAGES {
+            cache.clear();
+        }
+    }
+    cache.insert(page_url.to_owned(), (snippet, now));
+}
+
+/// A helper function which returns the snippet cache, initializing it on the first call.
+fn snippet_cache() -> &'static Mutex<SnippetCache> {
+    SNIPPETS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// A helper function which returns the prebuilt client, building it on the first call and again
+/// after the config was reloaded.
+///
+/// # Arguments
+///
+/// * `featured_snippet` - It takes the featured snippet config which provides the client options
+///   as an argument.
+/// * `generation` - It takes the generation of the config as an argument.
+fn client(featured_snippet: &server::FeaturedSnippet, generation: u64) -> Arc<Client> {
+    CLIENT.get_or_build(generation, || {
+        let mut default_headers = reqwest::header::HeaderMap::new();
+        if let Ok(user_agent) = HeaderValue::from_str(random_user_agent()) {
+            default_headers.insert(USER_AGENT, user_agent);
+        }
+
+        ClientBuilder::new()
+            .timeout(Duration::from_secs(featured_snippet.timeout as u64))
+            .https_only(true)
+            .gzip(true)
+            .brotli(true)
+            .default_headers(default_headers)
+            .build()
+            .unwrap()
+    })
+}
@@ -0,0 +1,184 @@
+//! This module provides the functionality to load the blocklist, allowlist and sensitive terms
+//! files and to combine the regex patterns of all the files of a filter list into a single compiled matcher.
+
+use crate::config::{search::Search, shared::Reloadable};
+use crate::handler::{file_path, FileType};
+use regex::RegexSet;
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    sync::Arc,
+};
+
+/// A static variable which stores the compiled matcher of the blocklist files.
+static BLOCKLIST: Reloadable<FilterList> = Reloadable::new();
+/// A static variable which stores the compiled matcher of the allowlist files.
+static ALLOWLIST: Reloadable<FilterList> = Reloadable::new();
+/// A static variable which stores the compiled matcher of the sensitive terms files.
+static SENSITIVE_TERMS: Reloadable<FilterList> = Reloadable::new();
+
+/// A named struct which stores the regex patterns of one or more filter list files compiled
+/// into a single matcher.
+#[derive(Debug)]
+pub struct FilterList {
+    /// The compiled regex patterns of all the loaded files.
+    patterns: RegexSet,
+}
+
+impl FilterList {
+    /// Constructs a new `FilterList` from the provided regex patterns.
+    ///
+    /// # Arguments
+    ///
+    /// * `patterns` - It takes the regex patterns to be matched as an argument.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the regex patterns is invalid.
+    pub fn new(patterns: &[String]) -> Result<Self, regex::Error> {
+        Ok(Self {
+            patterns: RegexSet::new(patterns)?,
+        })
+    }
+
+    /// Loads the regex patterns of all the provided files and combines them into a single
+    /// matcher. The files which can not be read or which contain an invalid regex pattern are
+    /// logged and skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_paths` - It takes the paths of the filter list files as an argument.
+    pub fn load<P: AsRef<str>>(file_paths: &[P]) -> Self {
+        let mut patterns: Vec<String> = Vec::new();
+
+        for file_path in file_paths {
+            let file_path = file_path.as_ref();
+            match read_patterns(file_path) {
+                Ok(file_patterns) => patterns.extend(file_patterns),
+                Err(error) => {
+                    log::error!("Failed to load the filter list file `{file_path}`: {error}");
+                    log::error!("Skipping the file");
+                }
+            }
+        }
+
+        Self::new(&patterns).unwrap_or_else(|error| {
+            log::error!("Failed to compile the filter list patterns: {error}");
+            Self {
+                patterns: RegexSet::empty(),
+            }
+        })
+    }
+
+    /// Returns whether the provided text matches any of the regex patterns.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - It takes the text to be matched as an argument.
+    pub fn is_match(&self, text: &str) -> bool {
+        self.patterns.is_match(text)
+    }
+
+    /// Returns whether the filter list has no regex patterns.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+}
+
+/// Reads the regex patterns of a filter list file. Each line of the file holds a pattern, the
+/// blank lines and the lines starting with a `#` are ignored.
+///
+/// # Arguments
+///
+/// * `file_path` - It takes the path of the filter list file as an argument.
+///
+/// # Errors
+///
+/// Returns an error if the file can not be opened or read, or if a regex pattern is invalid.
+pub fn read_patterns(file_path: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut patterns: Vec<String> = Vec::new();
+
+    for line in BufReader::new(File::open(file_path)?).lines() {
+        let line = line?;
+        let pattern = line.trim();
+        if pattern.is_empty() || pattern.starts_with('#') {
+            continue;
+        }
+        regex::Regex::new(pattern)?;
+        patterns.push(pattern.to_owned());
+    }
+
+    Ok(patterns)
+}
+
+/// Returns the configured filter list files, or the default file of the provided type when no
+/// files are configured.
+///
+/// # Arguments
+///
+/// * `file_paths` - It takes the configured paths of the filter list files as an argument.
+/// * `file_type` - It takes the type of the default filter list file as an argument.
+fn filter_list_files(file_paths: &[String], file_type: FileType) -> Vec<String> {
+    if !file_paths.is_empty() {
+        return file_paths.to_vec();
+    }
+
+    match file_path(file_type) {
+        Ok(default_file_path) => vec![default_file_path.to_owned()],
+        Err(error) => {
+            log::error!("{error}");
+            Vec::new()
+        }
+    }
+}
+
+/// Returns the compiled matcher of the blocklist files, which are loaded on the first call and again
+/// after the config was reloaded.
+///
+/// # Arguments
+///
+/// * `search` - It takes the search config as an argument.
+/// * `generation` - It takes the generation of the config as an argument.
+pub fn blocklist(search: &Search, generation: u64) -> Arc<FilterList> {
+    BLOCKLIST.get_or_build(generation, || {
+        FilterList::load(&filter_list_files(&search.blocklists, FileType::BlockList))
+    })
+}
+
+/// Returns the compiled matcher of the allowlist files, which are loaded on the first call and again
+/// after the config was reloaded.
+///
+/// # Arguments
+///
+/// * `search` - It takes the search config as an argument.
+/// * `generation` - It takes the generation of the config as an argument.
+pub fn allowlist(search: &Search, generation: u64) -> Arc<FilterList> {
+    ALLOWLIST.get_or_build(generation, || {
+        FilterList::load(&filter_list_files(&search.allowlists, FileType::AllowList))
+    })
+}
+
+/// Returns the compiled matcher of the sensitive terms files, which are loaded on the first call
+/// and again after the config was reloaded. Unlike the other filter lists no default file is
+/// used, so that no search is redacted when no files are configured.
+///
+/// # Arguments
+///
+/// * `search` - It takes the search config as an argument.
+/// * `generation` - It takes the generation of the config as an argument.
+pub fn sensitive_terms(search: &Search, generation: u64) -> Arc<FilterList> {
+    SENSITIVE_TERMS.get_or_build(generation, || FilterList::load(&search.sensitive_terms))
+}
+
+/// Returns whether the provided search query matches any of the sensitive terms, the query is
+/// lowercased before it is matched.
+///
+/// # Arguments
+///
+/// * `query` - It takes the search query as an argument.
+/// * `search` - It takes the search config as an argument.
+/// * `generation` - It takes the generation of the config as an argument.
+pub fn is_sensitive(query: &str, search: &Search, generation: u64) -> bool {
+    let sensitive_terms = sensitive_terms(search, generation);
+    !sensitive_terms.is_empty() && sensitive_terms.is_match(&query.to_lowercase())
+}
@@ -0,0 +1,115 @@
+//! This module provides the functionality to upgrade the urls of the search results to `https`
+//! when their host is on the HSTS preload list, which means that the browsers only ever connect
+//! to the host over `https` anyway, so the upgrade can not break a host which is `http` only.
+
+use crate::config::{search::HttpsUpgrade, shared::Reloadable};
+use reqwest::Url;
+use std::{collections::HashSet, fs::read_to_string, sync::Arc};
+
+/// The HSTS preload list bundled with the app.
+const BUNDLED_PRELOAD_LIST: &str = include_str!("hsts_preload.txt");
+
+/// A static variable which stores the parsed HSTS preload lists, which are loaded again once the
+/// config is reloaded.
+static PRELOAD_LIST: Reloadable<PreloadList> = Reloadable::new();
+
+/// A named struct which stores the domains of one or more HSTS preload lists.
+#[derive(Debug, Default)]
+pub struct PreloadList {
+    /// The preloaded domains which cover all of their subdomains as well.
+    domains: HashSet<String>,
+    /// The preloaded hosts which do not cover their subdomains.
+    hosts: HashSet<String>,
+}
+
+impl PreloadList {
+    /// Parses the domains of a HSTS preload list and adds them to the list. Each line holds a
+    /// domain which covers all of its subdomains, or a host prefixed with `=` which only covers
+    /// the host itself. The blank lines and the lines starting with a `#` are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `list` - It takes the contents of the preload list as an argument.
+    pub fn extend(&mut self, list: &str) {
+        for line in list.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.strip_prefix('=') {
+                Some(host) => self.hosts.insert(normalize(host)),
+                None => self.domains.insert(normalize(line)),
+            };
+        }
+    }
+
+    /// Returns whether the provided host is covered by the preload list, either because it is
+    /// preloaded itself or because one of its parent domains is preloaded along with its
+    /// subdomains.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - It takes the host name as an argument.
+    pub fn is_preloaded(&self, host: &str) -> bool {
+        let host = normalize(host);
+        self.hosts.contains(&host)
+            || std::iter::successors(Some(host.as_str()), |domain| {
+                domain.split_once('.').map(|(_, parent)| parent)
+            })
+            .any(|domain| self.domains.contains(domain))
+    }
+
+    /// Upgrades the provided `http` url to `https` if its host is covered by the preload list.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - It takes the url to be upgraded as an argument.
+    ///
+    /// # Returns
+    ///
+    /// Returns the upgraded url, or `None` when the url is not an `http` url or when its host
+    /// is not covered by the preload list.
+    pub fn upgrade(&self, url: &str) -> Option<String> {
+        let mut url = Url::parse(url).ok()?;
+        if url.scheme() != "http" || !url.domain().is_some_and(|host| self.is_preloaded(host)) {
+            return None;
+        }
+        url.set_scheme("https").ok()?;
+        Some(url.into())
+    }
+}
+
+/// Returns the HSTS preload list made of the bundled list and the configured preload list files,
+/// which are loaded on the first call and again after the config was reloaded. The files which
+/// can not be read are logged and skipped.
+///
+/// # Arguments
+///
+/// * `https_upgrade` - It takes the https upgrade config as an argument.
+/// * `generation` - It takes the generation of the config as an argument.
+pub fn preload_list(https_upgrade: &HttpsUpgrade, generation: u64) -> Arc<PreloadList> {
+    PRELOAD_LIST.get_or_build(generation, || {
+        let mut preload_list = PreloadList::default();
+        preload_list.extend(BUNDLED_PRELOAD_LIST);
+        for file_path in &https_upgrade.preload_lists {
+            match read_to_string(file_path) {
+                Ok(list) => preload_list.extend(&list),
+                Err(error) => {
+                    log::error!("Failed to load the HSTS preload list file `{file_path}`: {error}");
+                    log::error!("Skipping the file");
+                }
+            }
+        }
+        preload_list
+    })
+}
+
+/// A helper function which lowercases a domain and removes the trailing dot of a fully qualified
+/// domain, so that the domains are compared in the same form.
+///
+/// # Arguments
+///
+/// * `domain` - It takes the domain as an argument.
+fn normalize(domain: &str) -> String {
+    domain.trim_end_matches('.').to_lowercase()
+}
@@ -3,4 +3,14 @@
 //! provides various models to aggregate search results into a standardized form.
 
 pub mod aggregator;
+pub mod cursors;
+pub mod engine_stats;
+pub mod favicons;
+pub mod featured_snippet;
+pub mod filter_lists;
+pub mod hsts;
+pub mod rate_limits;
+pub mod sanitizer;
+pub mod suggestions;
+pub mod thumbnails;
 mod user_agent;
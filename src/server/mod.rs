@@ -3,5 +3,9 @@
 //! the search route. Also, caches the next, current and previous search results in the search
 //! routes with the help of the redis server.
 
+pub mod click_redirect;
+pub mod error;
+pub mod redaction;
 pub mod router;
 pub mod routes;
+pub mod tls;
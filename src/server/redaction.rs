@@ -0,0 +1,76 @@
+//! This module provides the functionality to redact the search queries which match any of the
+//! configured sensitive terms from the request urls written to the logs.
+
+use crate::results::filter_lists::FilterList;
+use percent_encoding::percent_decode_str;
+use reqwest::Url;
+use std::borrow::Cow;
+
+/// The text which replaces the redacted parts of the logged request urls.
+pub const REDACTED: &str = "[redacted]";
+
+/// The search url parameters which hold a search query.
+const QUERY_PARAMETERS: [&str; 2] = ["q", "refine"];
+
+/// A function which redacts a request url when it is the url of a search whose query matches any
+/// of the sensitive terms. The whole url query string is redacted as well as the search query
+/// provided as a path segment, while the other urls are returned as they are.
+///
+/// # Arguments
+///
+/// * `url` - It takes the request url as an argument, which is either an absolute url (like the
+///   `Referer` header) or only the path and the query string of the url.
+/// * `sensitive_terms` - It takes the compiled matcher of the sensitive terms as an argument.
+pub fn redact_url<'a>(url: &'a str, sensitive_terms: &FilterList) -> Cow<'a, str> {
+    if sensitive_terms.is_empty() {
+        return Cow::Borrowed(url);
+    }
+
+    let (location, query_string) = url.split_once('?').unwrap_or((url, ""));
+    let path_start = match location.find("://") {
+        Some(scheme_end) => location[scheme_end + 3..]
+            .find('/')
+            .map_or(location.len(), |index| scheme_end + 3 + index),
+        None => 0,
+    };
+    let (origin, path) = location.split_at(path_start);
+    let path_query = path
+        .strip_prefix("/search/")
+        .map(|segment| percent_decode_str(segment).decode_utf8_lossy());
+
+    if !path_query
+        .as_deref()
+        .is_some_and(|query| sensitive_terms.is_match(&query.to_lowercase()))
+        && !query_parameters(query_string)
+            .any(|query| sensitive_terms.is_match(&query.to_lowercase()))
+    {
+        return Cow::Borrowed(url);
+    }
+
+    let path = match path_query {
+        Some(_) => Cow::Owned(format!("/search/{REDACTED}")),
+        None => Cow::Borrowed(path),
+    };
+    match query_string.is_empty() {
+        true => Cow::Owned(format!("{origin}{path}")),
+        false => Cow::Owned(format!("{origin}{path}?{REDACTED}")),
+    }
+}
+
+/// A helper function which provides the decoded values of the search url parameters holding a
+/// search query.
+///
+/// # Arguments
+///
+/// * `query_string` - It takes the query string of the request url as an argument.
+fn query_parameters(query_string: &str) -> impl Iterator<Item = String> {
+    Url::parse(&format!("http://localhost/?{query_string}"))
+        .map(|url| {
+            url.query_pairs()
+                .filter(|(key, _)| QUERY_PARAMETERS.contains(&key.as_ref()))
+                .map(|(_, value)| value.into_owned())
+                .collect::<Vec<String>>()
+        })
+        .unwrap_or_default()
+        .into_iter()
+}
@@ -3,20 +3,74 @@
 //! when requested.
 
 use crate::{
-    config::Config,
-    handler::{file_path, FileType},
+    cache::cacher::SharedCache,
+    config::{
+        search::Search,
+        shared::{Reloadable, SharedConfig},
+        Config,
+    },
+    handler::{config_file_path, file_path, FileType},
+    models::{
+        engine_models::{EngineHandler, EngineInfo},
+        server_models::{
+            AutocompleteParams, ConfigReloadReport, FaviconParams, RedirectParams, ThumbnailParams,
+            VersionInfo,
+        },
+    },
+    results::{
+        engine_stats::report,
+        favicons::{fetch_favicon, is_proxied_host, FALLBACK_ICON},
+        suggestions::suggestions,
+        thumbnails::fetch_thumbnail,
+    },
+    server::{
+        click_redirect::{clicks, is_signed, outbound_url, record_click},
+        routes::search::dropped_cache_writes,
+    },
+};
+use actix_web::{
+    get,
+    http::header::{self, ContentType},
+    post, web, HttpRequest, HttpResponse,
 };
-use actix_web::{get, http::header::ContentType, web, HttpRequest, HttpResponse};
 use std::fs::read_to_string;
 
+/// A static variable which stores the contents of the custom css file, which is read again once
+/// the config is reloaded.
+static CUSTOM_CSS: Reloadable<Option<String>> = Reloadable::new();
+
+/// The names of the optional cargo features of the app along with whether the app was compiled
+/// with them.
+const FEATURES: [(&str, bool); 8] = [
+    ("lua-config", cfg!(feature = "lua-config")),
+    ("json-config", cfg!(feature = "json-config")),
+    ("memory-cache", cfg!(feature = "memory-cache")),
+    ("redis-cache", cfg!(feature = "redis-cache")),
+    (
+        "compress-cache-results",
+        cfg!(feature = "compress-cache-results"),
+    ),
+    (
+        "encrypt-cache-results",
+        cfg!(feature = "encrypt-cache-results"),
+    ),
+    ("cec-cache-results", cfg!(feature = "cec-cache-results")),
+    ("dhat-heap", cfg!(feature = "dhat-heap")),
+];
+
 /// Handles the route of index page or main page of the `websurfx` meta search engine website.
 #[get("/")]
-pub async fn index(config: web::Data<Config>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+pub async fn index(
+    config: web::Data<SharedConfig>,
+) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let config = config.get();
     Ok(HttpResponse::Ok().content_type(ContentType::html()).body(
         crate::templates::views::index::index(
             &config.style.colorscheme,
             &config.style.theme,
             &config.style.animation,
+            config.style.custom_css.is_some(),
+            &config.style.shortcuts,
         )
         .0,
     ))
@@ -25,36 +79,79 @@ pub async fn index(config: web::Data<Config>) -> Result<HttpResponse, Box<dyn st
 /// Handles the route of any other accessed route/page which is not provided by the
 /// website essentially the 404 error page.
 pub async fn not_found(
-    config: web::Data<Config>,
+    config: web::Data<SharedConfig>,
 ) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let config = config.get();
     Ok(HttpResponse::Ok().content_type(ContentType::html()).body(
         crate::templates::views::not_found::not_found(
             &config.style.colorscheme,
             &config.style.theme,
             &config.style.animation,
+            config.style.custom_css.is_some(),
         )
         .0,
     ))
 }
 
-/// Handles the route of robots.txt page of the `websurfx` meta search engine website.
+/// Handles the route of robots.txt page of the `websurfx` meta search engine website. The
+/// contents provided in the config are served if set, otherwise the default robots.txt file is
+/// served.
 #[get("/robots.txt")]
-pub async fn robots_data(_req: HttpRequest) -> Result<HttpResponse, Box<dyn std::error::Error>> {
-    let page_content: String =
-        read_to_string(format!("{}/robots.txt", file_path(FileType::Theme)?))?;
+pub async fn robots_data(
+    config: web::Data<SharedConfig>,
+) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let config = config.get();
+    let page_content: String = match &config.server.robots_txt {
+        Some(robots_txt) => robots_txt.to_owned(),
+        None => read_to_string(format!("{}/robots.txt", file_path(FileType::Theme)?))?,
+    };
     Ok(HttpResponse::Ok()
         .content_type(ContentType::plaintext())
         .body(page_content))
 }
 
+/// Handles the route of the custom css file configured by the operator, which is linked after
+/// the theme on every page. The file is read once and cached until the config is reloaded, and it
+/// is not available when no custom css file is configured.
+#[get("/custom.css")]
+pub async fn custom_css(
+    config: web::Data<SharedConfig>,
+) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let config = config.get();
+    let Some(custom_css) = &config.style.custom_css else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+
+    let contents = CUSTOM_CSS.get_or_build(config.generation, || {
+        config_file_path(custom_css)
+            .and_then(read_to_string)
+            .map_err(|error| {
+                log::error!("Config Error: Failed to read the custom css file: {error}")
+            })
+            .ok()
+    });
+
+    match contents.as_ref() {
+        Some(contents) => Ok(HttpResponse::Ok()
+            .content_type("text/css; charset=utf-8")
+            .insert_header((header::CACHE_CONTROL, "no-cache"))
+            .body(contents.to_owned())),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
 /// Handles the route of about page of the `websurfx` meta search engine website.
 #[get("/about")]
-pub async fn about(config: web::Data<Config>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+pub async fn about(
+    config: web::Data<SharedConfig>,
+) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let config = config.get();
     Ok(HttpResponse::Ok().content_type(ContentType::html()).body(
         crate::templates::views::about::about(
             &config.style.colorscheme,
             &config.style.theme,
             &config.style.animation,
+            config.style.custom_css.is_some(),
         )
         .0,
     ))
@@ -63,16 +160,439 @@ pub async fn about(config: web::Data<Config>) -> Result<HttpResponse, Box<dyn st
 /// Handles the route of settings page of the `websurfx` meta search engine website.
 #[get("/settings")]
 pub async fn settings(
-    config: web::Data<Config>,
+    config: web::Data<SharedConfig>,
 ) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let config = config.get();
     Ok(HttpResponse::Ok().content_type(ContentType::html()).body(
         crate::templates::views::settings::settings(
             config.search.safe_search,
             &config.style.colorscheme,
             &config.style.theme,
             &config.style.animation,
+            config.style.custom_css.is_some(),
             &config.search.upstream_search_engines,
+            &config.server.cookie,
         )?
         .0,
     ))
 }
+
+/// Handles the route of engines endpoint of the `websurfx` meta search engine website which
+/// provides the list of all the available upstream search engines, whether they are enabled or
+/// forced, the features they support and the native values of their safe search parameter for
+/// each safe search level as json.
+///
+/// # Example
+///
+/// ```bash
+/// curl "http://127.0.0.1:8080/engines"
+/// ```
+#[get("/engines")]
+pub async fn engines(
+    config: web::Data<SharedConfig>,
+) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let config = config.get();
+    let forced_engines = &config.search.forced_engines;
+    let is_forced = |name: &str| {
+        forced_engines
+            .iter()
+            .any(|forced| forced.eq_ignore_ascii_case(name))
+    };
+
+    let mut engines: Vec<EngineInfo> = config
+        .search
+        .upstream_search_engines
+        .iter()
+        .filter_map(|(name, enabled)| {
+            EngineHandler::new(name).ok().map(|engine| EngineInfo {
+                name: name.to_owned(),
+                enabled: *enabled || is_forced(name),
+                forced: is_forced(name),
+                features: engine.capabilities(),
+                safe_search_values: safe_search_values(&config.search, name, &engine),
+            })
+        })
+        .collect();
+
+    // The forced engines which are not listed in the upstream search engines are still queried.
+    for forced in forced_engines {
+        if !engines
+            .iter()
+            .any(|engine| engine.name.eq_ignore_ascii_case(forced))
+        {
+            if let Ok(engine) = EngineHandler::new(forced) {
+                engines.push(EngineInfo {
+                    name: forced.to_owned(),
+                    enabled: true,
+                    forced: true,
+                    features: engine.capabilities(),
+                    safe_search_values: safe_search_values(&config.search, forced, &engine),
+                });
+            }
+        }
+    }
+    engines.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(serde_json::to_string(&engines)?))
+}
+
+/// A helper function which provides the native values of the safe search parameter of an
+/// upstream search engine for each safe search level, which are the configured values or the
+/// default values of the engine, if the engine supports safe search.
+///
+/// # Arguments
+///
+/// * `search` - It takes the search config which provides the safe search mappings as an
+///   argument.
+/// * `name` - It takes the name of the engine as an argument.
+/// * `engine` - It takes the engine as an argument.
+fn safe_search_values(search: &Search, name: &str, engine: &EngineHandler) -> Option<Vec<String>> {
+    engine.safe_search_values().map(|defaults| {
+        search
+            .safe_search_mapping(name)
+            .unwrap_or_else(|| defaults.map(str::to_owned).to_vec())
+    })
+}
+
+/// Handles the route of the engine stats endpoint of the `websurfx` meta search engine website
+/// which provides the number of requests sent to each upstream search engine within the
+/// configured time window by their outcome, along with the fraction of the requests which did
+/// not fail as json. It is not available when the engine stats are disabled.
+///
+/// # Example
+///
+/// ```bash
+/// curl "http://127.0.0.1:8080/engines/stats"
+/// ```
+#[get("/engines/stats")]
+pub async fn engine_stats(
+    config: web::Data<SharedConfig>,
+) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let config = config.get();
+    if !config.server.engine_stats.enabled {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    let report = report(&config.server.engine_stats);
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(serde_json::to_string(&report)?))
+}
+
+/// Handles the route of the version endpoint of the `websurfx` meta search engine website which
+/// provides the version of the app, the git commit it was built from, the cargo features it was
+/// compiled with and the enabled upstream search engines as json. It only provides build
+/// information so that it can be included in bug reports.
+///
+/// # Example
+///
+/// ```bash
+/// curl "http://127.0.0.1:8080/version"
+/// ```
+#[get("/version")]
+pub async fn version(
+    config: web::Data<SharedConfig>,
+) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let config = config.get();
+    let mut enabled_engines: Vec<String> = config
+        .search
+        .upstream_search_engines
+        .iter()
+        .filter(|(_, enabled)| **enabled)
+        .map(|(name, _)| name.to_owned())
+        .chain(config.search.forced_engines.iter().cloned())
+        .collect();
+    enabled_engines.sort_by_key(|name| name.to_lowercase());
+    enabled_engines.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+
+    let version_info = VersionInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        commit: option_env!("WEBSURFX_GIT_COMMIT"),
+        features: FEATURES
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(feature, _)| *feature)
+            .collect(),
+        engines: enabled_engines,
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(serde_json::to_string(&version_info)?))
+}
+
+/// Handles the route of the analytics admin endpoint of the `websurfx` meta search engine website
+/// which provides the most searched queries and the number of searches for which each upstream
+/// search engine was queried within the configured time window as json. It requires the admin
+/// token to be provided as a bearer token and it is not available when the analytics are
+/// disabled or when no admin token is set.
+///
+/// # Example
+///
+/// ```bash
+/// curl -H "Authorization: Bearer <admin token>" "http://127.0.0.1:8080/admin/analytics"
+/// ```
+#[get("/admin/analytics")]
+pub async fn analytics(
+    req: HttpRequest,
+    config: web::Data<SharedConfig>,
+    cache: web::Data<SharedCache>,
+) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let config = config.get();
+    if !config.server.analytics.enabled || config.server.admin_token.is_none() {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    if !is_admin(&req, &config) {
+        return Ok(HttpResponse::Unauthorized()
+            .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+            .finish());
+    }
+
+    let mut report = cache
+        .search_analytics(&config.server.analytics)
+        .await
+        .map_err(|error| error.to_string())?;
+    report.clicks = clicks(config.server.analytics.window.into());
+    report.dropped_cache_writes = dropped_cache_writes();
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(serde_json::to_string(&report)?))
+}
+
+/// Handles the route of the config reload admin endpoint of the `websurfx` meta search engine
+/// website which parses the config file again and swaps the config used by the server for it, so
+/// that the changes to the config take effect without restarting the server. The options which
+/// only take effect when the server is started (like the port) keep their current values and are
+/// listed in the returned json. It requires the admin token to be provided as a bearer token and
+/// it is not available when no admin token is set.
+///
+/// # Example
+///
+/// ```bash
+/// curl -X POST -H "Authorization: Bearer <admin token>" "http://127.0.0.1:8080/admin/config/reload"
+/// ```
+#[post("/admin/config/reload")]
+pub async fn reload_config(
+    req: HttpRequest,
+    config: web::Data<SharedConfig>,
+) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let current_config = config.get();
+    if current_config.server.admin_token.is_none() {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    if !is_admin(&req, &current_config) {
+        return Ok(HttpResponse::Unauthorized()
+            .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+            .finish());
+    }
+
+    // The config is only parsed when the config file exists, otherwise the default config would
+    // silently replace the current config.
+    let parsed_config = file_path(FileType::Config)
+        .map_err(Into::into)
+        .and_then(|_| Config::parse(true));
+    let new_config = match parsed_config {
+        Ok(new_config) => new_config,
+        Err(error) => {
+            log::error!("Config Error: Failed to reload the config: {error}");
+            return Ok(HttpResponse::UnprocessableEntity()
+                .content_type(ContentType::plaintext())
+                .body(format!("Failed to reload the config: {error}")));
+        }
+    };
+
+    let report = ConfigReloadReport {
+        restart_required: config.reload(new_config),
+    };
+    if !report.restart_required.is_empty() {
+        log::warn!(
+            "Config Error: The changes to the `{}` options only take effect after restarting the server",
+            report.restart_required.join("`, `")
+        );
+    }
+    log::info!("Reloaded the config");
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(serde_json::to_string(&report)?))
+}
+
+/// Handles the route of the thumbnail proxy of the `websurfx` meta search engine website, which
+/// fetches the preview thumbnails of the search results on behalf of the users so that the
+/// websites hosting the thumbnails are not contacted by the browsers of the users. Only the
+/// thumbnails of the result pages of the search results, whose url is signed by the provided
+/// token, are fetched. It is not available when the thumbnails are disabled.
+#[get("/thumbnail")]
+pub async fn thumbnail(
+    params: web::Query<ThumbnailParams>,
+    config: web::Data<SharedConfig>,
+) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let config = config.get();
+    if !config.server.thumbnails.enabled {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    match fetch_thumbnail(
+        &params.url,
+        &params.token,
+        &config.server.thumbnails,
+        config.generation,
+    )
+    .await
+    {
+        // The images are served from the origin of the instance, so they are neither sniffed
+        // as another content type nor allowed to run scripts.
+        Some(image) => Ok(HttpResponse::Ok()
+            .content_type(image.content_type)
+            .insert_header((
+                header::CACHE_CONTROL,
+                format!("max-age={}", config.server.thumbnails.cache_expiry_time),
+            ))
+            .insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
+            .insert_header((header::CONTENT_SECURITY_POLICY, "sandbox"))
+            .body(image.bytes)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+/// Handles the route of the favicon proxy of the `websurfx` meta search engine website, which
+/// fetches the favicons of the websites of the search results on behalf of the users so that the
+/// websites are not contacted by the browsers of the users. The generic icon is served instead of
+/// the favicons which are unavailable when the fallback icon is enabled. Only the favicons of the
+/// websites of the search results, whose host is signed by the provided token, are fetched. It is
+/// not available when the favicons are disabled.
+#[get("/favicon")]
+pub async fn favicon(
+    params: web::Query<FaviconParams>,
+    config: web::Data<SharedConfig>,
+) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let config = config.get();
+    let favicons = &config.style.favicons;
+    if !favicons.enabled {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+    if !is_proxied_host(&params.host, &params.token, favicons) {
+        return Ok(HttpResponse::BadRequest().finish());
+    }
+
+    let cache_control = (
+        header::CACHE_CONTROL,
+        format!("max-age={}", favicons.cache_expiry_time),
+    );
+    match fetch_favicon(&params.host, favicons, config.generation).await {
+        Some(icon) => Ok(HttpResponse::Ok()
+            .content_type(icon.content_type.clone())
+            .insert_header(cache_control)
+            .body(icon.bytes.clone())),
+        None if favicons.fallback_icon => Ok(HttpResponse::Ok()
+            .content_type("image/svg+xml")
+            .insert_header(cache_control)
+            .body(FALLBACK_ICON)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+/// Handles the route of the autocomplete endpoint of the `websurfx` meta search engine website,
+/// which suggests the completions of a partially typed search query in the OpenSearch
+/// suggestions format. The suggestions are served from the cache when the query was typed
+/// recently. It is not available when the autocomplete is disabled.
+///
+/// # Example
+///
+/// ```bash
+/// curl "http://127.0.0.1:8080/autocomplete?q=rust"
+/// ```
+#[get("/autocomplete")]
+pub async fn autocomplete(
+    params: web::Query<AutocompleteParams>,
+    config: web::Data<SharedConfig>,
+    cache: web::Data<SharedCache>,
+) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let config = config.get();
+    let autocomplete = &config.server.autocomplete;
+    if !autocomplete.enabled {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    let suggestions = suggestions(&params.q, autocomplete, &cache, config.generation).await;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(serde_json::to_string(&(&params.q, suggestions))?))
+}
+
+/// Handles the route of the click-through redirect of the `websurfx` meta search engine website
+/// which the links of the search results point to when it is enabled. The url of the search
+/// result is only redirected to when it is signed by the provided token, so that the route can
+/// not be abused as an open redirect. The tracking parameters are removed from the url and the
+/// click is counted in the analytics (nothing but the count is stored) before redirecting. It is
+/// not available when the click-through redirect is disabled.
+///
+/// # Example
+///
+/// ```bash
+/// curl -i "http://127.0.0.1:8080/r?url=https%3A%2F%2Fexample.com&token=<token>"
+/// ```
+#[get("/r")]
+pub async fn redirect(
+    params: web::Query<RedirectParams>,
+    config: web::Data<SharedConfig>,
+) -> HttpResponse {
+    let config = config.get();
+    let click_redirect = &config.style.click_redirect;
+    if !click_redirect.enabled {
+        return HttpResponse::NotFound().finish();
+    }
+
+    let url = match is_signed(&params.url, &params.token, click_redirect) {
+        true => outbound_url(&params.url, click_redirect),
+        false => None,
+    };
+    let Some(url) = url else {
+        return HttpResponse::BadRequest()
+            .content_type(ContentType::plaintext())
+            .body("The redirect url is invalid or it is not the url of a search result.");
+    };
+
+    if config.server.analytics.enabled {
+        record_click(config.server.analytics.window.into());
+    }
+
+    let mut response = HttpResponse::Found();
+    response
+        .insert_header((header::LOCATION, url))
+        .insert_header((header::CACHE_CONTROL, "no-store"));
+    // The page of the search results would otherwise be sent as the referrer along the redirect.
+    if config.style.no_referrer {
+        response.insert_header((header::REFERRER_POLICY, "no-referrer"));
+    }
+    response.finish()
+}
+
+/// A helper function which checks whether the request provides the configured admin token as a
+/// bearer token. The tokens are compared by their hashes so that the comparison takes the same
+/// time regardless of where the tokens differ.
+///
+/// # Arguments
+///
+/// * `req` - It takes the `HttpRequest` struct as an argument.
+/// * `config` - It takes a parsed config struct.
+pub fn is_admin(req: &HttpRequest, config: &Config) -> bool {
+    let provided_token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match (provided_token, &config.server.admin_token) {
+        (Some(provided_token), Some(admin_token)) => {
+            blake3::hash(provided_token.as_bytes()) == blake3::hash(admin_token.as_bytes())
+        }
+        _ => false,
+    }
+}
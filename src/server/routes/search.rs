@@ -2,26 +2,206 @@
 
 use crate::{
     cache::cacher::SharedCache,
-    config::Config,
-    handler::{file_path, FileType},
+    config::{caching::Caching, shared::SharedConfig, style::ResultOrder, Config},
     models::{
         aggregation_models::SearchResults,
-        engine_models::EngineHandler,
-        server_models::{self, SearchParams},
+        engine_models::{Category, EngineHandler},
+        server_models::{self, BatchSearchResult, SearchParams},
     },
-    results::aggregator::aggregate,
+    results::{
+        aggregator::{
+            aggregate, apply_safe_search_terms, debug_engine, interleave_results, refilter_results,
+            refine_results, EngineResponses, SafeSearchTermOutcome,
+        },
+        filter_lists::{allowlist, blocklist, is_sensitive},
+    },
+    server::{error::SearchError, router::is_admin},
+    templates::locale::translations,
+};
+use actix_web::{
+    get,
+    http::header::{
+        ContentType, ETag, EntityTag, HeaderValue, IfNoneMatch, ACCEPT, ACCEPT_LANGUAGE,
+        CACHE_CONTROL, LOCATION, RETRY_AFTER, VARY,
+    },
+    post, web, HttpMessage, HttpRequest, HttpResponse, ResponseError,
+};
+use futures::{
+    channel::oneshot::{channel, Receiver, Sender},
+    stream, StreamExt,
 };
-use actix_web::{get, http::header::ContentType, web, HttpRequest, HttpResponse};
-use regex::Regex;
+use percent_encoding::percent_decode_str;
+use serde::{de::IgnoredAny, Deserialize};
 use std::{
     borrow::Cow,
-    fs::File,
-    io::{BufRead, BufReader, Read},
+    collections::HashMap,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Mutex, MutexGuard, OnceLock,
+    },
+    time::{SystemTime, UNIX_EPOCH},
 };
 use tokio::join;
 
+/// The name of the header from which the search query is read when the `q` search url parameter
+/// is not provided.
+pub const QUERY_HEADER: &str = "X-Search-Query";
+
+/// The number of searches which are currently being handled by the whole server.
+static IN_FLIGHT_SEARCHES: AtomicUsize = AtomicUsize::new(0);
+
+/// A guard which counts a search as being in flight until it is dropped.
+struct InFlightSearch;
+
+impl InFlightSearch {
+    /// Counts a new search as being in flight, unless the maximum number of concurrent searches
+    /// (`0` means no limit) are already being handled.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_searches` - It takes the maximum number of concurrent searches as an argument.
+    fn acquire(max_searches: usize) -> Option<Self> {
+        let in_flight = IN_FLIGHT_SEARCHES.fetch_add(1, Ordering::AcqRel);
+        if max_searches != 0 && in_flight >= max_searches {
+            IN_FLIGHT_SEARCHES.fetch_sub(1, Ordering::AcqRel);
+            return None;
+        }
+        Some(InFlightSearch)
+    }
+}
+
+impl Drop for InFlightSearch {
+    fn drop(&mut self) {
+        IN_FLIGHT_SEARCHES.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// The number of search results being written to the cache in the background by the whole
+/// server.
+static BACKGROUND_CACHE_WRITES: AtomicUsize = AtomicUsize::new(0);
+
+/// The number of background cache writes which were dropped since the server was started, as
+/// the maximum number of concurrent background cache writes were already in progress.
+static DROPPED_CACHE_WRITES: AtomicU64 = AtomicU64::new(0);
+
+/// A guard which counts a background cache write as being in progress until it is dropped.
+struct BackgroundCacheWrite;
+
+impl BackgroundCacheWrite {
+    /// Counts a new background cache write as being in progress, unless the maximum number of
+    /// concurrent background cache writes (`0` means no limit) are already in progress.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_writes` - It takes the maximum number of concurrent background cache writes as an
+    ///   argument.
+    fn acquire(max_writes: usize) -> Option<Self> {
+        let in_progress = BACKGROUND_CACHE_WRITES.fetch_add(1, Ordering::AcqRel);
+        if max_writes != 0 && in_progress >= max_writes {
+            BACKGROUND_CACHE_WRITES.fetch_sub(1, Ordering::AcqRel);
+            return None;
+        }
+        Some(BackgroundCacheWrite)
+    }
+}
+
+impl Drop for BackgroundCacheWrite {
+    fn drop(&mut self) {
+        BACKGROUND_CACHE_WRITES.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// A function which provides the number of background cache writes which were dropped since the
+/// server was started.
+pub fn dropped_cache_writes() -> u64 {
+    DROPPED_CACHE_WRITES.load(Ordering::Relaxed)
+}
+
+/// The results of an aggregation shared with the searches waiting for it.
+type SharedResults = Result<SearchResults, SearchError>;
+
+/// The cache keys of the searches whose results are being aggregated, along with the senders to
+/// the identical searches waiting for the results.
+static IN_FLIGHT_AGGREGATIONS: OnceLock<Mutex<HashMap<String, Vec<Sender<SharedResults>>>>> =
+    OnceLock::new();
+
+/// A guard which marks the aggregation of the results of a search as being in flight until it
+/// is dropped, so that the identical searches wait for its results.
+struct InFlightAggregation {
+    /// The cache key of the search.
+    cache_key: String,
+    /// The results of the aggregation, which are set once it completes.
+    results: Option<SharedResults>,
+}
+
+impl InFlightAggregation {
+    /// Marks the aggregation of the results of a search as being in flight, unless an identical
+    /// search is already in flight, in which case a receiver for its results is returned instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `cache_key` - It takes the cache key of the search as an argument.
+    fn join(cache_key: &str) -> Result<Self, Receiver<SharedResults>> {
+        let mut in_flight = in_flight_aggregations();
+        match in_flight.get_mut(cache_key) {
+            Some(waiting) => {
+                let (sender, receiver) = channel();
+                waiting.push(sender);
+                Err(receiver)
+            }
+            None => {
+                in_flight.insert(cache_key.to_owned(), Vec::new());
+                Ok(Self {
+                    cache_key: cache_key.to_owned(),
+                    results: None,
+                })
+            }
+        }
+    }
+
+    /// Completes the aggregation, so that its results are sent to all the searches waiting for
+    /// them.
+    ///
+    /// # Arguments
+    ///
+    /// * `results` - It takes the results of the aggregation as an argument.
+    fn complete(mut self, results: SharedResults) {
+        self.results = Some(results);
+    }
+}
+
+impl Drop for InFlightAggregation {
+    fn drop(&mut self) {
+        let waiting = in_flight_aggregations()
+            .remove(&self.cache_key)
+            .unwrap_or_default();
+
+        // The waiting searches are notified that the aggregation was cancelled when their
+        // senders are dropped without sending any results.
+        if let Some(results) = self.results.take() {
+            for sender in waiting {
+                let _ = sender.send(results.clone());
+            }
+        }
+    }
+}
+
+/// A helper function which locks the map of the aggregations in flight, initializing it on the
+/// first call.
+fn in_flight_aggregations() -> MutexGuard<'static, HashMap<String, Vec<Sender<SharedResults>>>> {
+    IN_FLIGHT_AGGREGATIONS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|error| error.into_inner())
+}
+
 /// Handles the route of search page of the `websurfx` meta search engine website and it takes
 /// two search url parameters `q` and `page` where `page` parameter is optional.
+/// When the `q` search url parameter is not provided, the search query is read from the
+/// `X-Search-Query` header instead, so that the front proxies can provide the query without it
+/// appearing in the url. When the search fails, the themed error page is provided along with the
+/// http status of the error instead.
 ///
 /// # Example
 ///
@@ -32,16 +212,272 @@ use tokio::join;
 /// Or
 ///
 /// ```bash
-/// curl "http://127.0.0.1:8080/search?q=sweden"
+/// curl -H "X-Search-Query: sweden" "http://127.0.0.1:8080/search"
 /// ```
 #[get("/search")]
 pub async fn search(
     req: HttpRequest,
-    config: web::Data<Config>,
+    config: web::Data<SharedConfig>,
+    cache: web::Data<SharedCache>,
+) -> HttpResponse {
+    let config = config.get();
+    let response = match web::Query::<SearchParams>::from_query(req.query_string()) {
+        Ok(params) => {
+            let mut params = params.into_inner();
+            let from_header = params.q.is_none();
+            if from_header {
+                params.q = header_query(&req);
+            }
+            // The http caches have to tell apart the searches which only differ by the header.
+            search_page(&req, &config, cache, params, None)
+                .await
+                .map(|mut response| {
+                    if from_header {
+                        vary_on(&mut response, QUERY_HEADER);
+                    }
+                    response
+                })
+        }
+        Err(error) => Err(error.into()),
+    };
+    response.unwrap_or_else(|error| error_page(&config, None, &error))
+}
+
+/// Handles the route of search page of the `websurfx` meta search engine website when the search
+/// query is provided as a path segment instead of the `q` search url parameter. The other search
+/// url parameters like `page` still apply.
+///
+/// # Example
+///
+/// ```bash
+/// curl "http://127.0.0.1:8080/search/sweden?page=1"
+/// ```
+#[get("/search/{query:.*}")]
+pub async fn search_path(
+    req: HttpRequest,
+    query: web::Path<String>,
+    config: web::Data<SharedConfig>,
     cache: web::Data<SharedCache>,
-) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+) -> HttpResponse {
+    let config = config.get();
+
+    // The relative urls of the page are resolved against the directory of the requested path, so
+    // the base url has to point back to the root directory of the website for each segment.
+    let segments = req
+        .path()
+        .trim_start_matches("/search/")
+        .matches('/')
+        .count()
+        + 1;
+    let base = "../".repeat(segments);
+
+    let response = match web::Query::<SearchParams>::from_query(req.query_string()) {
+        Ok(params) => {
+            let mut params = params.into_inner();
+            params.q = Some(query.into_inner());
+            search_page(&req, &config, cache, params, Some(&base)).await
+        }
+        Err(error) => Err(error.into()),
+    };
+    response.unwrap_or_else(|error| error_page(&config, Some(&base), &error))
+}
+
+/// Handles the route of the batch search endpoint of the `websurfx` meta search engine website,
+/// which searches several queries at once and provides the search results of the first page of
+/// each query as a json object keyed by the query. The queries are searched with the search
+/// settings of the user, up to the configured number of queries concurrently, and their results
+/// are cached just like the results of the individual searches. The queries whose search failed
+/// are mapped to an object holding the reason of the failure. It is not available when the
+/// maximum number of queries of a batch is set to `0`.
+///
+/// # Example
+///
+/// ```bash
+/// curl -X POST -H "Content-Type: application/json" -d '["sweden", "rust"]' "http://127.0.0.1:8080/search/batch"
+/// ```
+#[post("/search/batch")]
+pub async fn search_batch(
+    req: HttpRequest,
+    queries: web::Json<Vec<String>>,
+    config: web::Data<SharedConfig>,
+    cache: web::Data<SharedCache>,
+) -> Result<HttpResponse, SearchError> {
+    let config = config.get();
+    let batch_search = &config.server.batch_search;
+    if batch_search.max_queries == 0 {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    let mut queries = queries.into_inner();
+    queries.sort();
+    queries.dedup();
+    if queries.is_empty()
+        || queries.len() > batch_search.max_queries
+        || queries.iter().any(|query| query.trim().is_empty())
+    {
+        return Ok(HttpResponse::BadRequest()
+            .content_type(ContentType::plaintext())
+            .body(format!(
+                "A batch should contain between 1 and {} non empty queries.",
+                batch_search.max_queries
+            )));
+    }
+
+    let mut search_settings = search_settings(&req, &config, &None);
+    resolve_engines(&config, &mut search_settings);
+    let (engines, _) = selected_engines(&config, &search_settings);
+    let engines = category_engines(&config, &engines, Category::General).unwrap_or_default();
+
+    let results: HashMap<String, BatchSearchResult> = stream::iter(queries)
+        .map(|query| async {
+            let result =
+                batch_search_result(&config, &cache, &query, &search_settings, &engines).await;
+            (query, result)
+        })
+        .buffer_unordered(batch_search.concurrency)
+        .collect()
+        .await;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(serde_json::to_string(&results)?))
+}
+
+/// A helper function which searches one of the queries of a batch search, counting it as a
+/// search in flight so that the batch searches are limited by the maximum number of concurrent
+/// searches as well.
+///
+/// # Arguments
+///
+/// * `config` - It takes a parsed config struct.
+/// * `cache` - It takes the shared cache as an argument.
+/// * `query` - It takes the query to be searched as an argument.
+/// * `search_settings` - It takes the search settings of the user as an argument.
+/// * `engines` - It takes the names of the upstream search engines to be queried as an argument.
+async fn batch_search_result(
+    config: &Config,
+    cache: &web::Data<SharedCache>,
+    query: &str,
+    search_settings: &server_models::Cookie<'_>,
+    engines: &[&str],
+) -> BatchSearchResult {
+    let Some(_in_flight_search) = InFlightSearch::acquire(config.server.max_concurrent_searches)
+    else {
+        return BatchSearchResult::Error {
+            error: "The server is handling too many searches, please try again later.",
+        };
+    };
+
+    let engine_query = match config.search.normalize_query {
+        true => Cow::Owned(normalize_query(query, false)),
+        false => Cow::Borrowed(query),
+    };
+    // The searches which would be redirected are not searched, as a batch can not be redirected.
+    let engine_query = match apply_safe_search_terms(
+        &engine_query,
+        search_settings.safe_search_level,
+        &config.search.safe_search_terms,
+    ) {
+        SafeSearchTermOutcome::Query(engine_query) => engine_query.into_owned(),
+        SafeSearchTermOutcome::Redirect(_) => {
+            return BatchSearchResult::Error {
+                error: "The search query is not allowed at the safe search level.",
+            };
+        }
+    };
+    match results(
+        config,
+        cache,
+        &engine_query,
+        0,
+        search_settings,
+        engines,
+        Category::General,
+        CacheUse::Read,
+    )
+    .await
+    {
+        Ok((results, _)) => BatchSearchResult::Results(Box::new(results)),
+        Err(error) => {
+            log::error!("Search Error: {error}");
+            BatchSearchResult::Error {
+                error: error.user_message(),
+            }
+        }
+    }
+}
+
+/// A helper function which provides the themed error page displayed when a search fails, which
+/// is sent along with the http status of the error. The details of the error are only logged.
+///
+/// # Arguments
+///
+/// * `config` - It takes a parsed config struct.
+/// * `base` - It takes an optional base url against which the relative urls of the page are
+///   resolved as an argument.
+/// * `error` - It takes the error which occurred while handling the search as an argument.
+fn error_page(config: &Config, base: Option<&str>, error: &SearchError) -> HttpResponse {
+    log::error!("Search Error: {error}");
+    HttpResponse::build(error.status_code())
+        .content_type(ContentType::html())
+        .body(
+            crate::templates::views::error::error(
+                &config.style.colorscheme,
+                &config.style.theme,
+                &config.style.animation,
+                config.style.custom_css.is_some(),
+                base,
+                error.status_code(),
+                error.user_message(),
+            )
+            .0,
+        )
+}
+
+/// A helper function which reads the search query from the `X-Search-Query` header of the
+/// request. The header value is percent-decoded, so that the queries which are not plain ascii
+/// can be provided as well.
+///
+/// # Arguments
+///
+/// * `req` - It takes the `HttpRequest` struct as an argument.
+///
+/// # Returns
+///
+/// Returns the search query, or `None` when the header is not provided, is empty or is not valid
+/// utf-8.
+fn header_query(req: &HttpRequest) -> Option<String> {
+    let value = req.headers().get(QUERY_HEADER)?;
+    let value = std::str::from_utf8(value.as_bytes()).ok()?;
+    let query = percent_decode_str(value).decode_utf8().ok()?;
+    (!query.trim().is_empty()).then(|| query.into_owned())
+}
+
+/// A helper function which handles the search page for the provided search parameters by fetching
+/// the results for the current page along with prefetching and caching the results of the
+/// neighbouring pages.
+///
+/// # Arguments
+///
+/// * `req` - It takes the `HttpRequest` struct as an argument.
+/// * `config` - It takes a parsed config struct.
+/// * `cache` - It takes the shared cache as an argument.
+/// * `params` - It takes the parsed search parameters as an argument.
+/// * `base` - It takes an optional base url against which the relative urls of the page are
+///   resolved as an argument.
+///
+/// # Error
+///
+/// It returns the search page as a http response if the search results could be fetched otherwise
+/// it returns a `SearchError` describing the failure.
+async fn search_page(
+    req: &HttpRequest,
+    config: &Config,
+    cache: web::Data<SharedCache>,
+    params: SearchParams,
+    base: Option<&str>,
+) -> Result<HttpResponse, SearchError> {
     use std::sync::Arc;
-    let params = web::Query::<SearchParams>::from_query(req.query_string())?;
     match &params.q {
         Some(query) => {
             if query.trim().is_empty() {
@@ -50,92 +486,263 @@ pub async fn search(
                     .finish());
             }
 
-            let cookie = req.cookie("appCookie");
+            // The searches beyond the capacity of the server are rejected, asking the client to
+            // retry once the searches in flight are likely to have completed.
+            let Some(_in_flight_search) =
+                InFlightSearch::acquire(config.server.max_concurrent_searches)
+            else {
+                return Ok(HttpResponse::ServiceUnavailable()
+                    .insert_header((RETRY_AFTER, config.server.request_timeout.to_string()))
+                    .content_type(ContentType::plaintext())
+                    .body("The server is handling too many searches, please try again later."));
+            };
 
-            // Get search settings using the user's cookie or from the server's config
-            let mut search_settings: server_models::Cookie<'_> = cookie
-                .and_then(|cookie_value| serde_json::from_str(cookie_value.value()).ok())
-                .unwrap_or_else(|| {
-                    server_models::Cookie::build(
-                        &config.style,
-                        config
-                            .search
-                            .upstream_search_engines
-                            .iter()
-                            .filter_map(|(engine, enabled)| {
-                                enabled.then_some(Cow::Borrowed(engine.as_str()))
-                            })
-                            .collect(),
-                        config.search.safe_search,
+            let mut search_settings = search_settings(req, config, &params.safesearch);
+
+            // The unknown engine names selected in the cookie are reported along with the unknown
+            // engine names provided in the search url.
+            let mut ignored_engines = resolve_engines(config, &mut search_settings);
+            // The engines provided in the search url override the engines selected in the cookie
+            // for this search only, the cookie itself is left untouched.
+            if let Some(engines) = params.engines.as_deref() {
+                let unknown_engines = override_engines(config, &mut search_settings, engines);
+                ignored_engines.extend(unknown_engines.into_iter().map(str::to_owned));
+            }
+            let denied_engines = remove_denied_engines(config, &mut search_settings);
+
+            // The normalized query is only sent to the upstream search engines if configured,
+            // otherwise the query is sent as typed by the user.
+            let normalized_query;
+            let engine_query = if config.search.normalize_query {
+                normalized_query = normalize_query(query, false);
+                &normalized_query
+            } else {
+                query
+            };
+
+            // The safe search term rules either rewrite the query sent to the upstream search
+            // engines or redirect the search, when the safe search level is high enough.
+            let rewritten_query = match apply_safe_search_terms(
+                engine_query,
+                search_settings.safe_search_level,
+                &config.search.safe_search_terms,
+            ) {
+                SafeSearchTermOutcome::Query(engine_query) => engine_query,
+                SafeSearchTermOutcome::Redirect(url) => {
+                    return Ok(HttpResponse::Found()
+                        .insert_header((LOCATION, url))
+                        .finish());
+                }
+            };
+            let engine_query: &str = &rewritten_query;
+
+            let (engines, dropped_engines) = selected_engines(config, &search_settings);
+
+            // The general search results are provided instead when none of the selected engines
+            // support searching the requested category.
+            let requested_category = params.category.unwrap_or_default();
+            let (engines, category, category_fallback) =
+                match category_engines(config, &engines, requested_category) {
+                    Some(category_engines) => (category_engines, requested_category, false),
+                    None => (
+                        category_engines(config, &engines, Category::General).unwrap_or_default(),
+                        Category::General,
+                        true,
+                    ),
+                };
+
+            let page = match page_index(params.page, is_api_request(req), config) {
+                Ok(page) => page,
+                Err(error) => {
+                    return Ok(HttpResponse::BadRequest()
+                        .content_type(ContentType::json())
+                        .body(serde_json::json!({ "error": error }).to_string()))
+                }
+            };
+
+            let (engine_query, refinement) = refined_query(
+                config,
+                &cache,
+                engine_query,
+                params.refine.as_deref(),
+                || {
+                    cache_key(
+                        config,
+                        engine_query,
+                        page,
+                        search_settings.safe_search_level,
+                        &engines,
+                        category,
                     )
-                });
+                },
+            )
+            .await;
 
-            search_settings.safe_search_level = get_safesearch_level(
-                &Some(search_settings.safe_search_level),
-                &params.safesearch,
-                config.search.safe_search,
-            );
+            // The cache is only skipped for the requested page, the adjacent pages being
+            // prefetched as usual.
+            let cache_use = requested_cache_use(req, config, params.nocache);
+            let current_page = page;
 
             // Closure wrapping the results function capturing local references
-            let get_results = |page| results(&config, &cache, query, page, &search_settings);
+            let get_results = |page| {
+                results(
+                    config,
+                    &cache,
+                    &engine_query,
+                    page,
+                    &search_settings,
+                    &engines,
+                    category,
+                    match page == current_page {
+                        true => cache_use,
+                        false => CacheUse::Read,
+                    },
+                )
+            };
+
+            // The raw responses of the upstream search engines are strictly only returned in the
+            // debug mode, so that the upstream content is never leaked in production.
+            if let Some(engine) = params.debug_engine.as_deref() {
+                if config.server.debug {
+                    let debug_info = debug_engine(
+                        &engine_query,
+                        page,
+                        config,
+                        engine,
+                        search_settings.safe_search_level,
+                    )
+                    .await;
+                    return Ok(HttpResponse::Ok()
+                        .content_type(ContentType::json())
+                        .body(serde_json::to_string(&debug_info)?));
+                }
+            }
+
+            // Only the first page of a search is counted, so that browsing through the pages of
+            // the same search does not inflate the counts. The sensitive searches are never
+            // counted.
+            if config.server.analytics.enabled
+                && page == 0
+                && !is_sensitive(query, &config.search, config.generation)
+            {
+                if let Err(error) = cache
+                    .record_search(
+                        &config.server.analytics,
+                        &normalize_query(query, true),
+                        &engines,
+                    )
+                    .await
+                {
+                    log::error!("Analytics Error: {:?}", error);
+                }
+            }
+
+            // The json of the cached search results is sent as it is to the api requests when
+            // the search results need no changes, skipping their deserialization.
+            let json_output = is_api_request(req);
+            let result_order = params.order.unwrap_or(config.style.result_order);
+            if json_output
+                && cache_use == CacheUse::Read
+                && !config.search.strict_safe_search
+                && refinement.is_none()
+                && result_order == ResultOrder::Ranked
+                && dropped_engines.is_empty()
+                && ignored_engines.is_empty()
+                && denied_engines.is_empty()
+                && !category_fallback
+            {
+                let cache_key = cache_key(
+                    config,
+                    &engine_query,
+                    page,
+                    search_settings.safe_search_level,
+                    &engines,
+                    category,
+                );
+                if let Some(json) = cached_json(config, &cache, &cache_key).await {
+                    return Ok(tagged_response(req, &cache_key, ContentType::json(), json));
+                }
+            }
 
-            // .max(1) makes sure that the page >= 0.
-            let page = params.page.unwrap_or(1).max(1) - 1;
             let previous_page = page.saturating_sub(1);
             let next_page = page + 1;
 
-            let mut results = Arc::new((SearchResults::default(), String::default()));
-            if page != previous_page {
+            let (current_results, adjacent_results) = if page != previous_page {
                 let (previous_results, current_results, next_results) = join!(
                     get_results(previous_page),
                     get_results(page),
                     get_results(next_page)
                 );
-                let (parsed_previous_results, parsed_next_results) =
-                    (previous_results?, next_results?);
-
-                let (cache_keys, results_list) = (
-                    [
-                        parsed_previous_results.1,
-                        results.1.clone(),
-                        parsed_next_results.1,
-                    ],
-                    [
-                        parsed_previous_results.0,
-                        results.0.clone(),
-                        parsed_next_results.0,
-                    ],
-                );
-
-                results = Arc::new(current_results?);
-
-                tokio::spawn(async move { cache.cache_results(&results_list, &cache_keys).await });
+                (current_results, vec![previous_results, next_results])
             } else {
                 let (current_results, next_results) =
-                    join!(get_results(page), get_results(page + 1));
+                    join!(get_results(page), get_results(next_page));
+                (current_results, vec![next_results])
+            };
+            let adjacent_results = prefetched_results(config, adjacent_results)?;
 
-                let parsed_next_results = next_results?;
+            let mut current_results = current_results?;
+            current_results.0.set_dropped_engines(&dropped_engines);
+            current_results.0.set_ignored_engines(&ignored_engines);
+            current_results.0.set_denied_engines(&denied_engines);
+            if category_fallback {
+                current_results.0.set_category_fallback();
+            }
+            let results = Arc::new(current_results);
 
-                results = Arc::new(current_results?);
+            // The cached search results served in place of a failed live fetch are already cached,
+            // while the search results of a total outage are not cached at all.
+            let (results_list, cache_keys) = (cache_use != CacheUse::Bypass)
+                .then(|| (results.0.clone(), results.1.clone()))
+                .into_iter()
+                .chain(adjacent_results)
+                .filter(|(results, _)| !results.stale && !results.upstream_failure)
+                .unzip();
+            cache_in_background(
+                cache,
+                results_list,
+                cache_keys,
+                config.caching.max_background_writes,
+            );
 
-                let (cache_keys, results_list) = (
-                    [results.1.clone(), parsed_next_results.1.clone()],
-                    [results.0.clone(), parsed_next_results.0],
-                );
+            let presented_results = presented_results(&results.0, refinement, result_order);
+            let results_to_show = presented_results.as_ref().unwrap_or(&results.0);
+            let (content_type, body) = match json_output {
+                true => (ContentType::json(), serde_json::to_vec(results_to_show)?),
+                false => (
+                    ContentType::html(),
+                    crate::templates::views::search::search(
+                        &config.style,
+                        &translations(&config.style, config.generation)
+                            .locale(accept_language(req, config), &config.style.locale),
+                        query,
+                        results_to_show,
+                        params
+                            .template
+                            .as_deref()
+                            .unwrap_or(&config.style.results_template),
+                        params.view.unwrap_or(config.style.results_view),
+                        requested_category,
+                        base,
+                    )
+                    .0
+                    .into_bytes(),
+                ),
+            };
 
-                tokio::spawn(async move { cache.cache_results(&results_list, &cache_keys).await });
+            let mut response = tagged_response(req, &results.1, content_type, body);
+            if !json_output {
+                // The html pages differ by the settings cookie of the user, like the theme, and
+                // by the preferred languages of the browser.
+                vary_on(&mut response, "cookie");
+                if config.style.accept_language {
+                    vary_on(&mut response, "accept-language");
+                }
+                response
+                    .headers_mut()
+                    .insert(CACHE_CONTROL, config.server.cache_control_header.clone());
             }
-
-            Ok(HttpResponse::Ok().content_type(ContentType::html()).body(
-                crate::templates::views::search::search(
-                    &config.style.colorscheme,
-                    &config.style.theme,
-                    &config.style.animation,
-                    query,
-                    &results.0,
-                )
-                .0,
-            ))
+            Ok(response)
         }
         None => Ok(HttpResponse::TemporaryRedirect()
             .insert_header(("location", "/"))
@@ -143,6 +750,128 @@ pub async fn search(
     }
 }
 
+/// A helper function which applies the refinement and the presentation order of the search to the
+/// search results. They are applied once the search results are cached, so that the cached search
+/// results of the query are kept whole and in their ranked order.
+///
+/// # Arguments
+///
+/// * `results` - It takes the search results of the search as an argument.
+/// * `refinement` - It takes the terms by which the search results are narrowed down, if any, as
+///   an argument.
+/// * `result_order` - It takes the order in which the search results are presented as an
+///   argument.
+///
+/// # Returns
+///
+/// Returns the search results to be presented, or `None` when the search results are presented
+/// as they are.
+fn presented_results(
+    results: &SearchResults,
+    refinement: Option<&str>,
+    result_order: ResultOrder,
+) -> Option<SearchResults> {
+    if refinement.is_none() && result_order == ResultOrder::Ranked {
+        return None;
+    }
+
+    let mut presented_results = results.clone();
+    if let Some(refinement) = refinement {
+        refine_results(&mut presented_results, refinement);
+    }
+    if result_order == ResultOrder::Interleave {
+        interleave_results(&mut presented_results);
+    }
+    Some(presented_results)
+}
+
+/// A helper function which provides the search settings of the user, which are parsed from the
+/// cookie of the user or built from the config when the user has no valid cookie, with the safe
+/// search level provided in the search url taking precedence.
+///
+/// # Arguments
+///
+/// * `req` - It takes the `HttpRequest` struct as an argument.
+/// * `config` - It takes a parsed config struct.
+/// * `url_level` - It takes the safe search level provided in the search url, if any, as an
+///   argument.
+fn search_settings<'a>(
+    req: &HttpRequest,
+    config: &'a Config,
+    url_level: &Option<u8>,
+) -> server_models::Cookie<'a> {
+    let cookie = req.cookie("appCookie");
+    let default_safe_search = default_safesearch_level(req, config);
+
+    // Get search settings using the user's cookie or from the server's config
+    let mut search_settings: server_models::Cookie<'_> = cookie
+        .and_then(|cookie_value| serde_json::from_str(cookie_value.value()).ok())
+        .unwrap_or_else(|| {
+            server_models::Cookie::build(
+                &config.style,
+                config
+                    .search
+                    .upstream_search_engines
+                    .iter()
+                    .filter_map(|(engine, enabled)| {
+                        enabled.then_some(Cow::Borrowed(engine.as_str()))
+                    })
+                    .collect(),
+                default_safe_search,
+            )
+        });
+
+    search_settings.safe_search_level = get_safesearch_level(
+        &Some(search_settings.safe_search_level),
+        url_level,
+        default_safe_search,
+    );
+    search_settings
+}
+
+/// A helper function which writes the provided search results to the cache in the background.
+/// The results are not cached and the write is counted as dropped when the maximum number of
+/// concurrent background cache writes are already in progress, so that the cache backend is not
+/// flooded with writes under a traffic spike.
+///
+/// # Arguments
+///
+/// * `cache` - It takes the cache as an argument.
+/// * `results_list` - It takes the search results to be cached as an argument.
+/// * `cache_keys` - It takes the cache keys of the search results as an argument.
+/// * `max_writes` - It takes the maximum number of concurrent background cache writes as an
+///   argument (`0` means no limit).
+fn cache_in_background(
+    cache: web::Data<SharedCache>,
+    results_list: Vec<SearchResults>,
+    cache_keys: Vec<String>,
+    max_writes: usize,
+) {
+    let Some(cache_write) = BackgroundCacheWrite::acquire(max_writes) else {
+        DROPPED_CACHE_WRITES.fetch_add(1, Ordering::Relaxed);
+        log::debug!("Dropped a background cache write, too many cache writes are in progress");
+        return;
+    };
+
+    tokio::spawn(async move {
+        let _cache_write = cache_write;
+        if let Err(error) = cache.cache_results(&results_list, &cache_keys).await {
+            log::error!("Cache Error: {:?}", error);
+        }
+    });
+}
+
+/// The ways in which a search uses the cache of the search results.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CacheUse {
+    /// The fresh cached search results are served, and the fetched search results are cached.
+    Read,
+    /// The cached search results are skipped, and the fetched search results replace them.
+    Refresh,
+    /// The cache is not used at all, the fetched search results are not cached either.
+    Bypass,
+}
+
 /// Fetches the results for a query and page. It First checks the redis cache, if that
 /// fails it gets proper results by requesting from the upstream search engines.
 ///
@@ -153,121 +882,973 @@ pub async fn search(
 /// * `config` - It takes a parsed config struct.
 /// * `query` - It takes the page number as u32 value.
 /// * `req` - It takes the `HttpRequest` struct as a value.
+/// * `engines` - It takes the names of the upstream search engines to be queried as an argument.
+/// * `category` - It takes the category of the search results to be fetched as an argument.
+/// * `cache_use` - It takes how the cached search results are used as an argument.
 ///
 /// # Error
 ///
 /// It returns the `SearchResults` struct if the search results could be successfully fetched from
-/// the cache or from the upstream search engines otherwise it returns an appropriate error.
-async fn results(
+/// the cache or from the upstream search engines otherwise it returns a `SearchError` describing
+/// the failure.
+#[allow(clippy::too_many_arguments)]
+pub async fn results(
     config: &Config,
     cache: &web::Data<SharedCache>,
     query: &str,
     page: u32,
     search_settings: &server_models::Cookie<'_>,
-) -> Result<(SearchResults, String), Box<dyn std::error::Error>> {
+    engines: &[&str],
+    category: Category,
+    cache_use: CacheUse,
+) -> Result<(SearchResults, String), SearchError> {
     // eagerly parse cookie value to evaluate safe search level
     let safe_search_level = search_settings.safe_search_level;
 
-    let cache_key = format!(
-        "http://{}:{}/search?q={}&page={}&safesearch={}&engines={}",
-        config.server.binding_ip,
-        config.server.port,
-        query,
-        page,
-        safe_search_level,
-        search_settings.engines.join(",")
-    );
+    // The denied engines are never queried, however they were selected.
+    let allowed_engines: Vec<&str> = engines
+        .iter()
+        .copied()
+        .filter(|engine| !config.search.is_denied_engine(engine))
+        .collect();
+    let engines = allowed_engines.as_slice();
+
+    let cache_key = cache_key(config, query, page, safe_search_level, engines, category);
+    let write_cache = cache_use != CacheUse::Bypass;
 
-    // fetch the cached results json.
-    let cached_results = cache.cached_results(&cache_key).await;
+    // fetch the cached results json, unless the cache is bypassed.
+    let cached_results = match cache_use {
+        CacheUse::Read => Some(cache.cached_results(&cache_key).await),
+        CacheUse::Refresh | CacheUse::Bypass => None,
+    };
     // check if fetched cache results was indeed fetched or it was an error and if so
     // handle the data accordingly.
     match cached_results {
-        Ok(results) => Ok((results, cache_key)),
-        Err(_) => {
-            if safe_search_level == 4 {
-                let mut results: SearchResults = SearchResults::default();
-
-                let flag: bool =
-                    !is_match_from_filter_list(file_path(FileType::BlockList)?, query)?;
-                // Return early when query contains disallowed words,
-                if flag {
-                    results.set_disallowed();
+        Some(Ok(mut results)) if is_servable_cache_entry(&results, &config.caching) => {
+            refilter_cached_results(config, &mut results, safe_search_level);
+            Ok((results, cache_key))
+        }
+        cached_results => {
+            // The cached search results which can not be served are kept to be served in place of
+            // the search results of a failed live fetch, if configured.
+            let fallback = cached_results.and_then(Result::ok).filter(|results| {
+                config.caching.serve_cached_on_error && !results.results.is_empty()
+            });
+            let has_fallback = fallback.is_some();
+
+            let fetch_results = || async {
+                if safe_search_level == 4 {
+                    let mut results: SearchResults = SearchResults::default();
+
+                    let flag: bool = !blocklist(&config.search, config.generation).is_match(query);
+                    // Return early when query contains disallowed words,
+                    if flag {
+                        results.set_disallowed();
+                        if write_cache {
+                            cache
+                                .cache_results(&[results.clone()], &[cache_key.clone()])
+                                .await?;
+                        }
+                        return Ok(results);
+                    }
+                }
+
+                // check if the cookie value is empty or not if it is empty then use the
+                // default selected upstream search engines from the config file otherwise
+                // parse the non-empty cookie and grab the user selected engines from the
+                // UI and use that.
+                let mut results: SearchResults = match engines.is_empty() {
+                    false => {
+                        aggregate_engines(
+                            config,
+                            cache,
+                            query,
+                            page,
+                            safe_search_level,
+                            engines,
+                            category,
+                            cache_use,
+                        )
+                        .await?
+                    }
+                    true => {
+                        let mut search_results = SearchResults::default();
+                        search_results.set_no_engines_selected();
+                        search_results
+                    }
+                };
+                if results.engine_errors_info().is_empty()
+                    && results.results().is_empty()
+                    && !results.no_engines_selected()
+                {
+                    results.set_filtered();
+                }
+                // The search results of a total outage of the upstream search engines are not
+                // cached, so that the search is retried, and the search results of a failed live
+                // fetch do not replace the cached ones.
+                if write_cache
+                    && !(results.upstream_failure || has_fallback && is_failed_fetch(&results))
+                {
                     cache
                         .cache_results(&[results.clone()], &[cache_key.clone()])
                         .await?;
-                    results.set_safe_search_level(safe_search_level);
-                    return Ok((results, cache_key));
                 }
-            }
+                Ok::<_, SearchError>(results)
+            };
 
-            // check if the cookie value is empty or not if it is empty then use the
-            // default selected upstream search engines from the config file otherwise
-            // parse the non-empty cookie and grab the user selected engines from the
-            // UI and use that.
-            let mut results: SearchResults = match search_settings.engines.is_empty() {
-                false => {
-                    aggregate(
-                        query,
-                        page,
-                        config.server.aggregator.random_delay,
-                        config.server.debug,
-                        &search_settings
-                            .engines
-                            .iter()
-                            .filter_map(|engine| EngineHandler::new(engine).ok())
-                            .collect::<Vec<EngineHandler>>(),
-                        config.server.request_timeout,
-                        safe_search_level,
-                    )
-                    .await?
-                }
-                true => {
-                    let mut search_results = SearchResults::default();
-                    search_results.set_no_engines_selected();
-                    search_results
-                }
+            // The identical searches which miss the cache at the same time share a single
+            // aggregation if configured.
+            let fetched_results = match config.caching.coalesce_searches {
+                true => coalesced(&cache_key, fetch_results).await,
+                false => fetch_results().await,
             };
-            if results.engine_errors_info().is_empty()
-                && results.results().is_empty()
-                && !results.no_engines_selected()
-            {
-                results.set_filtered();
+            let mut results = with_cache_fallback(fetched_results, fallback)?;
+            if results.stale {
+                refilter_cached_results(config, &mut results, safe_search_level);
             }
-            cache
-                .cache_results(&[results.clone()], &[cache_key.clone()])
-                .await?;
             results.set_safe_search_level(safe_search_level);
             Ok((results, cache_key))
         }
     }
 }
 
-/// A helper function which checks whether the search query contains any keywords which should be
-/// disallowed/allowed based on the regex based rules present in the blocklist and allowlist files.
+/// A helper function which aggregates the search results of the provided upstream search engines.
+/// When the engine responses are cached, the cached responses of the engines are reused instead of
+/// querying these engines again and the responses of the queried engines are cached.
+///
+/// # Arguments
+///
+/// * `config` - It takes a parsed config struct.
+/// * `cache` - It takes the cache store as an argument.
+/// * `query` - It takes the search query as an argument.
+/// * `page` - It takes the page number as an argument.
+/// * `safe_search_level` - It takes the safe search level of the search as an argument.
+/// * `engines` - It takes the names of the upstream search engines to be queried as an argument.
+/// * `category` - It takes the category of the search results as an argument.
+/// * `cache_use` - It takes how the cached responses of the engines are used as an argument.
+///
+/// # Error
+///
+/// Returns a `SearchError` if the search results could not be aggregated or if the responses of
+/// the engines could not be cached.
+#[allow(clippy::too_many_arguments)]
+async fn aggregate_engines(
+    config: &Config,
+    cache: &web::Data<SharedCache>,
+    query: &str,
+    page: u32,
+    safe_search_level: u8,
+    engines: &[&str],
+    category: Category,
+    cache_use: CacheUse,
+) -> Result<SearchResults, SearchError> {
+    let cache_responses = config.caching.cache_engine_responses && cache_use != CacheUse::Bypass;
+    let response_keys: Vec<(String, String)> = match cache_responses {
+        true => engines
+            .iter()
+            .map(|engine| {
+                let key =
+                    engine_response_key(config, query, page, safe_search_level, engine, category);
+                (engine.to_lowercase(), key)
+            })
+            .collect(),
+        false => Vec::new(),
+    };
+
+    let mut engine_responses = EngineResponses::new();
+    let cached_response_keys = match cache_use {
+        CacheUse::Read => response_keys.as_slice(),
+        CacheUse::Refresh | CacheUse::Bypass => &[],
+    };
+    for (engine, key) in cached_response_keys {
+        match cache.cached_results(key).await {
+            Ok(cached) if is_fresh_cache_entry(cached.fetched_at, &config.caching) => {
+                let results = cached.results.into_iter();
+                engine_responses.insert(
+                    engine.to_owned(),
+                    results.map(|result| (result.url.clone(), result)).collect(),
+                );
+            }
+            _ => (),
+        }
+    }
+    let cached_engines: Vec<String> = engine_responses.keys().cloned().collect();
+
+    let results = aggregate(
+        query,
+        page,
+        config,
+        &engines
+            .iter()
+            .filter_map(|engine| EngineHandler::new(engine).ok())
+            .collect::<Vec<EngineHandler>>(),
+        safe_search_level,
+        category,
+        &mut engine_responses,
+    )
+    .await?;
+
+    // Only the responses of the engines which were queried are cached.
+    let (responses, keys): (Vec<SearchResults>, Vec<String>) = response_keys
+        .into_iter()
+        .filter(|(engine, _)| !cached_engines.contains(engine))
+        .filter_map(|(engine, key)| {
+            let results = engine_responses.remove(&engine)?;
+            let results = results.into_iter().map(|(_, result)| result).collect();
+            Some((SearchResults::new(results, &[]), key))
+        })
+        .unzip();
+    if !responses.is_empty() {
+        cache.cache_results(&responses, &keys).await?;
+    }
+    Ok(results)
+}
+
+/// A helper function which filters the cached search results again at the safe search level of
+/// the search in the strict safe search mode, as the level at which the search results were
+/// cached is not trusted then.
+///
+/// # Arguments
+///
+/// * `config` - It takes a parsed config struct.
+/// * `results` - It takes the cached search results as an argument.
+/// * `safe_search_level` - It takes the safe search level of the search as an argument.
+fn refilter_cached_results(config: &Config, results: &mut SearchResults, safe_search_level: u8) {
+    if config.search.strict_safe_search {
+        refilter_results(
+            results,
+            safe_search_level,
+            &blocklist(&config.search, config.generation),
+            &allowlist(&config.search, config.generation),
+        );
+    }
+}
+
+/// A helper function which checks whether a live fetch of the search results failed, which is
+/// when no search results were found as too many (or all) of the upstream search engines failed.
+///
+/// # Arguments
+///
+/// * `results` - It takes the search results of the live fetch as an argument.
+fn is_failed_fetch(results: &SearchResults) -> bool {
+    results.results.is_empty() && (results.degraded || results.upstream_failure)
+}
+
+/// A helper function which serves the cached search results in place of the search results of a
+/// live fetch which failed, when there are cached search results to fall back to, so that a
+/// transient failure does not break the search.
+///
+/// # Arguments
+///
+/// * `fetched_results` - It takes the outcome of the live fetch as an argument.
+/// * `fallback` - It takes the cached search results to fall back to as an argument.
+fn with_cache_fallback(
+    fetched_results: Result<SearchResults, SearchError>,
+    fallback: Option<SearchResults>,
+) -> Result<SearchResults, SearchError> {
+    match (fetched_results, fallback) {
+        (Ok(results), Some(mut fallback)) if is_failed_fetch(&results) => {
+            log::warn!("The upstream search engines failed, serving the cached search results");
+            fallback.set_stale();
+            Ok(fallback)
+        }
+        (Err(error), Some(mut fallback)) => {
+            log::error!("Search Error: {error}");
+            log::warn!("Serving the cached search results instead");
+            fallback.set_stale();
+            Ok(fallback)
+        }
+        (fetched_results, _) => fetched_results,
+    }
+}
+
+/// A helper function which provides the search results of the pages adjacent to the current page,
+/// which are fetched along with it so that they are cached by the time the user navigates to
+/// them. When the cached search results are served on errors, the adjacent pages which failed are
+/// only logged and skipped, so that they do not fail the search of the current page.
+///
+/// # Arguments
+///
+/// * `config` - It takes a parsed config struct.
+/// * `adjacent_results` - It takes the search results of the adjacent pages along with their
+///   cache keys as an argument.
+fn prefetched_results(
+    config: &Config,
+    adjacent_results: Vec<Result<(SearchResults, String), SearchError>>,
+) -> Result<Vec<(SearchResults, String)>, SearchError> {
+    match config.caching.serve_cached_on_error {
+        true => Ok(adjacent_results
+            .into_iter()
+            .filter_map(|results| {
+                results
+                    .map_err(|error| log::error!("Search Error: {error}"))
+                    .ok()
+            })
+            .collect()),
+        false => adjacent_results.into_iter().collect(),
+    }
+}
+
+/// A helper function which decides how a search with the `refine` search url parameter is
+/// handled. When the search results of the query are cached, the cached search results are
+/// narrowed down by the refinement terms without querying the upstream search engines again,
+/// otherwise the refinement terms are searched for along with the query.
+///
+/// # Arguments
+///
+/// * `config` - It takes a parsed config struct.
+/// * `cache` - It takes the shared cache as an argument.
+/// * `query` - It takes the search query sent to the upstream search engines as an argument.
+/// * `refinement` - It takes the value of the `refine` search url parameter as an argument.
+/// * `cache_key` - It takes the function which provides the cache key of the search results of
+///   the query as an argument.
+///
+/// # Returns
+///
+/// Returns the search query sent to the upstream search engines along with the refinement terms
+/// by which the search results are narrowed down, if any.
+async fn refined_query<'a>(
+    config: &Config,
+    cache: &SharedCache,
+    query: &'a str,
+    refinement: Option<&'a str>,
+    cache_key: impl FnOnce() -> String,
+) -> (Cow<'a, str>, Option<&'a str>) {
+    let Some(refinement) = refinement.map(str::trim).filter(|terms| !terms.is_empty()) else {
+        return (Cow::Borrowed(query), None);
+    };
+
+    match cache.cached_results(&cache_key()).await {
+        Ok(results) if is_servable_cache_entry(&results, &config.caching) => {
+            (Cow::Borrowed(query), Some(refinement))
+        }
+        _ => (Cow::Owned(format!("{query} {refinement}")), None),
+    }
+}
+
+/// A function which shares the results of a single aggregation between the identical searches
+/// which are in flight at the same time. The first search for the provided cache key fetches the
+/// results, while the searches for the same cache key started before it completes wait for its
+/// results instead of fetching them again. The waiting searches fetch the results themselves if
+/// the first search is cancelled before it completes.
 ///
 /// # Arguments
 ///
-/// * `file_path` - It takes the file path of the list as the argument.
-/// * `query` - It takes the search query to be checked against the list as an argument.
+/// * `cache_key` - It takes the cache key of the search as an argument.
+/// * `fetch_results` - It takes the function which fetches the results of the search as an
+///   argument.
 ///
 /// # Error
 ///
-/// Returns a bool indicating whether the results were found in the list or not on success
-/// otherwise returns a standard error type on a failure.
-fn is_match_from_filter_list(
-    file_path: &str,
+/// Returns the error of the aggregation shared by the searches if the results could not be
+/// fetched.
+async fn coalesced<F>(
+    cache_key: &str,
+    fetch_results: impl FnOnce() -> F,
+) -> Result<SearchResults, SearchError>
+where
+    F: Future<Output = Result<SearchResults, SearchError>>,
+{
+    match InFlightAggregation::join(cache_key) {
+        Ok(in_flight) => {
+            let results = fetch_results().await;
+            in_flight.complete(results.clone());
+            results
+        }
+        Err(receiver) => match receiver.await {
+            Ok(results) => results,
+            Err(_) => fetch_results().await,
+        },
+    }
+}
+
+/// A helper function which checks whether the cached search results are usable and fresh enough
+/// to be served.
+///
+/// # Arguments
+///
+/// * `results` - It takes the cached search results as an argument.
+/// * `caching` - It takes the caching config as an argument.
+fn is_servable_cache_entry(results: &SearchResults, caching: &Caching) -> bool {
+    is_usable_cache_entry(
+        results.results.len(),
+        results.disallowed || results.no_engines_selected,
+        caching.min_cached_results,
+    ) && is_fresh_cache_entry(results.fetched_at, caching)
+}
+
+/// A named struct which deserializes only the fields of the cached search results which decide
+/// whether they can be served, so that the json of the cached search results can be sent as it
+/// is without deserializing the search results themselves.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedResultsSummary {
+    /// The cached search results, which are only counted.
+    results: Vec<IgnoredAny>,
+    /// Whether the search was disallowed.
+    #[serde(default)]
+    disallowed: bool,
+    /// Whether no upstream search engines were selected for the search.
+    #[serde(default)]
+    no_engines_selected: bool,
+    /// The time (in seconds since the unix epoch) at which the search results were fetched.
+    #[serde(default)]
+    fetched_at: u64,
+}
+
+/// A helper function which fetches the json of the cached search results for the provided cache
+/// key without deserializing the search results, when they can be served.
+///
+/// # Arguments
+///
+/// * `config` - It takes a parsed config struct.
+/// * `cache` - It takes the shared cache as an argument.
+/// * `cache_key` - It takes the cache key of the search results as an argument.
+///
+/// # Returns
+///
+/// Returns the json bytes of the cached search results, or `None` when they are not cached or
+/// can not be served, so that they are fetched the usual way instead.
+async fn cached_json(config: &Config, cache: &SharedCache, cache_key: &str) -> Option<Vec<u8>> {
+    let json = cache.cached_results_json(cache_key).await.ok()?;
+    let summary: CachedResultsSummary = serde_json::from_slice(&json).ok()?;
+    let servable = is_usable_cache_entry(
+        summary.results.len(),
+        summary.disallowed || summary.no_engines_selected,
+        config.caching.min_cached_results,
+    ) && is_fresh_cache_entry(summary.fetched_at, &config.caching);
+    servable.then_some(json)
+}
+
+/// A helper function which builds the response of a search, tagging it with an entity tag so
+/// that the unchanged searches are answered with a `304 Not Modified` response instead. The
+/// response varies on the `Accept` header as the api requests are answered with json.
+///
+/// # Arguments
+///
+/// * `req` - It takes the `HttpRequest` struct as an argument.
+/// * `cache_key` - It takes the cache key of the search results as an argument.
+/// * `content_type` - It takes the content type of the response as an argument.
+/// * `body` - It takes the body of the response as an argument.
+fn tagged_response(
+    req: &HttpRequest,
+    cache_key: &str,
+    content_type: ContentType,
+    body: Vec<u8>,
+) -> HttpResponse {
+    // The cache key is part of the entity tag, so that the tag changes whenever the search
+    // settings (like the safe search level or the selected engines) change.
+    let etag = EntityTag::new_strong(
+        blake3::Hasher::new()
+            .update(cache_key.as_bytes())
+            .update(&body)
+            .finalize()
+            .to_string(),
+    );
+
+    let not_modified = match req.get_header::<IfNoneMatch>() {
+        Some(IfNoneMatch::Any) => true,
+        Some(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+        None => false,
+    };
+
+    let mut response = match not_modified {
+        true => HttpResponse::NotModified()
+            .insert_header(ETag(etag))
+            .finish(),
+        false => HttpResponse::Ok()
+            .content_type(content_type)
+            .insert_header(ETag(etag))
+            .body(body),
+    };
+    vary_on(&mut response, "accept");
+    response
+}
+
+/// A helper function which adds the provided header to the `Vary` header of the response. The
+/// headers are kept in a single `Vary` header, as only the first one is kept by the cors
+/// middleware when it adds its own headers to it.
+///
+/// # Arguments
+///
+/// * `response` - It takes the response to be modified as an argument.
+/// * `header` - It takes the name of the header on which the response varies as an argument.
+fn vary_on(response: &mut HttpResponse, header: &str) {
+    let vary = match response
+        .headers()
+        .get(VARY)
+        .and_then(|vary| vary.to_str().ok())
+    {
+        Some(vary) => format!("{vary}, {header}"),
+        None => header.to_owned(),
+    };
+    if let Ok(vary) = HeaderValue::from_str(&vary) {
+        response.headers_mut().insert(VARY, vary);
+    }
+}
+
+/// A helper function which checks whether the cached search results can be served. The cached
+/// searches with fewer results than the provided minimum are not served so that they are fetched
+/// again, unless no upstream search engines were queried for them as the search was disallowed
+/// or no engines were selected.
+///
+/// # Arguments
+///
+/// * `result_count` - It takes the number of the cached results as an argument.
+/// * `skipped_upstream` - It takes whether the search was disallowed or no engines were selected
+///   for it as an argument.
+/// * `min_results` - It takes the minimum number of results as an argument.
+fn is_usable_cache_entry(result_count: usize, skipped_upstream: bool, min_results: usize) -> bool {
+    skipped_upstream || result_count >= min_results
+}
+
+/// A helper function which checks whether the cached search results are fresh enough to be
+/// served. While more searches than the high load threshold are being handled by the server, the
+/// cached searches up to the high load maximum age are served to spare the upstream search
+/// engines, otherwise they are only served until the cache expiry time.
+///
+/// # Arguments
+///
+/// * `fetched_at` - It takes the time (in seconds since the unix epoch) at which the cached search
+///   results were fetched as an argument.
+/// * `caching` - It takes the caching config as an argument.
+fn is_fresh_cache_entry(fetched_at: u64, caching: &Caching) -> bool {
+    // The cached searches are only kept beyond the cache expiry time in the high load mode.
+    if caching.high_load_threshold == 0 {
+        return true;
+    }
+
+    let max_age = match IN_FLIGHT_SEARCHES.load(Ordering::Acquire) > caching.high_load_threshold {
+        true => caching.high_load_max_age,
+        #[cfg(any(feature = "redis-cache", feature = "memory-cache"))]
+        false => caching.cache_expiry_time,
+        // Without a cache backend no search results are cached, so there is nothing to expire.
+        #[cfg(not(any(feature = "redis-cache", feature = "memory-cache")))]
+        false => return true,
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    now.saturating_sub(fetched_at) < u64::from(max_age)
+}
+
+/// A helper function which provides the key under which the search results for the provided
+/// search are cached.
+///
+/// # Arguments
+///
+/// * `config` - It takes a parsed config struct.
+/// * `query` - It takes the search query as an argument.
+/// * `page` - It takes the page number as an argument.
+/// * `safe_search_level` - It takes the safe search level of the search as an argument.
+/// * `engines` - It takes the names of the upstream search engines to be queried as an argument.
+/// * `category` - It takes the category of the search results as an argument.
+pub fn cache_key(
+    config: &Config,
+    query: &str,
+    page: u32,
+    safe_search_level: u8,
+    engines: &[&str],
+    category: Category,
+) -> String {
+    // The category is left out of the cache key of the general search results, so that the
+    // cached general search results remain valid.
+    let category_param = match category {
+        Category::General => "",
+        Category::News => "&category=news",
+    };
+
+    format!(
+        "http://{}:{}/search?q={}&page={}&safesearch={}&engines={}{}",
+        config.server.binding_ip,
+        config.server.port,
+        normalize_query(query, config.caching.lowercase_cache_key),
+        page,
+        safe_search_level,
+        engines.join(","),
+        category_param
+    )
+}
+
+/// A helper function which provides the key under which the response of an upstream search
+/// engine to the provided search is cached, when the engine responses are cached. It is the key
+/// of the search without any engines along with the name of the engine, which never matches the
+/// key of a search.
+///
+/// # Arguments
+///
+/// * `config` - It takes a parsed config struct.
+/// * `query` - It takes the search query as an argument.
+/// * `page` - It takes the page number as an argument.
+/// * `safe_search_level` - It takes the safe search level of the search as an argument.
+/// * `engine` - It takes the name of the upstream search engine as an argument.
+/// * `category` - It takes the category of the search results as an argument.
+fn engine_response_key(
+    config: &Config,
     query: &str,
-) -> Result<bool, Box<dyn std::error::Error>> {
-    let mut reader = BufReader::new(File::open(file_path)?);
-    for line in reader.by_ref().lines() {
-        let re = Regex::new(&line?)?;
-        if re.is_match(query) {
-            return Ok(true);
+    page: u32,
+    safe_search_level: u8,
+    engine: &str,
+    category: Category,
+) -> String {
+    format!(
+        "{}&response={}",
+        cache_key(config, query, page, safe_search_level, &[], category),
+        engine.to_lowercase()
+    )
+}
+
+/// A helper function which provides the upstream search engines which support searching the
+/// provided category and are enabled for it in the config, or `None` if none of the provided
+/// engines can be queried for a category other than the general search results.
+///
+/// # Arguments
+///
+/// * `config` - It takes a parsed config struct.
+/// * `engines` - It takes the names of the upstream search engines to be queried as an argument.
+/// * `category` - It takes the requested category of the search results as an argument.
+fn category_engines<'a>(
+    config: &Config,
+    engines: &[&'a str],
+    category: Category,
+) -> Option<Vec<&'a str>> {
+    let supported_engines: Vec<&str> = engines
+        .iter()
+        .copied()
+        .filter(|engine| config.search.engine_enabled_for(engine, category))
+        .filter(|engine| {
+            category == Category::General
+                || EngineHandler::new(engine).is_ok_and(|engine| engine.capabilities().categories)
+        })
+        .collect();
+    (category == Category::General || engines.is_empty() || !supported_engines.is_empty())
+        .then_some(supported_engines)
+}
+
+/// A helper function which provides the upstream search engines to be queried, which are the
+/// engines selected by the user along with the forced engines, and the selected engines which are
+/// not queried as more engines than the configured maximum were selected.
+///
+/// # Arguments
+///
+/// * `config` - It takes a parsed config struct.
+/// * `search_settings` - It takes the search settings of the user as an argument.
+pub fn selected_engines<'a>(
+    config: &'a Config,
+    search_settings: &'a server_models::Cookie<'_>,
+) -> (Vec<&'a str>, Vec<&'a str>) {
+    // The forced engines are always queried in addition to the engines selected by the user.
+    let mut engines: Vec<&str> = search_settings
+        .engines
+        .iter()
+        .map(|engine| engine.as_ref())
+        .collect();
+    for forced in &config.search.forced_engines {
+        if !engines
+            .iter()
+            .any(|engine| engine.eq_ignore_ascii_case(forced))
+        {
+            engines.push(forced);
         }
     }
 
-    Ok(false)
+    // Fall back to the engines enabled by default in the config when no engines were selected,
+    // if configured, instead of reporting that no engines were selected.
+    if engines.is_empty() && config.search.fallback_to_default_engines {
+        engines = config
+            .search
+            .upstream_search_engines
+            .iter()
+            .filter_map(|(engine, enabled)| enabled.then_some(engine.as_str()))
+            .collect();
+        engines.sort();
+    }
+
+    // Only the engines with the highest weights are queried when more engines than the configured
+    // maximum were selected, the forced engines are always queried.
+    let mut dropped_engines = Vec::new();
+    let max_engines = config.search.max_engines_per_search;
+    if max_engines != 0 && engines.len() > max_engines {
+        let (mut kept_engines, mut optional_engines): (Vec<&str>, Vec<&str>) =
+            engines.into_iter().partition(|engine| {
+                config
+                    .search
+                    .forced_engines
+                    .iter()
+                    .any(|forced| forced.eq_ignore_ascii_case(engine))
+            });
+        optional_engines.sort_by(|a, b| {
+            config
+                .search
+                .engine_weight(b)
+                .total_cmp(&config.search.engine_weight(a))
+        });
+        let remaining = max_engines
+            .saturating_sub(kept_engines.len())
+            .min(optional_engines.len());
+        dropped_engines = optional_engines.split_off(remaining);
+        kept_engines.extend(optional_engines);
+        kept_engines.sort();
+        engines = kept_engines;
+    }
+
+    (engines, dropped_engines)
+}
+
+/// A helper function which overrides the engines selected in the search settings with the
+/// engines provided in the `engines` search url parameter. The engine names are resolved like the
+/// engine names selected in the cookie, the unknown engine names are ignored and the selected
+/// engines are kept when none of the provided engine names are known.
+///
+/// # Arguments
+///
+/// * `config` - It takes a parsed config struct.
+/// * `search_settings` - It takes the search settings of the user as an argument.
+/// * `engines` - It takes the comma separated names of the engines to be queried as an argument.
+///
+/// # Returns
+///
+/// Returns the unknown engine names which were ignored.
+fn override_engines<'a>(
+    config: &Config,
+    search_settings: &mut server_models::Cookie<'_>,
+    engines: &'a str,
+) -> Vec<&'a str> {
+    let mut known_engines: Vec<&'static str> = Vec::new();
+    let mut unknown_engines: Vec<&str> = Vec::new();
+    for engine in engines.split(',').map(str::trim) {
+        match resolve_engine_name(config, engine) {
+            Some(engine) => known_engines.push(engine),
+            None if !engine.is_empty() => unknown_engines.push(engine),
+            None => (),
+        }
+    }
+
+    if !known_engines.is_empty() {
+        known_engines.sort();
+        known_engines.dedup();
+        search_settings.engines =
+            Cow::Owned(known_engines.into_iter().map(Cow::Borrowed).collect());
+    }
+
+    unknown_engines
+}
+
+/// A helper function which resolves the engine names selected in the search settings to the
+/// names of the upstream search engines they refer to, so that the selections made with an
+/// alternative name (or an old name) of an engine are not lost. The unknown engine names are
+/// removed from the selected engines.
+///
+/// # Arguments
+///
+/// * `config` - It takes a parsed config struct.
+/// * `search_settings` - It takes the search settings of the user as an argument.
+///
+/// # Returns
+///
+/// Returns the unknown engine names which were removed.
+fn resolve_engines(
+    config: &Config,
+    search_settings: &mut server_models::Cookie<'_>,
+) -> Vec<String> {
+    let mut known_engines: Vec<Cow<'_, str>> = Vec::new();
+    let mut unknown_engines: Vec<String> = Vec::new();
+    for engine in search_settings.engines.iter() {
+        match resolve_engine_name(config, engine) {
+            Some(engine) if !known_engines.iter().any(|known| known == engine) => {
+                known_engines.push(Cow::Borrowed(engine))
+            }
+            Some(_) => (),
+            None => unknown_engines.push(engine.to_string()),
+        }
+    }
+
+    search_settings.engines = Cow::Owned(known_engines);
+    unknown_engines
+}
+
+/// A helper function which removes the upstream search engines denied by the instance from the
+/// engines selected in the search settings, so that they are reported to the user.
+///
+/// # Arguments
+///
+/// * `config` - It takes a parsed config struct.
+/// * `search_settings` - It takes the search settings of the user as an argument.
+///
+/// # Returns
+///
+/// Returns the names of the denied engines which were removed.
+fn remove_denied_engines(
+    config: &Config,
+    search_settings: &mut server_models::Cookie<'_>,
+) -> Vec<String> {
+    let (denied_engines, allowed_engines): (Vec<Cow<'_, str>>, Vec<Cow<'_, str>>) = search_settings
+        .engines
+        .iter()
+        .cloned()
+        .partition(|engine| config.search.is_denied_engine(engine));
+    search_settings.engines = Cow::Owned(allowed_engines);
+    denied_engines.into_iter().map(Cow::into_owned).collect()
+}
+
+/// A helper function which resolves an engine name to the name of the upstream search engine it
+/// refers to, which is matched regardless of its case. When the fuzzy engine names are enabled,
+/// the whitespace and the punctuation within the name are ignored as well and the name is looked
+/// up in the configured engine aliases before it is matched against the known engines.
+///
+/// # Arguments
+///
+/// * `config` - It takes a parsed config struct.
+/// * `name` - It takes the engine name to be resolved as an argument.
+///
+/// # Returns
+///
+/// Returns the name of the engine, or `None` if the name does not refer to any known engine.
+fn resolve_engine_name(config: &Config, name: &str) -> Option<&'static str> {
+    if !config.search.fuzzy_engine_names {
+        return EngineHandler::new(name.trim())
+            .ok()
+            .map(|engine| engine.name());
+    }
+
+    let name: String = name
+        .chars()
+        .filter(|character| character.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let name = config.search.engine_aliases.get(&name).unwrap_or(&name);
+    EngineHandler::new(name).ok().map(|engine| engine.name())
+}
+
+/// A helper function which normalizes the search query, so that the queries which only differ by
+/// whitespace (and optionally also by case) are treated as the same query. The query is trimmed,
+/// the consecutive whitespace characters within it are collapsed into a single space and then
+/// it is lowercased if requested.
+///
+/// # Arguments
+///
+/// * `query` - It takes the search query to be normalized as an argument.
+/// * `lowercase` - It takes a boolean which decides whether the query is lowercased as an
+///   argument.
+fn normalize_query(query: &str, lowercase: bool) -> String {
+    let normalized = query.split_whitespace().collect::<Vec<&str>>().join(" ");
+    match lowercase {
+        true => normalized.to_lowercase(),
+        false => normalized,
+    }
+}
+
+/// A function which converts the `page` search parameter, which numbers the pages from `1`, into
+/// the index of the page starting from `0`. The pages which are not positive or are beyond the
+/// last page which can be searched are clamped, unless the request is an api request and the
+/// `reject_invalid_pages` option is enabled, so that the api clients get a clear signal instead
+/// of a silent coercion.
+///
+/// # Arguments
+///
+/// * `page` - It takes the `page` search parameter as an argument.
+/// * `api_request` - It takes whether the request is an api request as an argument.
+/// * `config` - It takes the parsed config struct as an argument.
+///
+/// # Error
+///
+/// Returns the reason of the rejection if the page is rejected.
+fn page_index(page: Option<i64>, api_request: bool, config: &Config) -> Result<u32, String> {
+    let Some(page) = page else {
+        return Ok(0);
+    };
+    match u32::try_from(page) {
+        Ok(page @ 1..) => Ok(page - 1),
+        _ if api_request && config.server.reject_invalid_pages => Err(format!(
+            "The page should be a positive number not greater than {}.",
+            u32::MAX
+        )),
+        _ if page < 1 => Ok(0),
+        _ => Ok(u32::MAX - 1),
+    }
+}
+
+/// A helper function which checks whether the request is an api request, which is answered with
+/// the search results as json instead of the search page.
+///
+/// # Arguments
+///
+/// * `req` - It takes the `HttpRequest` struct as an argument.
+fn is_api_request(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(prefers_json)
+}
+
+/// A helper function which returns how the cache is used by the requested page of a search. The
+/// `nocache` search parameter skips the cached search results, but as it increases the load of
+/// the upstream search engines it is only honored in debug mode or for the requests providing
+/// the admin token, and only when it is allowed by the config.
+///
+/// # Arguments
+///
+/// * `req` - It takes the `HttpRequest` struct as an argument.
+/// * `config` - It takes the parsed config struct as an argument.
+/// * `nocache` - It takes the value of the `nocache` search parameter, if any, as an argument.
+fn requested_cache_use(req: &HttpRequest, config: &Config, nocache: Option<u8>) -> CacheUse {
+    let allowed = config.caching.allow_nocache && (config.server.debug || is_admin(req, config));
+    match nocache {
+        Some(1..) if allowed && config.caching.nocache_writes => CacheUse::Refresh,
+        Some(1..) if allowed => CacheUse::Bypass,
+        _ => CacheUse::Read,
+    }
+}
+
+/// A helper function which returns the value of the `Accept-Language` header of a request, which
+/// selects the language of the search page unless it is disabled by the `accept_language` option.
+///
+/// # Arguments
+///
+/// * `req` - It takes the `HttpRequest` struct as an argument.
+/// * `config` - It takes the parsed config struct as an argument.
+fn accept_language<'a>(req: &'a HttpRequest, config: &Config) -> Option<&'a str> {
+    req.headers()
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|accept_language| accept_language.to_str().ok())
+        .filter(|_| config.style.accept_language)
+}
+
+/// A helper function which returns the safe search level used when neither the cookie nor the
+/// url provide one, which differs for the api requests when the `api_safe_search` option is set.
+///
+/// # Arguments
+///
+/// * `req` - It takes the `HttpRequest` struct as an argument.
+/// * `config` - It takes the parsed config struct which provides the safe search levels.
+fn default_safesearch_level(req: &HttpRequest, config: &Config) -> u8 {
+    match config.search.api_safe_search {
+        Some(api_level) if is_api_request(req) => api_level,
+        _ => config.search.safe_search,
+    }
+}
+
+/// A function which checks whether the value of the `Accept` header of a request asks for a json
+/// response rather than a html page, which is how the api requests are told apart from the
+/// requests of the browsers.
+///
+/// # Arguments
+///
+/// * `accept` - It takes the value of the `Accept` header as an argument.
+fn prefers_json(accept: &str) -> bool {
+    let media_types: Vec<String> = accept
+        .split(',')
+        .filter_map(|media_type| media_type.split(';').next())
+        .map(|media_type| media_type.trim().to_ascii_lowercase())
+        .collect();
+
+    media_types
+        .iter()
+        .any(|media_type| media_type == "application/json")
+        && !media_types
+            .iter()
+            .any(|media_type| media_type == "text/html")
 }
 
 /// A helper function to modify the safe search level based on the url params.
@@ -291,3 +1872,424 @@ fn get_safesearch_level(cookie_level: &Option<u8>, url_level: &Option<u8>, confi
         None => cookie_level.unwrap_or(config_level),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cache::cacher::create_cache, models::aggregation_models::SearchResult};
+
+    #[test]
+    fn test_engines_param_overrides_cookie() {
+        let config = Config::parse(true).unwrap();
+        let mut search_settings =
+            server_models::Cookie::build(&config.style, vec![Cow::Borrowed("searx")], 0);
+
+        let ignored = override_engines(
+            &config,
+            &mut search_settings,
+            "Bing, duckduckgo,nonexistent,,bing",
+        );
+        assert_eq!(*search_settings.engines, vec!["bing", "duckduckgo"]);
+        assert_eq!(ignored, vec!["nonexistent"]);
+
+        // The selected engines are kept when none of the provided engine names are known.
+        let ignored = override_engines(&config, &mut search_settings, "nonexistent");
+        assert_eq!(*search_settings.engines, vec!["bing", "duckduckgo"]);
+        assert_eq!(ignored, vec!["nonexistent"]);
+    }
+
+    #[test]
+    fn test_resolve_engines() {
+        let mut config = Config::parse(true).unwrap();
+        let mut search_settings = server_models::Cookie::build(
+            &config.style,
+            vec![
+                Cow::Borrowed("DDG"),
+                Cow::Borrowed("DuckDuckGo"),
+                Cow::Borrowed("Duck Duck Go"),
+                Cow::Borrowed("SearXNG"),
+                Cow::Borrowed("nonexistent"),
+            ],
+            0,
+        );
+        let cookie_engines = search_settings.engines.clone();
+
+        let unknown = resolve_engines(&config, &mut search_settings);
+        assert_eq!(*search_settings.engines, vec!["duckduckgo", "searx"]);
+        assert_eq!(unknown, vec!["nonexistent"]);
+
+        // Only the engine names differing by case are matched when the fuzzy matching is disabled.
+        config.search.fuzzy_engine_names = false;
+        search_settings.engines = cookie_engines;
+        let unknown = resolve_engines(&config, &mut search_settings);
+        assert_eq!(*search_settings.engines, vec!["duckduckgo"]);
+        assert_eq!(
+            unknown,
+            vec!["DDG", "Duck Duck Go", "SearXNG", "nonexistent"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_denied_engines() {
+        let mut config = Config::parse(true).unwrap();
+        config.search.denied_engines = vec!["startpage".to_owned()];
+        let mut search_settings = server_models::Cookie::build(
+            &config.style,
+            vec![Cow::Borrowed("duckduckgo"), Cow::Borrowed("startpage")],
+            0,
+        );
+
+        let denied = remove_denied_engines(&config, &mut search_settings);
+        assert_eq!(*search_settings.engines, vec!["duckduckgo"]);
+        assert_eq!(denied, vec!["startpage"]);
+
+        // The denied engines are not queried even when they are provided to the results directly,
+        // so no engines are left to be queried.
+        let cache = web::Data::new(SharedCache::new(create_cache(&config).await, 0));
+        let (search_results, _) = results(
+            &config,
+            &cache,
+            "denied",
+            0,
+            &search_settings,
+            &["Startpage"],
+            Category::General,
+            CacheUse::Read,
+        )
+        .await
+        .unwrap();
+        assert!(search_results.no_engines_selected);
+    }
+
+    #[test]
+    fn test_page_index() {
+        let mut config = Config::parse(true).unwrap();
+
+        assert_eq!(page_index(None, true, &config), Ok(0));
+        assert_eq!(page_index(Some(1), true, &config), Ok(0));
+        assert_eq!(page_index(Some(3), false, &config), Ok(2));
+        assert_eq!(
+            page_index(Some(u32::MAX.into()), true, &config),
+            Ok(u32::MAX - 1)
+        );
+
+        // The out of range pages are rejected for the api requests and clamped for the browsers.
+        for page in [0, -1, i64::MIN, i64::from(u32::MAX) + 1, i64::MAX] {
+            assert!(page_index(Some(page), true, &config).is_err());
+        }
+        assert_eq!(page_index(Some(0), false, &config), Ok(0));
+        assert_eq!(page_index(Some(-1), false, &config), Ok(0));
+        assert_eq!(page_index(Some(i64::MAX), false, &config), Ok(u32::MAX - 1));
+
+        config.server.reject_invalid_pages = false;
+        assert_eq!(page_index(Some(0), true, &config), Ok(0));
+        assert_eq!(page_index(Some(i64::MAX), true, &config), Ok(u32::MAX - 1));
+    }
+
+    #[test]
+    fn test_requested_cache_use() {
+        use actix_web::{http::header::AUTHORIZATION, test::TestRequest};
+        let mut config = Config::parse(true).unwrap();
+        config.server.debug = false;
+        config.server.admin_token = Some("secret".to_owned());
+        let anonymous = TestRequest::default().to_http_request();
+        let admin = TestRequest::default()
+            .insert_header((AUTHORIZATION, "Bearer secret"))
+            .to_http_request();
+
+        // The cached results are skipped only for the admins outside of debug mode.
+        assert_eq!(
+            requested_cache_use(&anonymous, &config, Some(1)),
+            CacheUse::Read
+        );
+        assert_eq!(requested_cache_use(&admin, &config, None), CacheUse::Read);
+        assert_eq!(
+            requested_cache_use(&admin, &config, Some(0)),
+            CacheUse::Read
+        );
+        assert_eq!(
+            requested_cache_use(&admin, &config, Some(1)),
+            CacheUse::Refresh
+        );
+
+        config.server.debug = true;
+        assert_eq!(
+            requested_cache_use(&anonymous, &config, Some(1)),
+            CacheUse::Refresh
+        );
+        config.caching.nocache_writes = false;
+        assert_eq!(
+            requested_cache_use(&anonymous, &config, Some(1)),
+            CacheUse::Bypass
+        );
+        config.caching.allow_nocache = false;
+        assert_eq!(
+            requested_cache_use(&admin, &config, Some(1)),
+            CacheUse::Read
+        );
+    }
+
+    #[test]
+    fn test_cached_results_are_served_on_error() {
+        let cached_results = SearchResults::new(
+            vec![SearchResult::new(
+                "Rust Programming Language",
+                "https://www.rust-lang.org/",
+                "A language empowering everyone.",
+                &["duckduckgo"],
+            )],
+            &[],
+        );
+        let mut failed_results = SearchResults::default();
+        failed_results.set_degraded();
+
+        // The cached search results are served when the upstream search engines failed.
+        let results = with_cache_fallback(Ok(failed_results.clone()), Some(cached_results.clone()));
+        let results = results.unwrap();
+        assert_eq!(results.results.len(), 1);
+        assert!(results.stale);
+
+        // The cached search results are served when the search errored.
+        let error = SearchError::Cache("unreachable".to_owned());
+        let results = with_cache_fallback(Err(error.clone()), Some(cached_results.clone()));
+        assert!(results.unwrap().stale);
+
+        // The search results of a successful live fetch are served over the cached ones, and the
+        // failures are served as they are without cached search results to fall back to.
+        let mut fetched_results = cached_results.clone();
+        fetched_results.set_degraded();
+        let results = with_cache_fallback(Ok(fetched_results), Some(cached_results.clone()));
+        assert!(!results.unwrap().stale);
+        let no_engines_results =
+            with_cache_fallback(Ok(SearchResults::default()), Some(cached_results));
+        assert!(!no_engines_results.unwrap().stale);
+        let results = with_cache_fallback(Ok(failed_results), None).unwrap();
+        assert!(results.results.is_empty() && !results.stale);
+        assert!(with_cache_fallback(Err(error), None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_identical_searches_are_coalesced() {
+        let fetches = AtomicUsize::new(0);
+        let (release, released) = channel::<()>();
+
+        // The first search is held in flight until it is released, so the identical search started
+        // meanwhile waits for its results instead of fetching them.
+        let (first_results, second_results, _) = tokio::join!(
+            coalesced("coalesced-search", || async {
+                fetches.fetch_add(1, Ordering::SeqCst);
+                let _ = released.await;
+                let mut search_results = SearchResults::default();
+                search_results.set_disallowed();
+                Ok(search_results)
+            }),
+            coalesced("coalesced-search", || async {
+                fetches.fetch_add(1, Ordering::SeqCst);
+                Ok(SearchResults::default())
+            }),
+            async { release.send(()) },
+        );
+
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        assert!(first_results.unwrap().disallowed);
+        assert!(second_results.unwrap().disallowed);
+
+        // The search is no longer in flight once it completed, so it is fetched again.
+        coalesced("coalesced-search", || async {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            Ok(SearchResults::default())
+        })
+        .await
+        .unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_thin_cache_entry_is_refetched() {
+        let mut config = Config::parse(true).unwrap();
+        let thin_results = SearchResults::new(
+            vec![SearchResult::new(
+                "Rust Programming Language",
+                "https://www.rust-lang.org/",
+                "A language empowering everyone.",
+                &["duckduckgo"],
+            )],
+            &[],
+        );
+
+        // The cached search is served as long as it has enough results.
+        config.caching.min_cached_results = 1;
+        let search_settings = server_models::Cookie::build(&config.style, Vec::new(), 0);
+        let cache = web::Data::new(SharedCache::new(create_cache(&config).await, 0));
+        let key = cache_key(&config, "rust", 0, 0, &[], Category::General);
+        cache
+            .cache_results(&[thin_results.clone()], &[key.clone()])
+            .await
+            .unwrap();
+        let (search_results, _) = results(
+            &config,
+            &cache,
+            "rust",
+            0,
+            &search_settings,
+            &[],
+            Category::General,
+            CacheUse::Read,
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_results.results.len(), 1);
+
+        // The cached search with too few results is treated as a cache miss and searched again,
+        // which reports that no engines were selected as no engines are provided.
+        let mut config = config.clone();
+        config.caching.min_cached_results = 2;
+        let search_settings = server_models::Cookie::build(&config.style, Vec::new(), 0);
+        cache.cache_results(&[thin_results], &[key]).await.unwrap();
+        let (search_results, _) = results(
+            &config,
+            &cache,
+            "rust",
+            0,
+            &search_settings,
+            &[],
+            Category::General,
+            CacheUse::Read,
+        )
+        .await
+        .unwrap();
+        assert!(search_results.results.is_empty());
+        assert!(search_results.no_engines_selected);
+    }
+
+    #[test]
+    fn test_engine_response_key() {
+        let config = Config::parse(true).unwrap();
+        let key = engine_response_key(&config, "rust", 0, 0, "Bing", Category::General);
+
+        // The responses of the engines are cached apart from the searches, even from the searches
+        // querying the engine alone, and apart from the responses of the other engines.
+        assert_eq!(
+            key,
+            engine_response_key(&config, "rust", 0, 0, "bing", Category::General)
+        );
+        assert_ne!(
+            key,
+            cache_key(&config, "rust", 0, 0, &["bing"], Category::General)
+        );
+        assert_ne!(
+            key,
+            engine_response_key(&config, "rust", 0, 0, "brave", Category::General)
+        );
+        assert_ne!(
+            key,
+            engine_response_key(&config, "rust", 1, 0, "bing", Category::General)
+        );
+        assert_ne!(
+            key,
+            engine_response_key(&config, "rust", 0, 2, "bing", Category::General)
+        );
+        assert_ne!(
+            key,
+            engine_response_key(&config, "rust", 0, 0, "bing", Category::News)
+        );
+    }
+
+    #[cfg(all(feature = "memory-cache", not(feature = "redis-cache")))]
+    #[tokio::test]
+    async fn test_in_memory_cache_eviction() {
+        let mut config = Config::parse(true).unwrap();
+        config.caching.max_cache_entries = 2;
+        let cache = SharedCache::new(create_cache(&config).await, 0);
+        let keys: Vec<String> = ["rust", "go", "zig"]
+            .into_iter()
+            .map(|query| cache_key(&config, query, 0, 0, &[], Category::General))
+            .collect();
+
+        for key in &keys[..2] {
+            cache
+                .cache_results(&[SearchResults::default()], &[key.clone()])
+                .await
+                .unwrap();
+        }
+        // The first search is read again, so the second one is the least recently used.
+        assert!(cache.cached_results_json(&keys[0]).await.is_ok());
+        cache
+            .cache_results(&[SearchResults::default()], &[keys[2].clone()])
+            .await
+            .unwrap();
+
+        assert!(cache.cached_results_json(&keys[0]).await.is_ok());
+        assert!(cache.cached_results_json(&keys[1]).await.is_err());
+        assert!(cache.cached_results_json(&keys[2]).await.is_ok());
+    }
+
+    #[cfg(any(feature = "redis-cache", feature = "memory-cache"))]
+    #[tokio::test]
+    async fn test_stale_cache_entry_is_refetched_below_high_load() {
+        let mut config = Config::parse(true).unwrap();
+        config.caching.min_cached_results = 0;
+        config.caching.high_load_threshold = 100;
+        let mut stale_results = SearchResults::new(
+            vec![SearchResult::new(
+                "Rust Programming Language",
+                "https://www.rust-lang.org/",
+                "A language empowering everyone.",
+                &["duckduckgo"],
+            )],
+            &[],
+        );
+        let search_settings = server_models::Cookie::build(&config.style, Vec::new(), 0);
+        let cache = web::Data::new(SharedCache::new(create_cache(&config).await, 0));
+        let key = cache_key(&config, "rust", 0, 0, &[], Category::General);
+
+        // The cached search within the cache expiry time is served.
+        cache
+            .cache_results(&[stale_results.clone()], &[key.clone()])
+            .await
+            .unwrap();
+        let (search_results, _) = results(
+            &config,
+            &cache,
+            "rust",
+            0,
+            &search_settings,
+            &[],
+            Category::General,
+            CacheUse::Read,
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_results.results.len(), 1);
+
+        // The cached search older than the cache expiry time is only served under high load, so it
+        // is searched again otherwise.
+        stale_results.fetched_at -= u64::from(config.caching.cache_expiry_time);
+        cache.cache_results(&[stale_results], &[key]).await.unwrap();
+        let (search_results, _) = results(
+            &config,
+            &cache,
+            "rust",
+            0,
+            &search_settings,
+            &[],
+            Category::General,
+            CacheUse::Read,
+        )
+        .await
+        .unwrap();
+        assert!(search_results.results.is_empty());
+        assert!(search_results.no_engines_selected);
+    }
+
+    #[test]
+    fn test_prefers_json() {
+        assert!(prefers_json("application/json"));
+        assert!(prefers_json("Application/JSON; charset=utf-8, */*;q=0.1"));
+        assert!(!prefers_json(
+            "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8"
+        ));
+        assert!(!prefers_json("text/html, application/json"));
+        assert!(!prefers_json("*/*"));
+    }
+}
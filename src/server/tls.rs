@@ -0,0 +1,103 @@
+//! This module provides the functionality to load the configured certificate and private key
+//! into the TLS config of the server, so that the website can be served over https without a
+//! reverse proxy in front of it.
+
+use crate::config::server::Tls;
+use rustls::{Certificate, PrivateKey, ServerConfig};
+use rustls_pemfile::Item;
+use std::{
+    fs::File,
+    io::{self, BufReader},
+};
+
+/// A function which builds the TLS config of the server from the configured certificate and
+/// private key.
+///
+/// # Arguments
+///
+/// * `tls` - It takes the TLS config as an argument.
+///
+/// # Errors
+///
+/// Returns an error if the certificate or the private key can not be loaded, or if the private
+/// key is not supported.
+pub fn server_config(tls: &Tls) -> io::Result<ServerConfig> {
+    let certificates = load_certificates(&tls.cert_path)?;
+    let private_key = load_private_key(&tls.key_path)?;
+
+    ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certificates, private_key)
+        .map_err(|error| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Failed to use the TLS certificate and private key: {error}"),
+            )
+        })
+}
+
+/// A helper function which loads the certificate chain from a PEM file.
+///
+/// # Arguments
+///
+/// * `cert_path` - It takes the path of the PEM file as an argument.
+fn load_certificates(cert_path: &str) -> io::Result<Vec<Certificate>> {
+    let certificates = rustls_pemfile::certs(&mut BufReader::new(open(cert_path)?))
+        .map_err(|error| invalid_file(cert_path, error))?;
+
+    if certificates.is_empty() {
+        return Err(invalid_file(cert_path, "no certificate found"));
+    }
+
+    Ok(certificates.into_iter().map(Certificate).collect())
+}
+
+/// A helper function which loads the first private key from a PEM file. The PKCS#8, PKCS#1 (RSA)
+/// and SEC1 (EC) encoded private keys are supported.
+///
+/// # Arguments
+///
+/// * `key_path` - It takes the path of the PEM file as an argument.
+fn load_private_key(key_path: &str) -> io::Result<PrivateKey> {
+    let mut reader = BufReader::new(open(key_path)?);
+
+    loop {
+        match rustls_pemfile::read_one(&mut reader)
+            .map_err(|error| invalid_file(key_path, error))?
+        {
+            Some(Item::PKCS8Key(key) | Item::RSAKey(key) | Item::ECKey(key)) => {
+                return Ok(PrivateKey(key))
+            }
+            Some(_) => continue,
+            None => return Err(invalid_file(key_path, "no private key found")),
+        }
+    }
+}
+
+/// A helper function which opens a TLS file, providing a clear error when it can not be opened.
+///
+/// # Arguments
+///
+/// * `file_path` - It takes the path of the file as an argument.
+fn open(file_path: &str) -> io::Result<File> {
+    File::open(file_path).map_err(|error| {
+        io::Error::new(
+            error.kind(),
+            format!("Failed to open the TLS file `{file_path}`: {error}"),
+        )
+    })
+}
+
+/// A helper function which builds the error for a TLS file which could not be loaded.
+///
+/// # Arguments
+///
+/// * `file_path` - It takes the path of the file as an argument.
+/// * `error` - It takes the reason why the file could not be loaded as an argument.
+fn invalid_file(file_path: &str, error: impl std::fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Failed to load the TLS file `{file_path}`: {error}"),
+    )
+}
@@ -0,0 +1,174 @@
+//! This module provides the translations of the strings of the user interface, which are looked up
+//! in the string table of the language selected for the user and fall back to the English strings
+//! when they are not translated.
+
+use crate::config::{shared::Reloadable, style::Style};
+use std::{collections::HashMap, fs::read_to_string, sync::Arc};
+
+/// The language whose string table provides the strings which are missing from the string tables
+/// of the other languages. Its bundled string table provides every string of the user interface.
+pub const FALLBACK_LANGUAGE: &str = "en";
+
+/// The string tables bundled with the app along with their language.
+const BUNDLED_TRANSLATIONS: [(&str, &str); 2] = [
+    (FALLBACK_LANGUAGE, include_str!("locales/en.json")),
+    ("de", include_str!("locales/de.json")),
+];
+
+/// A static variable which stores the string tables, which are loaded again once the config is
+/// reloaded.
+static TRANSLATIONS: Reloadable<Translations> = Reloadable::new();
+
+/// A named struct which stores the string tables of the user interface keyed by their language.
+#[derive(Debug, Default)]
+pub struct Translations {
+    /// The translated strings keyed by their key, for each language.
+    tables: HashMap<String, HashMap<String, String>>,
+}
+
+impl Translations {
+    /// Parses a string table provided as a json object mapping the keys of the strings to their
+    /// translation and adds its strings to the strings of the language, replacing the strings
+    /// which were already translated.
+    ///
+    /// # Arguments
+    ///
+    /// * `language` - It takes the language of the string table as an argument.
+    /// * `table` - It takes the contents of the string table as an argument.
+    ///
+    /// # Error
+    ///
+    /// Returns the parsing error if the string table is not a json object of strings.
+    pub fn extend(&mut self, language: &str, table: &str) -> Result<(), serde_json::Error> {
+        let table: HashMap<String, String> = serde_json::from_str(table)?;
+        self.tables
+            .entry(language.to_lowercase())
+            .or_default()
+            .extend(table);
+        Ok(())
+    }
+
+    /// Selects the locale of a page, which is the available language most preferred by the
+    /// `Accept-Language` header of the browser, or the default language when the browser does
+    /// not prefer any of the available languages.
+    ///
+    /// # Arguments
+    ///
+    /// * `accept_language` - It takes the value of the `Accept-Language` header, if it is used,
+    ///   as an argument.
+    /// * `default` - It takes the default language configured for the instance as an argument.
+    pub fn locale(&self, accept_language: Option<&str>, default: &str) -> Locale<'_> {
+        let default = default.to_lowercase();
+        let language = accept_language
+            .and_then(|accept_language| self.negotiate(accept_language))
+            .or_else(|| {
+                self.tables
+                    .get_key_value(&default)
+                    .map(|(key, _)| key.as_str())
+            })
+            .unwrap_or(FALLBACK_LANGUAGE);
+        Locale {
+            language,
+            table: self.tables.get(language),
+            fallback: self.tables.get(FALLBACK_LANGUAGE),
+        }
+    }
+
+    /// A helper function which finds the available language most preferred by the value of an
+    /// `Accept-Language` header. The language ranges are tried in the order of their quality, the
+    /// primary language of a range (like `de` for `de-AT`) being tried after the range itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `accept_language` - It takes the value of the `Accept-Language` header as an argument.
+    fn negotiate(&self, accept_language: &str) -> Option<&str> {
+        let mut ranges: Vec<(f32, String)> = accept_language
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let tag = parts.next()?.trim().to_lowercase();
+                let quality = match parts.find_map(|part| part.trim().strip_prefix("q=")) {
+                    Some(quality) => quality.trim().parse().ok()?,
+                    None => 1.0,
+                };
+                (!tag.is_empty() && quality > 0.0).then_some((quality, tag))
+            })
+            .collect();
+        // The sort is stable, so the ranges of the same quality keep their order.
+        ranges.sort_by(|(first, _), (second, _)| second.total_cmp(first));
+
+        ranges.iter().find_map(|(_, tag)| {
+            let primary = tag.split('-').next().unwrap_or(tag);
+            [tag.as_str(), primary]
+                .into_iter()
+                .find_map(|language| self.tables.get_key_value(language))
+                .map(|(language, _)| language.as_str())
+        })
+    }
+}
+
+/// A named struct which provides the translated strings of the user interface in the language
+/// selected for a page.
+#[derive(Debug)]
+pub struct Locale<'a> {
+    /// The language of the page.
+    language: &'a str,
+    /// The string table of the language of the page.
+    table: Option<&'a HashMap<String, String>>,
+    /// The string table providing the strings which are not translated.
+    fallback: Option<&'a HashMap<String, String>>,
+}
+
+impl Locale<'_> {
+    /// Returns the language of the page, like `de`.
+    pub fn language(&self) -> &str {
+        self.language
+    }
+
+    /// Returns the translation of the string with the provided key, falling back to the English
+    /// string and then to the key itself when it is not translated.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - It takes the key of the string as an argument.
+    pub fn text<'a>(&'a self, key: &'a str) -> &'a str {
+        [self.table, self.fallback]
+            .into_iter()
+            .flatten()
+            .find_map(|table| table.get(key))
+            .map_or(key, String::as_str)
+    }
+}
+
+/// Returns the string tables made of the bundled string tables and the configured translation
+/// files, which are loaded on the first call and again after the config was reloaded. The files
+/// which can not be read or parsed are logged and skipped.
+///
+/// # Arguments
+///
+/// * `style` - It takes the style config providing the translation files as an argument.
+/// * `generation` - It takes the generation of the config as an argument.
+pub fn translations(style: &Style, generation: u64) -> Arc<Translations> {
+    TRANSLATIONS.get_or_build(generation, || {
+        let mut translations = Translations::default();
+        for (language, table) in BUNDLED_TRANSLATIONS {
+            if let Err(error) = translations.extend(language, table) {
+                log::error!("Failed to parse the bundled translations of `{language}`: {error}");
+            }
+        }
+        for (language, file_path) in &style.translations {
+            let loaded = read_to_string(file_path)
+                .map_err(|error| error.to_string())
+                .and_then(|table| {
+                    translations
+                        .extend(language, &table)
+                        .map_err(|error| error.to_string())
+                });
+            if let Err(error) = loaded {
+                log::error!("Failed to load the translation file `{file_path}`: {error}");
+                log::error!("Skipping the file");
+            }
+        }
+        translations
+    })
+}
@@ -1,5 +1,6 @@
 //! This module provides other modules to handle both the view and its partials for the `websurfx`
 //! search engine frontend.
 
+pub mod locale;
 mod partials;
 pub mod views;
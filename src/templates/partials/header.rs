@@ -9,24 +9,43 @@ use maud::{html, Markup, PreEscaped, DOCTYPE};
 ///
 /// * `colorscheme` - It takes the colorscheme name as an argument.
 /// * `theme` - It takes the theme name as an argument.
+/// * `animation` - It takes the animation name as an argument.
+/// * `custom_css` - It takes whether the custom css file configured by the operator is linked
+///   after the theme as an argument.
+/// * `base` - It takes an optional base url against which the relative urls of the page are
+///   resolved as an argument.
+/// * `language` - It takes the language of the page as an argument.
 ///
 /// # Returns
 ///
 /// It returns the compiled html markup code for the header as a result.
-pub fn header(colorscheme: &str, theme: &str, animation: &Option<String>) -> Markup {
+pub fn header(
+    colorscheme: &str,
+    theme: &str,
+    animation: &Option<String>,
+    custom_css: bool,
+    base: Option<&str>,
+    language: &str,
+) -> Markup {
     html!(
         (DOCTYPE)
-        html lang="en"
+        html lang=(language);
 
         head{
             title{"Websurfx"}
             meta charset="UTF-8";
             meta name="viewport" content="width=device-width, initial-scale=1";
+            @if let Some(base) = base {
+                base href=(base);
+            }
             link href=(format!("static/colorschemes/{colorscheme}.css")) rel="stylesheet" type="text/css";
             link href=(format!("static/themes/{theme}.css")) rel="stylesheet" type="text/css";
             @if animation.is_some() {
                     link href=(format!("static/animations/{}.css", animation.as_ref().unwrap())) rel="stylesheet" type="text/css";
             }
+            @if custom_css {
+                link href="custom.css" rel="stylesheet" type="text/css";
+            }
         }
 
         (PreEscaped("<body onload=\"getClientSettings()\">"))
@@ -13,6 +13,8 @@ const SAFE_SEARCH_LEVELS_NAME: [&str; 3] = ["None", "Low", "Moderate"];
 ///
 /// * `engine_errors_info` - It takes the engine errors list containing errors for each upstream
 /// search engine which failed to provide results as an argument.
+/// * `omitted_engine_errors` - It takes the number of the engine errors which were left out of
+///   the list as an argument.
 /// * `safe_search_level` - It takes the safe search level with values from 0-2 as an argument.
 /// * `query` - It takes the current search query provided by user as an argument.
 ///
@@ -21,6 +23,7 @@ const SAFE_SEARCH_LEVELS_NAME: [&str; 3] = ["None", "Low", "Moderate"];
 /// It returns the compiled html code for the search bar as a result.
 pub fn search_bar(
     engine_errors_info: &[EngineErrorInfo],
+    omitted_engine_errors: usize,
     safe_search_level: u8,
     query: &str,
 ) -> Markup {
@@ -40,6 +43,11 @@ pub fn search_bar(
                                span class="severity_color" style="background: {{{this.severity_color}}};"{}
                             }
                          }
+                         @if omitted_engine_errors > 0 {
+                            .error_item.omitted_errors{
+                               span class="engine_name"{(format!("+{omitted_engine_errors} more"))}
+                            }
+                         }
                       }
                    }
                    @else {
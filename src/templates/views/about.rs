@@ -2,7 +2,10 @@
 
 use maud::{html, Markup};
 
-use crate::templates::partials::{footer::footer, header::header};
+use crate::templates::{
+    locale::FALLBACK_LANGUAGE,
+    partials::{footer::footer, header::header},
+};
 
 /// A function that handles the html code for the about page view in the search engine frontend.
 ///
@@ -10,13 +13,21 @@ use crate::templates::partials::{footer::footer, header::header};
 ///
 /// * `colorscheme` - It takes the colorscheme name as an argument.
 /// * `theme` - It takes the theme name as an argument.
+/// * `animation` - It takes the animation name as an argument.
+/// * `custom_css` - It takes whether the custom css file configured by the operator is linked as
+///   an argument.
 ///
 /// # Returns
 ///
 /// It returns the compiled html markup code as a result.
-pub fn about(colorscheme: &str, theme: &str, animation: &Option<String>) -> Markup {
+pub fn about(
+    colorscheme: &str,
+    theme: &str,
+    animation: &Option<String>,
+    custom_css: bool,
+) -> Markup {
     html!(
-        (header(colorscheme, theme, animation))
+        (header(colorscheme, theme, animation, custom_css, None, FALLBACK_LANGUAGE))
         main class="about-container"{
          article {
              div{
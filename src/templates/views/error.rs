@@ -0,0 +1,50 @@
+//! A module that handles the view for the error page in the `websurfx` frontend.
+
+use crate::templates::{
+    locale::FALLBACK_LANGUAGE,
+    partials::{footer::footer, header::header},
+};
+use actix_web::http::StatusCode;
+use maud::{html, Markup};
+
+/// A function that handles the html code for the error page view in the search engine frontend,
+/// which is displayed when a search fails.
+///
+/// # Arguments
+///
+/// * `colorscheme` - It takes the colorscheme name as an argument.
+/// * `theme` - It takes the theme name as an argument.
+/// * `animation` - It takes the animation name as an argument.
+/// * `custom_css` - It takes whether the custom css file configured by the operator is linked as
+///   an argument.
+/// * `base` - It takes an optional base url against which the relative urls of the page are
+///   resolved as an argument.
+/// * `status` - It takes the http status of the error as an argument.
+/// * `message` - It takes the message describing the error to the user as an argument.
+///
+/// # Returns
+///
+/// It returns the compiled html markup code as a result.
+pub fn error(
+    colorscheme: &str,
+    theme: &str,
+    animation: &Option<String>,
+    custom_css: bool,
+    base: Option<&str>,
+    status: StatusCode,
+    message: &str,
+) -> Markup {
+    html!(
+        (header(colorscheme, theme, animation, custom_css, base, FALLBACK_LANGUAGE))
+        main class="error_container"{
+         img src="images/robot-404.svg" alt="Image of broken robot.";
+         .error_content{
+          h1{"Aw! snap"}
+          h2{(status.as_u16())" "(status.canonical_reason().unwrap_or_default())}
+          p{(message)}
+          p{"Go to "{a href="/"{"search page"}}}
+         }
+        }
+        (footer())
+    )
+}
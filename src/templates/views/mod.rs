@@ -2,6 +2,7 @@
 //! `websurfx` frontend.
 
 pub mod about;
+pub mod error;
 pub mod index;
 pub mod not_found;
 pub mod search;
@@ -1,6 +1,9 @@
 //! A module that handles the view for the 404 page in the `websurfx` frontend.
 
-use crate::templates::partials::{footer::footer, header::header};
+use crate::templates::{
+    locale::FALLBACK_LANGUAGE,
+    partials::{footer::footer, header::header},
+};
 use maud::{html, Markup};
 
 /// A function that handles the html code for the 404 page view in the search engine frontend.
@@ -9,13 +12,21 @@ use maud::{html, Markup};
 ///
 /// * `colorscheme` - It takes the colorscheme name as an argument.
 /// * `theme` - It takes the theme name as an argument.
+/// * `animation` - It takes the animation name as an argument.
+/// * `custom_css` - It takes whether the custom css file configured by the operator is linked as
+///   an argument.
 ///
 /// # Returns
 ///
 /// It returns the compiled html markup code as a result.
-pub fn not_found(colorscheme: &str, theme: &str, animation: &Option<String>) -> Markup {
+pub fn not_found(
+    colorscheme: &str,
+    theme: &str,
+    animation: &Option<String>,
+    custom_css: bool,
+) -> Markup {
     html!(
-        (header(colorscheme, theme, animation))
+        (header(colorscheme, theme, animation, custom_css, None, FALLBACK_LANGUAGE))
         main class="error_container"{
          img src="images/robot-404.svg" alt="Image of broken robot.";
          .error_content{
@@ -1,62 +1,151 @@
 //! A module that handles the view for the search page in the `websurfx` frontend.
 
-use maud::{html, Markup, PreEscaped};
+use std::{
+    borrow::Cow,
+    fmt::Write,
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use maud::{html, Escaper, Markup, PreEscaped};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use regex::Regex;
+use reqwest::Url;
 
 use crate::{
-    models::aggregation_models::SearchResults,
-    templates::partials::{footer::footer, header::header, search_bar::search_bar},
+    config::style::{DisplayUrl, IdnHosts, ResultsView, Style},
+    models::{
+        aggregation_models::{Answer, AnswerContent, FeaturedSnippet, SearchResult, SearchResults},
+        engine_models::Category,
+    },
+    results::favicons::favicon_token,
+    server::click_redirect::redirect_url,
+    templates::{
+        locale::Locale,
+        partials::{footer::footer, header::header, search_bar::search_bar},
+    },
 };
 
+/// The names of the templates which can be used to render the search results. The first one is
+/// the default template which is used when an unknown template name is requested.
+pub const RESULT_TEMPLATES: [&str; 2] = ["default", "compact"];
+
+/// The names of the categories of search results which can be selected on the search page along
+/// with the corresponding categories.
+const CATEGORIES: [(&str, Category); 2] =
+    [("general", Category::General), ("news", Category::News)];
+
 /// A function that handles the html code for the search page view in the search engine frontend.
 ///
 /// # Arguments
 ///
-/// * `colorscheme` - It takes the colorscheme name as an argument.
-/// * `theme` - It takes the theme name as an argument.
+/// * `style` - It takes the style related config options (like the theme and the colorscheme) as
+///   an argument.
+/// * `locale` - It takes the locale providing the translated strings of the page as an argument.
 /// * `query` - It takes the current search query provided by the user as an argument.
 /// * `search_results` - It takes the aggregated search results as an argument.
+/// * `template` - It takes the name of the template used to render the search results as an
+///   argument.
+/// * `view` - It takes the view in which the general search results are displayed as an
+///   argument.
+/// * `category` - It takes the requested category of the search results as an argument.
+/// * `base` - It takes an optional base url against which the relative urls of the page are
+///   resolved as an argument.
 ///
 /// # Returns
 ///
 /// It returns the compiled html markup code as a result.
+#[allow(clippy::too_many_arguments)]
 pub fn search(
-    colorscheme: &str,
-    theme: &str,
-    animation: &Option<String>,
+    style: &Style,
+    locale: &Locale<'_>,
     query: &str,
     search_results: &SearchResults,
+    template: &str,
+    view: ResultsView,
+    category: Category,
+    base: Option<&str>,
 ) -> Markup {
+    let query_terms = style
+        .highlight_query_terms
+        .then(|| query_terms_regex(query))
+        .flatten();
+    let user_query = html!(span class="user_query"{(query)});
+
     html!(
-        (header(colorscheme, theme, animation))
+        (header(&style.colorscheme, &style.theme, &style.animation, style.custom_css.is_some(), base, locale.language()))
         main class="results"{
-           (search_bar(&search_results.engine_errors_info, search_results.safe_search_level, query))
+           (search_bar(&search_results.engine_errors_info, search_results.omitted_engine_errors, search_results.safe_search_level, query))
+           .search_categories{
+              @for (name, value) in CATEGORIES {
+                 button type="button" class=[(category == value).then_some("active")]
+                    onclick=(format!("navigate_category('{name}')")){(name)}
+              }
+           }
            .results_aggregated{
+              @if search_results.category_fallback {
+                 .results_degraded{
+                    p{(locale.text("notice.category_fallback"))}
+                 }
+              }
+              @if search_results.degraded {
+                 .results_degraded{
+                    p{(locale.text("notice.degraded"))}
+                 }
+              }
+              @if !search_results.dropped_engines.is_empty() {
+                 .results_degraded{
+                    p{
+                       (locale.text("notice.dropped_engines")
+                          .replace("{engines}", &search_results.dropped_engines.join(", ")))
+                    }
+                 }
+              }
+              @if !search_results.ignored_engines.is_empty() {
+                 .results_degraded{
+                    p{
+                       (locale.text("notice.ignored_engines")
+                          .replace("{engines}", &search_results.ignored_engines.join(", ")))
+                    }
+                 }
+              }
+              @if !search_results.denied_engines.is_empty() {
+                 .results_degraded{
+                    p{
+                       (locale.text("notice.denied_engines")
+                          .replace("{engines}", &search_results.denied_engines.join(", ")))
+                    }
+                 }
+              }
+              @if search_results.stale {
+                 .results_degraded{
+                    p{(locale.text("notice.stale"))}
+                 }
+              }
+              @if let Some(refinement) = &search_results.refinement {
+                 .results_degraded{
+                    p{
+                       (with_placeholder(locale.text("notice.refinement"), "{refinement}", html!(q{(refinement)})))
+                    }
+                 }
+              }
+              @if search_results.timed_out {
+                 .results_degraded{
+                    p{(locale.text("notice.timed_out"))}
+                 }
+              }
+              (answers(&search_results.answers))
+              (featured_snippet(search_results.featured_snippet.as_ref(), style))
               @if !search_results.results.is_empty() {
-                  @for result in search_results.results.iter(){
-                      .result {
-                         h1{a href=(result.url){(PreEscaped(&result.title))}}
-                         small{(result.url)}
-                         p{(PreEscaped(&result.description))}
-                         .upstream_engines{
-                            @for name in result.clone().engine{
-                               span{(name)}
-                            }
-                         }
-                      }
-                  }
+                  (category_results(search_results, style, template, view, category, query_terms.as_ref()))
               }
               @else if search_results.disallowed{
                  .result_disallowed{
                     .description{
-                       p{
-                          "Your search - "{span class="user_query"{(query)}}" -
-                          has been disallowed."
-                       }
-                       p class="description_paragraph"{"Dear user,"}
+                       p{(with_placeholder(locale.text("disallowed.title"), "{query}", user_query.clone()))}
+                       p class="description_paragraph"{(locale.text("dear_user"))}
                        p class="description_paragraph"{
-                          "The query - "{span class="user_query"{(query)}}" - has
-                          been blacklisted via server configuration and hence disallowed by the
-                          server. Henceforth no results could be displayed for your query."
+                          (with_placeholder(locale.text("disallowed.description"), "{query}", user_query.clone()))
                        }
                     }
                     img src="./images/barricade.png" alt="Image of a Barricade";
@@ -65,16 +154,9 @@ pub fn search(
               @else if search_results.filtered {
                  .result_filtered{
                     .description{
-                       p{
-                          "Your search - "{span class="user_query"{(query)}}" -
-                          has been filtered."
-                       }
-                       p class="description_paragraph"{"Dear user,"}
-                       p class="description_paragraph"{
-                          "All the search results contain results that has been configured to be
-                          filtered out via server configuration and henceforth has been
-                          completely filtered out."
-                       }
+                       p{(with_placeholder(locale.text("filtered.title"), "{query}", user_query.clone()))}
+                       p class="description_paragraph"{(locale.text("dear_user"))}
+                       p class="description_paragraph"{(locale.text("filtered.description"))}
                     }
                     img src="./images/filter.png" alt="Image of a paper inside a funnel";
                  }
@@ -82,26 +164,26 @@ pub fn search(
               @else if search_results.no_engines_selected {
                  .result_engine_not_selected{
                     .description{
-                       p{
-                          "No results could be fetched for your search '{span class="user_query"{(query)}}'."
-                       }
-                       p class="description_paragraph"{"Dear user,"}
-                       p class="description_paragraph"{
-                          "No results could be retrieved from the upstream search engines as no
-                          upstream search engines were selected from the settings page."
-                       }
+                       p{(with_placeholder(locale.text("no_engines.title"), "{query}", user_query.clone()))}
+                       p class="description_paragraph"{(locale.text("dear_user"))}
+                       p class="description_paragraph"{(locale.text("no_engines.description"))}
                     }
                     img src="./images/no_selection.png" alt="Image of a white cross inside a red circle";
                  }
               }
+              @else if search_results.upstream_failure {
+                 (upstream_failure(style, locale, query, category))
+              }
               @else {
                  .result_not_found {
-                    p{"Your search - "{(query)}" - did not match any documents."}
-                    p class="suggestions"{"Suggestions:"}
-                    ul{
-                       li{"Make sure that all words are spelled correctly."}
-                       li{"Try different keywords."}
-                       li{"Try more general keywords."}
+                    p{(with_placeholder(locale.text("no_results.title"), "{query}", html!((query))))}
+                    @if !style.search_tips.tips.is_empty() {
+                       p class="suggestions"{(style.search_tips.heading)}
+                       ul{
+                          @for tip in &style.search_tips.tips {
+                             li{(tip)}
+                          }
+                       }
                     }
                     img src="./images/no_results.gif" alt="Man fishing gif";
                  }
@@ -109,15 +191,822 @@ pub fn search(
             }
             .page_navigation {
                button type="button" onclick="navigate_backward()"{
-                   (PreEscaped("&#8592;")) "previous"
+                   (PreEscaped("&#8592;")) (locale.text("navigation.previous"))
+               }
+               button type="button" onclick="navigate_forward()"{
+                   (locale.text("navigation.next")) (PreEscaped("&#8594;"))
                }
-               button type="button" onclick="navigate_forward()"{"next" (PreEscaped("&#8594;"))}
             }
         }
         script src="static/index.js"{}
         script src="static/search_area_options.js"{}
         script src="static/pagination.js"{}
+        script src="static/categories.js"{}
         script src="static/error_box.js"{}
         (footer())
     )
 }
+
+/// A function that handles the html code for the failure state of the search page, which is
+/// displayed when all the queried upstream search engines failed. It explains the outage and
+/// offers a retry link, which reloads the search page when scripts are enabled and searches the
+/// query again from the first page otherwise.
+///
+/// # Arguments
+///
+/// * `style` - It takes the style related config options (like the custom explanation of the
+///   outage) as an argument.
+/// * `locale` - It takes the locale providing the translated strings of the page as an argument.
+/// * `query` - It takes the current search query provided by the user as an argument.
+/// * `category` - It takes the requested category of the search results as an argument.
+///
+/// # Returns
+///
+/// It returns the compiled html markup code as a result.
+fn upstream_failure(style: &Style, locale: &Locale<'_>, query: &str, category: Category) -> Markup {
+    let mut retry_url = format!("search?q={}", utf8_percent_encode(query, NON_ALPHANUMERIC));
+    if category != Category::General {
+        if let Some((name, _)) = CATEGORIES.iter().find(|(_, value)| *value == category) {
+            retry_url.push_str(&format!("&category={name}"));
+        }
+    }
+
+    html!(
+        .result_upstream_failure{
+           .description{
+              p{
+                 (with_placeholder(
+                    locale.text("upstream_failure.title"),
+                    "{query}",
+                    html!(span class="user_query"{(query)}),
+                 ))
+              }
+              p class="description_paragraph"{(locale.text("dear_user"))}
+              p class="description_paragraph"{
+                 @match &style.outage_message {
+                    Some(message) => (message),
+                    None => (locale.text("upstream_failure.description")),
+                 }
+              }
+              a class="retry" href=(retry_url) onclick="window.location.reload(); return false;"{
+                 (locale.text("upstream_failure.retry"))
+              }
+           }
+           img src="./images/no_selection.png" alt="Image of a white cross inside a red circle";
+        }
+    )
+}
+
+/// A helper function which renders a translated string, replacing its placeholder with the
+/// provided markup, like the search query highlighted within a sentence.
+///
+/// # Arguments
+///
+/// * `text` - It takes the translated string as an argument.
+/// * `placeholder` - It takes the placeholder within the string, like `{query}`, as an argument.
+/// * `value` - It takes the markup replacing the placeholder as an argument.
+///
+/// # Returns
+///
+/// It returns the compiled html markup code as a result, which is the string as it is when it
+/// has no placeholder.
+fn with_placeholder(text: &str, placeholder: &str, value: Markup) -> Markup {
+    match text.split_once(placeholder) {
+        Some((before, after)) => html!((before)(value)(after)),
+        None => html!((text)),
+    }
+}
+
+/// A function that dispatches the search results to the layout of their category, the general
+/// search results are used as the fallback layout.
+///
+/// # Arguments
+///
+/// * `search_results` - It takes the aggregated search results as an argument.
+/// * `style` - It takes the style related config options as an argument.
+/// * `template` - It takes the name of the template used to render each search result as an
+///   argument.
+/// * `view` - It takes the view in which the general search results are displayed as an
+///   argument.
+/// * `category` - It takes the requested category of the search results as an argument.
+/// * `query_terms` - It takes the regex matching the terms of the search query which are
+///   highlighted, if the highlighting is enabled, as an argument.
+///
+/// # Returns
+///
+/// It returns the compiled html markup code as a result.
+fn category_results(
+    search_results: &SearchResults,
+    style: &Style,
+    template: &str,
+    view: ResultsView,
+    category: Category,
+    query_terms: Option<&Regex>,
+) -> Markup {
+    // The general search results are provided instead when the requested category could not be
+    // searched.
+    match (category, search_results.category_fallback) {
+        (Category::News, false) => {
+            news_results(&search_results.results, style, template, query_terms)
+        }
+        _ if view == ResultsView::Engines && !search_results.engine_groups.is_empty() => {
+            engine_tabs(search_results, style, template, query_terms)
+        }
+        _ => general_results(&search_results.results, style, template, query_terms),
+    }
+}
+
+/// A function that handles the html code for the general search results grouped by the upstream
+/// search engines which provided them, with a tab for each engine showing its results in the
+/// order in which it returned them. The pinned results are displayed above the tabs.
+///
+/// # Arguments
+///
+/// * `search_results` - It takes the aggregated search results as an argument.
+/// * `style` - It takes the style related config options as an argument.
+/// * `template` - It takes the name of the template used to render each search result as an
+///   argument.
+/// * `query_terms` - It takes the regex matching the terms of the search query which are
+///   highlighted, if the highlighting is enabled, as an argument.
+///
+/// # Returns
+///
+/// It returns the compiled html markup code as a result.
+fn engine_tabs(
+    search_results: &SearchResults,
+    style: &Style,
+    template: &str,
+    query_terms: Option<&Regex>,
+) -> Markup {
+    let results = &search_results.results;
+    html!(
+        (general_results(results.iter().filter(|result| result.pinned), style, template, query_terms))
+        .engine_tabs{
+           @for (index, group) in search_results.engine_groups.iter().enumerate() {
+              // The indices beyond the results are skipped, as the cached copies of the search
+              // results may have been trimmed.
+              @let group_results: Vec<&SearchResult> =
+                 group.results.iter().filter_map(|&index| results.get(index)).collect();
+              input type="radio" name="engine_tab" id=(format!("engine_tab_{index}")) checked[index == 0];
+              label for=(format!("engine_tab_{index}")){(group.engine)" ("(group_results.len())")"}
+              .engine_tab{
+                 (general_results(group_results, style, template, query_terms))
+              }
+           }
+        }
+    )
+}
+
+/// A function that handles the html code for the general search results, which are displayed as
+/// a list in their ranked order.
+///
+/// # Arguments
+///
+/// * `results` - It takes the search results to be displayed as an argument.
+/// * `style` - It takes the style related config options as an argument.
+/// * `template` - It takes the name of the template used to render each search result as an
+///   argument.
+/// * `query_terms` - It takes the regex matching the terms of the search query which are
+///   highlighted, if the highlighting is enabled, as an argument.
+///
+/// # Returns
+///
+/// It returns the compiled html markup code as a result.
+fn general_results<'a>(
+    results: impl IntoIterator<Item = &'a SearchResult>,
+    style: &Style,
+    template: &str,
+    query_terms: Option<&Regex>,
+) -> Markup {
+    html!(
+        @for result in results {
+            @match template {
+               "compact" => (compact_result(result, style, query_terms)),
+               _ => (default_result(result, style, query_terms)),
+            }
+        }
+    )
+}
+
+/// A function that handles the html code for the news search results, which are displayed as a
+/// timeline grouped by the day (in UTC) on which the news articles were published. The news
+/// articles without a publish time are grouped last.
+///
+/// # Arguments
+///
+/// * `results` - It takes the news results sorted by their publish time as an argument.
+/// * `style` - It takes the style related config options as an argument.
+/// * `template` - It takes the name of the template used to render each news result as an
+///   argument.
+/// * `query_terms` - It takes the regex matching the terms of the search query which are
+///   highlighted, if the highlighting is enabled, as an argument.
+///
+/// # Returns
+///
+/// It returns the compiled html markup code as a result.
+fn news_results(
+    results: &[SearchResult],
+    style: &Style,
+    template: &str,
+    query_terms: Option<&Regex>,
+) -> Markup {
+    let today = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs() / 86400);
+
+    let mut days: Vec<(Option<u64>, Vec<&SearchResult>)> = Vec::new();
+    for result in results {
+        let day = result.published.map(|published| published / 86400);
+        match days.iter_mut().find(|(group_day, _)| *group_day == day) {
+            Some((_, day_results)) => day_results.push(result),
+            None => days.push((day, vec![result])),
+        }
+    }
+    days.sort_by_key(|(day, _)| day.map_or(u64::MAX, |day| today.saturating_sub(day)));
+
+    html!(
+        @for (day, day_results) in days {
+            .timeline_day{
+               h2{
+                  @match day.map(|day| today.saturating_sub(day)) {
+                     Some(0) => "Today",
+                     Some(1) => "Yesterday",
+                     Some(days_ago) => (format!("{days_ago} days ago")),
+                     None => "Undated",
+                  }
+               }
+               (general_results(day_results, style, template, query_terms))
+            }
+        }
+    )
+}
+
+/// A function that handles the html code for a single search result in the default template,
+/// which displays the title, the url and the description of the result.
+///
+/// # Arguments
+///
+/// * `result` - It takes the search result to be displayed as an argument.
+/// * `style` - It takes the style related config options (like whether the badges of the
+///   upstream engines are displayed and the format of the displayed url) as an argument.
+/// * `query_terms` - It takes the regex matching the terms of the search query which are
+///   highlighted, if the highlighting is enabled, as an argument.
+///
+/// # Returns
+///
+/// It returns the compiled html markup code as a result.
+fn default_result(result: &SearchResult, style: &Style, query_terms: Option<&Regex>) -> Markup {
+    html!(
+        .result {
+           h1{
+              (favicon(&result.url, style))
+              a href=(result_link(&result.url, style)) rel=[style.no_referrer.then_some("noreferrer")]{
+                 (PreEscaped(highlight(&result.title, query_terms)))
+              }
+           }
+           (news_details(result))
+           small{(result_url(&result.url, style))}
+           (cached_link(result, style))
+           (thumbnail(result))
+           p{(PreEscaped(highlight(&result.description, query_terms)))}
+           (ranking(result))
+           (upstream_engines(result, style.engine_badges))
+        }
+    )
+}
+
+/// A function that handles the html code for a single search result in the compact template,
+/// which leaves out the description of the result so that more results fit on the page.
+///
+/// # Arguments
+///
+/// * `result` - It takes the search result to be displayed as an argument.
+/// * `style` - It takes the style related config options (like whether the badges of the
+///   upstream engines are displayed and the format of the displayed url) as an argument.
+/// * `query_terms` - It takes the regex matching the terms of the search query which are
+///   highlighted, if the highlighting is enabled, as an argument.
+///
+/// # Returns
+///
+/// It returns the compiled html markup code as a result.
+fn compact_result(result: &SearchResult, style: &Style, query_terms: Option<&Regex>) -> Markup {
+    html!(
+        .result.compact {
+           h1{
+              (favicon(&result.url, style))
+              a href=(result_link(&result.url, style)) rel=[style.no_referrer.then_some("noreferrer")]{
+                 (PreEscaped(highlight(&result.title, query_terms)))
+              }
+           }
+           (news_details(result))
+           small{(result_url(&result.url, style))}
+           (cached_link(result, style))
+           (ranking(result))
+           (upstream_engines(result, style.engine_badges))
+        }
+    )
+}
+
+/// A helper function which builds the regex matching the terms of the search query, which are
+/// html escaped first so that they match the html of the titles and the descriptions of the
+/// search results. It returns `None` if the query has no terms.
+///
+/// # Arguments
+///
+/// * `query` - It takes the search query provided by the user as an argument.
+fn query_terms_regex(query: &str) -> Option<Regex> {
+    let mut terms: Vec<String> = query
+        .split_whitespace()
+        .map(|term| {
+            let mut escaped_term = String::new();
+            let _ = Escaper::new(&mut escaped_term).write_str(term);
+            regex::escape(&escaped_term)
+        })
+        .collect();
+    if terms.is_empty() {
+        return None;
+    }
+
+    // The longer terms are matched first, so that a term which is part of another term does not
+    // cut the highlighting of the longer term short.
+    terms.sort_by_key(|term| std::cmp::Reverse(term.len()));
+    terms.dedup();
+    Regex::new(&format!("(?i){}", terms.join("|"))).ok()
+}
+
+/// A helper function which wraps the terms of the search query in `<mark>` tags within the html
+/// of a title or a description of a search result. Only the text between the html tags and the
+/// character references is highlighted, so the markup itself is never altered. The html is
+/// returned unchanged when the highlighting is disabled.
+///
+/// # Arguments
+///
+/// * `html` - It takes the html of the title or the description as an argument.
+/// * `query_terms` - It takes the regex matching the terms of the search query, if the
+///   highlighting is enabled, as an argument.
+fn highlight<'a>(html: &'a str, query_terms: Option<&Regex>) -> Cow<'a, str> {
+    let Some(query_terms) = query_terms else {
+        return Cow::Borrowed(html);
+    };
+
+    static MARKUP: OnceLock<Regex> = OnceLock::new();
+    let markup = MARKUP.get_or_init(|| Regex::new(r"<[^>]*>|&[#0-9A-Za-z]+;").unwrap());
+
+    let mut highlighted = String::with_capacity(html.len());
+    let mut last_match = 0;
+    for tag in markup.find_iter(html) {
+        highlighted
+            .push_str(&query_terms.replace_all(&html[last_match..tag.start()], "<mark>$0</mark>"));
+        highlighted.push_str(tag.as_str());
+        last_match = tag.end();
+    }
+    highlighted.push_str(&query_terms.replace_all(&html[last_match..], "<mark>$0</mark>"));
+    Cow::Owned(highlighted)
+}
+
+/// A function that handles the html code for the url of a search result displayed below its
+/// title, along with a warning when its host is suspicious.
+///
+/// # Arguments
+///
+/// * `url` - It takes the url of the search result as an argument.
+/// * `style` - It takes the style related config options (like the format of the displayed url
+///   and how the internationalized hosts are displayed) as an argument.
+///
+/// # Returns
+///
+/// It returns the compiled html markup code as a result.
+fn result_url(url: &str, style: &Style) -> Markup {
+    let (displayed_url, suspicious) = display_url(url, style.display_url, style.idn_hosts);
+    html!(
+        (displayed_url)
+        @if suspicious {
+           span class="idn_warning"
+             title="The host mixes the letters of different scripts and may imitate the host of another website, so it is displayed in its punycode form."
+             {"⚠ mixed scripts"}
+        }
+    )
+}
+
+/// The maximum number of characters of the path which is displayed in the short url format.
+const SHORT_URL_PATH_LENGTH: usize = 30;
+
+/// A helper function which formats the url of a search result for display below its title in the
+/// configured format, displaying its host in the configured form. The urls which could not be
+/// parsed are displayed as is.
+///
+/// # Arguments
+///
+/// * `url` - It takes the url of the search result as an argument.
+/// * `format` - It takes the format in which the url is displayed as an argument.
+/// * `idn_hosts` - It takes how the internationalized hosts are displayed as an argument.
+///
+/// # Returns
+///
+/// Returns the displayed url along with whether its host was annotated as suspicious.
+fn display_url(url: &str, format: DisplayUrl, idn_hosts: IdnHosts) -> (String, bool) {
+    let parsed_url = match Url::parse(url) {
+        Ok(parsed_url) => parsed_url,
+        Err(_) => return (url.to_owned(), false),
+    };
+    let ascii_host = parsed_url.host_str().unwrap_or_default();
+    let unicode_host = unicode_host(ascii_host);
+    let suspicious = idn_hosts == IdnHosts::Annotate && is_mixed_script(&unicode_host);
+    let host: &str = match (idn_hosts, suspicious) {
+        (IdnHosts::Punycode, _) | (_, true) => ascii_host,
+        _ => &unicode_host,
+    };
+    let path = parsed_url.path().trim_matches('/');
+
+    let displayed_url = match format {
+        // The host is replaced in the url as provided by the upstream engine, which may contain
+        // the host in either of its forms.
+        DisplayUrl::Full => match [ascii_host, &unicode_host]
+            .into_iter()
+            .find(|raw_host| url.contains(raw_host))
+        {
+            Some(raw_host) => url.replacen(raw_host, host, 1),
+            None => url.to_owned(),
+        },
+        DisplayUrl::Breadcrumb => std::iter::once(host)
+            .chain(path.split('/').filter(|segment| !segment.is_empty()))
+            .collect::<Vec<_>>()
+            .join(" › "),
+        _ if path.chars().count() > SHORT_URL_PATH_LENGTH => {
+            let path: String = path.chars().take(SHORT_URL_PATH_LENGTH).collect();
+            format!("{host}/{path}…")
+        }
+        _ if path.is_empty() => host.to_owned(),
+        _ => format!("{host}/{path}"),
+    };
+    (displayed_url, suspicious)
+}
+
+/// A helper function which decodes the punycode labels of a host into their unicode form, the
+/// hosts without punycode labels are returned as is.
+///
+/// # Arguments
+///
+/// * `ascii_host` - It takes the host in its punycode form as an argument.
+fn unicode_host(ascii_host: &str) -> Cow<'_, str> {
+    match ascii_host.split('.').any(|label| label.starts_with("xn--")) {
+        true => Cow::Owned(idna::domain_to_unicode(ascii_host).0),
+        false => Cow::Borrowed(ascii_host),
+    }
+}
+
+/// The scripts whose letters look alike and are mixed within the hosts crafted to imitate the
+/// hosts of other websites.
+#[derive(PartialEq)]
+enum Script {
+    /// The latin script.
+    Latin,
+    /// The greek script.
+    Greek,
+    /// The cyrillic script.
+    Cyrillic,
+}
+
+/// A helper function which returns the script of a letter, or `None` if the letter is not part of
+/// one of the lookalike scripts (like digits, hyphens or the letters of the other scripts).
+///
+/// # Arguments
+///
+/// * `letter` - It takes the letter as an argument.
+fn script(letter: char) -> Option<Script> {
+    match letter {
+        'a'..='z' | 'A'..='Z' | '\u{00C0}'..='\u{024F}' | '\u{1E00}'..='\u{1EFF}' => {
+            Some(Script::Latin)
+        }
+        '\u{0370}'..='\u{03FF}' | '\u{1F00}'..='\u{1FFF}' => Some(Script::Greek),
+        '\u{0400}'..='\u{052F}' | '\u{2DE0}'..='\u{2DFF}' | '\u{A640}'..='\u{A69F}' => {
+            Some(Script::Cyrillic)
+        }
+        _ => None,
+    }
+}
+
+/// A helper function which checks whether any of the labels of a host mixes the letters of
+/// different lookalike scripts, like a cyrillic `а` within an otherwise latin label.
+///
+/// # Arguments
+///
+/// * `host` - It takes the host in its unicode form as an argument.
+fn is_mixed_script(host: &str) -> bool {
+    host.split('.').any(|label| {
+        let mut scripts = label.chars().filter_map(script);
+        match scripts.next() {
+            Some(first) => scripts.any(|script| script != first),
+            None => false,
+        }
+    })
+}
+
+/// A function that handles the html code for the link to the cached copy of a search result on
+/// the configured archive service, no markup is emitted when the cached links are disabled.
+///
+/// # Arguments
+///
+/// * `result` - It takes the search result whose cached copy is linked as an argument.
+/// * `style` - It takes the style related config options (like the url of the archive service)
+///   as an argument.
+///
+/// # Returns
+///
+/// It returns the compiled html markup code as a result.
+fn cached_link(result: &SearchResult, style: &Style) -> Markup {
+    html!(
+        @if style.cached_link.enabled {
+           a class="cached_link"
+             href=(style.cached_link.url.replace("{url}", &result.url))
+             rel=[style.no_referrer.then_some("noreferrer")]{"cached"}
+        }
+    )
+}
+
+/// A helper function which provides the url the link of a search result points to, which is the
+/// url of the click-through redirect when it is enabled or the url of the result otherwise.
+///
+/// # Arguments
+///
+/// * `url` - It takes the url of the search result as an argument.
+/// * `style` - It takes the parsed style config which provides the click-through redirect options
+///   as an argument.
+fn result_link<'a>(url: &'a str, style: &Style) -> Cow<'a, str> {
+    match style.click_redirect.enabled {
+        true => Cow::Owned(redirect_url(url, &style.click_redirect)),
+        false => Cow::Borrowed(url),
+    }
+}
+
+/// A function that handles the html code for the featured snippet extracted from the page of the
+/// top ranked search result, which is displayed above the search results. No markup is emitted
+/// when there is no featured snippet.
+///
+/// # Arguments
+///
+/// * `snippet` - It takes the featured snippet, if any, as an argument.
+/// * `style` - It takes the parsed style config which provides the referrer option as an argument.
+///
+/// # Returns
+///
+/// It returns the compiled html markup code as a result.
+fn featured_snippet(snippet: Option<&FeaturedSnippet>, style: &Style) -> Markup {
+    html!(
+        @if let Some(snippet) = snippet {
+           .featured_snippet{
+              p{(snippet.text)}
+              a href=(result_link(&snippet.url, style)) rel=[style.no_referrer.then_some("noreferrer")]{(PreEscaped(&snippet.title))}
+              small{(snippet.url)}
+           }
+        }
+    )
+}
+
+/// A function that handles the html code for the structured answers provided by the upstream
+/// search engines, which are displayed above the search results. No markup is emitted when no
+/// answers are provided.
+///
+/// # Arguments
+///
+/// * `answers` - It takes the structured answers as an argument.
+///
+/// # Returns
+///
+/// It returns the compiled html markup code as a result.
+fn answers(answers: &[Answer]) -> Markup {
+    html!(
+        @for answer in answers {
+           .answer{
+              @match &answer.content {
+                 AnswerContent::Definition { word, part_of_speech, definition } => {
+                    h3{
+                       (word)
+                       @if let Some(part_of_speech) = part_of_speech {
+                          " " em{(part_of_speech)}
+                       }
+                    }
+                    p{(definition)}
+                 }
+                 AnswerContent::Weather { location, temperature, condition } => {
+                    h3{(location)}
+                    p{span class="answer_temperature"{(temperature)} " " (condition)}
+                 }
+              }
+              small{"Answer provided by " (answer.engine)}
+           }
+        }
+    )
+}
+
+/// A function that handles the html code for the publisher and the publish time of a news result,
+/// no markup is emitted for the other results.
+///
+/// # Arguments
+///
+/// * `result` - It takes the search result whose news details are displayed as an argument.
+///
+/// # Returns
+///
+/// It returns the compiled html markup code as a result.
+fn news_details(result: &SearchResult) -> Markup {
+    html!(
+        @if result.source.is_some() || result.published.is_some() {
+           .news_details{
+              @if let Some(source) = &result.source {
+                 span class="news_source"{(source)}
+              }
+              @if let Some(published) = result.published {
+                 span class="news_published"{(published_age(published))}
+              }
+           }
+        }
+    )
+}
+
+/// A helper function which describes how long ago a news article was published, for example
+/// `3 hours ago`.
+///
+/// # Arguments
+///
+/// * `published` - It takes the publish time of the news article as a unix timestamp.
+fn published_age(published: u64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(published, |now| now.as_secs());
+    let age = now.saturating_sub(published);
+    let (amount, unit) = match age {
+        0..=59 => return "just now".to_owned(),
+        60..=3599 => (age / 60, "minute"),
+        3600..=86399 => (age / 3600, "hour"),
+        _ => (age / 86400, "day"),
+    };
+    let plural = if amount == 1 { "" } else { "s" };
+    format!("{amount} {unit}{plural} ago")
+}
+
+/// A function that handles the html code for the favicon of the website of a search result,
+/// which is fetched through the favicon proxy when the favicons are enabled. The favicon is hidden
+/// when the proxy provides no icon for the website.
+///
+/// # Arguments
+///
+/// * `url` - It takes the url of the search result as an argument.
+/// * `style` - It takes the style related config options (like whether the favicons are enabled)
+///   as an argument.
+///
+/// # Returns
+///
+/// It returns the compiled html markup code as a result.
+fn favicon(url: &str, style: &Style) -> Markup {
+    let host = Url::parse(url)
+        .ok()
+        .filter(|_| style.favicons.enabled)
+        .and_then(|url| url.domain().map(str::to_owned));
+    html!(
+        @if let Some(host) = host {
+           img class="favicon" loading="lazy" alt="" width="16" height="16"
+              src=(format!(
+                  "favicon?host={}&token={}",
+                  utf8_percent_encode(&host, NON_ALPHANUMERIC),
+                  favicon_token(&host, &style.favicons)
+              ))
+              onerror="this.hidden = true";
+        }
+    )
+}
+
+/// A function that handles the html code for the preview thumbnail of a search result, which is
+/// fetched through the thumbnail proxy and only loaded once it is scrolled into view.
+///
+/// # Arguments
+///
+/// * `result` - It takes the search result whose thumbnail is displayed as an argument.
+///
+/// # Returns
+///
+/// It returns the compiled html markup code as a result.
+fn thumbnail(result: &SearchResult) -> Markup {
+    html!(
+        @if let Some(thumbnail) = &result.thumbnail {
+           img class="thumbnail" loading="lazy" alt="" src=(thumbnail)
+              onerror="this.hidden = true";
+        }
+    )
+}
+
+/// A function that handles the html code for the ranking details of a search result, which are
+/// only available in debug mode.
+///
+/// # Arguments
+///
+/// * `result` - It takes the search result whose ranking details are displayed as an argument.
+///
+/// # Returns
+///
+/// It returns the compiled html markup code as a result.
+fn ranking(result: &SearchResult) -> Markup {
+    html!(
+        @if let Some(ranking) = &result.ranking {
+           .ranking{
+              span{(format!("score: {:.3}", ranking.score))}
+              @for engine_position in &ranking.engine_positions {
+                 span{(format!("{}: #{}", engine_position.engine, engine_position.position + 1))}
+              }
+           }
+        }
+    )
+}
+
+/// A function that handles the html code for the badges of the upstream engines which provided a
+/// search result. No markup is emitted when the badges are disabled, unless the result is pinned.
+///
+/// # Arguments
+///
+/// * `result` - It takes the search result whose upstream engines are displayed as an argument.
+/// * `engine_badges` - It takes a boolean which decides whether the badges of the upstream
+///   engines are displayed as an argument.
+///
+/// # Returns
+///
+/// It returns the compiled html markup code as a result.
+fn upstream_engines(result: &SearchResult, engine_badges: bool) -> Markup {
+    html!(
+        @if engine_badges || result.pinned {
+           .upstream_engines{
+              @if result.pinned {
+                 span class="pinned"{"pinned"}
+              }
+              @if engine_badges {
+                 @for name in &result.engine{
+                    span class="engine_badge"{(name)}
+                 }
+              }
+           }
+        }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A helper function which removes the `<mark>` tags added by the highlighting, which gives
+    /// the html back exactly as it was provided when nothing else was altered.
+    fn unmarked(html: &str) -> String {
+        html.replace("<mark>", "").replace("</mark>", "")
+    }
+
+    #[test]
+    fn test_highlight() {
+        let highlighted = |query: &str, html: &str| {
+            highlight(html, query_terms_regex(query).as_ref()).into_owned()
+        };
+
+        // Each term is highlighted regardless of its case, and the longer overlapping terms are
+        // highlighted as a whole without nesting the marks.
+        assert_eq!(
+            highlighted("rust RUSTACEAN", "Rustaceans love <b>rust</b>"),
+            "<mark>Rustacean</mark>s love <b><mark>rust</mark></b>"
+        );
+        assert_eq!(highlighted("ab bc", "abc"), "<mark>ab</mark>c");
+        assert_eq!(highlighted("   ", "rust"), "rust");
+        assert_eq!(highlighted("rust", "rust"), "<mark>rust</mark>");
+        assert_eq!(highlight("rust", None), "rust");
+
+        // The terms matching within the tags, the attributes or the character references are not
+        // highlighted, so the markup is never altered.
+        for (query, html) in [
+            ("href b", r#"<a href="https://b.example">a</a>"#),
+            ("title", r#"<span title="title">x</span>"#),
+            ("onclick alert", r#"<i class="onclick">alert</i>"#),
+            (
+                "amp lt quot 39",
+                "AT&amp;T &lt;tag&gt; &quot;quoted&quot; &#39;",
+            ),
+            ("x>y", r#"<a title="x>y">x&gt;y</a>"#),
+        ] {
+            let output = highlighted(query, html);
+            assert_eq!(unmarked(&output), html, "{query}");
+            assert!(!output.contains("<mark><mark>"), "{query}");
+        }
+
+        // The terms holding the characters escaped in html are escaped first, so they can never
+        // add markup and are not highlighted across the character references.
+        for (query, html) in [
+            ("<script>", "a &lt;script&gt; tag"),
+            ("\"><img", "&quot;&gt;&lt;img src=x&gt;"),
+            ("AT&T", "AT&amp;T"),
+            ("' \"", "it&#39;s &quot;quoted&quot;"),
+        ] {
+            let output = highlighted(query, html);
+            assert_eq!(unmarked(&output), html, "{query}");
+            assert!(
+                output.match_indices('<').all(|(index, _)| {
+                    output[index..].starts_with("<mark>") || output[index..].starts_with("</mark>")
+                }),
+                "{query}"
+            );
+        }
+    }
+}
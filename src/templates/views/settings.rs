@@ -4,11 +4,16 @@ use std::collections::HashMap;
 
 use maud::{html, Markup};
 
-use crate::templates::partials::{
-    footer::footer,
-    header::header,
-    settings_tabs::{
-        cookies::cookies, engines::engines, general::general, user_interface::user_interface,
+use crate::config::server::CookieAttributes;
+
+use crate::templates::{
+    locale::FALLBACK_LANGUAGE,
+    partials::{
+        footer::footer,
+        header::header,
+        settings_tabs::{
+            cookies::cookies, engines::engines, general::general, user_interface::user_interface,
+        },
     },
 };
 
@@ -20,7 +25,10 @@ use crate::templates::partials::{
 /// * `colorscheme` - It takes the colorscheme name as an argument.
 /// * `theme` - It takes the theme name as an argument.
 /// * `animation` - It takes the animation name as an argument.
+/// * `custom_css` - It takes whether the custom css file configured by the operator is linked as
+///   an argument.
 /// * `engine_names` - It takes a list of engine names as an argument.
+/// * `cookie` - It takes the configured attributes of the settings cookie as an argument.
 ///
 /// # Error
 ///
@@ -31,11 +39,14 @@ pub fn settings(
     colorscheme: &str,
     theme: &str,
     animation: &Option<String>,
+    custom_css: bool,
     engine_names: &HashMap<String, bool>,
+    cookie: &CookieAttributes,
 ) -> Result<Markup, Box<dyn std::error::Error>> {
     Ok(html!(
-        (header(colorscheme, theme, animation))
-        main class="settings"{
+        (header(colorscheme, theme, animation, custom_css, None, FALLBACK_LANGUAGE))
+        main class="settings" data-cookie-attributes=(cookie.attributes())
+            data-cookie-secure-auto[cookie.secure.is_none()]{
            h1{"Settings"}
            hr;
            .settings_container{
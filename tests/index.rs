@@ -1,14 +1,33 @@
-use std::net::TcpListener;
+use std::{net::TcpListener, sync::Once};
 
-use websurfx::{config::Config, run, templates::views};
+use actix_web::web;
+use websurfx::{
+    cache::cacher::create_cache,
+    config::Config,
+    models::{
+        aggregation_models::{SearchResult, SearchResults},
+        engine_models::Category,
+    },
+    results::favicons::favicon_token,
+    run,
+    templates::{
+        locale::{translations, Translations},
+        views,
+    },
+};
+
+// Ensures that the logger is only initialized once for all the spawned apps.
+static LOGGING: Once = Once::new();
 
 // Starts a new instance of the HTTP server, bound to a random available port
 async fn spawn_app() -> String {
     // Binding to port 0 will trigger the OS to assign a port for us.
     let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind random port");
     let port = listener.local_addr().unwrap().port();
-    let config = Config::parse(false).unwrap();
-    let cache = websurfx::cache::cacher::create_cache(&config).await;
+    let mut logging_initialized = true;
+    LOGGING.call_once(|| logging_initialized = false);
+    let config = Config::parse(logging_initialized).unwrap();
+    let cache = create_cache(&config).await;
     let server = run(listener, config, cache).expect("Failed to bind address");
 
     tokio::spawn(server);
@@ -28,10 +47,801 @@ async fn test_index() {
         &config.style.colorscheme,
         &config.style.theme,
         &config.style.animation,
+        config.style.custom_css.is_some(),
+        &config.style.shortcuts,
     )
     .0;
     assert_eq!(res.text().await.unwrap(), template);
 }
 
+#[test]
+fn test_index_shortcuts() {
+    use websurfx::config::style::Shortcut;
+
+    let config = Config::parse(true).unwrap();
+    let index = |shortcuts: &[Shortcut]| {
+        views::index::index(
+            &config.style.colorscheme,
+            &config.style.theme,
+            &config.style.animation,
+            false,
+            shortcuts,
+        )
+        .0
+    };
+
+    assert!(!index(&[]).contains("class=\"shortcuts\""));
+
+    let page = index(&[
+        Shortcut {
+            label: "Wikipedia".to_owned(),
+            url: "https://www.wikipedia.org/".to_owned(),
+            icon: Some("📚".to_owned()),
+        },
+        Shortcut {
+            label: "Maps & <Places>".to_owned(),
+            url: "https://www.openstreetmap.org/".to_owned(),
+            icon: None,
+        },
+    ]);
+    assert!(page.contains("class=\"shortcuts\""));
+    assert!(page.contains("href=\"https://www.wikipedia.org/\""));
+    assert!(page.contains("📚"));
+    assert!(page.contains("Maps &amp; &lt;Places&gt;"));
+    assert_eq!(page.matches("class=\"shortcut-icon\"").count(), 1);
+}
+
+#[test]
+fn test_search_tips() {
+    use websurfx::config::style::{ResultsView, SearchTips};
+
+    let mut config = Config::parse(true).unwrap();
+    let translations = Translations::default();
+    let search = |config: &Config, search_results: &SearchResults| {
+        views::search::search(
+            &config.style,
+            &translations.locale(None, "en"),
+            "rust",
+            search_results,
+            "default",
+            ResultsView::Merged,
+            Category::General,
+            None,
+        )
+        .0
+    };
+
+    config.style.search_tips = SearchTips {
+        heading: "Tips & tricks:".to_owned(),
+        tips: vec!["Try <fewer> words.".to_owned()],
+    };
+    let no_results = SearchResults::new(Vec::new(), &[]);
+    let page = search(&config, &no_results);
+    assert!(page.contains("Tips &amp; tricks:"));
+    assert!(page.contains("<li>Try &lt;fewer&gt; words.</li>"));
+
+    // The tips are not displayed for the searches which have their own explanation.
+    let mut filtered = SearchResults::new(Vec::new(), &[]);
+    filtered.set_filtered();
+    assert!(!search(&config, &filtered).contains("Tips &amp; tricks:"));
+
+    config.style.search_tips.tips.clear();
+    assert!(!search(&config, &no_results).contains("Tips &amp; tricks:"));
+}
+
+#[test]
+fn test_featured_snippet_title() {
+    use websurfx::{config::style::ResultsView, models::aggregation_models::FeaturedSnippet};
+
+    let config = Config::parse(true).unwrap();
+    let translations = Translations::default();
+    let mut results = SearchResults::new(
+        vec![SearchResult::new(
+            "Rust &amp; Cargo",
+            "https://www.rust-lang.org/",
+            "A language empowering everyone.",
+            &["bing"],
+        )],
+        &[],
+    );
+    results.set_featured_snippet(FeaturedSnippet {
+        title: "Rust &amp; <b>Cargo</b>".to_owned(),
+        url: "https://www.rust-lang.org/".to_owned(),
+        text: "Fast & reliable.".to_owned(),
+    });
+
+    // The title is the html of the search result, so it is not escaped again, while the text
+    // extracted from the result page is.
+    let page = views::search::search(
+        &config.style,
+        &translations.locale(None, "en"),
+        "rust",
+        &results,
+        "default",
+        ResultsView::Merged,
+        Category::General,
+        None,
+    )
+    .0;
+    assert!(page.contains(">Rust &amp; <b>Cargo</b></a>"));
+    assert!(!page.contains("&amp;amp;"));
+    assert!(page.contains("Fast &amp; reliable."));
+}
+
+#[test]
+fn test_favicons() {
+    use websurfx::config::style::ResultsView;
+
+    let mut config = Config::parse(true).unwrap();
+    let translations = Translations::default();
+    let results = SearchResults::new(
+        vec![SearchResult::new(
+            "Example",
+            "https://www.example.com/page",
+            "An example page",
+            &["bing"],
+        )],
+        &[],
+    );
+    let search = |config: &Config| {
+        views::search::search(
+            &config.style,
+            &translations.locale(None, "en"),
+            "example",
+            &results,
+            "compact",
+            ResultsView::Merged,
+            Category::General,
+            None,
+        )
+        .0
+    };
+
+    // No favicons are rendered when they are disabled.
+    assert!(!search(&config).contains("class=\"favicon\""));
+    config.style.favicons.enabled = true;
+    let token = favicon_token("www.example.com", &config.style.favicons);
+    assert!(search(&config).contains(&format!(
+        "src=\"favicon?host=www%2Eexample%2Ecom&amp;token={token}\""
+    )));
+}
+
+#[tokio::test]
+async fn test_favicon_proxy_rejects_unsigned_hosts() {
+    use actix_web::{test, App};
+    use websurfx::{config::shared::SharedConfig, server::router};
+
+    let mut config = Config::parse(true).unwrap();
+    config.style.favicons.enabled = true;
+    let favicons = config.style.favicons.clone();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(SharedConfig::new(config)))
+            .service(router::favicon),
+    )
+    .await;
+
+    // The forged tokens and the signed internal hosts are refused without being fetched.
+    let forged = "/favicon?host=intranet.example.com&token=forged".to_owned();
+    let internal = ["localhost", "127.0.0.1", "169.254.169.254", "[::1]"].map(|host| {
+        format!(
+            "/favicon?host={host}&token={}",
+            favicon_token(host, &favicons)
+        )
+    });
+    for uri in std::iter::once(forged).chain(internal) {
+        let res = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(res.status(), 400, "{uri}");
+    }
+}
+
+#[tokio::test]
+async fn test_autocomplete_disabled() {
+    let address = spawn_app().await;
+
+    // The autocomplete is opt-in, so the endpoint is not available by default.
+    let res = reqwest::get(format!("{address}autocomplete?q=rust"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+}
+
+#[test]
+fn test_locale() {
+    use websurfx::config::style::ResultsView;
+
+    let config = Config::parse(true).unwrap();
+    let mut tables = Translations::default();
+    tables
+        .extend("en", r#"{"greeting": "Hello", "farewell": "Goodbye"}"#)
+        .unwrap();
+    tables.extend("DE", r#"{"greeting": "Hallo"}"#).unwrap();
+    assert!(tables.extend("fr", r#"["Bonjour"]"#).is_err());
+
+    // The available language most preferred by the browser is selected, or else the default.
+    let locale = |accept_language| tables.locale(accept_language, "en");
+    assert_eq!(
+        locale(Some("fr-CH, fr;q=0.9, de;q=0.8, *;q=0.5")).language(),
+        "de"
+    );
+    assert_eq!(locale(Some("en;q=0.5, de-AT")).language(), "de");
+    assert_eq!(locale(Some("de;q=0, fr")).language(), "en");
+    assert_eq!(locale(None).language(), "en");
+    assert_eq!(tables.locale(None, "De").language(), "de");
+    assert_eq!(tables.locale(None, "fr").language(), "en");
+
+    // The strings which are not translated fall back to english and then to their key.
+    let german = locale(Some("de"));
+    assert_eq!(german.text("greeting"), "Hallo");
+    assert_eq!(german.text("farewell"), "Goodbye");
+    assert_eq!(german.text("missing"), "missing");
+
+    // The bundled german translation is used on the search page.
+    let bundled = translations(&config.style, config.generation);
+    let page = views::search::search(
+        &config.style,
+        &bundled.locale(Some("de-DE,de;q=0.9"), "en"),
+        "rust",
+        &SearchResults::new(Vec::new(), &[]),
+        "default",
+        ResultsView::Merged,
+        Category::General,
+        None,
+    )
+    .0;
+    assert!(page.contains("<html lang=\"de\">"));
+    assert!(page.contains("ergab keine Treffer."));
+}
+
+#[tokio::test]
+async fn test_engines() {
+    let address = spawn_app().await;
+
+    let client = reqwest::Client::new();
+    let res = client
+        .get(format!("{address}engines"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    let config = Config::parse(true).unwrap();
+    let engines: Vec<serde_json::Value> = serde_json::from_str(&res.text().await.unwrap()).unwrap();
+    assert_eq!(engines.len(), config.search.upstream_search_engines.len());
+    for engine in engines {
+        let name = engine["name"].as_str().unwrap();
+        assert_eq!(
+            engine["enabled"].as_bool(),
+            config.search.upstream_search_engines.get(name).copied()
+        );
+        assert_eq!(
+            engine["forced"].as_bool(),
+            Some(config.search.forced_engines.contains(&name.to_lowercase()))
+        );
+        assert_eq!(engine["features"]["pagination"].as_bool(), Some(true));
+    }
+}
+
+#[tokio::test]
+async fn test_engine_stats() {
+    let address = spawn_app().await;
+
+    let client = reqwest::Client::new();
+    let res = client
+        .get(format!("{address}engines/stats"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    let config = Config::parse(true).unwrap();
+    let report: serde_json::Value = serde_json::from_str(&res.text().await.unwrap()).unwrap();
+    assert_eq!(
+        report["windowHours"].as_u64(),
+        Some(config.server.engine_stats.window.into())
+    );
+    assert!(report["engines"].is_array());
+}
+
+#[tokio::test]
+async fn test_search_path_empty_query() {
+    let address = spawn_app().await;
+
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let res = client
+        .get(format!("{address}search/"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 307);
+    assert_eq!(res.headers()["location"], "/");
+}
+
+#[tokio::test]
+async fn test_search_path_query() {
+    let address = spawn_app().await;
+
+    let client = reqwest::Client::new();
+    // No engines are selected in the cookie, so that no upstream search engines are queried.
+    let cookie = r#"appCookie={"theme":"simple","colorscheme":"catppuccin-mocha","engines":[],"safe_search_level":0}"#;
+    let search = |path: &str| {
+        client
+            .get(format!("{address}search/{path}"))
+            .header("cookie", cookie)
+            .send()
+    };
+
+    // The encoded slashes and spaces are decoded, while the unencoded slashes are kept as part of
+    // the query.
+    for (path, query) in [("a%2Fb%20c", "a/b c"), ("a/b", "a/b")] {
+        let res = search(path).await.unwrap();
+        assert_eq!(res.status(), 200);
+        let body = res.text().await.unwrap();
+        assert!(body.contains(&format!("value=\"{query}\"")));
+    }
+
+    // The search parameters of the url still apply to the query of the path.
+    let res = search("rust?safesearch=2").await.unwrap();
+    assert_eq!(res.status(), 200);
+    assert!(res
+        .text()
+        .await
+        .unwrap()
+        .contains("<option value=\"2\" selected>"));
+
+    let res = search("rust?page=first").await.unwrap();
+    assert_eq!(res.status(), 400);
+    let res = client
+        .get(format!("{address}search/rust?page=0"))
+        .header("cookie", cookie)
+        .header("accept", "application/json")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    assert!(res.text().await.unwrap().contains("The page should be"));
+}
+
+#[tokio::test]
+async fn test_search_query_header() {
+    let address = spawn_app().await;
+
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    // No engines are selected in the cookie, so that no upstream search engines are queried.
+    let cookie = r#"appCookie={"theme":"simple","colorscheme":"catppuccin-mocha","engines":[],"safe_search_level":0}"#;
+    let search = |url: String, query: Option<&'static str>| {
+        let request = client.get(url).header("cookie", cookie);
+        match query {
+            Some(query) => request.header("X-Search-Query", query),
+            None => request,
+        }
+        .send()
+    };
+
+    let res = search(format!("{address}search"), Some("header%20query"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let vary = |res: &reqwest::Response| res.headers()["vary"].to_str().unwrap().to_owned();
+    assert!(vary(&res).contains("X-Search-Query"));
+    assert!(vary(&res).contains("cookie"));
+    assert_eq!(res.headers()["cache-control"], "no-store");
+    assert!(res.text().await.unwrap().contains("header query"));
+
+    // The query provided in the search url takes precedence over the header.
+    let res = search(format!("{address}search?q=url+query"), Some("zanzibar"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert!(!vary(&res).contains("X-Search-Query"));
+    let body = res.text().await.unwrap();
+    assert!(body.contains("url query") && !body.contains("zanzibar"));
+
+    for query in [None, Some(" ")] {
+        let res = search(format!("{address}search"), query).await.unwrap();
+        assert_eq!(res.status(), 307);
+        assert_eq!(res.headers()["location"], "/");
+    }
+}
+
+#[tokio::test]
+async fn test_search_json_output() {
+    let address = spawn_app().await;
+
+    let client = reqwest::Client::new();
+    // No engines are selected in the cookie, so that no upstream search engines are queried.
+    let cookie = r#"appCookie={"theme":"simple","colorscheme":"catppuccin-mocha","engines":[],"safe_search_level":0}"#;
+    let search = || {
+        client
+            .get(format!("{address}search?q=json+output"))
+            .header("cookie", cookie)
+            .header("accept", "application/json")
+            .send()
+    };
+
+    // The first search is fetched the usual way and cached, while the second one is served with
+    // the json of the cached search results as it is.
+    let mut etags = Vec::new();
+    for _ in 0..2 {
+        let res = search().await.unwrap();
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "application/json");
+        assert!(res.headers()["vary"].to_str().unwrap().contains("accept"));
+        assert!(!res.headers().contains_key("cache-control"));
+        etags.push(res.headers()["etag"].clone());
+        let results: SearchResults = serde_json::from_str(&res.text().await.unwrap()).unwrap();
+        assert!(results.no_engines_selected);
+    }
+    assert_eq!(etags[0], etags[1]);
+}
+
+#[tokio::test]
+async fn test_search_invalid_params() {
+    let address = spawn_app().await;
+
+    let res = reqwest::get(format!("{address}search?q=rust&page=first"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    assert!(res.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/html"));
+    assert!(res
+        .text()
+        .await
+        .unwrap()
+        .contains("The search url contains invalid search parameters."));
+}
+
+#[tokio::test]
+async fn test_analytics_disabled() {
+    let address = spawn_app().await;
+
+    let client = reqwest::Client::new();
+    let res = client
+        .get(format!("{address}admin/analytics"))
+        .header("Authorization", "Bearer token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+}
+
+#[tokio::test]
+async fn test_custom_css_unset() {
+    let address = spawn_app().await;
+
+    let client = reqwest::Client::new();
+    let res = client
+        .get(format!("{address}custom.css"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+}
+
+#[tokio::test]
+async fn test_robots_txt() {
+    let address = spawn_app().await;
+
+    let client = reqwest::Client::new();
+    let res = client
+        .get(format!("{address}robots.txt"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert!(res
+        .text()
+        .await
+        .unwrap()
+        .lines()
+        .any(|line| line == "Disallow: /search"));
+}
+
+#[cfg(feature = "memory-cache")]
+#[test]
+fn test_lru_eviction_order() {
+    let mut index = websurfx::cache::eviction::LruIndex::new(3, 100);
+    for key in ["a", "b", "c"] {
+        assert!(index.insert(key, 10).is_empty());
+    }
+
+    // The least recently used entry is evicted first, and using an entry makes it the most
+    // recently used one.
+    index.touch("a");
+    assert_eq!(index.insert("d", 10), vec!["b"]);
+    assert_eq!(index.insert("e", 10), vec!["c"]);
+
+    // The entries are evicted until the total size fits again.
+    assert_eq!(index.insert("f", 85), vec!["a", "d"]);
+    assert_eq!((index.len(), index.size()), (2, 95));
+
+    // Updating an entry replaces its size and makes it the most recently used one, and an entry
+    // exceeding the size alone is evicted as well.
+    assert_eq!(index.insert("e", 20), vec!["f"]);
+    assert_eq!(index.size(), 20);
+    assert_eq!(index.insert("g", 101), vec!["e", "g"]);
+    assert!(index.is_empty());
+    assert_eq!(index.size(), 0);
+}
+
 // TODO: Write tests for testing parameters for search function that if provided with something
 // other than u32 like alphabets and special characters than it should panic
+
+#[test]
+fn test_redact_url() {
+    use websurfx::{results::filter_lists::FilterList, server::redaction::redact_url};
+
+    let sensitive_terms = FilterList::new(&[r"\bdiagnosis\b".to_owned()]).unwrap();
+
+    assert_eq!(
+        redact_url("/search?q=Cancer+Diagnosis&page=1", &sensitive_terms),
+        "/search?[redacted]"
+    );
+    assert_eq!(
+        redact_url("/search/my%20diagnosis?page=1", &sensitive_terms),
+        "/search/[redacted]?[redacted]"
+    );
+    assert_eq!(
+        redact_url(
+            "http://127.0.0.1:8080/search?q=rust&refine=diagnosis",
+            &sensitive_terms
+        ),
+        "http://127.0.0.1:8080/search?[redacted]"
+    );
+    assert_eq!(
+        redact_url("/search?q=sweden&page=1", &sensitive_terms),
+        "/search?q=sweden&page=1"
+    );
+    assert_eq!(
+        redact_url("/static/index.js", &sensitive_terms),
+        "/static/index.js"
+    );
+}
+
+#[test]
+fn test_click_redirect() {
+    use websurfx::{
+        config::style::ClickRedirect,
+        server::click_redirect::{is_signed, outbound_url, token},
+    };
+
+    let mut click_redirect = ClickRedirect {
+        enabled: true,
+        secret: Some("secret".to_owned()),
+        ..Default::default()
+    };
+
+    let url = "http://example.com/page?id=1&utm_source=websurfx&fbclid=abc";
+    assert!(is_signed(
+        url,
+        &token(url, &click_redirect),
+        &click_redirect
+    ));
+    assert!(!is_signed(
+        "https://evil.example",
+        &token(url, &click_redirect),
+        &click_redirect
+    ));
+
+    assert_eq!(
+        outbound_url(url, &click_redirect).as_deref(),
+        Some("http://example.com/page?id=1")
+    );
+    click_redirect.enforce_https = true;
+    assert_eq!(
+        outbound_url("http://example.com/?utm_medium=web", &click_redirect).as_deref(),
+        Some("https://example.com/")
+    );
+    assert_eq!(outbound_url("javascript:alert(1)", &click_redirect), None);
+}
+
+#[tokio::test]
+async fn test_search_batch_rejects_invalid_batches() {
+    let address = spawn_app().await;
+    let config = Config::parse(true).unwrap();
+
+    let client = reqwest::Client::new();
+    let too_many: Vec<String> = (0..=config.server.batch_search.max_queries)
+        .map(|index| format!("query {index}"))
+        .collect();
+    for body in [
+        "[]".to_owned(),
+        r#"["sweden", " "]"#.to_owned(),
+        serde_json::to_string(&too_many).unwrap(),
+    ] {
+        let res = client
+            .post(format!("{address}search/batch"))
+            .header("content-type", "application/json")
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 400);
+    }
+}
+
+#[test]
+fn test_hsts_upgrade() {
+    use websurfx::results::hsts::PreloadList;
+
+    let mut preload_list = PreloadList::default();
+    preload_list.extend("# comment\n\ndev\nexample.com\n=exact.example.org\n");
+
+    assert_eq!(
+        preload_list
+            .upgrade("http://www.rust.dev/learn?q=1")
+            .as_deref(),
+        Some("https://www.rust.dev/learn?q=1")
+    );
+    assert_eq!(
+        preload_list
+            .upgrade("http://Sub.Example.com:8080/")
+            .as_deref(),
+        Some("https://sub.example.com:8080/")
+    );
+    assert_eq!(
+        preload_list.upgrade("http://exact.example.org/").as_deref(),
+        Some("https://exact.example.org/")
+    );
+    // The subdomains of an exact host and the hosts which are not preloaded keep their scheme.
+    assert_eq!(preload_list.upgrade("http://sub.exact.example.org/"), None);
+    assert_eq!(preload_list.upgrade("http://example.net/"), None);
+    assert_eq!(preload_list.upgrade("http://notexample.com/"), None);
+    assert_eq!(preload_list.upgrade("https://www.rust.dev/"), None);
+    assert_eq!(preload_list.upgrade("http://127.0.0.1/"), None);
+}
+
+#[test]
+fn test_build_request() {
+    use reqwest::{header::HeaderMap, Client, Method};
+    use websurfx::{
+        engines::startpage::Startpage,
+        models::engine_models::{build_request, RequestTemplate, SearchEngine},
+    };
+
+    let client = Client::new();
+    let body = |request: &reqwest::Request| {
+        std::str::from_utf8(request.body().unwrap().as_bytes().unwrap())
+            .unwrap()
+            .to_owned()
+    };
+
+    let request = build_request(
+        &client,
+        "https://example.com/search?q=rust&page=2",
+        HeaderMap::new(),
+        RequestTemplate::Get,
+    )
+    .unwrap();
+    assert_eq!(request.method(), Method::GET);
+    assert_eq!(
+        request.url().as_str(),
+        "https://example.com/search?q=rust&page=2"
+    );
+    assert!(request.body().is_none());
+
+    // The url parameters used by the body are moved from the url into the body.
+    let request = build_request(
+        &client,
+        "https://example.com/search?q=rust lang&page=2&lang=en",
+        HeaderMap::new(),
+        Startpage::new().unwrap().request_template(),
+    )
+    .unwrap();
+    assert_eq!(request.method(), Method::POST);
+    assert_eq!(
+        request.url().as_str(),
+        "https://example.com/search?page=2&lang=en"
+    );
+    assert_eq!(
+        request.headers()["content-type"],
+        "application/x-www-form-urlencoded"
+    );
+    assert_eq!(body(&request), "q=rust%20lang&num=&start=");
+
+    let request = build_request(
+        &client,
+        "https://example.com/api?q=say+%22hi%22&page=2",
+        HeaderMap::new(),
+        RequestTemplate::PostJson(r#"{"query": {q}, "page": {page}, "missing": {missing}}"#),
+    )
+    .unwrap();
+    assert_eq!(request.method(), Method::POST);
+    assert_eq!(request.url().as_str(), "https://example.com/api");
+    assert_eq!(request.headers()["content-type"], "application/json");
+    assert_eq!(
+        body(&request),
+        r#"{"query": "say \"hi\"", "page": "2", "missing": ""}"#
+    );
+
+    assert!(build_request(
+        &client,
+        "not a url",
+        HeaderMap::new(),
+        RequestTemplate::PostForm("q={q}")
+    )
+    .is_err());
+}
+
+#[test]
+fn test_bing_answers() {
+    use scraper::Html;
+    use websurfx::{engines::bing::Bing, models::aggregation_models::AnswerContent};
+
+    let bing = Bing::new().unwrap();
+    let document = Html::parse_document(
+        r#"<ol id="b_results">
+            <li class="b_ans"><div class="dc_mn">
+                <div class="dc_hd">rust</div>
+                <span class="dc_pos">noun</span>
+                <div class="dc_dfn">A reddish-brown  <b>flaky</b> coating of iron oxide.</div>
+            </div></li>
+            <li class="b_ans"><div class="wtr_core">
+                <div class="wtr_locTitle">Stockholm, Sweden</div>
+                <span class="wtr_currTemp">12</span><span class="wtr_currUnit">°C</span>
+                <div class="wtr_caption">Partly cloudy</div>
+            </div></li>
+            <li class="b_ans"><div class="wtr_core"><div class="wtr_locTitle">Nowhere</div></div></li>
+            <li class="b_algo"><h2><a href="https://www.rust-lang.org/">Rust</a></h2></li>
+        </ol>"#,
+    );
+
+    assert_eq!(
+        bing.answers(&document),
+        vec![
+            AnswerContent::Definition {
+                word: "rust".to_owned(),
+                part_of_speech: Some("noun".to_owned()),
+                definition: "A reddish-brown flaky coating of iron oxide.".to_owned(),
+            },
+            AnswerContent::Weather {
+                location: "Stockholm, Sweden".to_owned(),
+                temperature: "12°C".to_owned(),
+                condition: "Partly cloudy".to_owned(),
+            },
+        ]
+    );
+
+    // The pages without answer boxes provide no answers.
+    assert!(bing
+        .answers(&Html::parse_document(r#"<ol id="b_results"></ol>"#))
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_search_json_invalid_pages() {
+    let address = spawn_app().await;
+    let client = reqwest::Client::new();
+    let cookie = r#"appCookie={"theme":"simple","colorscheme":"catppuccin-mocha","engines":[],"safe_search_level":0}"#;
+
+    for page in ["0", "-1", "99999999999999999999999"] {
+        let res = client
+            .get(format!("{address}search?q=rust&page={page}"))
+            .header("Accept", "application/json")
+            .header("Cookie", cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 400);
+        let body: serde_json::Value = serde_json::from_str(&res.text().await.unwrap()).unwrap();
+        assert!(body["error"].as_str().unwrap().contains("positive number"));
+
+        // The browsers are still served the search page.
+        let res = client
+            .get(format!("{address}search?q=rust&page={page}"))
+            .header("Cookie", cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+    }
+}
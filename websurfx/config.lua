@@ -6,18 +6,124 @@
 	port = 8080,
 	binding_ip = "127.0.0.1",
 	aggregator = {
-	    random_delay = false
+	    random_delay = false,
+	    engine_failure_tolerance = 0.5, -- the fraction of the selected engines allowed to fail before the results are marked as degraded.
+	    ranking = "reciprocal_rank", -- the scoring function used to rank the results ("reciprocal_rank" or "consensus").
+	    stable_ordering = true, -- whether the results with the same score are ordered by their url so that they do not reshuffle.
+	    shuffle_engines = false, -- whether to dispatch the requests to the upstream search engines in a shuffled order.
+	    fastest_engines_first = false, -- whether to dispatch the requests to the engines with the lowest latency first.
+	    validate_content_type = true, -- whether to report the responses of an engine in an unexpected format as errors.
+	    -- min_tls_version = "1.2", -- the minimum TLS version of the connections to the engines ("1.2" or "1.3").
+	    retries = {
+	        attempts = 0, -- the maximum number of retries of the requests which timed out or failed to connect (0 means no retries).
+	        timeout_escalation = 1.0, -- the factor by which the timeout is multiplied on each retry.
+	        max_timeout = 0, -- the maximum timeout of a retry in seconds (0 means no cap).
+	        deadline = 60, -- the maximum time in seconds spent on all the requests to an engine (0 means no deadline).
+	    },
 	},
 	request_timeout = 30,
 	rate_limiter = {
 	    number_of_requests = 20,
 	    time_limit = 3
+	},
+	-- the maximum number of searches handled at the same time by the whole server (0 means no limit).
+	max_concurrent_searches = 0,
+	-- admin_token = "", -- the bearer token required to access the admin endpoints.
+	-- custom contents of the robots.txt file, by default the search pages are disallowed.
+	-- robots_txt = [[
+	-- User-agent: *
+	-- Disallow: /search
+	-- ]],
+	-- the Cache-Control header of the search result pages (for example "private, max-age=60").
+	cache_control = "no-store",
+	-- whether the api requests for a page which is not a positive number (like `page=0`) are
+	-- rejected with a 400 error instead of being clamped to the first page like the browser requests.
+	reject_invalid_pages = true,
+	cookie = {
+	    same_site = "lax", -- the SameSite attribute of the settings cookie (strict, lax or none).
+	    -- secure = true, -- whether the cookie is only sent over https (by default when accessed over https).
+	    max_age = 31536000, -- the lifetime of the cookie in seconds (0 to expire it when the browser is closed).
+	},
+	analytics = {
+	    enabled = false, -- whether to count the searched queries and the queried engines.
+	    hash_queries = false, -- whether to store the hashes of the queries instead of the queries.
+	    window = 24, -- the size of the rolling time window over which the searches are counted (in hours).
+	    top_queries = 10 -- the number of the most searched queries provided by the analytics endpoint.
+	},
+	engine_stats = {
+	    enabled = true, -- whether to count the outcomes of the requests sent to each engine.
+	    window = 24 -- the size of the rolling time window over which the outcomes are counted (in hours).
+	},
+	batch_search = {
+	    max_queries = 10, -- the maximum number of queries of a batch (0 disables the `/search/batch` endpoint).
+	    concurrency = 4 -- the maximum number of queries of a batch which are searched concurrently.
+	},
+	thumbnails = {
+	    enabled = false, -- whether to display the Open Graph images of the result pages as thumbnails.
+	    max_per_search = 5, -- the maximum number of the top ranked results of a search with a thumbnail.
+	    timeout = 3, -- timeout for the requests fetching the result pages and the thumbnails (in seconds).
+	    cache_expiry_time = 86400, -- the time for which the thumbnail of a result page is cached (in seconds).
+	    -- secret = "${WEBSURFX_THUMBNAIL_SECRET}", -- the secret signing the result pages (a random key is used when unset).
+	},
+	featured_snippet = {
+	    enabled = false, -- whether to display the first paragraph of the top result's page above the results.
+	    max_length = 300, -- the maximum number of characters of the featured snippet.
+	    timeout = 3, -- timeout for the request fetching the page of the top result (in seconds).
+	    cache_expiry_time = 86400 -- the time for which the featured snippet of a result page is cached (in seconds).
+	},
+	autocomplete = {
+	    enabled = false, -- whether the `/autocomplete` endpoint suggests the completions of the typed queries.
+	    timeout = 2, -- timeout for the request fetching the suggestions from the upstream suggest api (in seconds).
+	    max_suggestions = 8, -- the maximum number of suggestions returned for a typed query.
+	    cache_expiry_time = 300 -- the time for which the suggestions of a typed query are cached (in seconds, 0 disables the caching).
+	},
+	tls = {
+	    enabled = false, -- whether to serve the website over https without a reverse proxy.
+	    cert_path = "", -- the path to the PEM file containing the certificate chain.
+	    key_path = "" -- the path to the PEM file containing the private key.
 	}
     },
 
     caching = {
 	-- redis_url = "redis://127.0.0.1::8082",
-	cache_expiry_time = 600
+	-- key_prefix = "websurfx:", -- the prefix prepended to all the keys stored on the redis server.
+	cache_expiry_time = 600,
+	-- The maximum number of results of each search which are stored in the cache, only the top
+	-- ranked results are kept (0 means no limit).
+	max_cached_results = 0,
+	-- the minimum number of results a cached search needs to be served from the cache, the cached
+	-- searches with fewer results are searched again (0 means cached searches are always served).
+	min_cached_results = 0,
+	-- whether the search query is lowercased in the cache key, so that searches which only differ
+	-- by case share a cache entry. This raises the cache hit rate, but the searches whose casing
+	-- matters to the engines are served the results cached for any casing of the query.
+	lowercase_cache_key = false,
+	-- whether the identical searches which miss the cache at the same time share a single
+	-- aggregation of the results from the upstream search engines.
+	coalesce_searches = true,
+	-- the number of searches handled concurrently above which the server is under high load
+	-- and serves older cached searches to spare the upstream search engines (0 to disable).
+	high_load_threshold = 0,
+	-- the maximum age of the cached searches served under high load (in seconds).
+	high_load_max_age = 3600,
+	-- the maximum number of searches written to the cache in the background at the same time, the
+	-- excess writes are dropped (0 means no limit).
+	max_background_writes = 32,
+	-- whether the cached searches which would be searched again (like the thin ones) are served
+	-- when searching them again fails.
+	serve_cached_on_error = true,
+	-- whether the response of each engine is cached on its own as well, so that the searches
+	-- with a different selection of engines reuse the cached responses of the engines.
+	cache_engine_responses = false,
+	-- whether the `nocache` search parameter skipping the cached results is honored in debug
+	-- mode and for the requests providing the admin token.
+	allow_nocache = true,
+	-- whether the results fetched for the searches skipping the cache refresh the cache.
+	nocache_writes = true,
+	-- the maximum number of searches and their maximum total size (in bytes) stored in the
+	-- in-memory cache, the least recently used searches are evicted beyond them (0 means no limit).
+	max_cache_entries = 10000,
+	max_cache_size = 268435456
     },
 
     search = {
@@ -30,12 +136,248 @@
        	    Mojeek = false,
        	    Bing = false,
     	},
-	safe_search = 2
+	safe_search = 2,
+	-- the safe search level used for the api requests (preferring a json response) instead of safe_search.
+	-- api_safe_search = 0,
+	-- filter the cached results again at the safe search level of each search when they are read
+	-- from the cache (for the family or school deployments, at the cost of filtering each read).
+	strict_safe_search = false,
+	-- the rules which rewrite the terms of the query or redirect the search from the `min_level`
+	-- safe search level on (for example to a safe search help page).
+	safe_search_terms = {
+	    min_level = 3,
+	    rules = {
+	        -- { term = "adult movies", rewrite = "family movies" },
+	        -- { term = "casino", redirect = "https://example.com/safe-search-help" },
+	    },
+	},
+	-- the native safe search values of the engines for each of the levels 0 to 4 (the engines
+	-- which are not listed use their default mapping, see the `/engines` endpoint).
+	safe_search_mappings = {
+	    -- Brave = { "off", "moderate", "moderate", "strict", "strict" },
+	},
+	-- the blocklist and allowlist files which are merged into the filter lists used by the safe
+	-- search levels 3 and 4 (the default blocklist.txt and allowlist.txt are used when empty).
+	blocklists = {}, -- e.g. { "/etc/websurfx/ads.txt", "/etc/websurfx/malware.txt" }
+	allowlists = {},
+	-- the files of regex patterns matching the sensitive search queries, which are redacted from
+	-- the logs and excluded from the analytics (no query is redacted when empty).
+	sensitive_terms = {}, -- e.g. { "/etc/websurfx/health.txt" }
+	-- engines which are always queried in addition to the engines selected by the user.
+	forced_engines = {}, -- e.g. { "Mojeek" }
+	-- engines which are never queried however they were selected, overriding all the other options.
+	denied_engines = {}, -- e.g. { "Startpage" }
+	-- whether the search query is sent to the engines with its whitespace trimmed and collapsed.
+	normalize_query = false,
+	-- whether the engines enabled above are queried when the user has not selected any engines,
+	-- instead of showing that no engines were selected.
+	fallback_to_default_engines = false,
+	-- whether the engine names selected by the user are matched regardless of their case, whitespace
+	-- and punctuation, and are looked up in the engine aliases below.
+	fuzzy_engine_names = true,
+	-- the alternative names of the engines which are resolved to the engine they refer to.
+	engine_aliases = {
+	    ddg = "DuckDuckGo",
+	    duck = "DuckDuckGo",
+	    searxng = "Searx",
+	    librey = "LibreX",
+	},
+	-- the weights of the engines (engines without a weight have a weight of 1.0).
+	engine_weights = {
+	    -- Brave = 2.0,
+	},
+	-- the weights of the engines for particular categories, which are multiplied with the weights
+	-- above when the results of the category are ranked.
+	category_engine_weights = {
+	    -- news = { Bing = 1.5 },
+	},
+	-- the multipliers of the rank scores of the results of particular domains and their subdomains
+	-- (above 1.0 boosts and below 1.0 penalizes the results of the domain).
+	domain_reputation = {
+	    -- ["wikipedia.org"] = 1.5,
+	    -- ["contentfarm.example"] = 0.2,
+	},
+	-- the categories for which each engine is enabled (engines which are not listed are enabled
+	-- for all the categories).
+	engine_categories = {
+	    -- Mojeek = { "general" },
+	    -- Bing = { "news" },
+	},
+	-- the engines whose errors are displayed first in the listed order, the errors of the other
+	-- engines follow ordered by name.
+	engine_priority = {
+	    -- "Brave", "DuckDuckGo",
+	},
+	-- the maximum number of engine errors kept along with the results (0 means no limit).
+	max_engine_errors = 20,
+	-- how the results with a plain http url are handled (allow, downrank or filter).
+	http_results = "allow",
+	-- how the results without a description are handled (allow, downrank or filter).
+	missing_snippets = "allow",
+	-- how the html of the titles and the descriptions of the results is sanitized, either "safe"
+	-- which keeps the formatting tags like <b> without their attributes or "strip" which keeps the
+	-- plain text alone.
+	snippet_html = "safe",
+	-- upgrades the http urls of the results to https when their host is on the bundled HSTS preload
+	-- list or on one of the preload list files, the other hosts keep their scheme.
+	https_upgrade = {
+	    enabled = false,
+	    preload_lists = {}, -- e.g. { "/etc/websurfx/hsts_preload.txt" }
+	},
+	-- the expansions of the words of the query which are sent to the engines instead of the words.
+	query_synonyms = {
+	    -- k8s = "kubernetes",
+	},
+	-- the maximum number of engines queried for a single search, the engines with the highest
+	-- weights are queried (0 means no limit).
+	max_engines_per_search = 0,
+	-- the number of results requested from the engines which support requesting fewer results
+	-- than their default page (0 means the default page).
+	results_per_page = 0,
+	-- the caps of the number of results requested from particular engines.
+	engine_result_limits = {
+	    -- Bing = 5,
+	},
+	-- the caps of the number of requests sent to particular engines within any second and within
+	-- any minute (0 means no limit), the searches exceeding a cap skip the engine.
+	engine_rate_limits = {
+	    -- Bing = { per_second = 1, per_minute = 30 },
+	},
+	-- ordered tiers of engines which are only queried when the previous tiers found fewer than
+	-- `fallback_min_results` results, the selected engines not listed in any tier are queried first.
+	fallback_engine_tiers = {
+	    -- { "Mojeek" },
+	    -- { "Bing", "Startpage" },
+	},
+	fallback_min_results = 10, -- the minimum number of results before no more fallback tiers are queried.
+	-- regex based find and replace rules applied to the search query before it is sent to an engine.
+	query_rewrite_rules = {
+	    -- Bing = {
+	    --     { find = "\\+", replace = " plus " },
+	    -- },
+	},
+	-- custom http headers sent along with the requests to an engine, the values can reference
+	-- environment variables with the `${NAME}` syntax.
+	engine_headers = {
+	    -- Brave = {
+	    --     ["Accept-Language"] = "en-US",
+	    --     ["X-Api-Key"] = "${BRAVE_API_KEY}",
+	    -- },
+	},
+	-- the json search api queried by the `Json` engine (which has to be added to the engines above),
+	-- its authentication headers can be provided with the `engine_headers` of the engine.
+	json_source = {
+	    url = "", -- e.g. "https://searx.example.com/search?q={query}&pageno={page}&format=json"
+	    mapping = {
+	        results = "results",
+	        title = "title",
+	        url = "url",
+	        description = "content",
+	    },
+	},
+	-- the directory of local documents searched by the `Local` engine (which has to be added to the
+	-- engines above), the documents are linked by their file url (which the browsers do not open
+	-- from the search page) when no url template is provided.
+	local_source = {
+	    directory = "", -- e.g. "/srv/docs"
+	    url = "", -- e.g. "https://docs.example.com/{path}"
+	    extensions = { "md", "txt" },
+	    max_file_size = 1048576, -- the maximum size of a searched file (in bytes).
+	    max_files = 10000,
+	},
     },
 
     style = {
    	colorscheme = "catppuccin-mocha",
    	theme = "simple",
-    	animation = "simple-frosted-glow"
+    	animation = "simple-frosted-glow",
+    	-- the path of a custom css file linked after the theme to override its styles (relative paths
+    	-- are looked up in the same folders as the config file).
+    	-- custom_css = "custom.css",
+    	-- The template used to render the search results. It can be overridden per request with the
+    	-- `template` search url parameter.
+    	-- Available options:
+    	-- 1. default
+    	-- 2. compact
+    	results_template = "default",
+    	-- whether the badges of the upstream engines which provided each result are displayed.
+    	engine_badges = true,
+    	-- The format in which the urls of the results are displayed, the links always point to the full urls.
+    	-- Available options:
+    	-- 1. full
+    	-- 2. breadcrumb (like `example.com › docs › page`)
+    	-- 3. short (the host followed by a truncated path)
+    	display_url = "full",
+    	-- How the internationalized (non-ASCII) hosts of the results are displayed.
+    	-- Available options:
+    	-- 1. unicode (like `bücher.example`)
+    	-- 2. punycode (like `xn--bcher-kva.example`)
+    	-- 3. annotate (unicode, except for the hosts mixing scripts which are shown in punycode with a warning)
+    	idn_hosts = "annotate",
+    	-- whether the terms of the search query are highlighted in the titles and descriptions of the results.
+    	highlight_query_terms = false,
+    	-- whether the search url (including the query) is kept from the websites visited from the results.
+    	no_referrer = true,
+    	cached_link = {
+    	    enabled = false, -- whether to display a link to the cached copy of each result.
+    	    url = "https://web.archive.org/web/{url}" -- the url of the cached copy, `{url}` is replaced with the url of the result.
+    	},
+    	click_redirect = {
+    	    enabled = false, -- whether the links of the results point to the `/r` click-through redirect.
+    	    strip_tracking_params = true, -- whether the tracking parameters are removed from the visited urls.
+    	    enforce_https = false, -- whether the plain http urls are upgraded to https.
+    	    -- secret = "${WEBSURFX_REDIRECT_SECRET}", -- the secret signing the urls (a random key is used when unset).
+    	},
+    	favicons = {
+    	    enabled = false, -- whether the favicons of the websites are displayed next to the titles of the results.
+    	    timeout = 3, -- timeout for the requests fetching the favicons (value in seconds).
+    	    cache_expiry_time = 86400, -- the time for which a favicon is cached (value in seconds).
+    	    max_cached = 1000, -- the maximum number of websites whose favicons are cached.
+    	    fallback_icon = true, -- whether a generic icon is displayed when the favicon is unavailable.
+    	    -- secret = "${WEBSURFX_FAVICON_SECRET}", -- the secret signing the hosts (a random key is used when unset).
+    	},
+    	-- The view in which the general search results are displayed. It can be overridden per
+    	-- request with the `view` search url parameter.
+    	-- Available options:
+    	-- 1. merged (a single ranked list)
+    	-- 2. engines (a tab for each engine with the results in the order it returned them)
+    	results_view = "merged",
+    	-- The order in which the general search results are presented. It can be overridden per
+    	-- request with the `order` search url parameter.
+    	-- Available options:
+    	-- 1. ranked (in the order of their rank scores)
+    	-- 2. interleave (the first result of each engine, then the second result of each engine...)
+    	result_order = "ranked",
+    	-- A custom explanation displayed on the search page when all the queried upstream search
+    	-- engines failed, a default explanation is displayed when none is configured.
+    	outage_message = nil,
+    	-- The quick links displayed as buttons below the search bar of the home page, each with a
+    	-- label, an http or https url and an optional icon (like an emoji). None are displayed when
+    	-- none are configured.
+    	shortcuts = {
+    	    -- { label = "Wikipedia", url = "https://www.wikipedia.org", icon = "📚" },
+    	    -- { label = "Maps", url = "https://www.openstreetmap.org" },
+    	},
+    	-- The tips displayed on the search page when a search did not match any results, which can
+    	-- be customized or translated. No tips are displayed when the list of tips is empty.
+    	search_tips = {
+    	    heading = "Suggestions:",
+    	    tips = {
+    	        "Make sure that all words are spelled correctly.",
+    	        "Try different keywords.",
+    	        "Try more general keywords.",
+    	        "Try fewer keywords.",
+    	        "Try enabling more upstream search engines on the settings page.",
+    	    },
+    	},
+    	-- The language of the search page used when the browser does not prefer any of the available
+    	-- languages ("en" or "de", or any language provided by a translation file).
+    	locale = "en",
+    	-- Whether the language of the search page is selected from the Accept-Language header.
+    	accept_language = true,
+    	-- The json translation files of the search page keyed by their language.
+    	-- translations = {
+    	--     fr = "/etc/websurfx/locales/fr.json",
+    	-- },
     }
 }